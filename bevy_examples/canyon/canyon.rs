@@ -27,6 +27,8 @@ use rules::{CustomComponents, RotationRandomizer, ScaleRandomizer, WindRotation}
 
 use crate::rules::rules_and_assets;
 
+#[path = "../../ghx_proc_gen/benches/common/canyon.rs"]
+mod canyon_models;
 mod rules;
 
 // --------------------------------------------
@@ -48,8 +50,8 @@ const BLOCK_SIZE: f32 = 1.;
 /// Size of a grid node in world units
 const NODE_SIZE: Vec3 = Vec3::splat(BLOCK_SIZE);
 
-const ASSETS_SCALE_FACTOR: f32 = BLOCK_SIZE / 2.;
-const ASSETS_SCALE: Vec3 = Vec3::splat(ASSETS_SCALE_FACTOR);
+/// Size of a grid node in the units of the models: they are 2 units wide
+const ASSETS_SIZE: Vec3 = Vec3::splat(2.);
 
 fn setup_scene(mut commands: Commands) {
     // Camera
@@ -183,7 +185,8 @@ fn setup_generator(mut commands: Commands, asset_server: Res<AssetServer>) {
                 NODE_SIZE,
                 // We spawn assets with a scale of 0 since we animate their scale in the examples
                 Vec3::ZERO,
-            ),
+            )
+            .with_assets_size(ASSETS_SIZE),
         },
         observer,
         DebugGridView3dBundle {
@@ -209,7 +212,6 @@ fn main() {
         }),
         ProcGenExamplesPlugin::<Cartesian3D, Handle<Scene>, CustomComponents>::new(
             GENERATION_VIEW_MODE,
-            ASSETS_SCALE,
         ),
     ));
     app.add_systems(Startup, (setup_generator, setup_scene))
@@ -36,8 +36,8 @@ const GRID_Z: u32 = 70;
 const BLOCK_SIZE: f32 = 1.;
 const NODE_SIZE: Vec3 = Vec3::splat(BLOCK_SIZE);
 
-const ASSETS_SCALE_FACTOR: f32 = BLOCK_SIZE / 4.; // Models are 4 units wide
-const ASSETS_SCALE: Vec3 = Vec3::splat(ASSETS_SCALE_FACTOR);
+/// Size of a grid node in the units of the models: they are 4 units wide
+const ASSETS_SIZE: Vec3 = Vec3::splat(4.);
 
 fn setup_scene(
     mut commands: Commands,
@@ -139,7 +139,8 @@ fn setup_generator(mut commands: Commands, asset_server: Res<AssetServer>) {
         NODE_SIZE,
         // We spawn assets with a scale of 0 since we animate their scale in the examples
         Vec3::ZERO,
-    );
+    )
+    .with_assets_size(ASSETS_SIZE);
 
     for i in 0..=1 {
         let mut gen_builder = gen_builder.clone();
@@ -177,10 +178,7 @@ fn main() {
             level: bevy::log::Level::DEBUG,
             ..default()
         }),
-        ProcGenExamplesPlugin::<Cartesian3D, Handle<Scene>>::new(
-            GENERATION_VIEW_MODE,
-            ASSETS_SCALE,
-        ),
+        ProcGenExamplesPlugin::<Cartesian3D, Handle<Scene>>::new(GENERATION_VIEW_MODE),
     ));
     app.add_systems(Startup, (setup_generator, setup_scene))
         .add_systems(Update, update_pan_orbit_camera);
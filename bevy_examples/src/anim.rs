@@ -2,12 +2,18 @@ use bevy::{
     ecs::{
         component::Component,
         entity::Entity,
+        query::Added,
         system::{Commands, Query, Res, Resource},
     },
+    hierarchy::Parent,
     math::Vec3,
     time::Time,
     transform::components::Transform,
 };
+use bevy_ghx_proc_gen::gen::{
+    assets::{AssetSpawner, AssetsBundleSpawner, ComponentSpawner},
+    GridNode,
+};
 
 /// Used for the examples
 #[derive(Component, Clone, Resource)]
@@ -49,6 +55,22 @@ impl SpawningScaleAnimation {
     }
 }
 
+/// Inserts a [`SpawningScaleAnimation`] cloned from the `Resource` to every newly spawned node. The final scale of the animation is multiplied by the `assets_scale` of the [`AssetSpawner`] of the node's generation.
+pub fn insert_spawning_scale_animation<A: AssetsBundleSpawner, T: ComponentSpawner>(
+    mut commands: Commands,
+    spawn_animation: Res<SpawningScaleAnimation>,
+    asset_spawners: Query<&AssetSpawner<A, T>>,
+    spawned_nodes: Query<(Entity, &Parent), Added<GridNode>>,
+) {
+    for (node, gen_entity) in spawned_nodes.iter() {
+        let mut animation = spawn_animation.clone();
+        if let Ok(asset_spawner) = asset_spawners.get(gen_entity.get()) {
+            animation.final_scale *= asset_spawner.assets_scale;
+        }
+        commands.entity(node).try_insert(animation);
+    }
+}
+
 pub fn animate_scale(
     mut commands: Commands,
     time: Res<Time>,
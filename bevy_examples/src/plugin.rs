@@ -40,18 +40,19 @@ use bevy_ghx_proc_gen::{
         assets::{AssetsBundleSpawner, ComponentSpawner, NoComponents},
         debug_plugin::{
             cursor::{CursorsOverlaysRoot, CursorsPanelRoot},
+            egui_control::toggle_control_panel,
             egui_editor::{paint, toggle_editor, update_painting_state, EditorContext},
+            generation::ActiveGeneration,
             CursorUiMode, GenerationControl, GenerationControlStatus, GenerationViewMode,
             ProcGenDebugPlugin,
         },
-        insert_bundle_from_resource_to_spawned_nodes,
     },
 };
 use bevy_ghx_utils::{camera::toggle_auto_orbit, systems::toggle_visibility};
 use bevy_mod_picking::{picking_core::Pickable, DefaultPickingPlugins};
 
 use crate::{
-    anim::{animate_scale, ease_in_cubic, SpawningScaleAnimation},
+    anim::{animate_scale, ease_in_cubic, insert_spawning_scale_animation, SpawningScaleAnimation},
     fps::{FpsDisplayPlugin, FpsRoot},
 };
 
@@ -61,17 +62,15 @@ pub struct ProcGenExamplesPlugin<
     T: ComponentSpawner = NoComponents,
 > {
     generation_view_mode: GenerationViewMode,
-    assets_scale: Vec3,
     typestate: PhantomData<(C, A, T)>,
 }
 
 impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner>
     ProcGenExamplesPlugin<C, A, T>
 {
-    pub fn new(generation_view_mode: GenerationViewMode, assets_scale: Vec3) -> Self {
+    pub fn new(generation_view_mode: GenerationViewMode) -> Self {
         Self {
             generation_view_mode,
-            assets_scale,
             typestate: PhantomData,
         }
     }
@@ -94,20 +93,22 @@ impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> Plugin
         ));
         app.insert_resource(SpawningScaleAnimation::new(
             DEFAULT_SPAWN_ANIMATION_DURATION,
-            self.assets_scale,
+            // Multiplied by the assets scale of each generation
+            Vec3::ONE,
             ease_in_cubic,
         ));
         app.add_systems(Startup, (setup_ui, customize_markers_gizmos_config));
         app.add_systems(
             Update,
             (
-                insert_bundle_from_resource_to_spawned_nodes::<SpawningScaleAnimation>,
+                insert_spawning_scale_animation::<A, T>,
                 animate_scale,
                 (
                     toggle_visibility::<ExamplesUiRoot>,
                     toggle_visibility::<CursorsPanelRoot>,
                     toggle_visibility::<CursorsOverlaysRoot>,
                     toggle_editor,
+                    toggle_control_panel,
                 )
                     .run_if(input_just_pressed(KeyCode::F1)),
                 toggle_visibility::<FpsRoot>.run_if(input_just_pressed(KeyCode::F2)),
@@ -157,7 +158,7 @@ pub struct ExamplesUiRoot;
 #[derive(Component)]
 pub struct GenerationControlText;
 
-pub fn setup_ui(mut commands: Commands, view_mode: Res<GenerationViewMode>) {
+pub fn setup_ui(mut commands: Commands) {
     let ui_root = commands
         .spawn((
             ExamplesUiRoot,
@@ -172,7 +173,7 @@ pub fn setup_ui(mut commands: Commands, view_mode: Res<GenerationViewMode>) {
             Pickable::IGNORE,
         ))
         .id();
-    let mut keybindings_text = "Toggles:\n\
+    let keybindings_text = "Toggles:\n\
         'F1' Show/hide UI\n\
         'F2' Show/hide fps\n\
         'F3' Show/hide grid\n\
@@ -183,16 +184,12 @@ pub fn setup_ui(mut commands: Commands, view_mode: Res<GenerationViewMode>) {
        'Click' Select\n\
        'x/y/z'+'Left/Right' Move selection\n\
        'Esc' Deselect\n\
-       'Tab' Switch active grid\n"
-        .to_string();
-
-    if *view_mode == GenerationViewMode::StepByStepManual {
-        keybindings_text.push_str(
-            "\nGeneration:\n\
-            'Down' Generate 1 step\n\
-            'Up' Generate while pressed",
-        );
-    }
+       'Tab' Switch active grid\n\
+        \n\
+        Generation:\n\
+       'R' Regenerate with a new seed\n\
+       'Down' Generate 1 step (StepByStepManual)\n\
+       'Up' Generate while pressed (StepByStepManual)";
     let keybindings_ui_background = commands
         .spawn((
             Pickable::IGNORE,
@@ -263,13 +260,10 @@ pub fn setup_ui(mut commands: Commands, view_mode: Res<GenerationViewMode>) {
                             font_size: DEFAULT_EXAMPLES_FONT_SIZE,
                             ..Default::default()
                         }),
-                        TextSection::new(
-                            format!("\nGenerationViewMode: {:?}", *view_mode),
-                            TextStyle {
-                                font_size: DEFAULT_EXAMPLES_FONT_SIZE,
-                                ..Default::default()
-                            },
-                        ),
+                        TextSection::from_style(TextStyle {
+                            font_size: DEFAULT_EXAMPLES_FONT_SIZE,
+                            ..Default::default()
+                        }),
                     ],
                     linebreak_behavior: BreakLineOn::NoWrap,
                     ..default()
@@ -292,9 +286,17 @@ pub const GENERATION_CONTROL_TEXT_SECTION_ID: usize = 2;
 pub const GENERATION_VIEW_MODE_TEXT_SECTION_ID: usize = 3;
 
 pub fn update_generation_control_ui(
-    gen_control: Res<GenerationControl>,
+    active_generation: Res<ActiveGeneration>,
+    generation_controls: Query<&GenerationControl>,
+    view_mode: Res<GenerationViewMode>,
     mut query: Query<&mut Text, With<GenerationControlText>>,
 ) {
+    let Some(gen_control) = active_generation
+        .0
+        .and_then(|gen_entity| generation_controls.get(gen_entity).ok())
+    else {
+        return;
+    };
     for mut text in &mut query {
         let status_section = &mut text.sections[GENERATION_CONTROL_STATUS_TEXT_SECTION_ID];
         (status_section.value, status_section.style.color) = match gen_control.status {
@@ -312,6 +314,9 @@ pub fn update_generation_control_ui(
             gen_control.pause_on_error,
             gen_control.pause_on_reinitialize
         );
+
+        let view_mode_section = &mut text.sections[GENERATION_VIEW_MODE_TEXT_SECTION_ID];
+        view_mode_section.value = format!("\nGenerationViewMode: {:?}", *view_mode);
     }
 }
 
@@ -1,13 +1,16 @@
+use std::sync::Arc;
+
 use bevy::{
     asset::{Asset, AssetServer, Handle},
     ecs::system::Res,
-    math::Vec3,
+    math::{Quat, Vec3},
 };
 
 use bevy_ghx_proc_gen::{
     bevy_ghx_grid::ghx_grid::direction::GridDelta,
     gen::assets::{
-        AssetsBundleSpawner, ComponentSpawner, ModelAsset, NoComponents, RulesModelsAssets,
+        AssetsBundleSpawner, ComponentSpawner, ModelAsset, NeighbourCondition, NoComponents,
+        RulesModelsAssets, SpawnEffect,
     },
 };
 
@@ -17,7 +20,11 @@ pub struct AssetDef<T = NoComponents> {
     path: &'static str,
     grid_offset: GridDelta,
     offset: Vec3,
+    scale: Vec3,
+    rotation: Quat,
+    conditions: Vec<NeighbourCondition>,
     components: Vec<T>,
+    spawn_effects: Vec<Arc<dyn SpawnEffect>>,
 }
 
 impl<T> AssetDef<T> {
@@ -26,7 +33,11 @@ impl<T> AssetDef<T> {
             path,
             grid_offset: GridDelta::new(0, 0, 0),
             offset: Vec3::ZERO,
+            scale: Vec3::ONE,
+            rotation: Quat::IDENTITY,
+            conditions: Vec::new(),
             components: Vec::new(),
+            spawn_effects: Vec::new(),
         }
     }
 
@@ -35,16 +46,41 @@ impl<T> AssetDef<T> {
         self
     }
 
+    /// Offset of the asset in its own units, multiplied with the assets scale of the generation
     pub fn with_offset(mut self, offset: Vec3) -> Self {
         self.offset = offset;
         self
     }
 
+    /// Scale of the asset, multiplied with the spawn scale of the generation
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Rotation of the asset, applied before the rotation of its model
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Only spawns the asset if `condition` on the neighbours of the node is met
+    pub fn with_condition(mut self, condition: NeighbourCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
     pub fn with_component(mut self, component: T) -> Self {
         self.components.push(component);
         self
     }
 
+    /// Triggers `effect` (a sound, some particles, ...) each time the asset is spawned
+    pub fn with_spawn_effect(mut self, effect: impl SpawnEffect) -> Self {
+        self.spawn_effects.push(Arc::new(effect));
+        self
+    }
+
     pub fn path(&self) -> &'static str {
         self.path
     }
@@ -69,13 +105,17 @@ where
             models_assets.add(
                 model_index,
                 ModelAsset {
-                    assets_bundle: asset_server.load(format!(
-                        "{assets_directory}/{}.{extension}",
-                        asset_def.path()
-                    )),
                     grid_offset: asset_def.grid_offset.clone(),
                     offset: asset_def.offset,
+                    scale: asset_def.scale,
+                    rotation: asset_def.rotation,
+                    conditions: asset_def.conditions.clone(),
                     components: asset_def.components.clone(),
+                    spawn_effects: asset_def.spawn_effects.clone(),
+                    ..ModelAsset::new(asset_server.load(format!(
+                        "{assets_directory}/{}.{extension}",
+                        asset_def.path()
+                    )))
                 },
             )
         }
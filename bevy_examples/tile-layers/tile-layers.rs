@@ -55,8 +55,6 @@ const TILE_SIZE: f32 = 32.;
 /// Size of a grid node in world units
 const NODE_SIZE: Vec3 = Vec3::new(TILE_SIZE, TILE_SIZE, 1.);
 
-const ASSETS_SCALE: Vec3 = Vec3::ONE;
-
 /// Number of z layers in the map, do not change without adapting the rules.
 const GRID_Z: u32 = 5;
 
@@ -117,10 +115,7 @@ fn main() {
                 ..default()
             })
             .set(ImagePlugin::default_nearest()),
-        ProcGenExamplesPlugin::<Cartesian3D, Handle<Image>>::new(
-            GENERATION_VIEW_MODE,
-            ASSETS_SCALE,
-        ),
+        ProcGenExamplesPlugin::<Cartesian3D, Handle<Image>>::new(GENERATION_VIEW_MODE),
     ));
     app.add_systems(Startup, (setup_generator, setup_scene));
     app.run();
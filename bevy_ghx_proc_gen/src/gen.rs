@@ -5,21 +5,52 @@ use bevy::{
         entity::Entity,
         query::Added,
         system::{Commands, Query, Res, Resource},
+        world::EntityWorldMut,
     },
     hierarchy::BuildChildren,
-    math::Vec3,
+    math::{Quat, Vec3},
+    transform::components::Transform,
+};
+use bevy_ghx_grid::ghx_grid::{
+    coordinate_system::CoordinateSystem,
+    direction::Direction,
+    grid::{GridData, GridDefinition, GridPosition},
+};
+use ghx_proc_gen::{
+    generator::{model::ModelInstance, GeneratedNode, Generator},
+    NodeIndex,
 };
-use bevy_ghx_grid::ghx_grid::{coordinate_system::CoordinateSystem, grid::GridDefinition};
-use ghx_proc_gen::{generator::model::ModelInstance, NodeIndex};
 
-use self::assets::{AssetSpawner, AssetsBundleSpawner, ComponentSpawner};
+use self::assets::{AssetSpawner, AssetsBundleSpawner, ComponentSpawner, ModelAsset};
 
 /// Types to define and spawn assets
 pub mod assets;
+/// Gates the generations until the assets of their [`AssetSpawner`] are loaded
+#[cfg(feature = "assets-preloading")]
+pub mod assets_preloading;
+
+/// Spawns named scenes, nodes or meshes of a GLTF file, to use tile kits packed in a single file
+#[cfg(feature = "gltf-sub-assets")]
+pub mod gltf_sub_assets;
+
+/// Bevy events sent by the plugins when a generation starts, succeeds or fails, so that game code does not need to poll the generators
+pub mod lifecycle;
+/// Live minimap image of the generations, see [`minimap::GenerationMinimap`]
+#[cfg(feature = "minimap")]
+pub mod minimap;
+/// Records the generations as timelines, and plays them back, see [`recording::GenerationRecorder`]
+pub mod recording;
+/// Replaces the spawned assets of generated nodes by some assets variants, see [`replacement::ReplaceNodeAsset`]
+pub mod replacement;
+/// Runs generations on the [`bevy::tasks::AsyncComputeTaskPool`] instead of the main schedule, see [`tasks::AsyncGeneration`]
+pub mod tasks;
 
 /// Debug plugin to run the generation & spawn assets automatically with different visualization options
 #[cfg(feature = "debug-plugin")]
 pub mod debug_plugin;
+/// Headless plugin to run the generation without spawning anything, results are sent as events
+#[cfg(feature = "headless-plugin")]
+pub mod headless_plugin;
 /// Simple plugin to run the generation & spawn assets automatically
 #[cfg(feature = "simple-plugin")]
 pub mod simple_plugin;
@@ -30,10 +61,90 @@ pub mod simple_plugin;
 #[cfg(feature = "default-assets-bundle-spawners")]
 pub mod default_bundles;
 
+/// Query filter matching the generation entities whose assets are loaded, see [`assets_preloading`]. Matches every entity when the `assets-preloading` feature is disabled.
+#[cfg(feature = "assets-preloading")]
+pub type AssetsReady = bevy::ecs::query::Without<assets_preloading::PendingAssets>;
+/// Query filter matching the generation entities whose assets are loaded. Matches every entity when the `assets-preloading` feature is disabled.
+#[cfg(not(feature = "assets-preloading"))]
+pub type AssetsReady = ();
+
 /// Used to mark a node spawned by a [`ghx_proc_gen::generator::Generator`]. Stores the [NodeIndex] of this node
 #[derive(Component)]
 pub struct GridNode(pub NodeIndex);
 
+/// Maps the nodes of a grid to translations, in the local space of the grid entity: the node `(x, y, z)` covers the box from `(x, y, z) * node_size` to `(x + 1, y + 1, z + 1) * node_size`.
+///
+/// Used by [`spawn_node`] (from [`AssetSpawner::node_size`]) and by the cursors, raycasts and labels of the debug plugin (from the `node_size` of the grid `DebugGridView`), so that all of them agree on where a node is. Can be inserted as a `Component` on a grid entity to share the node size with custom systems.
+///
+/// The markers of `bevy_ghx_grid` are placed by its own `get_translation_from_grid_coords_3d`, which follows the same convention:
+///
+/// ```
+/// # #[cfg(feature = "debug-plugin")]
+/// # {
+/// use bevy::math::Vec3;
+/// use bevy_ghx_proc_gen::{
+///     bevy_ghx_grid::{
+///         debug_plugin::get_translation_from_grid_coords_3d, ghx_grid::grid::GridPosition,
+///     },
+///     gen::GridWorldTransform,
+/// };
+///
+/// let node_size = Vec3::new(1., 2., 3.);
+/// let world_transform = GridWorldTransform::new(node_size);
+/// for (x, y, z) in [(0, 0, 0), (3, 1, 2), (7, 0, 5)] {
+///     assert_eq!(
+///         world_transform.node_to_world(&GridPosition::new(x, y, z)),
+///         get_translation_from_grid_coords_3d(x, y, z, &node_size)
+///     );
+/// }
+/// # }
+/// ```
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct GridWorldTransform {
+    /// Size of a node in world units
+    pub node_size: Vec3,
+}
+
+impl GridWorldTransform {
+    /// Creates a [`GridWorldTransform`] with nodes of size `node_size`
+    pub fn new(node_size: Vec3) -> Self {
+        Self { node_size }
+    }
+
+    /// Returns the translation of the center of the node at `pos`
+    pub fn node_to_world(&self, pos: &GridPosition) -> Vec3 {
+        self.coords_to_world(pos.x as f32, pos.y as f32, pos.z as f32)
+    }
+
+    /// Returns the translation of the center of the node at the (possibly out of the grid) coordinates `(x, y, z)`
+    pub fn coords_to_world(&self, x: f32, y: f32, z: f32) -> Vec3 {
+        // + 0.5 to center the translation in the node
+        self.node_size * (Vec3::new(x, y, z) + 0.5)
+    }
+
+    /// Returns the position of the node of `grid` containing `translation`, or `None` if `translation` is out of the grid
+    pub fn world_to_node<C: CoordinateSystem>(
+        &self,
+        grid: &GridDefinition<C>,
+        translation: Vec3,
+    ) -> Option<GridPosition> {
+        let coords = (translation / self.node_size).floor();
+        let size = Vec3::new(
+            grid.size_x() as f32,
+            grid.size_y() as f32,
+            grid.size_z() as f32,
+        );
+        if coords.cmplt(Vec3::ZERO).any() || coords.cmpge(size).any() {
+            return None;
+        }
+        Some(GridPosition::new(
+            coords.x as u32,
+            coords.y as u32,
+            coords.z as u32,
+        ))
+    }
+}
+
 /// Utility system. Adds a [`Bundle`] (or a [`Component`]) to every [`Entity`] that has [`GridNode`] Component (this is the case of nodes spawned by the `spawn_node` system). The `Bundle` will have its default value.
 ///
 /// ### Example
@@ -100,7 +211,9 @@ pub fn insert_bundle_from_resource_to_spawned_nodes<B: Bundle + Resource + Clone
 
 /// Utility system to spawn grid nodes. Can work for multiple asset types.
 ///
-/// Used by [`simple_plugin::ProcGenSimplePlugin`] and [`debug_plugin::ProcGenDebugPlugin`] to spawn assets automatically.
+/// The conditions of the [`assets::ModelAsset`] (see [`assets::NeighbourCondition`]) are checked as if no neighbour was generated: use [`spawn_node_with_neighbours`] to check them against the generated neighbours.
+///
+/// Returns the spawned [`GridNode`] entities, one per spawned asset.
 ///
 /// ### Examples
 ///
@@ -119,39 +232,132 @@ pub fn spawn_node<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawn
     asset_spawner: &AssetSpawner<A, T>,
     instance: &ModelInstance,
     node_index: NodeIndex,
-) {
+) -> Vec<Entity> {
+    spawn_node_with_neighbours(
+        commands,
+        gen_entity,
+        grid,
+        asset_spawner,
+        instance,
+        node_index,
+        |_| None,
+    )
+}
+
+/// Same as [`spawn_node`], but only spawns the [`assets::ModelAsset`] whose [`assets::NeighbourCondition`] are met.
+///
+/// Used by [`simple_plugin::ProcGenSimplePlugin`] and [`debug_plugin::ProcGenDebugPlugin`] to spawn assets automatically.
+///
+/// `neighbour` returns the model generated on the neighbour of the node in a direction, if any. It is only called for the [`assets::ModelAsset`] with some [`assets::NeighbourCondition`].
+pub fn spawn_node_with_neighbours<
+    C: CoordinateSystem,
+    A: AssetsBundleSpawner,
+    T: ComponentSpawner,
+>(
+    commands: &mut Commands,
+    gen_entity: Entity,
+    grid: &GridDefinition<C>,
+    asset_spawner: &AssetSpawner<A, T>,
+    instance: &ModelInstance,
+    node_index: NodeIndex,
+    neighbour: impl Fn(Direction) -> Option<ModelInstance>,
+) -> Vec<Entity> {
     let node_assets = match asset_spawner.assets.get(&instance.model_index) {
         Some(node_assets) => node_assets,
-        None => return,
+        None => return Vec::new(),
     };
+    spawn_node_assets(
+        commands,
+        gen_entity,
+        grid,
+        asset_spawner,
+        node_assets,
+        &GeneratedNode {
+            node_index,
+            model_instance: *instance,
+        },
+        neighbour,
+    )
+}
 
-    let pos = grid.pos_from_index(node_index);
+/// Same as [`spawn_node_with_neighbours`] for the generated `node`, but spawns the given `node_assets` instead of the default assets of its model. Used to spawn an assets variant, see [`replacement::ReplaceNodeAsset`].
+pub fn spawn_node_assets<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner>(
+    commands: &mut Commands,
+    gen_entity: Entity,
+    grid: &GridDefinition<C>,
+    asset_spawner: &AssetSpawner<A, T>,
+    node_assets: &[ModelAsset<A, T>],
+    node: &GeneratedNode,
+    neighbour: impl Fn(Direction) -> Option<ModelInstance>,
+) -> Vec<Entity> {
+    let pos = grid.pos_from_index(node.node_index);
+    let mut node_entities = Vec::with_capacity(node_assets.len());
     for node_asset in node_assets {
-        let offset = &node_asset.offset;
-        let grid_offset = &node_asset.grid_offset;
-        // + (0.5 * size) to center `translation` in the node
-        let mut translation = Vec3::new(
-            offset.x + asset_spawner.node_size.x * (pos.x as f32 + grid_offset.dx as f32 + 0.5),
-            offset.y + asset_spawner.node_size.y * (pos.y as f32 + grid_offset.dy as f32 + 0.5),
-            offset.z + asset_spawner.node_size.z * (pos.z as f32 + grid_offset.dz as f32 + 0.5),
-        );
-
-        if asset_spawner.z_offset_from_y {
-            translation.z += asset_spawner.node_size.z * (1. - pos.y as f32 / grid.size_y() as f32);
+        if !node_asset
+            .conditions
+            .iter()
+            .all(|condition| condition.is_met(&neighbour))
+        {
+            continue;
         }
+        let translation = asset_spawner.model_asset_translation(node_asset, &pos, grid.size_y());
 
-        let node_entity = commands.spawn(GridNode(node_index)).id();
+        let node_entity = commands.spawn(GridNode(node.node_index)).id();
 
         let node_entity_commands = &mut commands.entity(node_entity);
         node_asset.assets_bundle.insert_bundle(
             node_entity_commands,
             translation,
-            asset_spawner.spawn_scale,
-            instance.rotation,
+            asset_spawner.asset_scale(node_asset),
+            node.model_instance.rotation,
         );
+        let rotation_offset = asset_spawner.asset_rotation(node_asset);
+        if rotation_offset != Quat::IDENTITY {
+            node_entity_commands.add(move |mut entity: EntityWorldMut| {
+                if let Some(mut transform) = entity.get_mut::<Transform>() {
+                    transform.rotation *= rotation_offset;
+                }
+            });
+        }
         for component in node_asset.components.iter() {
             component.insert(node_entity_commands);
         }
         commands.entity(gen_entity).add_child(node_entity);
+        for effect in node_asset.spawn_effects.iter() {
+            effect.trigger(commands, node_entity, translation);
+        }
+        node_entities.push(node_entity);
+    }
+    node_entities
+}
+
+/// Returns a function giving the model generated on the neighbour of `node_index` in a direction, from a generation output. To be used with [`spawn_node_with_neighbours`].
+pub fn grid_data_neighbours<C: CoordinateSystem>(
+    grid_data: &GridData<C, ModelInstance>,
+    node_index: NodeIndex,
+) -> impl Fn(Direction) -> Option<ModelInstance> + '_ {
+    let position = grid_data.grid().pos_from_index(node_index);
+    move |direction| {
+        grid_data
+            .grid()
+            .get_next_index_in_direction(&position, direction)
+            .map(|neighbour_index| *grid_data.get(neighbour_index))
+    }
+}
+
+/// Returns a function giving the model generated on the neighbour of `node_index` in a direction, from the current state of an ongoing generation: only the neighbours with a single possible model are generated. To be used with [`spawn_node_with_neighbours`].
+pub fn generator_neighbours<C: CoordinateSystem>(
+    generator: &Generator<C>,
+    node_index: NodeIndex,
+) -> impl Fn(Direction) -> Option<ModelInstance> + '_ {
+    let position = generator.grid().pos_from_index(node_index);
+    move |direction| {
+        let neighbour_index = generator
+            .grid()
+            .get_next_index_in_direction(&position, direction)?;
+        match generator.get_models_on(neighbour_index).as_slice() {
+            [model_instance] => Some(*model_instance),
+            _ => None,
+        }
     }
 }
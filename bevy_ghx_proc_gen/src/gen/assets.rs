@@ -1,16 +1,34 @@
 use std::{
     collections::HashMap,
+    f32::consts::FRAC_PI_2,
+    fmt::{self, Debug},
     ops::{Deref, DerefMut},
     sync::Arc,
 };
 
+#[cfg(feature = "assets-preloading")]
+use bevy::asset::UntypedHandle;
 use bevy::{
-    ecs::{component::Component, system::EntityCommands},
-    math::Vec3,
+    ecs::{
+        bundle::Bundle,
+        component::Component,
+        entity::Entity,
+        system::{Commands, EntityCommands},
+    },
+    hierarchy::BuildChildren,
+    math::{Quat, Vec3},
 };
-use bevy_ghx_grid::ghx_grid::direction::GridDelta;
+use bevy_ghx_grid::ghx_grid::{direction::GridDelta, grid::GridPosition};
 use ghx_proc_gen::generator::model::{ModelIndex, ModelRotation};
 
+use super::GridWorldTransform;
+
+/// Conditions of the [`ModelAsset`] on the models generated on the neighbours of their node, shared with the decorations of [`ghx_proc_gen::decoration`]
+pub use ghx_proc_gen::grid_data::NeighbourCondition;
+
+/// Default value of [`AssetSpawner::node_anchor`]: the assets are anchored at the center of their node
+pub const DEFAULT_NODE_ANCHOR: Vec3 = Vec3::splat(0.5);
+
 /// Defines a struct which can spawn an assets [`bevy::prelude::Bundle`] (for example, a [`bevy::prelude::SpriteBundle`], a [`bevy::prelude::PbrBundle`], a [`bevy::prelude::SceneBundle`], ...).
 pub trait AssetsBundleSpawner: Sync + Send + 'static {
     /// From the `AssetsBundleSpawner` own data, a position, a scale and a rotation, inserts a [`bevy::prelude::Bundle`] into the spawned node `Entity`
@@ -21,6 +39,12 @@ pub trait AssetsBundleSpawner: Sync + Send + 'static {
         scale: Vec3,
         rotation: ModelRotation,
     );
+
+    /// Returns the handles of the assets used by this `AssetsBundleSpawner`, so that the plugins can wait for them to be loaded before spawning anything. Returns no handles by default.
+    #[cfg(feature = "assets-preloading")]
+    fn untyped_handles(&self) -> Vec<UntypedHandle> {
+        Vec::new()
+    }
 }
 
 /// Trait used to represent a generic [`Component`]/[`bevy::prelude::Bundle`] container.
@@ -40,10 +64,39 @@ impl ComponentSpawner for NoComponents {
     fn insert(&self, _commands: &mut EntityCommands) {}
 }
 
+/// One-shot effect (a sound, some particles, ...) triggered each time a [`ModelAsset`] is spawned, see [`ModelAsset::spawn_effects`]. Useful for step-by-step demos or in-game construction effects, without wrapping the spawn systems.
+pub trait SpawnEffect: Sync + Send + 'static {
+    /// Triggers the effect for `node_entity`, which was just spawned with its assets at `translation` (in the local space of the generation entity)
+    fn trigger(&self, commands: &mut Commands, node_entity: Entity, translation: Vec3);
+}
+
+/// [`SpawnEffect`] spawning a clone of a [`Bundle`] as a new child entity of the spawned node.
+///
+/// The effect entity is independent of the node assets: it can despawn itself once finished without despawning the node, for example an `AudioBundle` with `PlaybackSettings::DESPAWN` for a one-shot sound.
+///
+/// ### Example
+///
+/// ```ignore
+/// let effect = EffectBundle(AudioBundle {
+///     source: asset_server.load("sounds/place_block.ogg"),
+///     settings: PlaybackSettings::DESPAWN,
+/// });
+/// model_asset.spawn_effects.push(Arc::new(effect));
+/// ```
+#[derive(Clone, Debug)]
+pub struct EffectBundle<B: Bundle + Clone>(pub B);
+
+impl<B: Bundle + Clone> SpawnEffect for EffectBundle<B> {
+    fn trigger(&self, commands: &mut Commands, node_entity: Entity, _translation: Vec3) {
+        let effect_entity = commands.spawn(self.0.clone()).id();
+        commands.entity(node_entity).add_child(effect_entity);
+    }
+}
+
 /// Represents spawnable asset(s) & component(s) for a model.
 ///
 /// They will be spawned every time this model is generated. One `ModelAsset` will spawn exactly one [`bevy::prelude::Entity`] (but note that one Model may have more than one `ModelAsset`).
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct ModelAsset<A: AssetsBundleSpawner, T: ComponentSpawner = NoComponents> {
     /// Stores handle(s) to the asset(s) and spawns their bundle
     pub assets_bundle: A,
@@ -51,8 +104,47 @@ pub struct ModelAsset<A: AssetsBundleSpawner, T: ComponentSpawner = NoComponents
     pub components: Vec<T>,
     /// Grid offset from the generated grid node position. Added to `offset`.
     pub grid_offset: GridDelta,
-    /// World offset from the generated grid node position. Added to `grid_offset`.
+    /// Offset from the generated grid node position, in the units of the asset: multiplied with the `assets_scale` of the [`AssetSpawner`]. Added to `grid_offset`.
     pub offset: Vec3,
+    /// Scale of this asset, multiplied with the `spawn_scale` of the [`AssetSpawner`]
+    pub scale: Vec3,
+    /// Rotation offset of this asset, applied in the asset's local space before the model rotation. Useful for assets not authored with the rules rotation axis/orientation.
+    pub rotation: Quat,
+    /// Conditions on the neighbours of the node, used to select the assets spawned for this node (for example, to spawn a "waterfall" variant of a cliff when there is water above it). The asset is only spawned if all of them are met. Always spawned if empty.
+    pub conditions: Vec<NeighbourCondition>,
+    /// One-shot effects triggered each time this asset is spawned, after its bundle and components are inserted
+    pub spawn_effects: Vec<Arc<dyn SpawnEffect>>,
+}
+
+impl<A: AssetsBundleSpawner + Debug, T: ComponentSpawner + Debug> Debug for ModelAsset<A, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ModelAsset")
+            .field("assets_bundle", &self.assets_bundle)
+            .field("components", &self.components)
+            .field("grid_offset", &self.grid_offset)
+            .field("offset", &self.offset)
+            .field("scale", &self.scale)
+            .field("rotation", &self.rotation)
+            .field("conditions", &self.conditions)
+            .field("spawn_effects", &self.spawn_effects.len())
+            .finish()
+    }
+}
+
+impl<A: AssetsBundleSpawner, T: ComponentSpawner> ModelAsset<A, T> {
+    /// Creates a [`ModelAsset`] for `assets_bundle`, with no offsets, scale or rotation, and without components, conditions or spawn effects
+    pub fn new(assets_bundle: A) -> Self {
+        Self {
+            assets_bundle,
+            components: Vec::new(),
+            grid_offset: Default::default(),
+            offset: Vec3::ZERO,
+            scale: Vec3::ONE,
+            rotation: Quat::IDENTITY,
+            conditions: Vec::new(),
+            spawn_effects: Vec::new(),
+        }
+    }
 }
 
 /// Defines a map which links a `Model` via its [`ModelIndex`] to his spawnable(s) [`ModelAsset`]
@@ -60,6 +152,8 @@ pub struct ModelAsset<A: AssetsBundleSpawner, T: ComponentSpawner = NoComponents
 pub struct RulesModelsAssets<A: AssetsBundleSpawner, T: ComponentSpawner = NoComponents> {
     /// Only contains a ModelIndex if there are some assets for it. One model may have multiple [`ModelAsset`].
     map: HashMap<ModelIndex, Vec<ModelAsset<A, T>>>,
+    /// Alternative assets of the models, by variant name, see [`RulesModelsAssets::add_variant`]
+    variants: HashMap<String, HashMap<ModelIndex, Vec<ModelAsset<A, T>>>>,
 }
 impl<A: AssetsBundleSpawner, T: ComponentSpawner> Deref for RulesModelsAssets<A, T> {
     type Target = HashMap<ModelIndex, Vec<ModelAsset<A, T>>>;
@@ -79,23 +173,21 @@ impl<A: AssetsBundleSpawner, T: ComponentSpawner> RulesModelsAssets<A, T> {
     pub fn new() -> Self {
         Self {
             map: Default::default(),
+            variants: Default::default(),
         }
     }
 
     /// Create a new RulesModelsAssets with an existing map
     pub fn new_from_map(map: HashMap<ModelIndex, Vec<ModelAsset<A, T>>>) -> Self {
-        Self { map }
+        Self {
+            map,
+            variants: Default::default(),
+        }
     }
 
     /// Adds a [`ModelAsset`] with no grid offset, to the model `index`
     pub fn add_asset(&mut self, index: ModelIndex, asset: A) {
-        let model_asset = ModelAsset {
-            assets_bundle: asset,
-            grid_offset: Default::default(),
-            offset: Vec3::ZERO,
-            components: Vec::new(),
-        };
-        self.add(index, model_asset);
+        self.add(index, ModelAsset::new(asset));
     }
 
     /// Adds a [`ModelAsset`] to the model `index`
@@ -109,6 +201,67 @@ impl<A: AssetsBundleSpawner, T: ComponentSpawner> RulesModelsAssets<A, T> {
             }
         }
     }
+
+    /// Adds a [`ModelAsset`] to the `variant` assets of the model `index`: an alternative set of assets (damaged, seasonal, ...) which can replace the default assets of a spawned node, see [`super::replacement::ReplaceNodeAsset`]
+    pub fn add_variant(
+        &mut self,
+        variant: impl Into<String>,
+        index: ModelIndex,
+        model_asset: ModelAsset<A, T>,
+    ) {
+        self.variants
+            .entry(variant.into())
+            .or_default()
+            .entry(index)
+            .or_default()
+            .push(model_asset);
+    }
+
+    /// Returns the `variant` assets of the model `index`, if any
+    pub fn variant_assets(
+        &self,
+        variant: &str,
+        index: ModelIndex,
+    ) -> Option<&Vec<ModelAsset<A, T>>> {
+        self.variants.get(variant)?.get(&index)
+    }
+
+    /// Returns the assets of all the models, including all the variants
+    pub fn all_assets(&self) -> impl Iterator<Item = &ModelAsset<A, T>> {
+        self.map
+            .values()
+            .chain(self.variants.values().flat_map(|variant| variant.values()))
+            .flatten()
+    }
+
+    /// Returns `true` if some assets of the model `index` have [`NeighbourCondition`]: they depend on the models generated on the neighbours of the node
+    pub fn has_conditions(&self, index: ModelIndex) -> bool {
+        self.map.get(&index).is_some_and(|model_assets| {
+            model_assets
+                .iter()
+                .any(|model_asset| !model_asset.conditions.is_empty())
+        })
+    }
+}
+
+/// Up axis the assets were authored with, see [`AssetSpawner::with_assets_up_axis`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AssetsUpAxis {
+    /// Y+ is up in the assets, as in the grid: the assets are not corrected
+    #[default]
+    Y,
+    /// Z+ is up in the assets (as in Blender for example): the assets are rotated so that their Z+ axis points towards the Y+ axis of the grid
+    Z,
+}
+
+impl AssetsUpAxis {
+    /// Returns the rotation mapping the up axis of the assets to the Y+ axis of the grid, applied in the asset's local space
+    pub fn rotation(&self) -> Quat {
+        match self {
+            AssetsUpAxis::Y => Quat::IDENTITY,
+            AssetsUpAxis::Z => Quat::from_rotation_x(-FRAC_PI_2),
+        }
+    }
 }
 
 /// Stores information needed to spawn assets from a [`ghx_proc_gen::generator::Generator`]
@@ -122,10 +275,16 @@ pub struct AssetSpawner<A: AssetsBundleSpawner, T: ComponentSpawner = NoComponen
     pub spawn_scale: Vec3,
     /// Whether to offset the z coordinate of spawned nodes from the y coordinate (used for 2d ordering of sprites)
     pub z_offset_from_y: bool,
+    /// Scale factor fitting the assets to a node, multiplied with `spawn_scale`. For example `Vec3::splat(0.25)` for models 4 units wide in nodes of size 1.
+    pub assets_scale: Vec3,
+    /// Position of the origin of the assets inside their node, from `(0, 0, 0)` (the corner of the node with the lowest coordinates) to `(1, 1, 1)`
+    pub node_anchor: Vec3,
+    /// Up axis the assets were authored with
+    pub assets_up_axis: AssetsUpAxis,
 }
 
 impl<A: AssetsBundleSpawner, T: ComponentSpawner> AssetSpawner<A, T> {
-    /// Constructor for a `AssetSpawner`, `z_offset_from_y` defaults to `false`
+    /// Constructor for a `AssetSpawner`, `z_offset_from_y` defaults to `false`, the assets are not scaled (`assets_scale` of `Vec3::ONE`), anchored at the center of their node and authored with Y+ as their up axis
     pub fn new(
         models_assets: RulesModelsAssets<A, T>,
         node_size: Vec3,
@@ -136,12 +295,89 @@ impl<A: AssetsBundleSpawner, T: ComponentSpawner> AssetSpawner<A, T> {
             assets: Arc::new(models_assets),
             spawn_scale,
             z_offset_from_y: false,
+            assets_scale: Vec3::ONE,
+            node_anchor: DEFAULT_NODE_ANCHOR,
+            assets_up_axis: AssetsUpAxis::Y,
         }
     }
 
+    /// Returns the [`GridWorldTransform`] used to place the spawned assets
+    pub fn world_transform(&self) -> GridWorldTransform {
+        GridWorldTransform::new(self.node_size)
+    }
+
     /// Sets the `z_offset_from_y` value
     pub fn with_z_offset_from_y(mut self, z_offset_from_y: bool) -> Self {
         self.z_offset_from_y = z_offset_from_y;
         self
     }
+
+    /// Sets the scale factor fitting the assets to a node, see [`AssetSpawner::assets_scale`]
+    pub fn with_assets_scale(mut self, assets_scale: Vec3) -> Self {
+        self.assets_scale = assets_scale;
+        self
+    }
+
+    /// Sets the scale factor fitting the assets to a node from the size of a node in the units of the assets: `assets_scale` is `node_size / assets_size`. For example `Vec3::splat(4.)` for models 4 units wide.
+    pub fn with_assets_size(mut self, assets_size: Vec3) -> Self {
+        self.assets_scale = self.node_size / assets_size;
+        self
+    }
+
+    /// Sets the position of the origin of the assets inside their node. Defaults to the center of the node, [`DEFAULT_NODE_ANCHOR`]. For example `Vec3::new(0.5, 0., 0.5)` for 3d assets whose origin is at their base.
+    pub fn with_node_anchor(mut self, node_anchor: Vec3) -> Self {
+        self.node_anchor = node_anchor;
+        self
+    }
+
+    /// Sets the up axis the assets were authored with. Defaults to [`AssetsUpAxis::Y`].
+    pub fn with_assets_up_axis(mut self, assets_up_axis: AssetsUpAxis) -> Self {
+        self.assets_up_axis = assets_up_axis;
+        self
+    }
+
+    /// Returns the translation of an asset spawned on the (possibly out of the grid) node coordinates `(x, y, z)`, in the local space of the grid entity, taking the `node_anchor` into account
+    pub fn asset_translation(&self, x: f32, y: f32, z: f32) -> Vec3 {
+        self.world_transform().coords_to_world(x, y, z)
+            + self.node_size * (self.node_anchor - DEFAULT_NODE_ANCHOR)
+    }
+
+    /// Returns the translation of `model_asset` spawned on the node at `pos` of a grid with `size_y` layers, in the local space of the grid entity: its `grid_offset` and its `offset` (multiplied with the `assets_scale`) are applied, as well as the `z_offset_from_y`
+    pub fn model_asset_translation(
+        &self,
+        model_asset: &ModelAsset<A, T>,
+        pos: &GridPosition,
+        size_y: u32,
+    ) -> Vec3 {
+        let grid_offset = &model_asset.grid_offset;
+        let mut translation = self.assets_scale * model_asset.offset
+            + self.asset_translation(
+                pos.x as f32 + grid_offset.dx as f32,
+                pos.y as f32 + grid_offset.dy as f32,
+                pos.z as f32 + grid_offset.dz as f32,
+            );
+        if self.z_offset_from_y {
+            translation.z += self.node_size.z * (1. - pos.y as f32 / size_y as f32);
+        }
+        translation
+    }
+
+    /// Returns the scale of `model_asset` when spawned
+    pub fn asset_scale(&self, model_asset: &ModelAsset<A, T>) -> Vec3 {
+        self.spawn_scale * self.assets_scale * model_asset.scale
+    }
+
+    /// Returns the rotation of `model_asset` in its local space, applied before the model rotation: its `rotation` after the up axis correction
+    pub fn asset_rotation(&self, model_asset: &ModelAsset<A, T>) -> Quat {
+        model_asset.rotation * self.assets_up_axis.rotation()
+    }
+
+    /// Returns the handles of all the assets of all the models, see [`AssetsBundleSpawner::untyped_handles`]
+    #[cfg(feature = "assets-preloading")]
+    pub fn untyped_handles(&self) -> Vec<UntypedHandle> {
+        self.assets
+            .all_assets()
+            .flat_map(|model_asset| model_asset.assets_bundle.untyped_handles())
+            .collect()
+    }
 }
@@ -0,0 +1,64 @@
+use bevy::{
+    asset::{AssetServer, LoadState, UntypedHandle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventWriter},
+        query::{Added, With},
+        system::{Commands, Query, Res},
+    },
+    log::warn,
+};
+
+use super::assets::{AssetSpawner, AssetsBundleSpawner, ComponentSpawner};
+
+/// Event sent when all the assets of the [`AssetSpawner`] of a generation entity are loaded. Sent once per generation entity, even if its assets were already loaded.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GenerationAssetsReady(pub Entity);
+
+/// Component inserted on a generation entity while some assets of its [`AssetSpawner`] are still loading. The plugins do not step, generate or spawn a generation entity with this component.
+#[derive(Component, Debug)]
+pub struct PendingAssets(pub Vec<UntypedHandle>);
+
+/// System used to insert a [`PendingAssets`] component with the handles of the [`AssetSpawner`] of new generation entities
+pub fn insert_pending_assets_to_new_generations<A: AssetsBundleSpawner, T: ComponentSpawner>(
+    mut commands: Commands,
+    new_asset_spawners: Query<(Entity, &AssetSpawner<A, T>), Added<AssetSpawner<A, T>>>,
+) {
+    for (gen_entity, asset_spawner) in new_asset_spawners.iter() {
+        commands
+            .entity(gen_entity)
+            .insert(PendingAssets(asset_spawner.untyped_handles()));
+    }
+}
+
+/// System used to remove the [`PendingAssets`] component of generation entities once all their assets are loaded (with their dependencies), and to send a [`GenerationAssetsReady`] event.
+///
+/// Handles not loaded through the [`AssetServer`] (added directly to their `Assets` collection) are considered loaded. Assets which failed to load are ignored, with a warning.
+pub fn update_pending_assets<A: AssetsBundleSpawner, T: ComponentSpawner>(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut ready_events: EventWriter<GenerationAssetsReady>,
+    mut pending_generations: Query<(Entity, &mut PendingAssets), With<AssetSpawner<A, T>>>,
+) {
+    for (gen_entity, mut pending_assets) in pending_generations.iter_mut() {
+        pending_assets
+            .0
+            .retain(|handle| match asset_server.get_load_state(handle.id()) {
+                None => false,
+                Some(LoadState::Failed) => {
+                    warn!(
+                        "Asset {:?} of generation {:?} failed to load, ignoring it",
+                        handle.path(),
+                        gen_entity
+                    );
+                    false
+                }
+                Some(_) => !asset_server.is_loaded_with_dependencies(handle.id()),
+            });
+        if pending_assets.0.is_empty() {
+            commands.entity(gen_entity).remove::<PendingAssets>();
+            ready_events.send(GenerationAssetsReady(gen_entity));
+        }
+    }
+}
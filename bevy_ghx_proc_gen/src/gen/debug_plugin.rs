@@ -1,32 +1,62 @@
-use std::{marker::PhantomData, time::Duration};
+use std::marker::PhantomData;
 
 use bevy::{
     app::{App, Plugin, PostStartup, PostUpdate, PreUpdate, Startup, Update},
-    ecs::{schedule::IntoSystemConfigs, system::Resource},
+    ecs::{
+        component::Component,
+        schedule::{
+            common_conditions::{not, resource_changed, resource_exists},
+            Condition, IntoSystemConfigs,
+        },
+        system::Resource,
+    },
     input::keyboard::KeyCode,
     render::color::Color,
-    time::{Timer, TimerMode},
+    time::Timer,
 };
 use bevy_ghx_grid::ghx_grid::coordinate_system::CoordinateSystem;
 
 use self::{
+    coordinate_labels::{spawn_coordinate_labels, update_coordinate_labels},
     cursor::{
-        deselect_from_keybinds, move_selection_from_keybinds, setup_cursor, setup_cursors_overlays,
-        setup_cursors_panel, switch_generation_selection_from_keybinds,
-        update_cursors_info_from_generation_events, update_cursors_info_on_cursors_changes,
-        update_cursors_overlays, update_selection_cursor_panel_text, CursorKeyboardMovement,
-        CursorKeyboardMovementSettings, SelectCursor, SelectionCursorMarkerSettings,
+        collect_selectable_grids, deselect_from_keybinds, draw_2d_markers,
+        move_selection_from_keybinds, setup_cursor, setup_cursors_overlays, setup_cursors_panel,
+        switch_generation_selection_from_keybinds, update_cursors_info_from_generation_events,
+        update_cursors_info_on_cursors_changes, update_cursors_overlays,
+        update_over_cursor_from_generation_events, update_over_cursor_panel_text,
+        update_selection_cursor_from_events, update_selection_cursor_panel_text,
+        CursorKeyboardMovement, CursorKeyboardMovementSettings, Markers2dSettings, MoveCursorTo,
+        OverCursor, OverCursorMarkerSettings, SelectCursor, SelectNode, SelectableGrids,
+        SelectionCursorMarkerSettings,
     },
     generation::{
-        generate_all, insert_error_markers_to_new_generations,
-        insert_void_nodes_to_new_generations, step_by_step_input_update, step_by_step_timed_update,
-        update_active_generation, update_generation_control, update_generation_view,
-        ActiveGeneration, GenerationEvent,
+        final_view_mode, generate_all, insert_error_markers_to_new_generations,
+        insert_generation_control_to_new_generations, insert_void_nodes_to_new_generations,
+        regenerate_from_keybinds, step_by_step_input_update, step_by_step_manual_view_mode,
+        step_by_step_timed_update, step_by_step_timed_view_mode, update_active_generation,
+        update_generation_control, update_generation_view,
+        update_step_by_step_timed_from_view_mode, ActiveGeneration, GenerationEvent,
+    },
+    gizmo_grid::draw_gizmo_grids,
+    highlight::{highlight_nodes_from_events, update_nodes_highlights, HighlightNodesEvent},
+    layer_slicing::{apply_layer_slicing, update_layer_slicing_from_keybinds, LayerSlicing},
+    raycast::{grid_raycast_enabled, raycast_update_over_cursor, GridRaycastCursorSettings},
+    socket_view::{
+        draw_socket_directions, toggle_socket_directions_from_keybinds, update_socket_labels,
+        SocketDirectionsView,
     },
 };
 use super::{
-    assets::NoComponents, insert_default_bundle_to_spawned_nodes, spawn_node, AssetSpawner,
-    AssetsBundleSpawner, ComponentSpawner,
+    assets::NoComponents,
+    insert_default_bundle_to_spawned_nodes,
+    lifecycle::add_lifecycle_events,
+    replacement::{add_replace_node_asset_event, replace_node_assets},
+    AssetSpawner, AssetsBundleSpawner, ComponentSpawner,
+};
+
+#[cfg(feature = "assets-preloading")]
+use super::assets_preloading::{
+    insert_pending_assets_to_new_generations, update_pending_assets, GenerationAssetsReady,
 };
 
 #[cfg(feature = "picking")]
@@ -35,15 +65,24 @@ use bevy_mod_picking::PickableBundle;
 #[cfg(feature = "picking")]
 use self::picking::{
     insert_cursor_picking_handlers_to_grid_nodes, picking_remove_previous_over_cursor,
-    picking_update_cursors_position, setup_picking_assets, update_cursor_targets_nodes,
-    update_over_cursor_from_generation_events, update_over_cursor_panel_text, CursorTargetAssets,
-    NodeOutEvent, NodeOverEvent, NodeSelectedEvent, OverCursor, OverCursorMarkerSettings,
+    picking_select_nodes, picking_update_cursors_position, setup_picking_assets,
+    update_cursor_targets_nodes, CursorTargetAssets, NodeOutEvent, NodeOverEvent,
+    NodeSelectedEvent,
 };
 
-/// Module with picking features, enabled with the `picking` feature
+/// Module with the `bevy_mod_picking` adapter of the cursors, enabled with the `picking` feature
 #[cfg(feature = "picking")]
 pub mod picking;
 
+#[cfg(feature = "egui")]
+use self::egui_control::{
+    control_panel_enabled, draw_control_panel, ControlPanelConfig, ControlPanelContext,
+};
+
+/// Module providing a small egui control panel for the generation, enabled with the `egui` feature
+#[cfg(feature = "egui")]
+pub mod egui_control;
+
 #[cfg(feature = "egui-edit")]
 use self::egui_editor::{
     draw_edition_panel, editor_enabled, paint, update_brush, update_painting_state, BrushEvent,
@@ -54,10 +93,22 @@ use self::egui_editor::{
 #[cfg(feature = "egui-edit")]
 pub mod egui_editor;
 
+/// Module displaying coordinate labels along the axes of the grids
+pub mod coordinate_labels;
 /// Module providing all the grid cursors features
 pub mod cursor;
 /// Module handling the generation fetaures of the debug_plugin
 pub mod generation;
+/// Module drawing the grids lines with gizmos, an alternative to the grid mesh of `bevy_ghx_grid`
+pub mod gizmo_grid;
+/// Module highlighting nodes on request, with [`highlight::HighlightNodesEvent`]
+pub mod highlight;
+/// Module showing a single Y layer of the 3d grids, with [`layer_slicing::LayerSlicing`]
+pub mod layer_slicing;
+/// Module providing the grid-plane raycast of the Over cursor, an alternative to picking which does not need any component on the spawned nodes
+pub mod raycast;
+/// Module displaying the sockets of the selected node in each direction, with [`socket_view::SocketDirectionsView`]
+pub mod socket_view;
 
 /// Used to configure how the cursors UI should be displayed
 #[derive(Default, Debug, PartialEq, Eq)]
@@ -94,9 +145,13 @@ impl Default for GridCursorsUiSettings {
 
 /// A [`Plugin`] useful for debug/analysis/demo. It mainly run [`ghx_proc_gen::generator::Generator`] components and spawn the generated model's [`crate::gen::assets::ModelAsset`]
 ///
-/// It takes in a [`GenerationViewMode`] to control how the generators components will be run.
+/// It takes in an initial [`GenerationViewMode`] to control how the generators components will be run. The view mode is stored as a `Resource` and can be changed at runtime.
+///
+/// It also uses the following `Resources`: [`ProcGenKeyBindings`] and [`GenerationControl`] (and will init them to their defaults if not inserted by the user). The [`GenerationControl`] `Resource` is only used as the default of the per-generator [`GenerationControl`] components.
 ///
-/// It also uses the following `Resources`: [`ProcGenKeyBindings`] and [`GenerationControl`] (and will init them to their defaults if not inserted by the user).
+/// Along with its own [`GenerationEvent`], the plugin sends the [`super::lifecycle`] events, including a [`super::lifecycle::GenerationStarted`] each time a generator is reinitialized.
+///
+/// The plugin runs the generators of the coordinate system `C`. Generators of other coordinate systems can be handled by the same plugin with [`ProcGenDebugPlugin::with_coordinate_system`], instead of registering the plugin systems for each coordinate system.
 pub struct ProcGenDebugPlugin<
     C: CoordinateSystem,
     A: AssetsBundleSpawner,
@@ -104,6 +159,7 @@ pub struct ProcGenDebugPlugin<
 > {
     generation_view_mode: GenerationViewMode,
     cursor_ui_mode: CursorUiMode,
+    coordinate_systems: Vec<fn(&mut App)>,
     typestate: PhantomData<(C, A, T)>,
 }
 
@@ -113,9 +169,26 @@ impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> ProcGenDe
         Self {
             generation_view_mode,
             cursor_ui_mode,
+            coordinate_systems: vec![add_coordinate_system_systems::<C, A, T>],
             typestate: PhantomData,
         }
     }
+
+    /// Also runs the generators of the coordinate system `C2`, with the same assets types `A` and `T`
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// app.add_plugins(
+    ///     ProcGenDebugPlugin::<Cartesian3D, PbrMesh>::new(GenerationViewMode::Final, CursorUiMode::Overlay)
+    ///         .with_coordinate_system::<Cartesian2D>(),
+    /// );
+    /// ```
+    pub fn with_coordinate_system<C2: CoordinateSystem>(mut self) -> Self {
+        self.coordinate_systems
+            .push(add_coordinate_system_systems::<C2, A, T>);
+        self
+    }
 }
 
 impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> Plugin
@@ -130,8 +203,14 @@ impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> Plugin
         app.init_resource::<ProcGenKeyBindings>()
             .init_resource::<GenerationControl>()
             .init_resource::<SelectionCursorMarkerSettings>()
+            .init_resource::<OverCursorMarkerSettings>()
+            .init_resource::<GridRaycastCursorSettings>()
+            .init_resource::<Markers2dSettings>()
+            .init_resource::<LayerSlicing>()
+            .init_resource::<SocketDirectionsView>()
             .init_resource::<CursorKeyboardMovement>()
-            .init_resource::<CursorKeyboardMovementSettings>();
+            .init_resource::<CursorKeyboardMovementSettings>()
+            .init_resource::<SelectableGrids>();
         match self.cursor_ui_mode {
             CursorUiMode::None => (),
             _ => {
@@ -139,7 +218,16 @@ impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> Plugin
             }
         }
 
-        app.add_event::<GenerationEvent>();
+        add_lifecycle_events(app);
+        add_replace_node_asset_event(app);
+        app.add_event::<GenerationEvent>()
+            .add_event::<MoveCursorTo>()
+            .add_event::<SelectNode>()
+            .add_event::<HighlightNodesEvent>();
+
+        #[cfg(feature = "egui")]
+        app.init_resource::<ControlPanelConfig>()
+            .init_resource::<ControlPanelContext>();
 
         #[cfg(feature = "egui-edit")]
         app.init_resource::<EditorConfig>()
@@ -148,79 +236,50 @@ impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> Plugin
 
         #[cfg(feature = "picking")]
         app.init_resource::<CursorTargetAssets>()
-            .init_resource::<OverCursorMarkerSettings>()
             .add_event::<NodeOverEvent>()
             .add_event::<NodeOutEvent>()
             .add_event::<NodeSelectedEvent>();
 
         app
             // PostStartup to wait for setup_cursors_overlays to be applied.
-            .add_systems(PostStartup, setup_cursor::<C, SelectCursor>)
+            .add_systems(
+                PostStartup,
+                (
+                    setup_cursor::<C, SelectCursor>,
+                    setup_cursor::<C, OverCursor>,
+                ),
+            )
             // Keybinds and picking events handlers run in PreUpdate
             .add_systems(
                 PreUpdate,
                 (
                     deselect_from_keybinds,
-                    switch_generation_selection_from_keybinds::<C>,
-                    move_selection_from_keybinds::<C>,
+                    switch_generation_selection_from_keybinds,
+                    update_layer_slicing_from_keybinds,
+                    toggle_socket_directions_from_keybinds,
                 ),
             )
             .add_systems(
                 Update,
                 (
                     update_generation_control,
-                    update_active_generation::<C>,
-                    update_cursors_info_on_cursors_changes::<C>,
+                    update_coordinate_labels,
+                    update_nodes_highlights,
                 ),
             )
-            .add_systems(PostUpdate, update_cursors_info_from_generation_events::<C>);
+            .add_systems(PostUpdate, (draw_2d_markers, update_socket_labels));
 
         #[cfg(feature = "picking")]
-        app.add_systems(Startup, setup_picking_assets)
-            // PostStartup to wait for setup_cursors_overlays to be applied.
-            .add_systems(PostStartup, setup_cursor::<C, OverCursor>)
-            .add_systems(
-                Update,
-                (
-                    insert_default_bundle_to_spawned_nodes::<PickableBundle>,
-                    (
-                        update_cursor_targets_nodes::<C>,
-                        insert_cursor_picking_handlers_to_grid_nodes::<C>,
-                    )
-                        .chain(),
-                    (
-                        picking_remove_previous_over_cursor::<C>,
-                        picking_update_cursors_position::<
-                            C,
-                            OverCursorMarkerSettings,
-                            OverCursor,
-                            NodeOverEvent,
-                        >,
-                        picking_update_cursors_position::<
-                            C,
-                            SelectionCursorMarkerSettings,
-                            SelectCursor,
-                            NodeSelectedEvent,
-                        >,
-                    )
-                        .chain(),
-                ),
-            )
-            .add_systems(
-                PostUpdate,
-                update_over_cursor_from_generation_events::<C>
-                    .before(update_cursors_info_from_generation_events::<C>),
-            );
+        app.add_systems(Startup, setup_picking_assets).add_systems(
+            Update,
+            insert_default_bundle_to_spawned_nodes::<PickableBundle>
+                .run_if(not(grid_raycast_enabled)),
+        );
 
         #[cfg(feature = "egui-edit")]
         app.add_systems(
             Update,
-            (
-                draw_edition_panel::<C>,
-                update_brush,
-                update_painting_state,
-                paint::<C>,
-            )
+            (update_brush, update_painting_state)
                 .chain()
                 .run_if(editor_enabled),
         );
@@ -229,9 +288,13 @@ impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> Plugin
             CursorUiMode::None => (),
             CursorUiMode::Panel => {
                 app.add_systems(Startup, setup_cursors_panel);
-                app.add_systems(PostUpdate, update_selection_cursor_panel_text);
-                #[cfg(feature = "picking")]
-                app.add_systems(PostUpdate, update_over_cursor_panel_text);
+                app.add_systems(
+                    PostUpdate,
+                    (
+                        update_selection_cursor_panel_text,
+                        update_over_cursor_panel_text,
+                    ),
+                );
             }
             CursorUiMode::Overlay => {
                 app.add_systems(Startup, setup_cursors_overlays);
@@ -239,53 +302,134 @@ impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> Plugin
             }
         }
 
-        match self.generation_view_mode {
-            GenerationViewMode::StepByStepTimed {
-                steps_count,
-                interval_ms,
-            } => {
-                app.add_systems(
-                    Update,
-                    (
-                        (
-                            insert_error_markers_to_new_generations::<C>,
-                            insert_void_nodes_to_new_generations::<C, A, T>,
-                        ),
-                        step_by_step_timed_update::<C>,
-                        update_generation_view::<C, A, T>,
-                    )
-                        .chain(),
-                );
-                app.insert_resource(StepByStepTimed {
-                    steps_count,
-                    timer: Timer::new(Duration::from_millis(interval_ms), TimerMode::Repeating),
-                });
-            }
-            GenerationViewMode::StepByStepManual => {
-                app.add_systems(
-                    Update,
-                    (
-                        (
-                            insert_error_markers_to_new_generations::<C>,
-                            insert_void_nodes_to_new_generations::<C, A, T>,
-                        ),
-                        step_by_step_input_update::<C>,
-                        update_generation_view::<C, A, T>,
-                    )
-                        .chain(),
-                );
-            }
-            GenerationViewMode::Final => {
-                app.add_systems(
-                    Update,
-                    (generate_all::<C>, update_generation_view::<C, A, T>).chain(),
-                );
-            }
+        #[cfg(feature = "assets-preloading")]
+        app.add_event::<GenerationAssetsReady>().add_systems(
+            Update,
+            (
+                insert_pending_assets_to_new_generations::<A, T>,
+                update_pending_assets::<A, T>,
+            )
+                .chain(),
+        );
+
+        app.add_systems(
+            PreUpdate,
+            update_step_by_step_timed_from_view_mode.run_if(resource_changed::<GenerationViewMode>),
+        );
+
+        for add_systems in self.coordinate_systems.iter() {
+            add_systems(app);
         }
     }
 }
 
+/// Adds the systems of the [`ProcGenDebugPlugin`] which depend on the coordinate system `C`
+fn add_coordinate_system_systems<
+    C: CoordinateSystem,
+    A: AssetsBundleSpawner,
+    T: ComponentSpawner,
+>(
+    app: &mut App,
+) {
+    app.add_systems(
+        PreUpdate,
+        (
+            collect_selectable_grids::<C>.before(switch_generation_selection_from_keybinds),
+            move_selection_from_keybinds::<C>,
+        ),
+    )
+    .add_systems(
+        Update,
+        (
+            regenerate_from_keybinds::<C>,
+            update_active_generation::<C>,
+            spawn_coordinate_labels::<C>,
+            highlight_nodes_from_events::<C>,
+            (
+                raycast_update_over_cursor::<C>.run_if(grid_raycast_enabled),
+                update_selection_cursor_from_events::<C>,
+                update_cursors_info_on_cursors_changes::<C>,
+            )
+                .chain(),
+        ),
+    )
+    .add_systems(
+        PostUpdate,
+        (
+            (
+                update_over_cursor_from_generation_events::<C>,
+                update_cursors_info_from_generation_events::<C>,
+            )
+                .chain(),
+            draw_gizmo_grids::<C>,
+            apply_layer_slicing::<C>,
+            draw_socket_directions::<C>,
+        ),
+    );
+
+    #[cfg(feature = "picking")]
+    app.add_systems(
+        Update,
+        (
+            (
+                update_cursor_targets_nodes::<C>,
+                insert_cursor_picking_handlers_to_grid_nodes::<C>.run_if(not(grid_raycast_enabled)),
+            )
+                .chain(),
+            (
+                picking_remove_previous_over_cursor::<C>,
+                picking_update_cursors_position::<
+                    C,
+                    OverCursorMarkerSettings,
+                    OverCursor,
+                    NodeOverEvent,
+                >,
+            )
+                .chain(),
+            picking_select_nodes::<C>.before(update_selection_cursor_from_events::<C>),
+        ),
+    );
+
+    #[cfg(feature = "egui")]
+    app.add_systems(
+        Update,
+        draw_control_panel::<C>.run_if(control_panel_enabled),
+    );
+
+    #[cfg(feature = "egui-edit")]
+    app.add_systems(
+        Update,
+        (
+            draw_edition_panel::<C>.before(update_brush),
+            paint::<C>.after(update_painting_state),
+        )
+            .run_if(editor_enabled),
+    );
+
+    let generation_systems = (
+        (
+            insert_error_markers_to_new_generations::<C>,
+            insert_generation_control_to_new_generations::<C>,
+            insert_void_nodes_to_new_generations::<C, A, T>,
+        ),
+        (
+            step_by_step_timed_update::<C>
+                .run_if(step_by_step_timed_view_mode.and_then(resource_exists::<StepByStepTimed>)),
+            step_by_step_input_update::<C>.run_if(step_by_step_manual_view_mode),
+            generate_all::<C>.run_if(final_view_mode),
+        ),
+        update_generation_view::<C, A, T>,
+        replace_node_assets::<C, A, T>,
+    )
+        .chain();
+    #[cfg(feature = "assets-preloading")]
+    let generation_systems = generation_systems.after(update_pending_assets::<A, T>);
+    app.add_systems(Update, generation_systems);
+}
+
 /// Controls how the generation occurs.
+///
+/// Inserted as a `Resource` by the [`ProcGenDebugPlugin`], it can be modified at runtime to switch between the different modes.
 #[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GenerationViewMode {
     /// Generates steps by steps and waits at least the specified amount (in milliseconds) between each step.
@@ -303,7 +447,7 @@ pub enum GenerationViewMode {
 }
 
 /// Used to track the status of the generation control
-#[derive(Resource, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum GenerationControlStatus {
     /// Generation control is paused, systems won't automatically step the generation
     Paused,
@@ -311,8 +455,10 @@ pub enum GenerationControlStatus {
     Ongoing,
 }
 
-/// Read by the systems while generating
-#[derive(Resource)]
+/// Read by the systems while generating.
+///
+/// Inserted as a `Component` on each generator entity, so that each generation can be paused and configured independently. The `Resource` only holds the defaults: it is cloned into the generator entities which do not have their own [`GenerationControl`] yet.
+#[derive(Resource, Component, Clone, Debug)]
 pub struct GenerationControl {
     /// Current status of the generation
     pub status: GenerationControlStatus,
@@ -349,7 +495,9 @@ impl Default for GenerationControl {
     }
 }
 
-/// Resource to track the generation steps when using [`GenerationViewMode::StepByStepTimed`]
+/// Resource to track the generation steps when using [`GenerationViewMode::StepByStepTimed`].
+///
+/// Inserted and removed automatically by the [`ProcGenDebugPlugin`] when the [`GenerationViewMode`] changes.
 #[derive(Resource)]
 pub struct StepByStepTimed {
     /// How many steps should be done once the timer has expired
@@ -382,6 +530,18 @@ pub struct ProcGenKeyBindings {
     pub step: KeyCode,
     /// Key used only with [`GenerationViewMode::StepByStepManual`] to step continuously as long as pressed
     pub continuous_step: KeyCode,
+    /// Key to despawn the nodes of the active generation and restart it with a new random seed
+    pub regenerate: KeyCode,
+
+    /// Key to enable/disable the [`LayerSlicing`], starting on the first layer
+    pub toggle_layer_slicing: KeyCode,
+    /// Key to show the layer above the current one when the [`LayerSlicing`] is enabled
+    pub layer_up: KeyCode,
+    /// Key to show the layer below the current one when the [`LayerSlicing`] is enabled
+    pub layer_down: KeyCode,
+
+    /// Key to show/hide the sockets of the selected node, see [`SocketDirectionsView`]
+    pub toggle_socket_directions: KeyCode,
 }
 
 impl Default for ProcGenKeyBindings {
@@ -397,6 +557,11 @@ impl Default for ProcGenKeyBindings {
             pause_toggle: KeyCode::Space,
             step: KeyCode::ArrowDown,
             continuous_step: KeyCode::ArrowUp,
+            regenerate: KeyCode::KeyR,
+            toggle_layer_slicing: KeyCode::KeyL,
+            layer_up: KeyCode::PageUp,
+            layer_down: KeyCode::PageDown,
+            toggle_socket_directions: KeyCode::KeyK,
         }
     }
 }
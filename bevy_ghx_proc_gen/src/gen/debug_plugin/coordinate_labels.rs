@@ -0,0 +1,187 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Added, Has, With, Without},
+        system::{Commands, Query},
+    },
+    math::Vec3,
+    render::{camera::Camera, color::Color, view::Visibility},
+    text::{BreakLineOn, Text, TextSection, TextStyle},
+    transform::components::GlobalTransform,
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+};
+use bevy_ghx_grid::{
+    debug_plugin::view::{DebugGridView, DebugGridView3d},
+    ghx_grid::{coordinate_system::CoordinateSystem, grid::GridDefinition},
+};
+use ghx_proc_gen::generator::Generator;
+
+use crate::gen::GridWorldTransform;
+
+use super::cursor::GridCursorsOverlayCamera;
+
+/// Component to insert on a grid entity (next to its [DebugGridView]) to display the coordinates of its nodes along its axes: a tick label every [GridCoordinateLabels::tick_interval] nodes, and the name of each axis after its last node.
+///
+/// The labels are anchored to world positions and displayed as UI texts over the grid, so that a node index found in a log can be located without counting cells. Axes with a single node have no labels.
+#[derive(Component, Clone, Debug)]
+pub struct GridCoordinateLabels {
+    /// Number of nodes between two coordinate ticks. The first and last nodes of an axis are always labeled.
+    pub tick_interval: u32,
+    /// Whether or not to display the name of each axis
+    pub axis_labels: bool,
+    /// Font size of the labels
+    pub font_size: f32,
+    /// Color of the labels
+    pub color: Color,
+}
+
+impl Default for GridCoordinateLabels {
+    fn default() -> Self {
+        Self {
+            tick_interval: 5,
+            axis_labels: true,
+            font_size: 13.0,
+            color: Color::WHITE.with_a(0.8),
+        }
+    }
+}
+
+/// A label spawned for a grid with [GridCoordinateLabels]
+#[derive(Component, Debug)]
+pub struct CoordinateLabel {
+    /// Grid entity of the label
+    pub grid: Entity,
+    /// Position of the label in the grid local space
+    pub local_position: Vec3,
+}
+
+/// System spawning the labels of the grids of the coordinate system `C` with an added [GridCoordinateLabels] component
+pub fn spawn_coordinate_labels<C: CoordinateSystem>(
+    mut commands: Commands,
+    grids: Query<
+        (
+            Entity,
+            &GridDefinition<C>,
+            &DebugGridView,
+            &GridCoordinateLabels,
+            Has<DebugGridView3d>,
+        ),
+        (With<Generator<C>>, Added<GridCoordinateLabels>),
+    >,
+) {
+    for (grid_entity, grid, grid_view, labels, view_3d) in grids.iter() {
+        let node_size = grid_view.node_size;
+        let world_transform = GridWorldTransform::new(node_size);
+        // Labels are shifted by one node out of the grid, on the side of the origin
+        let side_x = Vec3::new(-node_size.x, 0., 0.);
+        let side_xz = match view_3d {
+            true => Vec3::new(0., 0., -node_size.z),
+            false => Vec3::new(0., -node_size.y, 0.),
+        };
+        let axes = [
+            ("x", grid.size_x(), side_xz),
+            ("y", grid.size_y(), side_x),
+            ("z", grid.size_z(), side_x),
+        ];
+        for (axis, (name, size, side)) in axes.into_iter().enumerate() {
+            if size <= 1 {
+                continue;
+            }
+            let coords = |i: u32| {
+                let mut coords = [0.; 3];
+                coords[axis] = i as f32;
+                world_transform.coords_to_world(coords[0], coords[1], coords[2])
+            };
+            let interval = labels.tick_interval.max(1);
+            for i in (0..size).filter(|i| i % interval == 0 || *i == size - 1) {
+                spawn_label(
+                    &mut commands,
+                    labels,
+                    grid_entity,
+                    coords(i) + side,
+                    i.to_string(),
+                );
+            }
+            if labels.axis_labels {
+                let after_last = coords(size);
+                spawn_label(
+                    &mut commands,
+                    labels,
+                    grid_entity,
+                    after_last + side,
+                    name.to_string(),
+                );
+            }
+        }
+    }
+}
+
+fn spawn_label(
+    commands: &mut Commands,
+    labels: &GridCoordinateLabels,
+    grid: Entity,
+    local_position: Vec3,
+    value: String,
+) {
+    commands.spawn((
+        CoordinateLabel {
+            grid,
+            local_position,
+        },
+        TextBundle {
+            text: Text {
+                linebreak_behavior: BreakLineOn::NoWrap,
+                sections: vec![TextSection {
+                    value,
+                    style: TextStyle {
+                        font_size: labels.font_size,
+                        color: labels.color,
+                        ..Default::default()
+                    },
+                }],
+                ..Default::default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+    ));
+}
+
+/// System moving the [CoordinateLabel] over their world position, and despawning the labels of despawned grids or of grids without [GridCoordinateLabels] anymore
+pub fn update_coordinate_labels(
+    mut commands: Commands,
+    just_one_camera: Query<(&Camera, &GlobalTransform), Without<GridCursorsOverlayCamera>>,
+    overlay_camera: Query<(&Camera, &GlobalTransform), With<GridCursorsOverlayCamera>>,
+    grids: Query<&GlobalTransform, With<GridCoordinateLabels>>,
+    mut labels: Query<(Entity, &CoordinateLabel, &mut Style, &mut Visibility)>,
+) {
+    let camera = just_one_camera
+        .get_single()
+        .or_else(|_| overlay_camera.get_single())
+        .ok();
+    for (label_entity, label, mut style, mut visibility) in labels.iter_mut() {
+        let Ok(grid_gtransform) = grids.get(label.grid) else {
+            commands.entity(label_entity).despawn();
+            continue;
+        };
+        let viewport_pos = camera.and_then(|(camera, cam_gtransform)| {
+            camera.world_to_viewport(
+                cam_gtransform,
+                grid_gtransform.transform_point(label.local_position),
+            )
+        });
+        match viewport_pos {
+            Some(viewport_pos) => {
+                style.left = Val::Px(viewport_pos.x);
+                style.top = Val::Px(viewport_pos.y);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
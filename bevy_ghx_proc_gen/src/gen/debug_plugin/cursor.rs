@@ -5,11 +5,12 @@ use bevy::{
     ecs::{
         component::Component,
         entity::Entity,
-        event::{EventReader, EventWriter},
+        event::{Event, EventReader, EventWriter},
         query::{Changed, With, Without},
         system::{Commands, Local, Query, Res, ResMut, Resource},
     },
-    hierarchy::BuildChildren,
+    gizmos::gizmos::Gizmos,
+    hierarchy::{BuildChildren, Parent},
     input::{keyboard::KeyCode, ButtonInput},
     log::warn,
     render::{camera::Camera, color::Color},
@@ -23,7 +24,10 @@ use bevy::{
     utils::default,
 };
 use bevy_ghx_grid::{
-    debug_plugin::markers::{spawn_marker, GridMarker, MarkerDespawnEvent},
+    debug_plugin::{
+        markers::{spawn_marker, GridMarker, MarkerDespawnEvent, MarkersGroup},
+        view::{DebugGridView, DebugGridView2d, DebugGridView3d},
+    },
     ghx_grid::{
         coordinate_system::CoordinateSystem,
         direction::Direction,
@@ -147,6 +151,54 @@ impl CursorBehavior for SelectCursor {
     }
 }
 
+/// Used to customize the color of the Over cursor [GridMarker]
+#[derive(Resource)]
+pub struct OverCursorMarkerSettings(pub Color);
+impl Default for OverCursorMarkerSettings {
+    fn default() -> Self {
+        Self(Color::rgb(0.85, 0.85, 0.73))
+    }
+}
+impl CursorMarkerSettings for OverCursorMarkerSettings {
+    fn color(&self) -> Color {
+        self.0
+    }
+}
+
+/// Main component for the Over cursor
+#[derive(Component, Debug)]
+pub struct OverCursor;
+impl CursorBehavior for OverCursor {
+    fn new() -> Self {
+        Self
+    }
+    fn updates_active_gen() -> bool {
+        false
+    }
+}
+
+/// Event moving the selection cursor to the node `node_index` of the grid entity `grid`, without changing the [ActiveGeneration].
+///
+/// Sent by the keyboard systems. Custom input systems can send it too: the selection cursor does not depend on any picking backend.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MoveCursorTo {
+    /// Grid entity of the node
+    pub grid: Entity,
+    /// Index of the node in its grid
+    pub node_index: NodeIndex,
+}
+
+/// Event selecting the node `node_index` of the grid entity `grid`: moves the selection cursor to it and makes its grid the [ActiveGeneration].
+///
+/// Sent by the grid switch keybind and by the `bevy_mod_picking` adapter (with the `picking` feature). Custom raycasters can send it to select nodes with their own picking logic.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SelectNode {
+    /// Grid entity of the node
+    pub grid: Entity,
+    /// Index of the node in its grid
+    pub node_index: NodeIndex,
+}
+
 /// Used to index text sections when displaying cursors Ui in a panel
 pub const OVER_CURSOR_SECTION_INDEX: usize = 0;
 /// Used to index text sections when displaying cursors Ui in a panel
@@ -326,6 +378,52 @@ pub fn update_selection_cursor_panel_text(
     }
 }
 
+/// System that update the over cursor UI panel
+pub fn update_over_cursor_panel_text(
+    mut cursors_panel_text: Query<&mut Text, With<CursorsPanelText>>,
+    updated_cursors: Query<(&CursorInfo, &Cursor), (Changed<CursorInfo>, With<OverCursor>)>,
+) {
+    if let Ok((cursor_info, cursor)) = updated_cursors.get_single() {
+        for mut text in &mut cursors_panel_text {
+            let ui_text = &mut text.sections[OVER_CURSOR_SECTION_INDEX].value;
+            match &cursor.0 {
+                Some(overed_node) => {
+                    *ui_text = format!(
+                        "Hovered:\n{}",
+                        cursor_info_to_string(overed_node, cursor_info)
+                    );
+                }
+                None => ui_text.clear(),
+            }
+        }
+    }
+}
+
+/// System updating the Over [Cursor] by reading all the [GenerationEvent]
+///
+/// Should run after update_cursors_info_on_cursors_changes and before update_cursors_info_from_generation_events
+pub fn update_over_cursor_from_generation_events<C: CoordinateSystem>(
+    mut cursors_events: EventReader<GenerationEvent>,
+    mut marker_events: EventWriter<MarkerDespawnEvent>,
+    mut over_cursor: Query<&mut Cursor, With<OverCursor>>,
+) {
+    let Ok(mut cursor) = over_cursor.get_single_mut() else {
+        return;
+    };
+    for event in cursors_events.read() {
+        match event {
+            GenerationEvent::Reinitialized(_grid_entity) => {
+                // If there is an Over cursor, force despawn it, since we will despawn the underlying node there won't be any NodeOutEvent.
+                if let Some(overed_node) = &cursor.0 {
+                    marker_events.send(MarkerDespawnEvent::Marker(overed_node.marker));
+                    cursor.0 = None;
+                }
+            }
+            GenerationEvent::Updated(_grid_entity, _node_index) => {}
+        }
+    }
+}
+
 /// Listen to [KeyCode] to deselect the current selection cursor
 pub fn deselect_from_keybinds(
     keys: Res<ButtonInput<KeyCode>>,
@@ -374,38 +472,33 @@ impl EntityProvider {
     }
 }
 
-/// System that listens to the generation switch [KeyCode] to switch the current active generation grid
-pub fn switch_generation_selection_from_keybinds<C: CoordinateSystem>(
+/// Resource listing the grids which can be selected by [switch_generation_selection_from_keybinds], filled by [collect_selectable_grids] for each registered coordinate system
+#[derive(Resource, Default, Debug)]
+pub struct SelectableGrids(pub Vec<Entity>);
+
+/// System collecting the grids of the coordinate system `C` into the [SelectableGrids] `Resource`
+pub fn collect_selectable_grids<C: CoordinateSystem>(
+    mut selectable_grids: ResMut<SelectableGrids>,
+    generators: Query<Entity, (With<Generator<C>>, With<GridDefinition<C>>)>,
+) {
+    selectable_grids.0.extend(generators.iter());
+}
+
+/// System that listens to the generation switch [KeyCode] to switch the current active generation grid, among the [SelectableGrids] of all the coordinate systems, by sending a [SelectNode] event on the first node of the next grid
+pub fn switch_generation_selection_from_keybinds(
     mut local_grid_cycler: Local<EntityProvider>,
-    mut commands: Commands,
-    mut active_generation: ResMut<ActiveGeneration>,
+    mut selectable_grids: ResMut<SelectableGrids>,
     keys: Res<ButtonInput<KeyCode>>,
-    selection_marker_settings: Res<SelectionCursorMarkerSettings>,
     proc_gen_key_bindings: Res<ProcGenKeyBindings>,
-    mut marker_events: EventWriter<MarkerDespawnEvent>,
-    mut selection_cursor: Query<&mut Cursor, With<SelectCursor>>,
-    generators: Query<Entity, (With<Generator<C>>, With<GridDefinition<C>>)>,
+    mut select_events: EventWriter<SelectNode>,
 ) {
-    if keys.just_pressed(proc_gen_key_bindings.switch_grid) {
-        let Ok(mut cursor) = selection_cursor.get_single_mut() else {
-            return;
-        };
-
-        local_grid_cycler.update(generators.iter().collect());
-        let grid_entity = local_grid_cycler.get();
-        active_generation.0 = Some(grid_entity);
-        // Despawn previous if any
-        if let Some(grid_cursor) = &cursor.0 {
-            marker_events.send(MarkerDespawnEvent::Marker(grid_cursor.marker));
-        }
-        // Spawn on new selected grid
-        cursor.0 = Some(spawn_marker_and_create_cursor(
-            &mut commands,
-            grid_entity,
-            GridPosition::new(0, 0, 0),
-            0,
-            selection_marker_settings.color(),
-        ));
+    let grids = std::mem::take(&mut selectable_grids.0);
+    if keys.just_pressed(proc_gen_key_bindings.switch_grid) && !grids.is_empty() {
+        local_grid_cycler.update(grids);
+        select_events.send(SelectNode {
+            grid: local_grid_cycler.get(),
+            node_index: 0,
+        });
     }
 }
 
@@ -461,20 +554,18 @@ impl Default for CursorKeyboardMovement {
     }
 }
 
-/// System handling movements of the selection cursor from the keyboard
+/// System handling movements of the selection cursor from the keyboard, by sending [MoveCursorTo] events
 pub fn move_selection_from_keybinds<C: CoordinateSystem>(
-    mut commands: Commands,
     keys: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
-    selection_marker_settings: Res<SelectionCursorMarkerSettings>,
     proc_gen_key_bindings: Res<ProcGenKeyBindings>,
-    mut marker_events: EventWriter<MarkerDespawnEvent>,
     key_mvmt_values: Res<CursorKeyboardMovementSettings>,
     mut key_mvmt: ResMut<CursorKeyboardMovement>,
-    mut selection_cursor: Query<&mut Cursor, With<SelectCursor>>,
+    mut move_events: EventWriter<MoveCursorTo>,
+    selection_cursor: Query<&Cursor, With<SelectCursor>>,
     grids: Query<(Entity, &GridDefinition<C>)>,
 ) {
-    let Ok(mut cursor) = selection_cursor.get_single_mut() else {
+    let Ok(cursor) = selection_cursor.get_single() else {
         return;
     };
 
@@ -544,48 +635,98 @@ pub fn move_selection_from_keybinds<C: CoordinateSystem>(
         if let Some(movement) = cursor_movement {
             key_mvmt.cooldown.reset();
 
-            let update_cursor = match &cursor.0 {
+            let move_event = match &cursor.0 {
                 Some(grid_cursor) => {
                     let Ok((_grid_entity, grid)) = grids.get(grid_cursor.grid) else {
                         return;
                     };
-                    match grid.get_index_in_direction(&grid_cursor.position, axis, movement) {
-                        Some(node_index) => {
-                            marker_events.send(MarkerDespawnEvent::Marker(grid_cursor.marker));
-                            Some((
-                                grid_cursor.grid,
-                                node_index,
-                                grid.pos_from_index(node_index),
-                            ))
-                        }
-                        None => None,
-                    }
+                    grid.get_index_in_direction(&grid_cursor.position, axis, movement)
+                        .map(|node_index| MoveCursorTo {
+                            grid: grid_cursor.grid,
+                            node_index,
+                        })
                 }
                 None => {
                     // Currently no selection cursor, spawn it on the last Grid
                     let Some((grid_entity, _grid)) = grids.iter().last() else {
                         return;
                     };
-                    Some((grid_entity, 0, GridPosition::new(0, 0, 0)))
+                    Some(MoveCursorTo {
+                        grid: grid_entity,
+                        node_index: 0,
+                    })
                 }
             };
-
-            match update_cursor {
-                Some((grid_entity, node_index, position)) => {
-                    cursor.0 = Some(spawn_marker_and_create_cursor(
-                        &mut commands,
-                        grid_entity,
-                        position,
-                        node_index,
-                        selection_marker_settings.color(),
-                    ));
-                }
-                None => (),
+            if let Some(move_event) = move_event {
+                move_events.send(move_event);
             }
         }
     }
 }
 
+/// System moving the selection cursor from the last [MoveCursorTo] or [SelectNode] event targeting a grid of the coordinate system `C`.
+///
+/// This is the only system moving the selection cursor: keyboard systems, the picking adapter and custom raycasters all go through these events.
+pub fn update_selection_cursor_from_events<C: CoordinateSystem>(
+    mut commands: Commands,
+    mut active_generation: ResMut<ActiveGeneration>,
+    selection_marker_settings: Res<SelectionCursorMarkerSettings>,
+    mut move_events: EventReader<MoveCursorTo>,
+    mut select_events: EventReader<SelectNode>,
+    mut marker_events: EventWriter<MarkerDespawnEvent>,
+    mut selection_cursor: Query<&mut Cursor, With<SelectCursor>>,
+    grids: Query<&GridDefinition<C>>,
+) {
+    // A selection takes precedence over a simple move in the same frame
+    let target = match select_events
+        .read()
+        .filter(|event| grids.contains(event.grid))
+        .last()
+    {
+        Some(event) => Some((event.grid, event.node_index, true)),
+        None => move_events
+            .read()
+            .filter(|event| grids.contains(event.grid))
+            .last()
+            .map(|event| (event.grid, event.node_index, false)),
+    };
+    move_events.clear();
+    let Some((grid_entity, node_index, select)) = target else {
+        return;
+    };
+    let Ok(mut cursor) = selection_cursor.get_single_mut() else {
+        return;
+    };
+    let Ok(grid) = grids.get(grid_entity) else {
+        return;
+    };
+    if node_index >= grid.total_size() {
+        warn!(
+            "Cursor event targets node {} but the grid only has {} nodes",
+            node_index,
+            grid.total_size()
+        );
+        return;
+    }
+
+    if select {
+        active_generation.0 = Some(grid_entity);
+    }
+    if let Some(grid_cursor) = &cursor.0 {
+        if grid_cursor.grid == grid_entity && grid_cursor.node_index == node_index {
+            return;
+        }
+        marker_events.send(MarkerDespawnEvent::Marker(grid_cursor.marker));
+    }
+    cursor.0 = Some(spawn_marker_and_create_cursor(
+        &mut commands,
+        grid_entity,
+        grid.pos_from_index(node_index),
+        node_index,
+        selection_marker_settings.color(),
+    ));
+}
+
 /// Utility function to spanw a [GridMarker]
 pub fn spawn_marker_and_create_cursor(
     commands: &mut Commands,
@@ -717,3 +858,45 @@ pub fn update_cursors_overlays(
         });
     }
 }
+
+/// Resource used to customize the rendering of the [GridMarker] (and thus of the cursors) of the grids displayed with a 2d camera
+#[derive(Resource, Debug)]
+pub struct Markers2dSettings {
+    /// Size of the marker rectangles, relative to the size of a node
+    pub size_ratio: f32,
+    /// Size of the inner marker rectangles, relative to the size of a node. `None` to only draw one rectangle per marker.
+    pub inner_size_ratio: Option<f32>,
+}
+
+impl Default for Markers2dSettings {
+    fn default() -> Self {
+        Self {
+            size_ratio: 0.95,
+            inner_size_ratio: Some(0.8),
+        }
+    }
+}
+
+/// System drawing the [GridMarker] of the grids displayed with a [DebugGridView2d] as 2d gizmo rectangles, in the [MarkersGroup] gizmos group, since the default markers are 3d line cubes which are not suited to a 2d camera
+pub fn draw_2d_markers(
+    mut gizmos: Gizmos<MarkersGroup>,
+    settings: Res<Markers2dSettings>,
+    markers: Query<(&GridMarker, &GlobalTransform, &Parent)>,
+    grids_2d: Query<
+        (&DebugGridView, &GlobalTransform),
+        (With<DebugGridView2d>, Without<DebugGridView3d>),
+    >,
+) {
+    for (marker, marker_gtransform, parent) in markers.iter() {
+        let Ok((grid_view, grid_gtransform)) = grids_2d.get(parent.get()) else {
+            continue;
+        };
+        let node_size =
+            (grid_view.node_size * grid_gtransform.compute_transform().scale).truncate();
+        let center = marker_gtransform.translation().truncate();
+        gizmos.rect_2d(center, 0., node_size * settings.size_ratio, marker.color);
+        if let Some(inner_size_ratio) = settings.inner_size_ratio {
+            gizmos.rect_2d(center, 0., node_size * inner_size_ratio, marker.color);
+        }
+    }
+}
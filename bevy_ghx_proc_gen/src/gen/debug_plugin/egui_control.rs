@@ -0,0 +1,239 @@
+use bevy::{
+    ecs::{
+        query::With,
+        system::{Query, Res, ResMut, Resource},
+    },
+    log::warn,
+};
+use bevy_egui::{
+    egui::{self, Color32, Pos2},
+    EguiContexts,
+};
+use bevy_ghx_grid::ghx_grid::coordinate_system::CoordinateSystem;
+use ghx_proc_gen::generator::{
+    node_heuristic::NodeSelectionHeuristic, observer::QueuedObserver, Generator, RngMode,
+};
+
+use crate::gen::lifecycle::LifecycleEventWriters;
+
+use super::{
+    generation::{regenerate, step_generation, ActiveGeneration, VoidNodes},
+    GenerationControl, GenerationControlStatus, GenerationViewMode, StepByStepTimed,
+};
+
+const NODE_HEURISTICS: [NodeSelectionHeuristic; 6] = [
+    NodeSelectionHeuristic::MinimumRemainingValue,
+    NodeSelectionHeuristic::MinimumEntropy,
+    NodeSelectionHeuristic::Random,
+    NodeSelectionHeuristic::GrowFromAnchors,
+    NodeSelectionHeuristic::LayerByLayer,
+    NodeSelectionHeuristic::PriorityFirst,
+];
+
+/// View mode used when switching to [`GenerationViewMode::StepByStepTimed`] from the panel
+const DEFAULT_STEP_BY_STEP_TIMED_VIEW_MODE: GenerationViewMode =
+    GenerationViewMode::StepByStepTimed {
+        steps_count: 1,
+        interval_ms: 10,
+    };
+
+/// Resource used to track the status of the egui control panel
+#[derive(Resource)]
+pub struct ControlPanelConfig {
+    /// Whether or not the control panel is currently enabled
+    pub enabled: bool,
+}
+
+impl Default for ControlPanelConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Context of the egui control panel
+#[derive(Resource, Default)]
+pub struct ControlPanelContext {
+    /// Text currently entered in the seed field. A random seed is used when empty.
+    pub seed_input: String,
+}
+
+/// System condition to check if the egui control panel is enabled
+pub fn control_panel_enabled(control_panel_config: Res<ControlPanelConfig>) -> bool {
+    control_panel_config.enabled
+}
+
+/// System that can be used to toggle on/off the egui control panel
+pub fn toggle_control_panel(mut control_panel_config: ResMut<ControlPanelConfig>) {
+    control_panel_config.enabled = !control_panel_config.enabled;
+}
+
+/// System used to draw the generation control egui window.
+///
+/// The panel acts on the [`GenerationViewMode`] `Resource`, the [`StepByStepTimed`] `Resource` (if present) and the [`Generator`] and [`GenerationControl`] of the [`ActiveGeneration`].
+pub fn draw_control_panel<C: CoordinateSystem>(
+    mut contexts: EguiContexts,
+    mut panel_context: ResMut<ControlPanelContext>,
+    mut view_mode: ResMut<GenerationViewMode>,
+    mut steps_and_timer: Option<ResMut<StepByStepTimed>>,
+    active_generation: Res<ActiveGeneration>,
+    mut lifecycle_events: LifecycleEventWriters,
+    mut observed_generations: Query<
+        (
+            &mut Generator<C>,
+            &mut GenerationControl,
+            Option<&VoidNodes>,
+        ),
+        With<QueuedObserver>,
+    >,
+) {
+    let Some(active_generation) = active_generation.0 else {
+        return;
+    };
+    let Ok((mut generator, mut generation_control, void_nodes)) =
+        observed_generations.get_mut(active_generation)
+    else {
+        return;
+    };
+    let no_void_nodes = VoidNodes::default();
+    let void_nodes = void_nodes.unwrap_or(&no_void_nodes);
+
+    egui::Window::new("Generation control")
+        .title_bar(true)
+        .default_pos(Pos2::new(10., 100.))
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("View mode: ");
+                let current_mode = *view_mode;
+                let timed_mode = match current_mode {
+                    GenerationViewMode::StepByStepTimed { .. } => current_mode,
+                    _ => DEFAULT_STEP_BY_STEP_TIMED_VIEW_MODE,
+                };
+                let mut mode = current_mode;
+                egui::ComboBox::from_id_source("view_mode")
+                    .selected_text(format!("{:?}", mode))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut mode, GenerationViewMode::Final, "Final");
+                        ui.selectable_value(
+                            &mut mode,
+                            GenerationViewMode::StepByStepManual,
+                            "StepByStepManual",
+                        );
+                        ui.selectable_value(&mut mode, timed_mode, "StepByStepTimed");
+                    });
+                // Only write on changes to not trigger the change detection every frame
+                if mode != current_mode {
+                    *view_mode = mode;
+                }
+            });
+            ui.horizontal_wrapped(|ui| {
+                let (status_text, button_text) = match generation_control.status {
+                    GenerationControlStatus::Ongoing => ("Ongoing", "⏸ Pause"),
+                    GenerationControlStatus::Paused => ("Paused", "▶ Resume"),
+                };
+                ui.label("Status: ");
+                ui.colored_label(Color32::WHITE, status_text);
+                if ui.button(button_text).clicked() {
+                    generation_control.status = match generation_control.status {
+                        GenerationControlStatus::Ongoing => GenerationControlStatus::Paused,
+                        GenerationControlStatus::Paused => GenerationControlStatus::Ongoing,
+                    };
+                }
+                if ui.button("⏭ Step").clicked() {
+                    step_generation(
+                        &mut generator,
+                        active_generation,
+                        void_nodes,
+                        &mut generation_control,
+                        &mut lifecycle_events,
+                    );
+                }
+            });
+            if let Some(steps_and_timer) = steps_and_timer.as_mut() {
+                ui.horizontal(|ui| {
+                    ui.label("Steps per tick: ");
+                    ui.add(
+                        egui::DragValue::new(&mut steps_and_timer.steps_count)
+                            .clamp_range(1..=u32::MAX),
+                    );
+                });
+            }
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Nodes left: ");
+                ui.colored_label(Color32::WHITE, format!("{}", generator.nodes_left()));
+            });
+
+            ui.separator();
+            ui.horizontal_wrapped(|ui| {
+                ui.label("🌱 Seed: ");
+                ui.colored_label(Color32::WHITE, format!("{}", generator.seed()));
+            });
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut panel_context.seed_input);
+                if ui
+                    .button("Regenerate")
+                    .on_hover_text(
+                        "Restart the generation with the entered seed, or a random seed if empty",
+                    )
+                    .clicked()
+                {
+                    let seed_input = panel_context.seed_input.trim();
+                    let rng_mode = match seed_input.is_empty() {
+                        true => Some(RngMode::RandomSeed),
+                        false => match seed_input.parse::<u64>() {
+                            Ok(seed) => Some(RngMode::Seeded(seed)),
+                            Err(_) => {
+                                warn!("Invalid seed `{}`, expected an u64", seed_input);
+                                None
+                            }
+                        },
+                    };
+                    if let Some(rng_mode) = rng_mode {
+                        regenerate(
+                            &mut generation_control,
+                            &mut generator,
+                            active_generation,
+                            rng_mode,
+                            &mut lifecycle_events,
+                        );
+                    }
+                }
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Max retry count: ");
+                let mut max_retry_count = generator.max_retry_count();
+                if ui.add(egui::DragValue::new(&mut max_retry_count)).changed() {
+                    generator.set_max_retry_count(max_retry_count);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Node heuristic: ");
+                let current_heuristic = generator.node_heuristic();
+                let mut heuristic = current_heuristic;
+                egui::ComboBox::from_id_source("node_heuristic")
+                    .selected_text(format!("{:?}", heuristic))
+                    .show_ui(ui, |ui| {
+                        for node_heuristic in NODE_HEURISTICS {
+                            ui.selectable_value(
+                                &mut heuristic,
+                                node_heuristic,
+                                format!("{:?}", node_heuristic),
+                            );
+                        }
+                    });
+                if heuristic != current_heuristic {
+                    generator.set_node_heuristic(heuristic);
+                }
+            });
+
+            ui.separator();
+            ui.checkbox(&mut generation_control.skip_void_nodes, "Skip void nodes");
+            ui.checkbox(&mut generation_control.pause_when_done, "Pause when done");
+            ui.checkbox(&mut generation_control.pause_on_error, "Pause on error");
+            ui.checkbox(
+                &mut generation_control.pause_on_reinitialize,
+                "Pause on reinitialize",
+            );
+        });
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, time::Duration};
 
 use bevy::{
     ecs::{
@@ -13,7 +13,7 @@ use bevy::{
     log::{info, warn},
     prelude::{Deref, DerefMut},
     render::color::Color,
-    time::Time,
+    time::{Time, Timer, TimerMode},
 };
 use bevy_ghx_grid::{
     debug_plugin::markers::{spawn_marker, MarkerDespawnEvent},
@@ -21,21 +21,23 @@ use bevy_ghx_grid::{
 };
 use ghx_proc_gen::{
     generator::{
-        model::ModelIndex,
-        observer::{GenerationUpdate, QueuedObserver},
-        GenerationStatus, Generator,
+        model::ModelIndex, observer::QueuedObserver, GenInfo, GeneratedNode, GenerationStatus,
+        Generator, RngMode,
     },
     GeneratorError, NodeIndex,
 };
 
-use crate::gen::GridNode;
+use crate::gen::{
+    generator_neighbours, lifecycle::LifecycleEventWriters, spawn_node_with_neighbours,
+    AssetsReady, GridNode,
+};
 
 use super::{
-    spawn_node, AssetSpawner, AssetsBundleSpawner, ComponentSpawner, GenerationControl,
-    GenerationControlStatus, ProcGenKeyBindings, StepByStepTimed,
+    AssetSpawner, AssetsBundleSpawner, ComponentSpawner, GenerationControl,
+    GenerationControlStatus, GenerationViewMode, ProcGenKeyBindings, StepByStepTimed,
 };
 
-/// Component used to store model indexes of models with no assets, just to be able to skip their generation when stepping
+/// Component used to store model indexes of models with no assets or marked as void in the rules, just to be able to skip their generation when stepping
 #[derive(Component, Default, Deref, DerefMut)]
 pub struct VoidNodes(pub HashSet<ModelIndex>);
 
@@ -58,6 +60,42 @@ pub enum GenerationEvent {
 #[derive(Resource, Default)]
 pub struct ActiveGeneration(pub Option<Entity>);
 
+/// Run condition returning `true` if the current [`GenerationViewMode`] is [`GenerationViewMode::StepByStepTimed`]
+pub fn step_by_step_timed_view_mode(view_mode: Res<GenerationViewMode>) -> bool {
+    matches!(*view_mode, GenerationViewMode::StepByStepTimed { .. })
+}
+
+/// Run condition returning `true` if the current [`GenerationViewMode`] is [`GenerationViewMode::StepByStepManual`]
+pub fn step_by_step_manual_view_mode(view_mode: Res<GenerationViewMode>) -> bool {
+    *view_mode == GenerationViewMode::StepByStepManual
+}
+
+/// Run condition returning `true` if the current [`GenerationViewMode`] is [`GenerationViewMode::Final`]
+pub fn final_view_mode(view_mode: Res<GenerationViewMode>) -> bool {
+    *view_mode == GenerationViewMode::Final
+}
+
+/// System that inserts (or removes) the [`StepByStepTimed`] `Resource` to match the current [`GenerationViewMode`]
+pub fn update_step_by_step_timed_from_view_mode(
+    mut commands: Commands,
+    view_mode: Res<GenerationViewMode>,
+) {
+    match *view_mode {
+        GenerationViewMode::StepByStepTimed {
+            steps_count,
+            interval_ms,
+        } => {
+            commands.insert_resource(StepByStepTimed {
+                steps_count,
+                timer: Timer::new(Duration::from_millis(interval_ms), TimerMode::Repeating),
+            });
+        }
+        _ => {
+            commands.remove_resource::<StepByStepTimed>();
+        }
+    }
+}
+
 /// Simple system that calculates and add a [`VoidNodes`] component for generator entites which don't have one yet.
 pub fn insert_void_nodes_to_new_generations<
     C: CoordinateSystem,
@@ -73,7 +111,9 @@ pub fn insert_void_nodes_to_new_generations<
     for (gen_entity, generation, asset_spawner) in new_generations.iter_mut() {
         let mut void_nodes = HashSet::new();
         for model_index in 0..generation.rules().original_models_count() {
-            if !asset_spawner.assets.contains_key(&model_index) {
+            if !asset_spawner.assets.contains_key(&model_index)
+                || generation.rules().is_void(model_index)
+            {
                 void_nodes.insert(model_index);
             }
         }
@@ -105,14 +145,36 @@ pub fn update_active_generation<C: CoordinateSystem>(
     }
 }
 
-/// This system pauses/unpauses the [`GenerationControlStatus`] in the [`GenerationControl`] `Resource` on a keypress.
+/// System used to insert a [GenerationControl] component, cloned from the [GenerationControl] `Resource`, into new generation entities
+pub fn insert_generation_control_to_new_generations<C: CoordinateSystem>(
+    mut commands: Commands,
+    mut lifecycle_events: LifecycleEventWriters,
+    default_generation_control: Res<GenerationControl>,
+    mut new_generations: Query<Entity, (With<Generator<C>>, Without<GenerationControl>)>,
+) {
+    for gen_entity in new_generations.iter_mut() {
+        commands
+            .entity(gen_entity)
+            .insert(default_generation_control.clone());
+        lifecycle_events.started(gen_entity);
+    }
+}
+
+/// This system pauses/unpauses the [`GenerationControlStatus`] in the [`GenerationControl`] of the [`ActiveGeneration`] on a keypress.
 ///
 /// The keybind is read from the [`ProcGenKeyBindings`] `Resource`
 pub fn update_generation_control(
     keys: Res<ButtonInput<KeyCode>>,
     proc_gen_key_bindings: Res<ProcGenKeyBindings>,
-    mut generation_control: ResMut<GenerationControl>,
+    active_generation: Res<ActiveGeneration>,
+    mut generation_controls: Query<&mut GenerationControl>,
 ) {
+    let Some(active_generation) = active_generation.0 else {
+        return;
+    };
+    let Ok(mut generation_control) = generation_controls.get_mut(active_generation) else {
+        return;
+    };
     if keys.just_pressed(proc_gen_key_bindings.pause_toggle) {
         generation_control.status = match generation_control.status {
             GenerationControlStatus::Ongoing => GenerationControlStatus::Paused,
@@ -121,15 +183,87 @@ pub fn update_generation_control(
     }
 }
 
+/// This system reinitializes the [`ActiveGeneration`] with a new random seed on a keypress, and resumes the [`GenerationControl`] so that the generation restarts according to the current [`super::GenerationViewMode`].
+///
+/// The previously spawned nodes are despawned by [`update_generation_view`] when it reads the reinitialization update.
+///
+/// The keybind is read from the [`ProcGenKeyBindings`] `Resource`
+pub fn regenerate_from_keybinds<C: CoordinateSystem>(
+    keys: Res<ButtonInput<KeyCode>>,
+    proc_gen_key_bindings: Res<ProcGenKeyBindings>,
+    active_generation: Res<ActiveGeneration>,
+    mut lifecycle_events: LifecycleEventWriters,
+    mut observed_generations: Query<
+        (&mut Generator<C>, &mut GenerationControl),
+        With<QueuedObserver>,
+    >,
+) {
+    if !keys.just_pressed(proc_gen_key_bindings.regenerate) {
+        return;
+    }
+    let Some(active_generation) = active_generation.0 else {
+        return;
+    };
+    let Ok((mut generator, mut generation_control)) =
+        observed_generations.get_mut(active_generation)
+    else {
+        return;
+    };
+
+    regenerate(
+        &mut generation_control,
+        &mut generator,
+        active_generation,
+        RngMode::RandomSeed,
+        &mut lifecycle_events,
+    );
+}
+
+/// Function used to reinitialize a generator with a new seed given by `rng_mode`, and to resume the [`GenerationControl`] so that the generation restarts.
+pub fn regenerate<C: CoordinateSystem>(
+    generation_control: &mut GenerationControl,
+    generator: &mut Generator<C>,
+    gen_entity: Entity,
+    rng_mode: RngMode,
+    lifecycle_events: &mut LifecycleEventWriters,
+) {
+    let status = generator.reinitialize_with_rng(rng_mode);
+    info!(
+        "Regenerating {:?} with seed: {}",
+        gen_entity,
+        generator.seed()
+    );
+    lifecycle_events.started(gen_entity);
+    match status {
+        GenerationStatus::Ongoing => {
+            generation_control.need_reinit = false;
+            generation_control.status = GenerationControlStatus::Ongoing;
+        }
+        GenerationStatus::Done => {
+            handle_generation_done(
+                generation_control,
+                generator,
+                gen_entity,
+                1,
+                lifecycle_events,
+            );
+        }
+    }
+}
+
 /// - reinitializes the generator if needed
 /// - returns `true` if the generation operation should continue, and `false` if it should stop
 pub fn handle_reinitialization_and_continue<C: CoordinateSystem>(
-    generation_control: &mut ResMut<GenerationControl>,
+    generation_control: &mut GenerationControl,
     generator: &mut Generator<C>,
+    gen_entity: Entity,
+    lifecycle_events: &mut LifecycleEventWriters,
 ) -> bool {
     if generation_control.need_reinit {
         generation_control.need_reinit = false;
-        match generator.reinitialize() {
+        let status = generator.reinitialize();
+        lifecycle_events.started(gen_entity);
+        match status {
             GenerationStatus::Ongoing => (),
             GenerationStatus::Done => {
                 info!(
@@ -137,6 +271,7 @@ pub fn handle_reinitialization_and_continue<C: CoordinateSystem>(
                     generator.seed(),
                     generator.grid()
                 );
+                lifecycle_events.done(gen_entity, GenInfo { try_count: 1 });
                 if generation_control.pause_when_done {
                     generation_control.status = GenerationControlStatus::Paused;
                 }
@@ -155,10 +290,11 @@ pub fn handle_reinitialization_and_continue<C: CoordinateSystem>(
 /// Function used to display some info about a generation that finished,
 /// as well as to properly handle reinitialization status and pause.
 pub fn handle_generation_done<C: CoordinateSystem>(
-    generation_control: &mut ResMut<GenerationControl>,
+    generation_control: &mut GenerationControl,
     generator: &mut Generator<C>,
     gen_entity: Entity,
     try_count: u32,
+    lifecycle_events: &mut LifecycleEventWriters,
 ) {
     info!(
         "Generation done {:?}, try_count: {}, seed: {}; grid: {}",
@@ -167,6 +303,7 @@ pub fn handle_generation_done<C: CoordinateSystem>(
         generator.seed(),
         generator.grid()
     );
+    lifecycle_events.done(gen_entity, GenInfo { try_count });
     generation_control.need_reinit = true;
     if generation_control.pause_when_done {
         generation_control.status = GenerationControlStatus::Paused;
@@ -176,10 +313,11 @@ pub fn handle_generation_done<C: CoordinateSystem>(
 /// Function used to display some info about a generation that failed,
 /// as well as to properly handle reinitialization status and pause.
 pub fn handle_generation_error<C: CoordinateSystem>(
-    generation_control: &mut ResMut<GenerationControl>,
+    generation_control: &mut GenerationControl,
     generator: &mut Generator<C>,
     gen_entity: Entity,
     node_index: NodeIndex,
+    lifecycle_events: &mut LifecycleEventWriters,
 ) {
     warn!(
         "Generation Failed {:?} at node {}, seed: {}; grid: {}",
@@ -188,27 +326,38 @@ pub fn handle_generation_error<C: CoordinateSystem>(
         generator.seed(),
         generator.grid()
     );
+    lifecycle_events.failed(gen_entity, GeneratorError { node_index });
     generation_control.need_reinit = true;
     if generation_control.pause_on_error {
         generation_control.status = GenerationControlStatus::Paused;
     }
 }
 
-/// This system request the full generation to a [`Generator`] component, if it is observed through a [`QueuedObserver`] component, if the current control status is [`GenerationControlStatus::Ongoing`] and if it is currently the [`ActiveGeneration`]
+/// This system request the full generation to a [`Generator`] component, if it is observed through a [`QueuedObserver`] component, if its [`GenerationControl`] status is [`GenerationControlStatus::Ongoing`] and if it is currently the [`ActiveGeneration`]
 pub fn generate_all<C: CoordinateSystem>(
-    mut generation_control: ResMut<GenerationControl>,
     active_generation: Res<ActiveGeneration>,
-    mut observed_generatiors: Query<&mut Generator<C>, With<QueuedObserver>>,
+    mut lifecycle_events: LifecycleEventWriters,
+    mut observed_generatiors: Query<
+        (&mut Generator<C>, &mut GenerationControl),
+        (With<QueuedObserver>, AssetsReady),
+    >,
 ) {
     let Some(active_generation) = active_generation.0 else {
         return;
     };
-    let Ok(mut generator) = observed_generatiors.get_mut(active_generation) else {
+    let Ok((mut generator, mut generation_control)) =
+        observed_generatiors.get_mut(active_generation)
+    else {
         return;
     };
 
     if generation_control.status == GenerationControlStatus::Ongoing {
-        if !handle_reinitialization_and_continue(&mut generation_control, &mut generator) {
+        if !handle_reinitialization_and_continue(
+            &mut generation_control,
+            &mut generator,
+            active_generation,
+            &mut lifecycle_events,
+        ) {
             return;
         }
 
@@ -219,6 +368,7 @@ pub fn generate_all<C: CoordinateSystem>(
                     &mut generator,
                     active_generation,
                     gen_info.try_count,
+                    &mut lifecycle_events,
                 );
             }
             Err(GeneratorError { node_index }) => {
@@ -227,68 +377,83 @@ pub fn generate_all<C: CoordinateSystem>(
                     &mut generator,
                     active_generation,
                     node_index,
+                    &mut lifecycle_events,
                 );
             }
         }
     }
 }
 
-/// This system steps a [`Generator`] component if it is  observed through a [`QueuedObserver`] component, if the current control status is [`GenerationControlStatus::Ongoing`], if it is currently the [`ActiveGeneration`] and if the appropriate keys are pressed.
+/// This system steps a [`Generator`] component if it is  observed through a [`QueuedObserver`] component, if its [`GenerationControl`] status is [`GenerationControlStatus::Ongoing`], if it is currently the [`ActiveGeneration`] and if the appropriate keys are pressed.
 ///
 /// The keybinds are read from the [`ProcGenKeyBindings`] `Resource`
 pub fn step_by_step_input_update<C: CoordinateSystem>(
     keys: Res<ButtonInput<KeyCode>>,
     proc_gen_key_bindings: Res<ProcGenKeyBindings>,
-    mut generation_control: ResMut<GenerationControl>,
     active_generation: Res<ActiveGeneration>,
-    mut observed_generations: Query<(&mut Generator<C>, &VoidNodes), With<QueuedObserver>>,
+    mut lifecycle_events: LifecycleEventWriters,
+    mut observed_generations: Query<
+        (&mut Generator<C>, &VoidNodes, &mut GenerationControl),
+        (With<QueuedObserver>, AssetsReady),
+    >,
 ) {
     let Some(active_generation) = active_generation.0 else {
         return;
     };
+    let Ok((mut generation, void_nodes, mut generation_control)) =
+        observed_generations.get_mut(active_generation)
+    else {
+        return;
+    };
 
     if generation_control.status == GenerationControlStatus::Ongoing
         && (keys.just_pressed(proc_gen_key_bindings.step)
             || keys.pressed(proc_gen_key_bindings.continuous_step))
     {
-        if let Ok((mut generation, void_nodes)) = observed_generations.get_mut(active_generation) {
-            step_generation(
-                &mut generation,
-                active_generation,
-                void_nodes,
-                &mut generation_control,
-            );
-        }
+        step_generation(
+            &mut generation,
+            active_generation,
+            void_nodes,
+            &mut generation_control,
+            &mut lifecycle_events,
+        );
     }
 }
 
-/// This system steps a [`Generator`] component if it is observed through a [`QueuedObserver`] component, if the current control status is [`GenerationControlStatus::Ongoing`] if it is currently the [`ActiveGeneration`] and if the timer in the [`StepByStepTimed`] `Resource` has finished.
+/// This system steps a [`Generator`] component if it is observed through a [`QueuedObserver`] component, if its [`GenerationControl`] status is [`GenerationControlStatus::Ongoing`] if it is currently the [`ActiveGeneration`] and if the timer in the [`StepByStepTimed`] `Resource` has finished.
 pub fn step_by_step_timed_update<C: CoordinateSystem>(
-    mut generation_control: ResMut<GenerationControl>,
     mut steps_and_timer: ResMut<StepByStepTimed>,
     time: Res<Time>,
     active_generation: Res<ActiveGeneration>,
-    mut observed_generations: Query<(&mut Generator<C>, &VoidNodes), With<QueuedObserver>>,
+    mut lifecycle_events: LifecycleEventWriters,
+    mut observed_generations: Query<
+        (&mut Generator<C>, &VoidNodes, &mut GenerationControl),
+        (With<QueuedObserver>, AssetsReady),
+    >,
 ) {
     let Some(active_generation) = active_generation.0 else {
         return;
     };
 
     steps_and_timer.timer.tick(time.delta());
+    let Ok((mut generation, void_nodes, mut generation_control)) =
+        observed_generations.get_mut(active_generation)
+    else {
+        return;
+    };
     if steps_and_timer.timer.finished()
         && generation_control.status == GenerationControlStatus::Ongoing
     {
-        if let Ok((mut generation, void_nodes)) = observed_generations.get_mut(active_generation) {
-            for _ in 0..steps_and_timer.steps_count {
-                step_generation(
-                    &mut generation,
-                    active_generation,
-                    void_nodes,
-                    &mut generation_control,
-                );
-                if generation_control.status != GenerationControlStatus::Ongoing {
-                    return;
-                }
+        for _ in 0..steps_and_timer.steps_count {
+            step_generation(
+                &mut generation,
+                active_generation,
+                void_nodes,
+                &mut generation_control,
+                &mut lifecycle_events,
+            );
+            if generation_control.status != GenerationControlStatus::Ongoing {
+                return;
             }
         }
     }
@@ -299,84 +464,147 @@ pub fn update_generation_view<C: CoordinateSystem, A: AssetsBundleSpawner, T: Co
     mut commands: Commands,
     mut marker_events: EventWriter<MarkerDespawnEvent>,
     mut generation_events: EventWriter<GenerationEvent>,
-    mut generators: Query<(
-        Entity,
-        &GridDefinition<C>,
-        &AssetSpawner<A, T>,
-        &mut QueuedObserver,
-        Option<&Children>,
-        Option<&mut ErrorMarkers>,
-    )>,
-    existing_nodes: Query<Entity, With<GridNode>>,
+    mut generators: Query<
+        (
+            Entity,
+            &GridDefinition<C>,
+            &Generator<C>,
+            &AssetSpawner<A, T>,
+            &mut QueuedObserver,
+            Option<&Children>,
+            Option<&mut ErrorMarkers>,
+        ),
+        AssetsReady,
+    >,
+    existing_nodes: Query<&GridNode>,
 ) {
-    for (grid_entity, grid, asset_spawner, mut observer, children, mut error_markers) in
+    for (grid_entity, grid, generator, asset_spawner, mut observer, children, mut error_markers) in
         generators.iter_mut()
     {
-        let mut reinitialized = false;
-        let mut nodes_to_spawn = Vec::new();
-        for update in observer.dequeue_all() {
-            match update {
-                GenerationUpdate::Generated(grid_node) => {
-                    nodes_to_spawn.push(grid_node);
+        let batch = observer.dequeue_batch();
+        if batch.reinitialized() {
+            generation_events.send(GenerationEvent::Reinitialized(grid_entity));
+            if let Some(children) = children {
+                for &child in children.iter() {
+                    if existing_nodes.get(child).is_ok() {
+                        commands.entity(child).despawn_recursive();
+                    }
+                }
+            }
+
+            if let Some(error_markers) = error_markers.as_mut() {
+                for marker in error_markers.iter() {
+                    marker_events.send(MarkerDespawnEvent::Marker(*marker));
                 }
-                GenerationUpdate::Reinitializing(_) => {
-                    reinitialized = true;
-                    nodes_to_spawn.clear();
+                error_markers.clear();
+            }
+        }
+
+        if let Some(error_markers) = error_markers.as_mut() {
+            for &node_index in batch.failed() {
+                error_markers.push(spawn_marker(
+                    &mut commands,
+                    grid_entity,
+                    Color::RED,
+                    grid.pos_from_index(node_index),
+                ));
+            }
+        }
+
+        let reverted_nodes: HashSet<NodeIndex> = batch.reverted().collect();
+        let generated_nodes: HashSet<NodeIndex> =
+            batch.generated().map(|node| node.node_index).collect();
+        // The assets of the generated neighbours of the updated nodes may depend on them, see `NeighbourCondition`: they are spawned again
+        let mut nodes_to_respawn = Vec::new();
+        for &node_index in reverted_nodes.iter().chain(generated_nodes.iter()) {
+            let position = grid.pos_from_index(node_index);
+            for &direction in grid.directions() {
+                let Some(neighbour_index) = grid.get_next_index_in_direction(&position, direction)
+                else {
+                    continue;
+                };
+                if reverted_nodes.contains(&neighbour_index)
+                    || generated_nodes.contains(&neighbour_index)
+                    || nodes_to_respawn
+                        .iter()
+                        .any(|(index, _)| *index == neighbour_index)
+                {
+                    continue;
                 }
-                GenerationUpdate::Failed(node_index) => {
-                    if let Some(error_markers) = error_markers.as_mut() {
-                        error_markers.push(spawn_marker(
-                            &mut commands,
-                            grid_entity,
-                            Color::RED,
-                            grid.pos_from_index(node_index),
-                        ));
+                if let [model_instance] = generator.get_models_on(neighbour_index).as_slice() {
+                    if asset_spawner
+                        .assets
+                        .has_conditions(model_instance.model_index)
+                    {
+                        nodes_to_respawn.push((neighbour_index, *model_instance));
                     }
                 }
             }
         }
 
-        if reinitialized {
-            generation_events.send(GenerationEvent::Reinitialized(grid_entity));
+        if !reverted_nodes.is_empty() || !nodes_to_respawn.is_empty() {
             if let Some(children) = children {
                 for &child in children.iter() {
                     if let Ok(node) = existing_nodes.get(child) {
-                        commands.entity(node).despawn_recursive();
+                        if reverted_nodes.contains(&node.0)
+                            || nodes_to_respawn.iter().any(|(index, _)| *index == node.0)
+                        {
+                            commands.entity(child).despawn_recursive();
+                        }
                     }
                 }
             }
-
-            if let Some(error_markers) = error_markers.as_mut() {
-                for marker in error_markers.iter() {
-                    marker_events.send(MarkerDespawnEvent::Marker(*marker));
-                }
-                error_markers.clear();
+            for &node_index in reverted_nodes.iter() {
+                generation_events.send(GenerationEvent::Updated(grid_entity, node_index));
             }
         }
 
-        for grid_node in nodes_to_spawn {
-            generation_events.send(GenerationEvent::Updated(grid_entity, grid_node.node_index));
+        let respawned_nodes = nodes_to_respawn
+            .into_iter()
+            .map(|(node_index, model_instance)| GeneratedNode {
+                node_index,
+                model_instance,
+            });
+        for grid_node in batch.generated().copied().chain(respawned_nodes) {
+            if generated_nodes.contains(&grid_node.node_index) {
+                generation_events.send(GenerationEvent::Updated(grid_entity, grid_node.node_index));
+            }
 
-            spawn_node(
+            if generator.rules().is_spawned_empty(
+                grid_node.model_instance.model_index,
+                generator.seed(),
+                grid_node.node_index,
+            ) {
+                continue;
+            }
+            spawn_node_with_neighbours(
                 &mut commands,
                 grid_entity,
                 &grid,
                 asset_spawner,
                 &grid_node.model_instance,
                 grid_node.node_index,
+                generator_neighbours(generator, grid_node.node_index),
             );
         }
     }
 }
 
-fn step_generation<C: CoordinateSystem>(
+/// Function used to step a generation once, skipping over void nodes if requested by the [`GenerationControl`]
+pub fn step_generation<C: CoordinateSystem>(
     generator: &mut Generator<C>,
     gen_entity: Entity,
     void_nodes: &VoidNodes,
-    generation_control: &mut ResMut<GenerationControl>,
+    generation_control: &mut GenerationControl,
+    lifecycle_events: &mut LifecycleEventWriters,
 ) {
     loop {
-        if !handle_reinitialization_and_continue(generation_control, generator) {
+        if !handle_reinitialization_and_continue(
+            generation_control,
+            generator,
+            gen_entity,
+            lifecycle_events,
+        ) {
             break;
         }
 
@@ -392,13 +620,25 @@ fn step_generation<C: CoordinateSystem>(
                 match status {
                     GenerationStatus::Ongoing => {}
                     GenerationStatus::Done => {
-                        handle_generation_done(generation_control, generator, gen_entity, 1);
+                        handle_generation_done(
+                            generation_control,
+                            generator,
+                            gen_entity,
+                            1,
+                            lifecycle_events,
+                        );
                         break;
                     }
                 }
             }
             Err(GeneratorError { node_index }) => {
-                handle_generation_error(generation_control, generator, gen_entity, node_index);
+                handle_generation_error(
+                    generation_control,
+                    generator,
+                    gen_entity,
+                    node_index,
+                    lifecycle_events,
+                );
                 break;
             }
         }
@@ -0,0 +1,93 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        query::Has,
+        system::{Query, Res},
+    },
+    gizmos::gizmos::Gizmos,
+    math::Vec3,
+    render::color::Color,
+    transform::components::GlobalTransform,
+};
+use bevy_ghx_grid::{
+    debug_plugin::view::DebugGridView3d,
+    ghx_grid::{coordinate_system::CoordinateSystem, grid::GridDefinition},
+};
+
+use super::layer_slicing::LayerSlicing;
+
+/// Component to insert on a grid entity to draw its grid lines with Bevy's [`Gizmos`], an alternative to the mesh drawn by the `DebugGridView` of `bevy_ghx_grid`.
+///
+/// No mesh or material is needed: the lines are drawn every frame from this component, so [`GizmoGridView::color`] and [`GizmoGridView::visible`] can be changed at any time. The lines are drawn in the local space of the grid entity, following the node layout of [`crate::gen::GridWorldTransform`].
+///
+/// Axes of size 1 are flat: only their first plane is drawn, so that 2d grids (and single layer 3d grids) are drawn as a single plane. Grids with a [`DebugGridView3d`] only draw the plane of the sliced layer when the [`LayerSlicing`] is enabled.
+#[derive(Component, Clone, Debug)]
+pub struct GizmoGridView {
+    /// Whether or not the grid lines are drawn
+    pub visible: bool,
+    /// Color of the grid lines
+    pub color: Color,
+    /// Size of a node in world units, should be the same as the `node_size` of the assets spawner
+    pub node_size: Vec3,
+}
+
+impl GizmoGridView {
+    /// Creates a visible [`GizmoGridView`]
+    pub fn new(color: Color, node_size: Vec3) -> Self {
+        Self {
+            visible: true,
+            color,
+            node_size,
+        }
+    }
+}
+
+/// System drawing the grid lines of the grids of the coordinate system `C` with a [`GizmoGridView`]
+pub fn draw_gizmo_grids<C: CoordinateSystem>(
+    mut gizmos: Gizmos,
+    slicing: Res<LayerSlicing>,
+    grids: Query<(
+        &GridDefinition<C>,
+        &GizmoGridView,
+        &GlobalTransform,
+        Has<DebugGridView3d>,
+    )>,
+) {
+    for (grid, view, grid_gtransform, view_3d) in grids.iter() {
+        if !view.visible {
+            continue;
+        }
+        let mut size = [grid.size_x(), grid.size_y(), grid.size_z()];
+        let mut origin = Vec3::ZERO;
+        if let (true, Some(layer)) = (view_3d, slicing.layer) {
+            if layer >= size[1] {
+                continue;
+            }
+            // Flattens the Y axis on the sliced layer
+            size[1] = 1;
+            origin.y = layer as f32;
+        }
+        // Number of planes along each axis, a flat axis only has its first plane
+        let planes = size.map(|size| if size <= 1 { 1 } else { size + 1 });
+        for axis in 0..3 {
+            if size[axis] <= 1 {
+                continue;
+            }
+            let (axis_a, axis_b) = ((axis + 1) % 3, (axis + 2) % 3);
+            for a in 0..planes[axis_a] {
+                for b in 0..planes[axis_b] {
+                    let mut start = origin;
+                    start[axis_a] += a as f32;
+                    start[axis_b] += b as f32;
+                    let mut end = start;
+                    end[axis] += size[axis] as f32;
+                    gizmos.line(
+                        grid_gtransform.transform_point(start * view.node_size),
+                        grid_gtransform.transform_point(end * view.node_size),
+                        view.color,
+                    );
+                }
+            }
+        }
+    }
+}
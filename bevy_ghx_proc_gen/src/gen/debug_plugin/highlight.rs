@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        system::{Commands, Query, Res},
+    },
+    log::warn,
+    render::color::Color,
+    time::{Time, Timer, TimerMode},
+};
+use bevy_ghx_grid::{
+    debug_plugin::markers::{spawn_marker, MarkerDespawnEvent},
+    ghx_grid::{coordinate_system::CoordinateSystem, grid::GridDefinition},
+};
+use ghx_proc_gen::NodeIndex;
+
+/// Event requesting the debug plugin to highlight some nodes of a grid with [GridMarker](bevy_ghx_grid::debug_plugin::markers::GridMarker)s of a given color, for a given duration.
+///
+/// Can be sent from any system to visualize propagation waves, the neighbourhood of a contradiction, the results of a query, ...
+#[derive(Event, Clone, Debug)]
+pub struct HighlightNodesEvent {
+    /// Grid entity of the nodes
+    pub grid: Entity,
+    /// Indexes of the nodes to highlight. Indexes out of the grid are ignored.
+    pub nodes: Vec<NodeIndex>,
+    /// Color of the highlight
+    pub color: Color,
+    /// How long the nodes stay highlighted
+    pub duration: Duration,
+}
+
+/// Component of the entities tracking the markers spawned for a [HighlightNodesEvent]
+#[derive(Component, Debug)]
+pub struct NodesHighlight {
+    /// Remaining time before the markers are despawned
+    pub timer: Timer,
+    /// Markers entities
+    pub markers: Vec<Entity>,
+}
+
+/// System spawning the markers requested by the [HighlightNodesEvent] targeting grids of the coordinate system `C`
+pub fn highlight_nodes_from_events<C: CoordinateSystem>(
+    mut commands: Commands,
+    mut events: EventReader<HighlightNodesEvent>,
+    grids: Query<&GridDefinition<C>>,
+) {
+    for event in events.read() {
+        let Ok(grid) = grids.get(event.grid) else {
+            continue;
+        };
+        let markers: Vec<Entity> = event
+            .nodes
+            .iter()
+            .filter(|&&node_index| node_index < grid.total_size())
+            .map(|&node_index| {
+                spawn_marker(
+                    &mut commands,
+                    event.grid,
+                    event.color,
+                    grid.pos_from_index(node_index),
+                )
+            })
+            .collect();
+        if markers.len() != event.nodes.len() {
+            warn!(
+                "{} nodes to highlight are out of the grid ({} nodes)",
+                event.nodes.len() - markers.len(),
+                grid.total_size()
+            );
+        }
+        commands.spawn(NodesHighlight {
+            timer: Timer::new(event.duration, TimerMode::Once),
+            markers,
+        });
+    }
+}
+
+/// System despawning the markers of the [NodesHighlight] which have expired
+pub fn update_nodes_highlights(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut marker_events: EventWriter<MarkerDespawnEvent>,
+    mut highlights: Query<(Entity, &mut NodesHighlight)>,
+) {
+    for (highlight_entity, mut highlight) in highlights.iter_mut() {
+        if highlight.timer.tick(time.delta()).finished() {
+            for marker in highlight.markers.drain(..) {
+                marker_events.send(MarkerDespawnEvent::Marker(marker));
+            }
+            commands.entity(highlight_entity).despawn();
+        }
+    }
+}
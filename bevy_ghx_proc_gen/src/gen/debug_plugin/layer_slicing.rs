@@ -0,0 +1,90 @@
+use bevy::{
+    ecs::{
+        query::With,
+        system::{Query, Res, ResMut, Resource},
+        world::Ref,
+    },
+    hierarchy::Parent,
+    input::{keyboard::KeyCode, ButtonInput},
+    log::info,
+    render::view::Visibility,
+};
+use bevy_ghx_grid::{
+    debug_plugin::view::DebugGridView3d,
+    ghx_grid::{coordinate_system::CoordinateSystem, grid::GridDefinition},
+};
+
+use crate::gen::GridNode;
+
+use super::ProcGenKeyBindings;
+
+/// Resource used to only show a single Y layer of the grids displayed with a [`DebugGridView3d`], to inspect the interior of dense 3d generations.
+///
+/// When a layer is set, the spawned nodes of the other layers are hidden, and the [`super::gizmo_grid::GizmoGridView`] of the grids only draw the sliced layer. The layer can be changed with the keybindings of the [`ProcGenKeyBindings`] or directly through the resource.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerSlicing {
+    /// Y layer shown. `None` to show all the layers.
+    pub layer: Option<u32>,
+}
+
+impl LayerSlicing {
+    /// Returns `true` if the nodes on layer `y` are shown
+    pub fn shows(&self, y: u32) -> bool {
+        self.layer.map_or(true, |layer| layer == y)
+    }
+}
+
+/// System enabling/disabling the [`LayerSlicing`] and moving the sliced layer up/down on a keypress.
+///
+/// The keybinds are read from the [`ProcGenKeyBindings`] `Resource`
+pub fn update_layer_slicing_from_keybinds(
+    keys: Res<ButtonInput<KeyCode>>,
+    proc_gen_key_bindings: Res<ProcGenKeyBindings>,
+    mut slicing: ResMut<LayerSlicing>,
+) {
+    let layer = if keys.just_pressed(proc_gen_key_bindings.toggle_layer_slicing) {
+        match slicing.layer {
+            Some(_) => None,
+            None => Some(0),
+        }
+    } else if keys.just_pressed(proc_gen_key_bindings.layer_up) {
+        slicing.layer.map(|layer| layer.saturating_add(1))
+    } else if keys.just_pressed(proc_gen_key_bindings.layer_down) {
+        slicing.layer.map(|layer| layer.saturating_sub(1))
+    } else {
+        return;
+    };
+    // Only write on changes to not trigger the change detection
+    if layer != slicing.layer {
+        slicing.layer = layer;
+        match layer {
+            Some(layer) => info!("Layer slicing: showing layer {}", layer),
+            None => info!("Layer slicing: showing all layers"),
+        }
+    }
+}
+
+/// System hiding the spawned nodes of the grids of the coordinate system `C` which are not on the layer of the [`LayerSlicing`].
+///
+/// Nodes are updated when the [`LayerSlicing`] changes or when they are spawned.
+pub fn apply_layer_slicing<C: CoordinateSystem>(
+    slicing: Res<LayerSlicing>,
+    grids: Query<&GridDefinition<C>, With<DebugGridView3d>>,
+    mut nodes: Query<(Ref<GridNode>, &Parent, &mut Visibility)>,
+) {
+    for (node, parent, mut visibility) in nodes.iter_mut() {
+        if !slicing.is_changed() && !node.is_added() {
+            continue;
+        }
+        let Ok(grid) = grids.get(parent.get()) else {
+            continue;
+        };
+        let target = match slicing.shows(grid.pos_from_index(node.0).y) {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        if *visibility != target {
+            *visibility = target;
+        }
+    }
+}
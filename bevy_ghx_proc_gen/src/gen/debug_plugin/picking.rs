@@ -4,7 +4,7 @@ use bevy::{
         component::Component,
         entity::Entity,
         event::{Event, EventReader, EventWriter},
-        query::{Added, Changed, With, Without},
+        query::{Added, With, Without},
         system::{Commands, Local, Query, Res, ResMut, Resource},
     },
     hierarchy::{BuildChildren, DespawnRecursiveExt, Parent},
@@ -14,18 +14,20 @@ use bevy::{
     prelude::{Deref, DerefMut},
     render::{color::Color, mesh::Mesh},
     sprite::{Sprite, SpriteBundle},
-    text::Text,
     transform::components::Transform,
     utils::default,
 };
 
 use bevy_ghx_grid::{
     debug_plugin::{
-        get_translation_from_grid_coords_3d,
         markers::{GridMarker, MarkerDespawnEvent},
         view::{DebugGridView, DebugGridView2d, DebugGridView3d},
     },
-    ghx_grid::{coordinate_system::CoordinateSystem, direction::Direction, grid::GridDefinition},
+    ghx_grid::{
+        coordinate_system::CoordinateSystem,
+        direction::Direction,
+        grid::{GridDefinition, GridPosition},
+    },
 };
 use bevy_mod_picking::{
     events::Out,
@@ -34,43 +36,17 @@ use bevy_mod_picking::{
 };
 use ghx_proc_gen::{generator::Generator, NodeIndex};
 
-use crate::gen::GridNode;
+use crate::gen::{GridNode, GridWorldTransform};
 
 use super::{
     cursor::{
-        cursor_info_to_string, Cursor, CursorBehavior, CursorInfo, CursorMarkerSettings,
-        CursorsPanelText, SelectCursor, TargetedNode, OVER_CURSOR_SECTION_INDEX,
+        Cursor, CursorBehavior, CursorMarkerSettings, OverCursor, SelectCursor, SelectNode,
+        TargetedNode,
     },
-    generation::{ActiveGeneration, GenerationEvent},
+    generation::ActiveGeneration,
     ProcGenKeyBindings,
 };
 
-/// Used to customize the color of the Over cursor [GridMarker]
-#[derive(Resource)]
-pub struct OverCursorMarkerSettings(pub Color);
-impl Default for OverCursorMarkerSettings {
-    fn default() -> Self {
-        Self(Color::rgb(0.85, 0.85, 0.73))
-    }
-}
-impl CursorMarkerSettings for OverCursorMarkerSettings {
-    fn color(&self) -> Color {
-        self.0
-    }
-}
-
-/// Main component for the Over cursor
-#[derive(Component, Debug)]
-pub struct OverCursor;
-impl CursorBehavior for OverCursor {
-    fn new() -> Self {
-        Self
-    }
-    fn updates_active_gen() -> bool {
-        false
-    }
-}
-
 /// Event raised when a node starts being overed by a mouse pointer
 #[derive(Event, Deref, DerefMut)]
 pub struct NodeOverEvent(pub Entity);
@@ -116,52 +92,6 @@ pub fn insert_cursor_picking_handlers_to_grid_nodes<C: CoordinateSystem>(
     }
 }
 
-/// System that update the over cursor UI panel
-pub fn update_over_cursor_panel_text(
-    mut cursors_panel_text: Query<&mut Text, With<CursorsPanelText>>,
-    updated_cursors: Query<(&CursorInfo, &Cursor), (Changed<CursorInfo>, With<OverCursor>)>,
-) {
-    if let Ok((cursor_info, cursor)) = updated_cursors.get_single() {
-        for mut text in &mut cursors_panel_text {
-            let ui_text = &mut text.sections[OVER_CURSOR_SECTION_INDEX].value;
-            match &cursor.0 {
-                Some(overed_node) => {
-                    *ui_text = format!(
-                        "Hovered:\n{}",
-                        cursor_info_to_string(overed_node, cursor_info)
-                    );
-                }
-                None => ui_text.clear(),
-            }
-        }
-    }
-}
-
-/// System updating the Over [Cursor] by reading all the [GenerationEvent]
-///
-/// Should run after update_cursors_info_on_cursors_changes and before update_cursors_info_from_generation_events
-pub fn update_over_cursor_from_generation_events<C: CoordinateSystem>(
-    mut cursors_events: EventReader<GenerationEvent>,
-    mut marker_events: EventWriter<MarkerDespawnEvent>,
-    mut over_cursor: Query<&mut Cursor, With<OverCursor>>,
-) {
-    let Ok(mut cursor) = over_cursor.get_single_mut() else {
-        return;
-    };
-    for event in cursors_events.read() {
-        match event {
-            GenerationEvent::Reinitialized(_grid_entity) => {
-                // If there is an Over cursor, force despawn it, since we will despawn the underlying node there won't be any NodeOutEvent.
-                if let Some(overed_node) = &cursor.0 {
-                    marker_events.send(MarkerDespawnEvent::Marker(overed_node.marker));
-                    cursor.0 = None;
-                }
-            }
-            GenerationEvent::Updated(_grid_entity, _node_index) => {}
-        }
-    }
-}
-
 /// System used to update cursor positions from picking events
 pub fn picking_update_cursors_position<
     C: CoordinateSystem,
@@ -224,6 +154,26 @@ pub fn picking_update_cursors_position<
     }
 }
 
+/// Adapter system translating the [NodeSelectedEvent] on nodes of grids of the coordinate system `C` into [SelectNode] events, which are then handled like any other selection
+pub fn picking_select_nodes<C: CoordinateSystem>(
+    mut events: EventReader<NodeSelectedEvent>,
+    mut select_events: EventWriter<SelectNode>,
+    grid_nodes: Query<(&GridNode, &Parent)>,
+    generations: Query<(), (With<Generator<C>>, With<GridDefinition<C>>)>,
+) {
+    for event in events.read() {
+        let Ok((node, node_parent)) = grid_nodes.get(**event) else {
+            continue;
+        };
+        if generations.contains(node_parent.get()) {
+            select_events.send(SelectNode {
+                grid: node_parent.get(),
+                node_index: node.0,
+            });
+        }
+    }
+}
+
 /// System used to remove an Over cursor on a [NodeOutEvent]
 pub fn picking_remove_previous_over_cursor<C: CoordinateSystem>(
     mut out_events: EventReader<NodeOutEvent>,
@@ -431,7 +381,8 @@ pub fn spawn_cursor_targets_3d<C: CoordinateSystem>(
     node_size: &Vec3,
 ) {
     let mut spawn_cursor_target = |x: u32, y: u32, z: u32| {
-        let translation = get_translation_from_grid_coords_3d(x, y, z, node_size);
+        let translation =
+            GridWorldTransform::new(*node_size).node_to_world(&GridPosition::new(x, y, z));
         let helper_node_entity = commands
             .spawn((
                 GridNode(grid.index_from_coords(x, y, z)),
@@ -495,7 +446,8 @@ pub fn spawn_cursor_targets_2d<C: CoordinateSystem>(
     node_size: &Vec3,
 ) {
     let mut spawn_cursor_target = |x: u32, y: u32, z: u32| {
-        let mut translation = get_translation_from_grid_coords_3d(x, y, z, node_size);
+        let mut translation =
+            GridWorldTransform::new(*node_size).node_to_world(&GridPosition::new(x, y, z));
         translation.z += node_size.z;
         let helper_node_entity = commands
             .spawn((
@@ -0,0 +1,190 @@
+use bevy::{
+    ecs::{
+        entity::Entity,
+        event::EventWriter,
+        query::{Has, With, Without},
+        system::{Commands, Query, Res, Resource},
+    },
+    input::{mouse::MouseButton, ButtonInput},
+    math::{Ray3d, Vec3},
+    render::camera::Camera,
+    transform::components::GlobalTransform,
+    window::{PrimaryWindow, Window},
+};
+use bevy_ghx_grid::{
+    debug_plugin::{
+        markers::MarkerDespawnEvent,
+        view::{DebugGridView, DebugGridView3d},
+    },
+    ghx_grid::{coordinate_system::CoordinateSystem, grid::GridDefinition},
+};
+use ghx_proc_gen::{generator::Generator, NodeIndex};
+
+use crate::gen::GridWorldTransform;
+
+use super::cursor::{
+    spawn_marker_and_create_cursor, Cursor, CursorMarkerSettings, GridCursorsOverlayCamera,
+    OverCursor, OverCursorMarkerSettings, SelectCursor, SelectNode,
+};
+
+/// Resource used to configure the grid-plane raycast of the Over cursor.
+///
+/// When enabled, the node under the mouse is computed from the camera ray and the grids [`GridDefinition`] and [`GlobalTransform`], without any picking component on the spawned nodes. The ray is intersected with a single layer of each grid: the Y layer for grids viewed with a [`DebugGridView3d`], the Z layer otherwise. A left click on the hovered node sends a [`SelectNode`] event.
+#[derive(Resource, Debug)]
+pub struct GridRaycastCursorSettings {
+    /// Whether or not the Over cursor is updated by the grid-plane raycast. Defaults to `true` when the `picking` feature is disabled.
+    ///
+    /// When enabled, no picking event handlers are inserted on the spawned nodes.
+    pub enabled: bool,
+    /// Layer intersected by the ray when the selection cursor is not on the grid. When the selection cursor is on the grid, the layer of the selected node is used.
+    pub default_layer: u32,
+}
+
+impl Default for GridRaycastCursorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: cfg!(not(feature = "picking")),
+            default_layer: 0,
+        }
+    }
+}
+
+/// Run condition returning `true` when the grid-plane raycast is enabled in the [`GridRaycastCursorSettings`]
+pub fn grid_raycast_enabled(settings: Res<GridRaycastCursorSettings>) -> bool {
+    settings.enabled
+}
+
+/// System moving the Over cursor to the closest node of a grid of the coordinate system `C` hit by the mouse ray, and sending a [`SelectNode`] event on a left click
+pub fn raycast_update_over_cursor<C: CoordinateSystem>(
+    mut commands: Commands,
+    settings: Res<GridRaycastCursorSettings>,
+    over_marker_settings: Res<OverCursorMarkerSettings>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut marker_events: EventWriter<MarkerDespawnEvent>,
+    mut select_events: EventWriter<SelectNode>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    just_one_camera: Query<(&Camera, &GlobalTransform), Without<GridCursorsOverlayCamera>>,
+    overlay_camera: Query<(&Camera, &GlobalTransform), With<GridCursorsOverlayCamera>>,
+    selection_cursor: Query<&Cursor, (With<SelectCursor>, Without<OverCursor>)>,
+    mut over_cursor: Query<&mut Cursor, With<OverCursor>>,
+    grids: Query<
+        (
+            Entity,
+            &GridDefinition<C>,
+            &DebugGridView,
+            &GlobalTransform,
+            Has<DebugGridView3d>,
+        ),
+        With<Generator<C>>,
+    >,
+) {
+    let Ok(mut cursor) = over_cursor.get_single_mut() else {
+        return;
+    };
+    let Ok((camera, cam_gtransform)) = just_one_camera
+        .get_single()
+        .or_else(|_| overlay_camera.get_single())
+    else {
+        return;
+    };
+    let ray = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|cursor_position| camera.viewport_to_world(cam_gtransform, cursor_position));
+
+    let selected_node = selection_cursor
+        .get_single()
+        .ok()
+        .and_then(|c| c.0.as_ref());
+    let mut closest_hit: Option<(f32, Entity, NodeIndex)> = None;
+    if let Some(ray) = ray {
+        for (grid_entity, grid, grid_view, grid_gtransform, view_3d) in grids.iter() {
+            let axis = if view_3d { 1 } else { 2 };
+            let layer = match selected_node {
+                Some(node) if node.grid == grid_entity => match view_3d {
+                    true => node.position.y,
+                    false => node.position.z,
+                },
+                _ => settings.default_layer,
+            };
+            if let Some((distance, node_index)) =
+                raycast_grid_layer(ray, grid, grid_view.node_size, grid_gtransform, axis, layer)
+            {
+                if closest_hit.map_or(true, |(closest, _, _)| distance < closest) {
+                    closest_hit = Some((distance, grid_entity, node_index));
+                }
+            }
+        }
+    }
+
+    let Some((_, grid_entity, node_index)) = closest_hit else {
+        // Nothing hovered on the grids of this coordinate system
+        if let Some(overed_node) = &cursor.0 {
+            if grids.contains(overed_node.grid) {
+                marker_events.send(MarkerDespawnEvent::Marker(overed_node.marker));
+                cursor.0 = None;
+            }
+        }
+        return;
+    };
+
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        select_events.send(SelectNode {
+            grid: grid_entity,
+            node_index,
+        });
+    }
+    if let Some(overed_node) = &cursor.0 {
+        if overed_node.grid == grid_entity && overed_node.node_index == node_index {
+            return;
+        }
+        marker_events.send(MarkerDespawnEvent::Marker(overed_node.marker));
+    }
+    let Ok((_, grid, _, _, _)) = grids.get(grid_entity) else {
+        return;
+    };
+    cursor.0 = Some(spawn_marker_and_create_cursor(
+        &mut commands,
+        grid_entity,
+        grid.pos_from_index(node_index),
+        node_index,
+        over_marker_settings.color(),
+    ));
+}
+
+/// Intersects `ray` with the middle plane of the layer `layer` of `grid`, along the local `axis` (0 for X, 1 for Y, 2 for Z).
+///
+/// Returns the distance from the ray origin to the hit point and the index of the hit node, or `None` if the ray misses the layer.
+pub fn raycast_grid_layer<C: CoordinateSystem>(
+    ray: Ray3d,
+    grid: &GridDefinition<C>,
+    node_size: Vec3,
+    grid_gtransform: &GlobalTransform,
+    axis: usize,
+    layer: u32,
+) -> Option<(f32, NodeIndex)> {
+    let to_local = grid_gtransform.affine().inverse();
+    let origin = to_local.transform_point3(ray.origin);
+    let direction = to_local.transform_vector3(*ray.direction);
+    if direction[axis].abs() <= f32::EPSILON {
+        return None;
+    }
+
+    let world_transform = GridWorldTransform::new(node_size);
+    let plane = world_transform.coords_to_world(0., 0., 0.)[axis] + layer as f32 * node_size[axis];
+    let t = (plane - origin[axis]) / direction[axis];
+    if t < 0. {
+        return None;
+    }
+    let mut hit = origin + direction * t;
+    // Avoid rounding errors on the intersected axis
+    hit[axis] = plane;
+
+    let position = world_transform.world_to_node(grid, hit)?;
+    let distance = ray.origin.distance(grid_gtransform.transform_point(hit));
+    Some((
+        distance,
+        grid.index_from_coords(position.x, position.y, position.z),
+    ))
+}
@@ -0,0 +1,274 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        system::{Commands, Local, Query, Res, ResMut, Resource},
+    },
+    gizmos::gizmos::Gizmos,
+    input::{keyboard::KeyCode, ButtonInput},
+    log::info,
+    math::Vec3,
+    render::{camera::Camera, color::Color, view::Visibility},
+    text::{BreakLineOn, Text, TextSection, TextStyle},
+    transform::components::GlobalTransform,
+    ui::{node_bundles::TextBundle, PositionType, Style, Val},
+};
+use bevy_ghx_grid::{
+    debug_plugin::view::DebugGridView,
+    ghx_grid::{coordinate_system::CoordinateSystem, direction::Direction},
+};
+use ghx_proc_gen::{
+    generator::{model::ModelVariantIndex, Generator},
+    NodeIndex,
+};
+
+use crate::gen::GridWorldTransform;
+
+use super::{
+    cursor::{Cursor, GridCursorsOverlayCamera, SelectCursor},
+    ProcGenKeyBindings,
+};
+
+/// Distance from the center of the node to the socket labels, as a ratio of the node size
+const SOCKET_LABEL_DISTANCE: f32 = 0.45;
+/// Distance from the center of the node to the base of the socket arrows, as a ratio of the node size
+const SOCKET_ARROW_START: f32 = 0.1;
+
+/// Resource used to display the sockets of the model generated on the node selected by the [`SelectCursor`]: an arrow from the center of the node towards each direction, and a label with the sockets of the model in this direction.
+///
+/// The labels use the socket names of the rules when the sockets were named (see [`ghx_proc_gen::generator::socket::SocketCollection::set_name`]), which makes it easier to see why two neighbours are, or are not, compatible. Nothing is displayed while the selected node is not generated.
+///
+/// Disabled by default, can be toggled with [`ProcGenKeyBindings::toggle_socket_directions`].
+#[derive(Resource, Clone, Debug)]
+pub struct SocketDirectionsView {
+    /// Whether or not the socket directions of the selected node are displayed
+    pub enabled: bool,
+    /// Color of the direction arrows
+    pub arrow_color: Color,
+    /// Color of the socket labels
+    pub text_color: Color,
+    /// Font size of the socket labels
+    pub font_size: f32,
+}
+
+impl Default for SocketDirectionsView {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            arrow_color: Color::ORANGE,
+            text_color: Color::WHITE,
+            font_size: 14.0,
+        }
+    }
+}
+
+/// A label displaying the sockets of the selected node in one direction, spawned when the [`SocketDirectionsView`] is enabled
+#[derive(Component, Debug)]
+pub struct SocketLabel {
+    /// Grid entity of the label
+    pub grid: Entity,
+    /// Position of the label in the grid local space
+    pub local_position: Vec3,
+}
+
+/// Socket labels currently spawned by [`draw_socket_directions`]
+#[derive(Default)]
+pub struct SpawnedSocketLabels {
+    /// Grid, node and model variant displayed by the labels
+    displayed: Option<(Entity, NodeIndex, ModelVariantIndex)>,
+    labels: Vec<Entity>,
+}
+
+/// System enabling/disabling the [`SocketDirectionsView`] on a keypress.
+///
+/// The keybinds are read from the [`ProcGenKeyBindings`] `Resource`
+pub fn toggle_socket_directions_from_keybinds(
+    keys: Res<ButtonInput<KeyCode>>,
+    proc_gen_key_bindings: Res<ProcGenKeyBindings>,
+    mut view: ResMut<SocketDirectionsView>,
+) {
+    if keys.just_pressed(proc_gen_key_bindings.toggle_socket_directions) {
+        view.enabled = !view.enabled;
+        info!(
+            "Socket directions of the selected node: {}",
+            match view.enabled {
+                true => "shown",
+                false => "hidden",
+            }
+        );
+    }
+}
+
+/// System drawing the socket directions of the model generated on the node selected by the [`SelectCursor`], for the grids of the coordinate system `C`: the arrows are drawn with [`Gizmos`], and the [`SocketLabel`] are respawned each time the selected node or its model change.
+pub fn draw_socket_directions<C: CoordinateSystem>(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    mut spawned: Local<SpawnedSocketLabels>,
+    view: Res<SocketDirectionsView>,
+    selection_cursor: Query<&Cursor, With<SelectCursor>>,
+    generators: Query<(&Generator<C>, &DebugGridView, &GlobalTransform)>,
+) {
+    let selected = match (view.enabled, selection_cursor.get_single()) {
+        (true, Ok(Cursor(Some(selected)))) => Some(selected),
+        _ => None,
+    };
+    let displayed = selected.and_then(|selected| {
+        let (generator, grid_view, grid_gtransform) = generators.get(selected.grid).ok()?;
+        let [instance] = generator.get_models_on(selected.node_index)[..] else {
+            return None;
+        };
+        let variant = generator
+            .rules()
+            .variant_index(instance.model_index, instance.rotation)?;
+        Some((
+            (selected.grid, selected.node_index, variant),
+            generator,
+            grid_view,
+            grid_gtransform,
+        ))
+    });
+
+    let displayed_key = displayed.as_ref().map(|(key, ..)| *key);
+    if spawned.displayed != displayed_key {
+        for label in spawned.labels.drain(..) {
+            commands.entity(label).despawn();
+        }
+        spawned.displayed = displayed_key;
+    }
+    let Some(((grid_entity, node_index, variant), generator, grid_view, grid_gtransform)) =
+        displayed
+    else {
+        return;
+    };
+
+    let grid = generator.grid();
+    let rules = generator.rules();
+    let node_size = grid_view.node_size;
+    let center = GridWorldTransform::new(node_size).node_to_world(&grid.pos_from_index(node_index));
+    // Labels are only spawned once for a given node and model
+    let spawn_labels = spawned.labels.is_empty();
+    for &direction in grid.directions() {
+        let offset = direction_vector(direction) * node_size;
+        gizmos.arrow(
+            grid_gtransform.transform_point(center + SOCKET_ARROW_START * offset),
+            grid_gtransform.transform_point(center + SOCKET_LABEL_DISTANCE * offset),
+            view.arrow_color,
+        );
+        if spawn_labels {
+            let sockets: Vec<String> = rules
+                .sockets_in(variant, direction)
+                .iter()
+                .map(|socket| rules.socket_label(socket))
+                .collect();
+            let label = spawn_label(
+                &mut commands,
+                &view,
+                grid_entity,
+                center + SOCKET_LABEL_DISTANCE * offset,
+                format!(
+                    "{}: {}",
+                    rules
+                        .direction_aliases()
+                        .alias(direction)
+                        .unwrap_or(direction_name(direction)),
+                    sockets.join(", ")
+                ),
+            );
+            spawned.labels.push(label);
+        }
+    }
+}
+
+/// System moving the [`SocketLabel`] over their world position
+pub fn update_socket_labels(
+    just_one_camera: Query<(&Camera, &GlobalTransform), Without<GridCursorsOverlayCamera>>,
+    overlay_camera: Query<(&Camera, &GlobalTransform), With<GridCursorsOverlayCamera>>,
+    grids: Query<&GlobalTransform>,
+    mut labels: Query<(&SocketLabel, &mut Style, &mut Visibility)>,
+) {
+    let camera = just_one_camera
+        .get_single()
+        .or_else(|_| overlay_camera.get_single())
+        .ok();
+    for (label, mut style, mut visibility) in labels.iter_mut() {
+        let Ok(grid_gtransform) = grids.get(label.grid) else {
+            continue;
+        };
+        let viewport_pos = camera.and_then(|(camera, cam_gtransform)| {
+            camera.world_to_viewport(
+                cam_gtransform,
+                grid_gtransform.transform_point(label.local_position),
+            )
+        });
+        match viewport_pos {
+            Some(viewport_pos) => {
+                style.left = Val::Px(viewport_pos.x);
+                style.top = Val::Px(viewport_pos.y);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn spawn_label(
+    commands: &mut Commands,
+    view: &SocketDirectionsView,
+    grid: Entity,
+    local_position: Vec3,
+    value: String,
+) -> Entity {
+    commands
+        .spawn((
+            SocketLabel {
+                grid,
+                local_position,
+            },
+            TextBundle {
+                text: Text {
+                    linebreak_behavior: BreakLineOn::NoWrap,
+                    sections: vec![TextSection {
+                        value,
+                        style: TextStyle {
+                            font_size: view.font_size,
+                            color: view.text_color,
+                            ..Default::default()
+                        },
+                    }],
+                    ..Default::default()
+                },
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..Default::default()
+                },
+                // Hidden until positioned over the node
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        ))
+        .id()
+}
+
+/// Returns the unit vector of `direction` in the grid local space
+fn direction_vector(direction: Direction) -> Vec3 {
+    match direction {
+        Direction::XForward => Vec3::X,
+        Direction::XBackward => Vec3::NEG_X,
+        Direction::YForward => Vec3::Y,
+        Direction::YBackward => Vec3::NEG_Y,
+        Direction::ZForward => Vec3::Z,
+        Direction::ZBackward => Vec3::NEG_Z,
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::XForward => "x+",
+        Direction::XBackward => "x-",
+        Direction::YForward => "y+",
+        Direction::YBackward => "y-",
+        Direction::ZForward => "z+",
+        Direction::ZBackward => "z-",
+    }
+}
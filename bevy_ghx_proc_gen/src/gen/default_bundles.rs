@@ -1,3 +1,5 @@
+#[cfg(feature = "assets-preloading")]
+use bevy::asset::UntypedHandle;
 use bevy::{
     asset::Handle,
     ecs::system::EntityCommands,
@@ -5,7 +7,7 @@ use bevy::{
     pbr::{Material, MaterialMeshBundle, PbrBundle, StandardMaterial},
     render::{mesh::Mesh, texture::Image},
     scene::{Scene, SceneBundle},
-    sprite::SpriteBundle,
+    sprite::{Sprite, SpriteBundle, SpriteSheetBundle, TextureAtlas, TextureAtlasLayout},
     transform::components::Transform,
     utils::default,
 };
@@ -30,6 +32,79 @@ impl AssetsBundleSpawner for Handle<Image> {
             ..default()
         });
     }
+
+    #[cfg(feature = "assets-preloading")]
+    fn untyped_handles(&self) -> Vec<UntypedHandle> {
+        vec![self.clone().untyped()]
+    }
+}
+
+/// A sprite from a texture atlas: a single image shared by all the models, with one atlas index per model
+#[derive(Clone)]
+pub struct AtlasSprite {
+    /// Handle to the atlas image
+    pub texture: Handle<Image>,
+    /// Layout of the atlas
+    pub layout: Handle<TextureAtlasLayout>,
+    /// Index of the sprite in the atlas layout
+    pub index: usize,
+    /// Flips the sprite horizontally
+    pub flip_x: bool,
+    /// Flips the sprite vertically
+    pub flip_y: bool,
+}
+
+impl AtlasSprite {
+    /// Creates an [`AtlasSprite`] for the sprite at `index` in the atlas, without flipping
+    pub fn new(texture: Handle<Image>, layout: Handle<TextureAtlasLayout>, index: usize) -> Self {
+        Self {
+            texture,
+            layout,
+            index,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    /// Sets the flipping of the sprite. Flipping is applied before the rotation of the model.
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+}
+
+/// **WARNING**: Assumes a specific `Rotation Axis` for the `Models`: Z+
+impl AssetsBundleSpawner for AtlasSprite {
+    fn insert_bundle(
+        &self,
+        commands: &mut EntityCommands,
+        translation: Vec3,
+        scale: Vec3,
+        rotation: ModelRotation,
+    ) {
+        commands.insert(SpriteSheetBundle {
+            sprite: Sprite {
+                flip_x: self.flip_x,
+                flip_y: self.flip_y,
+                ..default()
+            },
+            atlas: TextureAtlas {
+                layout: self.layout.clone(),
+                index: self.index,
+            },
+            texture: self.texture.clone(),
+            transform: Transform::from_translation(translation)
+                .with_scale(scale)
+                .with_rotation(Quat::from_rotation_z(rotation.rad())),
+            ..default()
+        });
+    }
+
+    #[cfg(feature = "assets-preloading")]
+    fn untyped_handles(&self) -> Vec<UntypedHandle> {
+        vec![self.texture.clone().untyped()]
+    }
 }
 
 /// **WARNING**: Assumes a specific `Rotation Axis` for the `Models`: Y+
@@ -49,6 +124,11 @@ impl AssetsBundleSpawner for Handle<Scene> {
             ..default()
         });
     }
+
+    #[cfg(feature = "assets-preloading")]
+    fn untyped_handles(&self) -> Vec<UntypedHandle> {
+        vec![self.clone().untyped()]
+    }
 }
 
 /// Custom type to store [`Handle`] to a [`Mesh`] asset and its [`Material`]
@@ -89,6 +169,11 @@ impl<M: Material> AssetsBundleSpawner for MaterialMesh<M> {
             ..default()
         });
     }
+
+    #[cfg(feature = "assets-preloading")]
+    fn untyped_handles(&self) -> Vec<UntypedHandle> {
+        vec![self.mesh.clone().untyped(), self.material.clone().untyped()]
+    }
 }
 
 /// **WARNING**: Assumes a specific `Rotation Axis` for the `Models`: Y+
@@ -109,4 +194,9 @@ impl AssetsBundleSpawner for PbrMesh {
             ..default()
         });
     }
+
+    #[cfg(feature = "assets-preloading")]
+    fn untyped_handles(&self) -> Vec<UntypedHandle> {
+        vec![self.mesh.clone().untyped(), self.material.clone().untyped()]
+    }
 }
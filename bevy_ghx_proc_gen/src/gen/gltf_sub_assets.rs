@@ -0,0 +1,207 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{AssetServer, Assets, Handle, LoadState},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        system::{Commands, EntityCommands, Query, Res},
+    },
+    gltf::{Gltf, GltfMesh, GltfNode},
+    hierarchy::{BuildChildren, ChildBuilder},
+    log::warn,
+    math::{Quat, Vec3},
+    pbr::PbrBundle,
+    render::prelude::SpatialBundle,
+    transform::components::Transform,
+    utils::default,
+};
+use ghx_proc_gen::generator::model::ModelRotation;
+
+#[cfg(feature = "assets-preloading")]
+use bevy::asset::UntypedHandle;
+
+use super::assets::AssetsBundleSpawner;
+
+/// Part of a GLTF file to spawn, referenced by its name in the GLTF file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GltfSubAssetName {
+    /// The default scene of the file (usually `#Scene0`)
+    DefaultScene,
+    /// A named scene
+    Scene(String),
+    /// A named node, with its children. The transform of the node itself is ignored: it is usually the position of the tile in the kit.
+    Node(String),
+    /// A named mesh, with all its primitives
+    Mesh(String),
+}
+
+/// [`AssetsBundleSpawner`] for a scene, a node or a mesh inside a GLTF file, referenced by name. Allows to use tile kits packed into a single GLTF/GLB file without splitting them per tile.
+///
+/// The sub-asset can only be resolved once the GLTF is loaded: the spawned entity is filled by the systems of the [`GltfSubAssetsPlugin`], which needs to be added to the app.
+///
+/// **WARNING**: Assumes a specific `Rotation Axis` for the `Models`: Y+
+///
+/// ### Example
+///
+/// ```ignore
+/// let gltf = asset_server.load("tiles.glb");
+/// models_assets.add_asset(0, GltfSubAsset::node(gltf.clone(), "grass_corner"));
+/// models_assets.add_asset(1, GltfSubAsset::mesh(gltf.clone(), "water"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct GltfSubAsset {
+    /// Handle to the GLTF file
+    pub gltf: Handle<Gltf>,
+    /// Part of the file to spawn
+    pub name: GltfSubAssetName,
+}
+
+impl GltfSubAsset {
+    /// Creates a [`GltfSubAsset`] for the named scene `name`
+    pub fn scene(gltf: Handle<Gltf>, name: impl Into<String>) -> Self {
+        Self {
+            gltf,
+            name: GltfSubAssetName::Scene(name.into()),
+        }
+    }
+
+    /// Creates a [`GltfSubAsset`] for the named node `name`
+    pub fn node(gltf: Handle<Gltf>, name: impl Into<String>) -> Self {
+        Self {
+            gltf,
+            name: GltfSubAssetName::Node(name.into()),
+        }
+    }
+
+    /// Creates a [`GltfSubAsset`] for the named mesh `name`
+    pub fn mesh(gltf: Handle<Gltf>, name: impl Into<String>) -> Self {
+        Self {
+            gltf,
+            name: GltfSubAssetName::Mesh(name.into()),
+        }
+    }
+}
+
+impl AssetsBundleSpawner for GltfSubAsset {
+    fn insert_bundle(
+        &self,
+        commands: &mut EntityCommands,
+        translation: Vec3,
+        scale: Vec3,
+        rotation: ModelRotation,
+    ) {
+        commands.insert((
+            SpatialBundle::from_transform(
+                Transform::from_translation(translation)
+                    .with_scale(scale)
+                    .with_rotation(Quat::from_rotation_y(rotation.rad())),
+            ),
+            PendingGltfSubAsset(self.clone()),
+        ));
+    }
+
+    #[cfg(feature = "assets-preloading")]
+    fn untyped_handles(&self) -> Vec<UntypedHandle> {
+        vec![self.gltf.clone().untyped()]
+    }
+}
+
+/// Component inserted on the spawned nodes entities of a [`GltfSubAsset`] until their GLTF is loaded and the sub-asset is spawned
+#[derive(Component, Clone, Debug)]
+pub struct PendingGltfSubAsset(pub GltfSubAsset);
+
+/// Plugin resolving the [`GltfSubAsset`] of the spawned nodes once their GLTF files are loaded
+pub struct GltfSubAssetsPlugin;
+
+impl Plugin for GltfSubAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_pending_gltf_sub_assets);
+    }
+}
+
+/// System used by [`GltfSubAssetsPlugin`] to spawn the [`GltfSubAsset`] of the nodes whose GLTF file is loaded.
+///
+/// A sub-asset which does not exist in its GLTF file, or whose file failed to load, is skipped with a warning.
+pub fn spawn_pending_gltf_sub_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    gltfs: Res<Assets<Gltf>>,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    pending_nodes: Query<(Entity, &PendingGltfSubAsset)>,
+) {
+    for (node_entity, PendingGltfSubAsset(sub_asset)) in pending_nodes.iter() {
+        let Some(gltf) = gltfs.get(&sub_asset.gltf) else {
+            if let Some(LoadState::Failed) = asset_server.get_load_state(&sub_asset.gltf) {
+                warn!("GLTF {:?} failed to load", sub_asset.gltf.path());
+                commands.entity(node_entity).remove::<PendingGltfSubAsset>();
+            }
+            continue;
+        };
+        let mut node_commands = commands.entity(node_entity);
+        node_commands.remove::<PendingGltfSubAsset>();
+        let found = match &sub_asset.name {
+            GltfSubAssetName::DefaultScene => gltf
+                .default_scene
+                .clone()
+                .or_else(|| gltf.scenes.first().cloned())
+                .map(|scene| {
+                    node_commands.insert(scene);
+                }),
+            GltfSubAssetName::Scene(name) => gltf.named_scenes.get(name).map(|scene| {
+                node_commands.insert(scene.clone());
+            }),
+            GltfSubAssetName::Node(name) => gltf
+                .named_nodes
+                .get(name)
+                .and_then(|node| gltf_nodes.get(node))
+                .map(|node| {
+                    node_commands.with_children(|parent| {
+                        spawn_gltf_node_content(parent, node, &gltf_meshes);
+                    });
+                }),
+            GltfSubAssetName::Mesh(name) => gltf
+                .named_meshes
+                .get(name)
+                .and_then(|mesh| gltf_meshes.get(mesh))
+                .map(|mesh| {
+                    node_commands.with_children(|parent| {
+                        spawn_gltf_mesh(parent, mesh);
+                    });
+                }),
+        };
+        if found.is_none() {
+            warn!(
+                "{:?} not found in GLTF {:?}",
+                sub_asset.name,
+                sub_asset.gltf.path()
+            );
+        }
+    }
+}
+
+/// Spawns the mesh and the children of a GLTF node, without its own transform
+fn spawn_gltf_node_content(
+    parent: &mut ChildBuilder,
+    node: &GltfNode,
+    gltf_meshes: &Assets<GltfMesh>,
+) {
+    if let Some(mesh) = node.mesh.as_ref().and_then(|mesh| gltf_meshes.get(mesh)) {
+        spawn_gltf_mesh(parent, mesh);
+    }
+    for child in node.children.iter() {
+        parent
+            .spawn(SpatialBundle::from_transform(child.transform))
+            .with_children(|parent| spawn_gltf_node_content(parent, child, gltf_meshes));
+    }
+}
+
+fn spawn_gltf_mesh(parent: &mut ChildBuilder, mesh: &GltfMesh) {
+    for primitive in mesh.primitives.iter() {
+        parent.spawn(PbrBundle {
+            mesh: primitive.mesh.clone(),
+            material: primitive.material.clone().unwrap_or_default(),
+            ..default()
+        });
+    }
+}
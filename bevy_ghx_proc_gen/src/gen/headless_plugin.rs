@@ -0,0 +1,242 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        entity::Entity,
+        event::{Event, EventWriter},
+        query::{Added, Has, With},
+        schedule::IntoSystemConfigs,
+        system::{Commands, Query, ResMut, Resource},
+    },
+    log::{info, warn},
+    utils::HashSet,
+};
+use bevy_ghx_grid::ghx_grid::{coordinate_system::CoordinateSystem, grid::GridData};
+use ghx_proc_gen::{
+    generator::{
+        model::ModelInstance, observer::GenerationUpdate, GenInfo, GeneratedNode, Generator,
+    },
+    GeneratorError,
+};
+
+use super::{
+    lifecycle::{add_lifecycle_events, LifecycleEventWriters},
+    tasks::{
+        return_generator, start_generation_task, AsyncGeneration, GenerationTask, ReturnedGenerator,
+    },
+};
+
+/// A headless [`Plugin`] that automatically detects any [`Entity`] with a [`Generator`] `Component` and tries to run the contained generator once per frame until it succeeds.
+///
+/// Nothing is spawned: the results are only sent as [`GenerationUpdateEvent`] events, along with the [`super::lifecycle`] events. The plugin does not need any rendering, assets or picking plugins, which makes it usable on a dedicated server (with Bevy's `MinimalPlugins` for example).
+///
+/// Generators with an [`AsyncGeneration`] component are run in a [`GenerationTask`] on the `AsyncComputeTaskPool`, their results are sent once the task is finished.
+///
+/// Generators of other coordinate systems than `C` can be handled by the same plugin with [`ProcGenHeadlessPlugin::with_coordinate_system`].
+pub struct ProcGenHeadlessPlugin<C: CoordinateSystem> {
+    coordinate_systems: Vec<fn(&mut App)>,
+    typestate: PhantomData<C>,
+}
+
+impl<C: CoordinateSystem> Plugin for ProcGenHeadlessPlugin<C> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GenerationUpdateEvent>();
+        add_lifecycle_events(app);
+        for add_systems in self.coordinate_systems.iter() {
+            add_systems(app);
+        }
+    }
+}
+
+impl<C: CoordinateSystem> ProcGenHeadlessPlugin<C> {
+    /// Constructor
+    pub fn new() -> Self {
+        Self {
+            coordinate_systems: vec![add_coordinate_system_systems::<C>],
+            typestate: PhantomData,
+        }
+    }
+
+    /// Also runs the generators of the coordinate system `C2`
+    pub fn with_coordinate_system<C2: CoordinateSystem>(mut self) -> Self {
+        self.coordinate_systems
+            .push(add_coordinate_system_systems::<C2>);
+        self
+    }
+}
+
+/// Adds the systems of the [`ProcGenHeadlessPlugin`] which depend on the coordinate system `C`
+fn add_coordinate_system_systems<C: CoordinateSystem>(app: &mut App) {
+    app.init_resource::<PendingHeadlessGenerations<C>>();
+    app.add_systems(
+        Update,
+        (
+            register_new_headless_generations::<C>,
+            generate_and_send_updates::<C>,
+            poll_generation_tasks_and_send_updates::<C>,
+        )
+            .chain(),
+    );
+}
+
+/// Event sent by the [`ProcGenHeadlessPlugin`] for each [`GenerationUpdate`] of a generator
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GenerationUpdateEvent {
+    /// Entity holding the [`Generator`] `Component`
+    pub gen_entity: Entity,
+    /// Update of the generation
+    pub update: GenerationUpdate,
+}
+
+/// Resource used by [`ProcGenHeadlessPlugin`] to track the generations of the coordinate system `C` that are yet to generate a result
+///
+/// There is one resource per coordinate system, so that the systems of a coordinate system never see (and drop) the generators of another one.
+#[derive(Resource)]
+pub struct PendingHeadlessGenerations<C: CoordinateSystem> {
+    pendings: HashSet<Entity>,
+    typestate: PhantomData<C>,
+}
+
+impl<C: CoordinateSystem> Default for PendingHeadlessGenerations<C> {
+    fn default() -> Self {
+        Self {
+            pendings: Default::default(),
+            typestate: PhantomData,
+        }
+    }
+}
+
+/// System used by [`ProcGenHeadlessPlugin`] to track entities with newly added [`Generator`] components
+///
+/// Generators put back on their entity at the end of a [`GenerationTask`] are not registered again.
+pub fn register_new_headless_generations<C: CoordinateSystem>(
+    mut commands: Commands,
+    mut lifecycle_events: LifecycleEventWriters,
+    mut pending_generations: ResMut<PendingHeadlessGenerations<C>>,
+    mut new_generations: Query<(Entity, Has<ReturnedGenerator>), Added<Generator<C>>>,
+) {
+    for (gen_entity, returned) in new_generations.iter_mut() {
+        if returned {
+            commands.entity(gen_entity).remove::<ReturnedGenerator>();
+            continue;
+        }
+        pending_generations.pendings.insert(gen_entity);
+        lifecycle_events.started(gen_entity);
+    }
+}
+
+/// System used by [`ProcGenHeadlessPlugin`] to run generators and send their results as [`GenerationUpdateEvent`]
+///
+/// - When a generation succeeds, a [`GenerationUpdate::Generated`] is sent for each node of the grid.
+/// - When a generation fails, a [`GenerationUpdate::Failed`] is sent and the generation will be retried on the next frame.
+///
+/// Generators with an [`AsyncGeneration`] component are moved into a [`GenerationTask`] instead, see [`poll_generation_tasks_and_send_updates`].
+pub fn generate_and_send_updates<C: CoordinateSystem>(
+    mut commands: Commands,
+    mut update_events: EventWriter<GenerationUpdateEvent>,
+    mut lifecycle_events: LifecycleEventWriters,
+    mut pending_generations: ResMut<PendingHeadlessGenerations<C>>,
+    mut generations: Query<(&mut Generator<C>, Has<AsyncGeneration>)>,
+    tasks: Query<(), With<GenerationTask<C>>>,
+) {
+    let mut generations_done = vec![];
+    for &gen_entity in pending_generations.pendings.iter() {
+        let Ok((mut generation, async_generation)) = generations.get_mut(gen_entity) else {
+            if !tasks.contains(gen_entity) {
+                // The generator was removed, no need to track it anymore
+                generations_done.push(gen_entity);
+            }
+            continue;
+        };
+        if async_generation {
+            commands.entity(gen_entity).add(start_generation_task::<C>);
+            continue;
+        }
+        let result = generation.generate_grid();
+        if send_generation_result(
+            &mut update_events,
+            &mut lifecycle_events,
+            gen_entity,
+            &generation,
+            result,
+        ) {
+            generations_done.push(gen_entity);
+        }
+    }
+    for gen_entity in generations_done {
+        pending_generations.pendings.remove(&gen_entity);
+    }
+}
+
+/// System used by [`ProcGenHeadlessPlugin`] to poll the [`GenerationTask`] of the generators, send their results as [`GenerationUpdateEvent`] once they are finished (see [`generate_and_send_updates`]), and put their [`Generator`] back on their entity.
+pub fn poll_generation_tasks_and_send_updates<C: CoordinateSystem>(
+    mut commands: Commands,
+    mut update_events: EventWriter<GenerationUpdateEvent>,
+    mut lifecycle_events: LifecycleEventWriters,
+    mut pending_generations: ResMut<PendingHeadlessGenerations<C>>,
+    mut tasks: Query<(Entity, &mut GenerationTask<C>)>,
+) {
+    for (gen_entity, mut task) in tasks.iter_mut() {
+        let Some((generation, result)) = task.poll() else {
+            continue;
+        };
+        if send_generation_result(
+            &mut update_events,
+            &mut lifecycle_events,
+            gen_entity,
+            &generation,
+            result,
+        ) {
+            pending_generations.pendings.remove(&gen_entity);
+        }
+        return_generator(&mut commands, gen_entity, generation);
+    }
+}
+
+/// Sends the [`GenerationUpdateEvent`] and the [`super::lifecycle`] event matching the `result` of a generation. Returns `true` if the generation succeeded.
+fn send_generation_result<C: CoordinateSystem>(
+    update_events: &mut EventWriter<GenerationUpdateEvent>,
+    lifecycle_events: &mut LifecycleEventWriters,
+    gen_entity: Entity,
+    generation: &Generator<C>,
+    result: Result<(GenInfo, GridData<C, ModelInstance>), GeneratorError>,
+) -> bool {
+    match result {
+        Ok((gen_info, grid_data)) => {
+            info!(
+                "Generation {:?} done, try_count: {}, seed: {}; grid: {}",
+                gen_entity,
+                gen_info.try_count,
+                generation.seed(),
+                generation.grid()
+            );
+            update_events.send_batch(grid_data.nodes().iter().enumerate().map(
+                |(node_index, model_instance)| GenerationUpdateEvent {
+                    gen_entity,
+                    update: GenerationUpdate::Generated(GeneratedNode {
+                        node_index,
+                        model_instance: *model_instance,
+                    }),
+                },
+            ));
+            lifecycle_events.done(gen_entity, gen_info);
+            true
+        }
+        Err(error @ GeneratorError { node_index }) => {
+            lifecycle_events.failed(gen_entity, error);
+            warn!(
+                "Generation {:?} failed at node {}, seed: {}; grid: {}",
+                gen_entity,
+                node_index,
+                generation.seed(),
+                generation.grid()
+            );
+            update_events.send(GenerationUpdateEvent {
+                gen_entity,
+                update: GenerationUpdate::Failed(node_index),
+            });
+            false
+        }
+    }
+}
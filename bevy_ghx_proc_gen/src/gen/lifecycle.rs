@@ -0,0 +1,53 @@
+use bevy::{
+    app::App,
+    ecs::{
+        entity::Entity,
+        event::{Event, EventWriter},
+        system::SystemParam,
+    },
+};
+use ghx_proc_gen::{generator::GenInfo, GeneratorError};
+
+/// Event sent by the plugins when they start (or restart) a generation: when a new [`ghx_proc_gen::generator::Generator`] `Component` is registered, and when the debug plugin reinitializes a generator.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GenerationStarted(pub Entity);
+
+/// Event sent by the plugins when a generation succeeds, with the [`GenInfo`] of the generation
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GenerationDone(pub Entity, pub GenInfo);
+
+/// Event sent by the plugins when a generation fails, with the [`GeneratorError`] of the generation
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GenerationFailed(pub Entity, pub GeneratorError);
+
+/// Registers the generation lifecycle events: [`GenerationStarted`], [`GenerationDone`] and [`GenerationFailed`]
+pub fn add_lifecycle_events(app: &mut App) {
+    app.add_event::<GenerationStarted>()
+        .add_event::<GenerationDone>()
+        .add_event::<GenerationFailed>();
+}
+
+/// [`SystemParam`] used by the plugins systems to send the generation lifecycle events
+#[derive(SystemParam)]
+pub struct LifecycleEventWriters<'w> {
+    started: EventWriter<'w, GenerationStarted>,
+    done: EventWriter<'w, GenerationDone>,
+    failed: EventWriter<'w, GenerationFailed>,
+}
+
+impl<'w> LifecycleEventWriters<'w> {
+    /// Sends a [`GenerationStarted`] event
+    pub fn started(&mut self, gen_entity: Entity) {
+        self.started.send(GenerationStarted(gen_entity));
+    }
+
+    /// Sends a [`GenerationDone`] event
+    pub fn done(&mut self, gen_entity: Entity, gen_info: GenInfo) {
+        self.done.send(GenerationDone(gen_entity, gen_info));
+    }
+
+    /// Sends a [`GenerationFailed`] event
+    pub fn failed(&mut self, gen_entity: Entity, error: GeneratorError) {
+        self.failed.send(GenerationFailed(gen_entity, error));
+    }
+}
@@ -0,0 +1,268 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{Assets, Handle},
+    ecs::{
+        component::Component,
+        system::{Query, ResMut},
+    },
+    render::{
+        color::Color,
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::{Image, ImageSampler},
+    },
+};
+use bevy_ghx_grid::ghx_grid::coordinate_system::CoordinateSystem;
+use ghx_proc_gen::{
+    generator::{
+        model::ModelIndex,
+        observer::{GenerationUpdate, QueuedObserver},
+        Generator,
+    },
+    NodeIndex,
+};
+
+/// Default color of the nodes not generated yet in a [`GenerationMinimap`]
+pub const DEFAULT_MINIMAP_EMPTY_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+
+/// A [`Plugin`] updating the [`GenerationMinimap`] components from the updates of their generators
+pub struct ProcGenMinimapPlugin;
+
+impl Plugin for ProcGenMinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_minimaps);
+    }
+}
+
+/// Colors of the models in a [`GenerationMinimap`]
+#[derive(Clone, Debug)]
+pub struct MinimapPalette {
+    /// Colors overrides, indexed by [`ModelIndex`]
+    colors: Vec<Option<Color>>,
+    empty_color: Color,
+}
+
+impl Default for MinimapPalette {
+    fn default() -> Self {
+        Self {
+            colors: Vec::new(),
+            empty_color: DEFAULT_MINIMAP_EMPTY_COLOR,
+        }
+    }
+}
+
+impl MinimapPalette {
+    /// Creates a palette where each model gets a distinct color derived from its model index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the color of the model `model_index`
+    pub fn with_model_color(mut self, model_index: ModelIndex, color: Color) -> Self {
+        if self.colors.len() <= model_index {
+            self.colors.resize(model_index + 1, None);
+        }
+        self.colors[model_index] = Some(color);
+        self
+    }
+
+    /// Sets the color of the nodes not generated yet. Defaults to [`DEFAULT_MINIMAP_EMPTY_COLOR`].
+    pub fn with_empty_color(mut self, color: Color) -> Self {
+        self.empty_color = color;
+        self
+    }
+
+    /// Returns the color of the model `model_index`
+    pub fn model_color(&self, model_index: ModelIndex) -> Color {
+        match self.colors.get(model_index).copied().flatten() {
+            Some(color) => color,
+            // Steps the hue with the golden ratio to get distinct colors for consecutive models
+            None => Color::hsl((model_index as f32 * 0.618_034).fract() * 360., 0.6, 0.6),
+        }
+    }
+
+    /// Returns the color of the nodes not generated yet
+    pub fn empty_color(&self) -> Color {
+        self.empty_color
+    }
+}
+
+/// Plane of the grid displayed by a [`GenerationMinimap`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinimapView {
+    /// The whole XY plane of a 2d grid (the nodes with `z = 0`), with Y+ towards the top of the image
+    Plane2d,
+    /// A single Y layer of a 3d grid seen from above, with X+ towards the right and Z+ towards the bottom of the image
+    Layer(u32),
+}
+
+/// Component maintaining an [`Image`] with one pixel per node of a generation, colored by the model of the node (see [`MinimapPalette`]), to display a live minimap of the generation progress in a UI.
+///
+/// The image is updated by the [`ProcGenMinimapPlugin`] from the [`GenerationUpdate`]s received by its own [`QueuedObserver`]: it works along any of the other plugins, and with the generations running in a [`super::tasks::GenerationTask`]. It should be created before the generator runs, since the nodes generated before its creation are not displayed.
+///
+/// ### Example
+///
+/// ```ignore
+/// let minimap = GenerationMinimap::new(
+///     &mut generator,
+///     MinimapView::Layer(0),
+///     MinimapPalette::new().with_model_color(0, Color::GREEN),
+///     &mut images,
+/// );
+/// // Display the minimap in the UI
+/// commands.spawn(ImageBundle {
+///     image: UiImage::new(minimap.image.clone()),
+///     ..default()
+/// });
+/// commands.spawn((GeneratorBundle { generator, ..default() }, minimap));
+/// ```
+#[derive(Component)]
+pub struct GenerationMinimap {
+    /// Handle to the minimap [`Image`]
+    pub image: Handle<Image>,
+    observer: QueuedObserver,
+    palette: MinimapPalette,
+    view: MinimapView,
+    grid_size: (u32, u32, u32),
+    /// Generated model of each node of the grid, to be able to repaint the image when the view changes
+    nodes: Vec<Option<ModelIndex>>,
+}
+
+impl GenerationMinimap {
+    /// Creates a minimap of `generator` displaying `view`, and adds its [`Image`] to `images`
+    pub fn new<C: CoordinateSystem>(
+        generator: &mut Generator<C>,
+        view: MinimapView,
+        palette: MinimapPalette,
+        images: &mut Assets<Image>,
+    ) -> Self {
+        let grid_size = generator.grid().size();
+        let mut minimap = Self {
+            image: Handle::default(),
+            observer: QueuedObserver::new(generator),
+            palette,
+            view,
+            grid_size,
+            nodes: vec![None; generator.grid().total_size()],
+        };
+        minimap.image = images.add(minimap.create_image());
+        minimap
+    }
+
+    /// Returns the plane of the grid currently displayed
+    pub fn view(&self) -> MinimapView {
+        self.view
+    }
+
+    /// Changes the plane of the grid displayed by the minimap, and repaints its image in `images`
+    pub fn set_view(&mut self, view: MinimapView, images: &mut Assets<Image>) {
+        self.view = view;
+        images.insert(self.image.clone(), self.create_image());
+    }
+
+    /// Returns the palette of the minimap
+    pub fn palette(&self) -> &MinimapPalette {
+        &self.palette
+    }
+
+    /// Returns the size in pixels of the minimap image for the current view
+    fn image_size(&self) -> (u32, u32) {
+        let (size_x, size_y, size_z) = self.grid_size;
+        match self.view {
+            MinimapView::Plane2d => (size_x, size_y),
+            MinimapView::Layer(_) => (size_x, size_z),
+        }
+    }
+
+    /// Returns the pixel of the node `node_index`, if it is in the current view
+    fn node_pixel(&self, node_index: NodeIndex) -> Option<(u32, u32)> {
+        let (size_x, size_y, _) = self.grid_size;
+        let node_index = node_index as u32;
+        let (x, y, z) = (
+            node_index % size_x,
+            (node_index / size_x) % size_y,
+            node_index / (size_x * size_y),
+        );
+        match self.view {
+            // Image rows go down while the grid y axis goes up
+            MinimapView::Plane2d => (z == 0).then(|| (x, size_y - 1 - y)),
+            MinimapView::Layer(layer) => (y == layer).then(|| (x, z)),
+        }
+    }
+
+    /// Creates an image of the current view, from the generated nodes
+    fn create_image(&self) -> Image {
+        let (width, height) = self.image_size();
+        let mut image = Image::new_fill(
+            Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &self.palette.empty_color().as_rgba_u8(),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        // One pixel per node, without blurring when the image is scaled up
+        image.sampler = ImageSampler::nearest();
+        for node_index in 0..self.nodes.len() {
+            self.paint_node(&mut image, node_index);
+        }
+        image
+    }
+
+    /// Paints the pixel of the node `node_index` in `image`, if it is in the current view
+    fn paint_node(&self, image: &mut Image, node_index: NodeIndex) {
+        let Some((px, py)) = self.node_pixel(node_index) else {
+            return;
+        };
+        let color = match self.nodes[node_index] {
+            Some(model_index) => self.palette.model_color(model_index),
+            None => self.palette.empty_color(),
+        };
+        let (width, _) = self.image_size();
+        let offset = 4 * (py * width + px) as usize;
+        if let Some(pixel) = image.data.get_mut(offset..offset + 4) {
+            pixel.copy_from_slice(&color.as_rgba_u8());
+        }
+    }
+
+    /// Applies a generation `update` to the nodes and to the minimap `image`
+    fn apply_update(&mut self, update: GenerationUpdate, image: &mut Image) {
+        match update {
+            GenerationUpdate::Generated(generated) => {
+                self.nodes[generated.node_index] = Some(generated.model_instance.model_index);
+                self.paint_node(image, generated.node_index);
+            }
+            GenerationUpdate::Reverted(node_index) => {
+                self.nodes[node_index] = None;
+                self.paint_node(image, node_index);
+            }
+            GenerationUpdate::Reinitializing(_) => {
+                self.nodes.fill(None);
+                *image = self.create_image();
+            }
+            GenerationUpdate::Failed(_) => (),
+        }
+    }
+}
+
+/// System used by [`ProcGenMinimapPlugin`] to apply the queued generation updates to the images of the [`GenerationMinimap`] components
+pub fn update_minimaps(
+    mut images: ResMut<Assets<Image>>,
+    mut minimaps: Query<&mut GenerationMinimap>,
+) {
+    for mut minimap in minimaps.iter_mut() {
+        let updates = minimap.observer.dequeue_all();
+        if updates.is_empty() {
+            continue;
+        }
+        let Some(image) = images.get_mut(minimap.image.clone()) else {
+            continue;
+        };
+        for update in updates {
+            minimap.apply_update(update, image);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use std::{collections::HashMap, marker::PhantomData, path::Path, time::Duration};
+
+use bevy::{
+    app::{App, Plugin, PostUpdate, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::DespawnRecursiveExt,
+    log::warn,
+    time::Time,
+};
+use bevy_ghx_grid::ghx_grid::{coordinate_system::CoordinateSystem, grid::GridDefinition};
+use ghx_proc_gen::{
+    generator::{
+        model::ModelInstance,
+        observer::{GenerationUpdate, QueuedObserver},
+        Generator,
+    },
+    serialization::{timeline_from_bytes, timeline_to_bytes, TimelineStep},
+    NodeIndex, SerializationError,
+};
+
+use super::{
+    assets::{AssetSpawner, AssetsBundleSpawner, ComponentSpawner, NoComponents},
+    spawn_node_with_neighbours,
+};
+
+/// A [`Plugin`] recording the generation observed by the [`GenerationRecorder`] `Resource` (if any), and playing back the [`TimelinePlayback`] components of the coordinate system `C` with the assets types `A` and `T`
+pub struct ProcGenRecordingPlugin<
+    C: CoordinateSystem,
+    A: AssetsBundleSpawner,
+    T: ComponentSpawner = NoComponents,
+> {
+    typestate: PhantomData<(C, A, T)>,
+}
+
+impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner>
+    ProcGenRecordingPlugin<C, A, T>
+{
+    /// Constructor
+    pub fn new() -> Self {
+        Self {
+            typestate: PhantomData,
+        }
+    }
+}
+
+impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> Plugin
+    for ProcGenRecordingPlugin<C, A, T>
+{
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, play_timelines::<C, A, T>)
+            .add_systems(PostUpdate, record_generation);
+    }
+}
+
+/// Resource recording the updates of a generation with their timestamps, to export them as a timeline file which can be played back later at the recorded pace with a [`TimelinePlayback`] (for trailers, or deterministic demos).
+///
+/// The updates observed during the same frame are recorded as a single [`TimelineStep`], the timestamps are taken from the Bevy [`Time`]. The recorder gets the updates from its own [`QueuedObserver`]: it works along any of the other plugins, but the generation is best recorded one step at a time (with a step by step [`super::debug_plugin::GenerationViewMode`] for example), since a generation run in a single frame is recorded as a single step.
+///
+/// ### Example
+///
+/// ```ignore
+/// commands.insert_resource(GenerationRecorder::new(&mut generator));
+/// commands.spawn(GeneratorBundle { generator, ..default() });
+///
+/// // Later, once the generation is done
+/// recorder.export("generation.ghxt").unwrap();
+/// ```
+#[derive(Resource)]
+pub struct GenerationRecorder {
+    observer: QueuedObserver,
+    grid_size: (u32, u32, u32),
+    recording: bool,
+    /// Recording time, paused along with the recording
+    elapsed: Duration,
+    steps: Vec<TimelineStep>,
+}
+
+impl GenerationRecorder {
+    /// Creates a recorder for `generator`, already recording. It should be created before the generator runs, since the updates sent before its creation are not recorded.
+    pub fn new<C: CoordinateSystem>(generator: &mut Generator<C>) -> Self {
+        Self {
+            observer: QueuedObserver::new(generator),
+            grid_size: generator.grid().size(),
+            recording: true,
+            elapsed: Duration::ZERO,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the recorder is recording
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Pauses the recording: the updates sent while paused are not recorded, and the recording time is paused too
+    pub fn pause(&mut self) {
+        self.recording = false;
+    }
+
+    /// Resumes a paused recording
+    pub fn resume(&mut self) {
+        self.recording = true;
+    }
+
+    /// Clears the recorded steps and restarts the recording time
+    pub fn clear(&mut self) {
+        self.steps.clear();
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Returns the recorded steps, in order
+    pub fn steps(&self) -> &[TimelineStep] {
+        &self.steps
+    }
+
+    /// Returns the recorded timeline, in the format of [`timeline_to_bytes`]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        timeline_to_bytes(self.grid_size, &self.steps)
+    }
+
+    /// Writes the recorded timeline to the file at `path`, see [`GenerationRecorder::to_bytes`]
+    pub fn export(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    /// Returns a [`TimelinePlayback`] of the recorded timeline
+    pub fn playback(&self) -> TimelinePlayback {
+        TimelinePlayback::new(self.grid_size, self.steps.clone())
+    }
+}
+
+/// Component playing back a generation timeline (recorded by a [`GenerationRecorder`]) on its entity, at the recorded pace: the assets of the nodes are spawned (and despawned) by the [`ProcGenRecordingPlugin`] as if the generation was running.
+///
+/// The entity needs a [`GridDefinition`] of the same size as the recorded grid and an [`AssetSpawner`], but no [`Generator`].
+///
+/// ### Example
+///
+/// ```ignore
+/// let bytes = std::fs::read("generation.ghxt").unwrap();
+/// commands.spawn((
+///     SpatialBundle::default(),
+///     grid,
+///     asset_spawner,
+///     TimelinePlayback::from_bytes(&bytes).unwrap().with_speed(2.),
+/// ));
+/// ```
+#[derive(Component, Clone, Debug)]
+pub struct TimelinePlayback {
+    grid_size: (u32, u32, u32),
+    steps: Vec<TimelineStep>,
+    /// Index of the next step to play
+    next_step: usize,
+    elapsed: Duration,
+    speed: f32,
+    /// Model of each node of the grid, to evaluate the spawn conditions of the assets. Only allocated by [`play_timelines`], once `grid_size` was checked against the size of the grid.
+    nodes: Vec<Option<ModelInstance>>,
+    /// Entities spawned for each node by the playback, despawned when the node is reverted or generated again
+    spawned_nodes: HashMap<NodeIndex, Vec<Entity>>,
+}
+
+impl TimelinePlayback {
+    /// Creates a playback of the timeline `steps` for a grid of size `grid_size`
+    pub fn new(grid_size: (u32, u32, u32), steps: Vec<TimelineStep>) -> Self {
+        Self {
+            grid_size,
+            steps,
+            next_step: 0,
+            elapsed: Duration::ZERO,
+            speed: 1.,
+            nodes: Vec::new(),
+            spawned_nodes: HashMap::new(),
+        }
+    }
+
+    /// Creates a playback of a timeline written by [`GenerationRecorder::export`] (see [`timeline_from_bytes`])
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let (grid_size, steps) = timeline_from_bytes(bytes)?;
+        Ok(Self::new(grid_size, steps))
+    }
+
+    /// Sets the speed multiplier of the playback. Defaults to `1`, the recorded pace.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed.max(0.);
+        self
+    }
+
+    /// Returns `true` once all the steps of the timeline were played
+    pub fn is_finished(&self) -> bool {
+        self.next_step >= self.steps.len()
+    }
+}
+
+/// System used by [`ProcGenRecordingPlugin`] to record the updates queued for the [`GenerationRecorder`]
+pub fn record_generation(time: Res<Time>, recorder: Option<ResMut<GenerationRecorder>>) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    let updates = recorder.observer.dequeue_all();
+    if !recorder.recording {
+        return;
+    }
+    recorder.elapsed += time.delta();
+    if updates.is_empty() {
+        return;
+    }
+    let time_ms = recorder.elapsed.as_millis() as u64;
+    recorder.steps.push(TimelineStep { time_ms, updates });
+}
+
+/// System used by [`ProcGenRecordingPlugin`] to play the steps of the [`TimelinePlayback`] components which are due, spawning and despawning the assets of their nodes
+pub fn play_timelines<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut playbacks: Query<(
+        Entity,
+        &GridDefinition<C>,
+        &AssetSpawner<A, T>,
+        &mut TimelinePlayback,
+    )>,
+) {
+    for (entity, grid, asset_spawner, mut playback) in playbacks.iter_mut() {
+        if playback.is_finished() {
+            continue;
+        }
+        if grid.size() != playback.grid_size {
+            warn!(
+                "Cannot play the timeline of entity {:?}: recorded for a grid of size {:?}, not {:?}",
+                entity,
+                playback.grid_size,
+                grid.size()
+            );
+            commands.entity(entity).remove::<TimelinePlayback>();
+            continue;
+        }
+        if playback.nodes.len() != grid.total_size() {
+            playback.nodes = vec![None; grid.total_size()];
+        }
+        let delta = time.delta().mul_f32(playback.speed);
+        playback.elapsed += delta;
+        let elapsed_ms = playback.elapsed.as_millis() as u64;
+
+        while let Some(step) = playback.steps.get(playback.next_step) {
+            if step.time_ms > elapsed_ms {
+                break;
+            }
+            let updates = step.updates.clone();
+            playback.next_step += 1;
+            for update in updates {
+                match update {
+                    GenerationUpdate::Generated(node) => {
+                        let Some(slot) = playback.nodes.get_mut(node.node_index) else {
+                            continue;
+                        };
+                        *slot = Some(node.model_instance);
+                        // A node generated again replaces its previous assets
+                        despawn_node(&mut commands, &mut playback, node.node_index);
+                        let position = grid.pos_from_index(node.node_index);
+                        let node_entities = spawn_node_with_neighbours(
+                            &mut commands,
+                            entity,
+                            grid,
+                            asset_spawner,
+                            &node.model_instance,
+                            node.node_index,
+                            |direction| {
+                                let neighbour_index =
+                                    grid.get_next_index_in_direction(&position, direction)?;
+                                playback.nodes[neighbour_index]
+                            },
+                        );
+                        playback
+                            .spawned_nodes
+                            .insert(node.node_index, node_entities);
+                    }
+                    GenerationUpdate::Reverted(node_index) => {
+                        if let Some(slot) = playback.nodes.get_mut(node_index) {
+                            *slot = None;
+                        }
+                        despawn_node(&mut commands, &mut playback, node_index);
+                    }
+                    GenerationUpdate::Reinitializing(_) => {
+                        playback.nodes.fill(None);
+                        for (_, node_entities) in playback.spawned_nodes.drain() {
+                            for node_entity in node_entities {
+                                commands.entity(node_entity).despawn_recursive();
+                            }
+                        }
+                    }
+                    GenerationUpdate::Failed(_) => (),
+                }
+            }
+        }
+    }
+}
+
+/// Despawns the entities spawned by `playback` for `node_index`, if any
+fn despawn_node(commands: &mut Commands, playback: &mut TimelinePlayback, node_index: NodeIndex) {
+    for node_entity in playback
+        .spawned_nodes
+        .remove(&node_index)
+        .into_iter()
+        .flatten()
+    {
+        commands.entity(node_entity).despawn_recursive();
+    }
+}
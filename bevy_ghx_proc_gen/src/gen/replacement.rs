@@ -0,0 +1,114 @@
+use bevy::{
+    app::App,
+    ecs::{
+        entity::Entity,
+        event::{Event, EventReader},
+        system::{Commands, Query},
+    },
+    hierarchy::{Children, DespawnRecursiveExt},
+    log::warn,
+};
+use bevy_ghx_grid::ghx_grid::coordinate_system::CoordinateSystem;
+use ghx_proc_gen::{
+    generator::{model::ModelInstance, GeneratedNode, Generator},
+    NodeIndex,
+};
+
+use super::{
+    assets::{AssetSpawner, AssetsBundleSpawner, ComponentSpawner},
+    spawn_node_assets, GridNode,
+};
+
+/// Event to send to the simple and debug plugins to replace the spawned assets of a generated node, without touching the generator: the assets of the node are despawned and the assets of `variant` for the same model are spawned instead (see [`super::assets::RulesModelsAssets::add_variant`]). Used for damage states, seasonal palette swaps, ...
+///
+/// The node must be generated (have a single possible model) in the [`Generator`] of `gen_entity`. If the model has no assets for `variant`, the node is left without assets.
+#[derive(Event, Clone, Debug)]
+pub struct ReplaceNodeAsset {
+    /// Entity holding the [`Generator`] and the [`AssetSpawner`] of the node
+    pub gen_entity: Entity,
+    /// Index of the node whose assets are replaced
+    pub node_index: NodeIndex,
+    /// Name of the assets variant to spawn, `None` to spawn the default assets of the model
+    pub variant: Option<String>,
+}
+
+/// Registers the [`ReplaceNodeAsset`] event. If it is already registered, nothing happens.
+pub fn add_replace_node_asset_event(app: &mut App) {
+    app.add_event::<ReplaceNodeAsset>();
+}
+
+/// System used by the simple and debug plugins to replace the spawned assets of the nodes targeted by [`ReplaceNodeAsset`] events
+pub fn replace_node_assets<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner>(
+    mut commands: Commands,
+    mut replace_events: EventReader<ReplaceNodeAsset>,
+    generations: Query<(&Generator<C>, &AssetSpawner<A, T>, Option<&Children>)>,
+    existing_nodes: Query<&GridNode>,
+) {
+    for event in replace_events.read() {
+        let Ok((generator, asset_spawner, children)) = generations.get(event.gen_entity) else {
+            continue;
+        };
+        let grid = generator.grid();
+        if event.node_index >= grid.total_size() {
+            warn!(
+                "Cannot replace the assets of node {} of generation {:?}: not in the grid",
+                event.node_index, event.gen_entity
+            );
+            continue;
+        }
+        let Some(instance) = generated_model(generator, event.node_index) else {
+            warn!(
+                "Cannot replace the assets of node {} of generation {:?}: the node is not generated",
+                event.node_index, event.gen_entity
+            );
+            continue;
+        };
+
+        if let Some(children) = children {
+            for &child in children.iter() {
+                if let Ok(node) = existing_nodes.get(child) {
+                    if node.0 == event.node_index {
+                        commands.entity(child).despawn_recursive();
+                    }
+                }
+            }
+        }
+
+        let node_assets = match &event.variant {
+            Some(variant) => asset_spawner
+                .assets
+                .variant_assets(variant, instance.model_index),
+            None => asset_spawner.assets.get(&instance.model_index),
+        };
+        let Some(node_assets) = node_assets else {
+            continue;
+        };
+        let position = grid.pos_from_index(event.node_index);
+        spawn_node_assets(
+            &mut commands,
+            event.gen_entity,
+            grid,
+            asset_spawner,
+            node_assets,
+            &GeneratedNode {
+                node_index: event.node_index,
+                model_instance: instance,
+            },
+            |direction| {
+                let neighbour_index = grid.get_next_index_in_direction(&position, direction)?;
+                generated_model(generator, neighbour_index)
+            },
+        );
+    }
+}
+
+/// Returns the model generated on `node_index`, if the node has a single possible model
+fn generated_model<C: CoordinateSystem>(
+    generator: &Generator<C>,
+    node_index: NodeIndex,
+) -> Option<ModelInstance> {
+    match generator.get_models_on(node_index).as_slice() {
+        [model_instance] => Some(*model_instance),
+        _ => None,
+    }
+}
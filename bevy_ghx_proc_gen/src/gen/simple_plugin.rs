@@ -4,28 +4,54 @@ use bevy::{
     app::{App, Plugin, Update},
     ecs::{
         entity::Entity,
-        query::Added,
+        query::{Added, Has},
         schedule::IntoSystemConfigs,
         system::{Commands, Query, ResMut, Resource},
     },
     log::{info, warn},
     utils::HashSet,
 };
-use bevy_ghx_grid::ghx_grid::coordinate_system::CoordinateSystem;
-use ghx_proc_gen::{generator::Generator, GeneratorError};
+use bevy_ghx_grid::ghx_grid::{coordinate_system::CoordinateSystem, grid::GridData};
+use ghx_proc_gen::{
+    generator::{model::ModelInstance, GenInfo, Generator},
+    GeneratorError,
+};
+
+use crate::gen::{
+    grid_data_neighbours,
+    lifecycle::{add_lifecycle_events, LifecycleEventWriters},
+    replacement::{add_replace_node_asset_event, replace_node_assets},
+    spawn_node_with_neighbours,
+    tasks::{
+        return_generator, start_generation_task, AsyncGeneration, GenerationTask, ReturnedGenerator,
+    },
+    AssetsReady,
+};
 
-use crate::gen::spawn_node;
+#[cfg(feature = "assets-preloading")]
+use super::assets_preloading::{
+    insert_pending_assets_to_new_generations, update_pending_assets, GenerationAssetsReady,
+};
 
 use super::{assets::NoComponents, AssetSpawner, AssetsBundleSpawner, ComponentSpawner};
 
 /// A simple [`Plugin`] that automatically detects any [`Entity`] with a [`Generator`] `Component` and tries to run the contained generator once per frame until it succeeds.
 ///
 /// Once the generation is successful, the plugin will spawn the generated nodes assets.
+///
+/// The plugin sends the [`super::lifecycle`] events: [`super::lifecycle::GenerationStarted`] when a generator is registered, then [`super::lifecycle::GenerationDone`] or [`super::lifecycle::GenerationFailed`] after each try.
+///
+/// With the `assets-preloading` feature, a generation only starts once all the assets of its [`AssetSpawner`] are loaded, see [`super::assets_preloading`].
+///
+/// Generators with an [`AsyncGeneration`] component are run in a [`GenerationTask`] on the `AsyncComputeTaskPool`, their nodes are spawned once the task is finished.
+///
+/// Generators of other coordinate systems than `C` can be handled by the same plugin with [`ProcGenSimplePlugin::with_coordinate_system`].
 pub struct ProcGenSimplePlugin<
     C: CoordinateSystem,
     A: AssetsBundleSpawner,
     T: ComponentSpawner = NoComponents,
 > {
+    coordinate_systems: Vec<fn(&mut App)>,
     typestate: PhantomData<(C, A, T)>,
 }
 
@@ -34,10 +60,22 @@ impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner> Plugin
 {
     fn build(&self, app: &mut App) {
         app.insert_resource(PendingGenerations::default());
-        app.add_systems(
+        add_lifecycle_events(app);
+        add_replace_node_asset_event(app);
+
+        #[cfg(feature = "assets-preloading")]
+        app.add_event::<GenerationAssetsReady>().add_systems(
             Update,
-            (register_new_generations::<C>, generate_and_spawn::<C, A, T>).chain(),
+            (
+                insert_pending_assets_to_new_generations::<A, T>,
+                update_pending_assets::<A, T>,
+            )
+                .chain(),
         );
+
+        for add_systems in self.coordinate_systems.iter() {
+            add_systems(app);
+        }
     }
 }
 
@@ -47,9 +85,37 @@ impl<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner>
     /// Constructor
     pub fn new() -> Self {
         Self {
+            coordinate_systems: vec![add_coordinate_system_systems::<C, A, T>],
             typestate: PhantomData,
         }
     }
+
+    /// Also runs the generators of the coordinate system `C2`, with the same assets types `A` and `T`
+    pub fn with_coordinate_system<C2: CoordinateSystem>(mut self) -> Self {
+        self.coordinate_systems
+            .push(add_coordinate_system_systems::<C2, A, T>);
+        self
+    }
+}
+
+/// Adds the systems of the [`ProcGenSimplePlugin`] which depend on the coordinate system `C`
+fn add_coordinate_system_systems<
+    C: CoordinateSystem,
+    A: AssetsBundleSpawner,
+    T: ComponentSpawner,
+>(
+    app: &mut App,
+) {
+    let systems = (
+        register_new_generations::<C>,
+        generate_and_spawn::<C, A, T>,
+        poll_generation_tasks_and_spawn::<C, A, T>,
+        replace_node_assets::<C, A, T>,
+    )
+        .chain();
+    #[cfg(feature = "assets-preloading")]
+    let systems = systems.after(update_pending_assets::<A, T>);
+    app.add_systems(Update, systems);
 }
 
 /// Resource used by [`ProcGenSimplePlugin`] to track generations that are yet to generate a result
@@ -67,54 +133,55 @@ impl Default for PendingGenerations {
 }
 
 /// System used by [`ProcGenSimplePlugin`] to track entities with newly added [`Generator`] components
+///
+/// Generators put back on their entity at the end of a [`GenerationTask`] are not registered again.
 pub fn register_new_generations<C: CoordinateSystem>(
+    mut commands: Commands,
+    mut lifecycle_events: LifecycleEventWriters,
     mut pending_generations: ResMut<PendingGenerations>,
-    mut new_generations: Query<Entity, Added<Generator<C>>>,
+    mut new_generations: Query<(Entity, Has<ReturnedGenerator>), Added<Generator<C>>>,
 ) {
-    for gen_entity in new_generations.iter_mut() {
+    for (gen_entity, returned) in new_generations.iter_mut() {
+        if returned {
+            commands.entity(gen_entity).remove::<ReturnedGenerator>();
+            continue;
+        }
         pending_generations.pendings.insert(gen_entity);
+        lifecycle_events.started(gen_entity);
     }
 }
 
 /// System used by [`ProcGenSimplePlugin`] to run generators and spawn their node's assets
+///
+/// Generators with an [`AsyncGeneration`] component are moved into a [`GenerationTask`] instead, see [`poll_generation_tasks_and_spawn`].
 pub fn generate_and_spawn<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner>(
     mut commands: Commands,
+    mut lifecycle_events: LifecycleEventWriters,
     mut pending_generations: ResMut<PendingGenerations>,
-    mut generations: Query<(&mut Generator<C>, &AssetSpawner<A, T>)>,
+    mut generations: Query<
+        (&mut Generator<C>, &AssetSpawner<A, T>, Has<AsyncGeneration>),
+        AssetsReady,
+    >,
 ) {
     let mut generations_done = vec![];
     for &gen_entity in pending_generations.pendings.iter() {
-        if let Ok((mut generation, asset_spawner)) = generations.get_mut(gen_entity) {
-            match generation.generate_grid() {
-                Ok((gen_info, grid_data)) => {
-                    info!(
-                        "Generation {:?} done, try_count: {}, seed: {}; grid: {}",
-                        gen_entity,
-                        gen_info.try_count,
-                        generation.seed(),
-                        generation.grid()
-                    );
-                    for (node_index, node) in grid_data.nodes().iter().enumerate() {
-                        spawn_node(
-                            &mut commands,
-                            gen_entity,
-                            &generation.grid(),
-                            asset_spawner,
-                            node,
-                            node_index,
-                        );
-                    }
-                    generations_done.push(gen_entity);
-                }
-                Err(GeneratorError { node_index }) => {
-                    warn!(
-                        "Generation {:?} failed at node {}, seed: {}; grid: {}",
-                        gen_entity,
-                        node_index,
-                        generation.seed(),
-                        generation.grid()
-                    );
-                }
+        if let Ok((mut generation, asset_spawner, async_generation)) =
+            generations.get_mut(gen_entity)
+        {
+            if async_generation {
+                commands.entity(gen_entity).add(start_generation_task::<C>);
+                continue;
+            }
+            let result = generation.generate_grid();
+            if spawn_generation_result(
+                &mut commands,
+                &mut lifecycle_events,
+                gen_entity,
+                &generation,
+                asset_spawner,
+                result,
+            ) {
+                generations_done.push(gen_entity);
             }
         }
     }
@@ -122,3 +189,87 @@ pub fn generate_and_spawn<C: CoordinateSystem, A: AssetsBundleSpawner, T: Compon
         pending_generations.pendings.remove(&gen_entity);
     }
 }
+
+/// System used by [`ProcGenSimplePlugin`] to poll the [`GenerationTask`] of the generators, spawn their node's assets once they are finished, and put their [`Generator`] back on their entity.
+///
+/// As with synchronous generations, a failed generation is retried (in a new task) on the next frame.
+pub fn poll_generation_tasks_and_spawn<
+    C: CoordinateSystem,
+    A: AssetsBundleSpawner,
+    T: ComponentSpawner,
+>(
+    mut commands: Commands,
+    mut lifecycle_events: LifecycleEventWriters,
+    mut pending_generations: ResMut<PendingGenerations>,
+    mut tasks: Query<(Entity, &mut GenerationTask<C>, &AssetSpawner<A, T>)>,
+) {
+    for (gen_entity, mut task, asset_spawner) in tasks.iter_mut() {
+        let Some((generation, result)) = task.poll() else {
+            continue;
+        };
+        if spawn_generation_result(
+            &mut commands,
+            &mut lifecycle_events,
+            gen_entity,
+            &generation,
+            asset_spawner,
+            result,
+        ) {
+            pending_generations.pendings.remove(&gen_entity);
+        }
+        return_generator(&mut commands, gen_entity, generation);
+    }
+}
+
+/// Spawns the node's assets of a successful generation and sends the [`super::lifecycle`] event matching the `result`. Returns `true` if the generation succeeded.
+fn spawn_generation_result<C: CoordinateSystem, A: AssetsBundleSpawner, T: ComponentSpawner>(
+    commands: &mut Commands,
+    lifecycle_events: &mut LifecycleEventWriters,
+    gen_entity: Entity,
+    generation: &Generator<C>,
+    asset_spawner: &AssetSpawner<A, T>,
+    result: Result<(GenInfo, GridData<C, ModelInstance>), GeneratorError>,
+) -> bool {
+    match result {
+        Ok((gen_info, grid_data)) => {
+            info!(
+                "Generation {:?} done, try_count: {}, seed: {}; grid: {}",
+                gen_entity,
+                gen_info.try_count,
+                generation.seed(),
+                generation.grid()
+            );
+            for (node_index, node) in grid_data.nodes().iter().enumerate() {
+                if generation.rules().is_spawned_empty(
+                    node.model_index,
+                    generation.seed(),
+                    node_index,
+                ) {
+                    continue;
+                }
+                spawn_node_with_neighbours(
+                    commands,
+                    gen_entity,
+                    &generation.grid(),
+                    asset_spawner,
+                    node,
+                    node_index,
+                    grid_data_neighbours(&grid_data, node_index),
+                );
+            }
+            lifecycle_events.done(gen_entity, gen_info);
+            true
+        }
+        Err(error @ GeneratorError { node_index }) => {
+            lifecycle_events.failed(gen_entity, error);
+            warn!(
+                "Generation {:?} failed at node {}, seed: {}; grid: {}",
+                gen_entity,
+                node_index,
+                generation.seed(),
+                generation.grid()
+            );
+            false
+        }
+    }
+}
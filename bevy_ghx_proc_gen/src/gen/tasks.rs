@@ -0,0 +1,75 @@
+use bevy::{
+    ecs::{component::Component, entity::Entity, system::Commands, world::EntityWorldMut},
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+use bevy_ghx_grid::ghx_grid::{coordinate_system::CoordinateSystem, grid::GridData};
+use ghx_proc_gen::{
+    generator::{model::ModelInstance, GenInfo, Generator},
+    GeneratorError,
+};
+
+/// Output of a [`GenerationTask`]: the [`Generator`] moved into the task, and the result of its generation
+pub type GenerationTaskOutput<C> = (
+    Generator<C>,
+    Result<(GenInfo, GridData<C, ModelInstance>), GeneratorError>,
+);
+
+/// Marker component to insert next to a [`Generator`] to have the simple and headless plugins run its generations in a [`GenerationTask`] instead of running them on the main schedule, which stalls the frames on big grids.
+///
+/// The results go through the same flow as the ones of synchronous generations: the nodes are spawned (or sent as events) and the [`super::lifecycle`] events are sent once the task is finished.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct AsyncGeneration;
+
+/// Component holding a generation running on the [`AsyncComputeTaskPool`].
+///
+/// The [`Generator`] of the entity is moved into the task while it runs (the entity has no [`Generator`] component during this time) and is put back on the entity, with a [`ReturnedGenerator`] marker, once the task is finished. Despawning the entity or removing this component cancels the generation.
+#[derive(Component)]
+pub struct GenerationTask<C: CoordinateSystem> {
+    task: Task<GenerationTaskOutput<C>>,
+}
+
+impl<C: CoordinateSystem> GenerationTask<C> {
+    /// Moves `generator` into a new task running [`Generator::generate_grid`] on the [`AsyncComputeTaskPool`]
+    pub fn spawn(generator: Generator<C>) -> Self {
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let mut generator = generator;
+            let result = generator.generate_grid();
+            (generator, result)
+        });
+        Self { task }
+    }
+
+    /// Returns the output of the task if it is finished, without blocking. Once the output has been returned, the task must not be polled again.
+    pub fn poll(&mut self) -> Option<GenerationTaskOutput<C>> {
+        block_on(future::poll_once(&mut self.task))
+    }
+}
+
+/// Marker component inserted along with a [`Generator`] put back on its entity at the end of a [`GenerationTask`], so that the plugins do not register it as a new generation. Removed by the plugins when they detect the returned [`Generator`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ReturnedGenerator;
+
+/// Entity command moving the [`Generator`] of an entity into a new [`GenerationTask`] inserted on the same entity. Does nothing if the entity has no [`Generator`].
+///
+/// ### Example
+///
+/// ```ignore
+/// commands.entity(gen_entity).add(start_generation_task::<Cartesian3D>);
+/// ```
+pub fn start_generation_task<C: CoordinateSystem>(mut entity: EntityWorldMut) {
+    if let Some(generator) = entity.take::<Generator<C>>() {
+        entity.insert(GenerationTask::spawn(generator));
+    }
+}
+
+/// Removes the finished [`GenerationTask`] of `gen_entity` and puts its `generator` back on the entity, with a [`ReturnedGenerator`] marker
+pub fn return_generator<C: CoordinateSystem>(
+    commands: &mut Commands,
+    gen_entity: Entity,
+    generator: Generator<C>,
+) {
+    commands
+        .entity(gen_entity)
+        .remove::<GenerationTask<C>>()
+        .insert((generator, ReturnedGenerator));
+}
@@ -5,6 +5,8 @@
 
 /// Utilities & debug tools/plugins for using the ghx_proc_gen generator
 pub mod gen;
+/// Re-exports the most used types of this crate and of `ghx_proc_gen` (see [`ghx_proc_gen::prelude`]), to be glob-imported with `use bevy_ghx_proc_gen::prelude::*;`
+pub mod prelude;
 
 pub use bevy_ghx_grid;
 pub use ghx_proc_gen as proc_gen;
@@ -12,7 +14,7 @@ pub use ghx_proc_gen as proc_gen;
 #[cfg(feature = "picking")]
 pub use bevy_mod_picking;
 
-#[cfg(feature = "egui-edit")]
+#[cfg(feature = "egui")]
 pub use bevy_egui;
 
 use bevy::{ecs::bundle::Bundle, prelude::SpatialBundle};
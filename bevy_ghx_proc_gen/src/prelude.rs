@@ -0,0 +1,29 @@
+pub use ghx_proc_gen::prelude::*;
+
+pub use crate::{
+    gen::{
+        assets::{
+            AssetSpawner, AssetsBundleSpawner, AssetsUpAxis, ComponentSpawner, EffectBundle,
+            ModelAsset, NoComponents, RulesModelsAssets, SpawnEffect,
+        },
+        lifecycle::{GenerationDone, GenerationFailed, GenerationStarted},
+        recording::{GenerationRecorder, ProcGenRecordingPlugin, TimelinePlayback},
+        replacement::ReplaceNodeAsset,
+        tasks::{AsyncGeneration, GenerationTask},
+        GridNode,
+    },
+    GeneratorBundle,
+};
+
+#[cfg(feature = "debug-plugin")]
+pub use crate::gen::debug_plugin::{GenerationControl, GenerationViewMode, ProcGenDebugPlugin};
+#[cfg(feature = "default-assets-bundle-spawners")]
+pub use crate::gen::default_bundles::{AtlasSprite, MaterialMesh, PbrMesh};
+#[cfg(feature = "headless-plugin")]
+pub use crate::gen::headless_plugin::{GenerationUpdateEvent, ProcGenHeadlessPlugin};
+#[cfg(feature = "minimap")]
+pub use crate::gen::minimap::{
+    GenerationMinimap, MinimapPalette, MinimapView, ProcGenMinimapPlugin,
+};
+#[cfg(feature = "simple-plugin")]
+pub use crate::gen::simple_plugin::ProcGenSimplePlugin;
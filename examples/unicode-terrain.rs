@@ -1,28 +1,22 @@
-use std::{
-    io::{stdin, stdout, Write},
-    thread, time,
-};
-
 use ghx_proc_gen::{
     generator::{
-        model::{ModelCollection, ModelInstance},
+        model::ModelCollection,
         node_heuristic::NodeSelectionHeuristic,
         observer::QueuedStatefulObserver,
         rules::RulesBuilder,
         socket::{SocketCollection, SocketsCartesian2D},
-        GenerationStatus, ModelSelectionHeuristic,
-    },
-    ghx_grid::{
-        coordinate_system::Cartesian2D,
-        grid::{GridData, GridDefinition},
+        ModelSelectionHeuristic,
     },
+    ghx_grid::{coordinate_system::Cartesian2D, grid::GridDefinition},
+    terminal_viewer::{TerminalViewer, ViewerMode},
 };
 
 use ghx_proc_gen::generator::{builder::GeneratorBuilder, RngMode};
 
 pub enum GenerationViewMode {
-    /// The parameter is the number of milliseconds to wait between each step.
+    /// Step by step in the terminal viewer, with keyboard controls. The parameter is the number of milliseconds to wait between each step.
     StepByStepTimed(u64),
+    /// Step by step in the terminal viewer, with keyboard controls, starting paused.
     StepByStepPaused,
     Final,
 }
@@ -95,61 +89,24 @@ fn main() {
         .with_model_heuristic(ModelSelectionHeuristic::WeightedProbability)
         .build()
         .unwrap();
-    let mut observer = QueuedStatefulObserver::new(&mut generator);
+    let mut viewer = TerminalViewer::new(icons);
 
     match GENERATION_VIEW_MODE {
         GenerationViewMode::Final => {
+            let mut observer = QueuedStatefulObserver::new(&mut generator);
             generator.generate().unwrap();
             observer.dequeue_all();
             println!("Final grid:");
-            display_grid(observer.grid_data(), &icons);
-        }
-        _ => {
-            let mut step = 0;
-            let mut done = false;
-            while !done {
-                match generator.select_and_propagate() {
-                    Ok(status) => match status {
-                        GenerationStatus::Ongoing => (),
-                        GenerationStatus::Done => done = true,
-                    },
-                    Err(_) => (),
-                }
-                observer.dequeue_all();
-                println!("Grid at iteration n°{}:", step);
-                display_grid(observer.grid_data(), &icons);
-                match GENERATION_VIEW_MODE {
-                    GenerationViewMode::StepByStepTimed(delay) => {
-                        thread::sleep(time::Duration::from_millis(delay));
-                    }
-                    GenerationViewMode::StepByStepPaused => pause(),
-                    _ => (),
-                }
-                step += 1;
+            for line in viewer.render(observer.grid_data()) {
+                println!("{}", line);
             }
         }
-    }
-}
-
-fn display_grid(
-    data_grid: &GridData<Cartesian2D, Option<ModelInstance>>,
-    icons: &Vec<&'static str>,
-) {
-    for y in (0..data_grid.grid().size_y()).rev() {
-        for x in 0..data_grid.grid().size_x() {
-            match data_grid.get_2d(x, y) {
-                None => print!("❓"),
-                Some(node) => print!("{}", icons[node.model_index]),
-            }
+        GenerationViewMode::StepByStepTimed(delay) => {
+            viewer = viewer
+                .with_mode(ViewerMode::Auto)
+                .with_step_delay(std::time::Duration::from_millis(delay));
+            viewer.run(&mut generator).unwrap();
         }
-        println!();
+        GenerationViewMode::StepByStepPaused => viewer.run(&mut generator).unwrap(),
     }
 }
-
-fn pause() {
-    let mut word = String::new();
-    let mut stdout = stdout();
-    stdout.write(b"Press Enter to continue").unwrap();
-    stdout.flush().unwrap();
-    stdin().read_line(&mut word).unwrap();
-}
@@ -0,0 +1,67 @@
+//! Generation benchmarks on the canyon rule set (shared with the `canyon` example of the `bevy_examples`), mostly bound by the propagation of the constraints.
+//!
+//! Run with and without the `simd-propagation` feature to compare the scalar and bitmask propagations.
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ghx_proc_gen::{
+    generator::{
+        builder::GeneratorBuilder,
+        model::ModelInstance,
+        node_heuristic::NodeSelectionHeuristic,
+        rules::{Rules, RulesBuilder},
+        RngMode,
+    },
+    ghx_grid::{coordinate_system::Cartesian3D, grid::GridDefinition},
+};
+
+use canyon::canyon_models;
+
+#[path = "common/canyon.rs"]
+mod canyon;
+
+const SEED: u64 = 42;
+const GRID_HEIGHT: u32 = 6;
+
+/// Canyon rules, without the assets. Returns the rules and the void model.
+fn canyon_rules() -> (Rules<Cartesian3D>, ModelInstance) {
+    let (canyon_models, _names, models, sockets) = canyon_models();
+    let rules = RulesBuilder::new_cartesian_3d(models, sockets)
+        .build()
+        .unwrap();
+    (rules, canyon_models.void)
+}
+
+fn canyon_generation(c: &mut Criterion) {
+    let (rules, void_instance) = canyon_rules();
+    let rules = Arc::new(rules);
+
+    let mut group = c.benchmark_group("canyon");
+    group.sample_size(20);
+    for size in [20, 40] {
+        let grid = GridDefinition::new_cartesian_3d(size, GRID_HEIGHT, size, false, false, false);
+        let mut initial_constraints = grid.new_grid_data(None);
+        initial_constraints.set_all_y(GRID_HEIGHT - 1, Some(void_instance));
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &grid, |b, grid| {
+            b.iter(|| {
+                let mut generator = GeneratorBuilder::new()
+                    .with_shared_rules(Arc::clone(&rules))
+                    .with_grid(grid.clone())
+                    .with_max_retry_count(100)
+                    .with_rng(RngMode::Seeded(SEED))
+                    .with_node_heuristic(NodeSelectionHeuristic::MinimumRemainingValue)
+                    .with_initial_grid(initial_constraints.clone())
+                    .unwrap()
+                    .build()
+                    .unwrap();
+                generator.generate_grid().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, canyon_generation);
+criterion_main!(benches);
@@ -0,0 +1,158 @@
+use ghx_grid::{coordinate_system::CoordinateSystem, grid::GridData};
+
+use crate::{
+    generator::model::{ModelIndex, ModelInstance},
+    grid_data::NeighbourCondition,
+    NodeIndex,
+};
+
+/// Describes when to spawn a decoration `D` (any user data: an asset, an identifier, ...) on the nodes of a generation output
+#[derive(Clone, Debug)]
+pub struct DecorationRule<D> {
+    decoration: D,
+    chance: f32,
+    models: Vec<ModelIndex>,
+    conditions: Vec<NeighbourCondition>,
+}
+
+impl<D> DecorationRule<D> {
+    /// Creates a rule spawning `decoration` on any node, with a probability of `chance` (clamped between `0` and `1`)
+    pub fn new(decoration: D, chance: f32) -> Self {
+        Self {
+            decoration,
+            chance: chance.clamp(0., 1.),
+            models: Vec::new(),
+            conditions: Vec::new(),
+        }
+    }
+
+    /// Only spawns the decoration on the nodes with one of the given models
+    pub fn on_models(mut self, models: Vec<ModelIndex>) -> Self {
+        self.models = models;
+        self
+    }
+
+    /// Only spawns the decoration on the nodes meeting `condition`. All the conditions of a rule must be met.
+    pub fn with_condition(mut self, condition: NeighbourCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+}
+
+/// A decoration to spawn, returned by [`Decorations::decorate`]
+#[derive(Clone, Debug)]
+pub struct DecorationSpawn<'a, D> {
+    /// Node to decorate
+    pub node_index: NodeIndex,
+    /// Index of the [`DecorationRule`] which spawned the decoration
+    pub rule_index: usize,
+    /// Decoration of the rule
+    pub decoration: &'a D,
+    /// Deterministic random value of this node and rule, which can be used to vary the decoration (offset, rotation, scale, ...)
+    pub random: u64,
+}
+
+/// A post-generation decoration pass: a set of [`DecorationRule`] applied on a generation output, without involving the generator. Used to spawn extra details such as grass clumps, debris or puddles.
+///
+/// The random draws only depend on the seed, the node and the rule: the same output and seed always give the same decorations, and adding a rule does not change the decorations of the other rules.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::{decoration::{DecorationRule, Decorations}, generator::model::{ModelInstance, ModelRotation}, grid_data::NeighbourCondition};
+/// use ghx_grid::{direction::Direction, grid::{GridData, GridDefinition}};
+///
+/// let grid = GridDefinition::new_cartesian_2d(4, 4, false, false);
+/// // Model 0 is grass, model 1 is water
+/// let output = GridData::new(
+///     grid.clone(),
+///     (0..16)
+///         .map(|i| ModelInstance { model_index: (i / 8) as usize, rotation: ModelRotation::Rot0 })
+///         .collect(),
+/// );
+/// let decorations = Decorations::new(42)
+///     .with_rule(DecorationRule::new("grass_clump", 0.5).on_models(vec![0]))
+///     .with_rule(
+///         DecorationRule::new("reeds", 1.)
+///             .on_models(vec![0])
+///             .with_condition(NeighbourCondition::Is(Direction::YForward, vec![1])),
+///     );
+/// let spawns = decorations.decorate(&output);
+/// assert_eq!(spawns.iter().filter(|spawn| *spawn.decoration == "reeds").count(), 4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Decorations<D> {
+    seed: u64,
+    rules: Vec<DecorationRule<D>>,
+}
+
+impl<D> Decorations<D> {
+    /// Creates an empty decoration pass using `seed` for its random draws
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Adds a [`DecorationRule`]
+    pub fn with_rule(mut self, rule: DecorationRule<D>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Adds a [`DecorationRule`]
+    pub fn add_rule(&mut self, rule: DecorationRule<D>) {
+        self.rules.push(rule);
+    }
+
+    /// Returns the rules of the decoration pass
+    pub fn rules(&self) -> &Vec<DecorationRule<D>> {
+        &self.rules
+    }
+
+    /// Returns the decorations to spawn on `grid_data`, in node index order then rule order. A node may receive multiple decorations.
+    pub fn decorate<C: CoordinateSystem>(
+        &self,
+        grid_data: &GridData<C, ModelInstance>,
+    ) -> Vec<DecorationSpawn<'_, D>> {
+        let mut spawns = Vec::new();
+        for (node_index, model_instance) in grid_data.nodes().iter().enumerate() {
+            for (rule_index, rule) in self.rules.iter().enumerate() {
+                if !rule.models.is_empty() && !rule.models.contains(&model_instance.model_index) {
+                    continue;
+                }
+                let random = node_random(self.seed, node_index, rule_index);
+                // Use the 24 high bits for the chance, the full value is given to the user
+                let draw = (random >> 40) as f32 / (1u64 << 24) as f32;
+                if draw >= rule.chance {
+                    continue;
+                }
+                if !rule
+                    .conditions
+                    .iter()
+                    .all(|condition| condition.is_met_in(grid_data, node_index))
+                {
+                    continue;
+                }
+                spawns.push(DecorationSpawn {
+                    node_index,
+                    rule_index,
+                    decoration: &rule.decoration,
+                    random,
+                });
+            }
+        }
+        spawns
+    }
+}
+
+/// Deterministic random value for a node and a rule (SplitMix64 finalizer), independent of the iteration order and of the `rand` implementations
+fn node_random(seed: u64, node_index: NodeIndex, rule_index: usize) -> u64 {
+    let mut z = seed
+        ^ (node_index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (rule_index as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
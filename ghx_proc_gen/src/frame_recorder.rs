@@ -0,0 +1,193 @@
+use std::{fs::File, path::Path};
+
+use ghx_grid::coordinate_system::Cartesian2D;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, ImageResult, Rgba, RgbaImage,
+};
+
+use crate::{
+    generator::{model::ModelIndex, observer::QueuedStatefulObserver, GenerationStatus, Generator},
+    GeneratorError,
+};
+
+/// Color of the nodes not generated yet
+pub const DEFAULT_EMPTY_COLOR: Rgba<u8> = Rgba([24, 24, 24, 255]);
+
+/// Records the steps of a 2d generation as image frames (one pixel block per node, one color per model), to document or debug the behavior of some rules.
+///
+/// The frames can be saved as PNG files or composed into an animated GIF.
+///
+/// ### Example
+///
+/// ```no_run
+/// use ghx_proc_gen::{frame_recorder::FrameRecorder, generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection}};
+/// use ghx_grid::grid::GridDefinition;
+/// use image::Rgba;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (white, black) = (sockets.create(), sockets.create());
+/// sockets.add_connection(white, vec![black]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(white));
+/// models.create(SocketsCartesian2D::Mono(black));
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+///
+/// let mut generator = GeneratorBuilder::new()
+///     .with_rules(rules)
+///     .with_grid(GridDefinition::new_cartesian_2d(8, 8, false, false))
+///     .build()
+///     .unwrap();
+/// let mut recorder = FrameRecorder::new(&mut generator)
+///     .with_scale(8)
+///     .with_model_color(0, Rgba([255, 255, 255, 255]))
+///     .with_model_color(1, Rgba([0, 0, 0, 255]));
+/// recorder.record_generation(&mut generator, 1).unwrap();
+/// recorder.save_gif("chessboard.gif", 50).unwrap();
+/// ```
+pub struct FrameRecorder {
+    observer: QueuedStatefulObserver<Cartesian2D>,
+    /// Colors overrides, indexed by [`ModelIndex`]
+    palette: Vec<Option<Rgba<u8>>>,
+    empty_color: Rgba<u8>,
+    /// Size in pixels of a node in the frames
+    scale: u32,
+    frames: Vec<RgbaImage>,
+}
+
+impl FrameRecorder {
+    /// Creates a recorder observing `generator`. The models without a registered color get a color derived from their model index.
+    pub fn new(generator: &mut Generator<Cartesian2D>) -> Self {
+        Self {
+            observer: QueuedStatefulObserver::new(generator),
+            palette: Vec::new(),
+            empty_color: DEFAULT_EMPTY_COLOR,
+            scale: 1,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Sets the size in pixels of a node in the frames (at least 1). Defaults to 1.
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    /// Sets the color of the nodes not generated yet. Defaults to [`DEFAULT_EMPTY_COLOR`].
+    pub fn with_empty_color(mut self, color: Rgba<u8>) -> Self {
+        self.empty_color = color;
+        self
+    }
+
+    /// Sets the color of the model `model_index`
+    pub fn with_model_color(mut self, model_index: ModelIndex, color: Rgba<u8>) -> Self {
+        if self.palette.len() <= model_index {
+            self.palette.resize(model_index + 1, None);
+        }
+        self.palette[model_index] = Some(color);
+        self
+    }
+
+    /// Returns the color of the model `model_index`
+    pub fn model_color(&self, model_index: ModelIndex) -> Rgba<u8> {
+        match self.palette.get(model_index).copied().flatten() {
+            Some(color) => color,
+            None => palette_color(model_index),
+        }
+    }
+
+    /// Applies the updates received from the generator and records the current state of the generation as a new frame
+    pub fn record_frame(&mut self) {
+        self.observer.dequeue_all();
+        let grid_data = self.observer.grid_data();
+        let (size_x, size_y, _) = grid_data.grid().size();
+        let mut frame = RgbaImage::new(size_x * self.scale, size_y * self.scale);
+        for (node_index, node) in grid_data.nodes().iter().enumerate() {
+            let color = match node {
+                Some(model_instance) => self.model_color(model_instance.model_index),
+                None => self.empty_color,
+            };
+            let position = grid_data.grid().pos_from_index(node_index);
+            // Image rows go down while the grid y axis goes up
+            let (px, py) = (
+                position.x * self.scale,
+                (size_y - 1 - position.y) * self.scale,
+            );
+            for dy in 0..self.scale {
+                for dx in 0..self.scale {
+                    frame.put_pixel(px + dx, py + dy, color);
+                }
+            }
+        }
+        self.frames.push(frame);
+    }
+
+    /// Reinitializes `generator` and runs its generation step by step (see [`Generator::select_and_propagate`]), recording a frame after the reinitialization, every `steps_per_frame` steps, and at the end of the generation.
+    ///
+    /// On a contradiction, the error is returned and the last recorded frame shows the state of the generation a few steps before it.
+    pub fn record_generation(
+        &mut self,
+        generator: &mut Generator<Cartesian2D>,
+        steps_per_frame: usize,
+    ) -> Result<(), GeneratorError> {
+        let steps_per_frame = steps_per_frame.max(1);
+        generator.reinitialize();
+        self.record_frame();
+        let mut steps = 0;
+        loop {
+            let status = generator.select_and_propagate()?;
+            steps += 1;
+            match status {
+                GenerationStatus::Done => {
+                    self.record_frame();
+                    return Ok(());
+                }
+                GenerationStatus::Ongoing if steps % steps_per_frame == 0 => self.record_frame(),
+                GenerationStatus::Ongoing => (),
+            }
+        }
+    }
+
+    /// Returns the recorded frames
+    pub fn frames(&self) -> &[RgbaImage] {
+        &self.frames
+    }
+
+    /// Removes all the recorded frames
+    pub fn clear_frames(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Saves each recorded frame as a PNG file named `{prefix}_{frame_index}.png` (frame index padded to 4 digits) in the `directory`
+    pub fn save_frames<P: AsRef<Path>>(&self, directory: P, prefix: &str) -> ImageResult<()> {
+        for (frame_index, frame) in self.frames.iter().enumerate() {
+            frame.save(
+                directory
+                    .as_ref()
+                    .join(format!("{}_{:04}.png", prefix, frame_index)),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Composes the recorded frames into a looping animated GIF, each frame being displayed `frame_delay_ms` milliseconds
+    pub fn save_gif<P: AsRef<Path>>(&self, path: P, frame_delay_ms: u32) -> ImageResult<()> {
+        let mut encoder = GifEncoder::new(File::create(path)?);
+        encoder.set_repeat(Repeat::Infinite)?;
+        encoder.encode_frames(self.frames.iter().map(|frame| {
+            Frame::from_parts(
+                frame.clone(),
+                0,
+                0,
+                Delay::from_numer_denom_ms(frame_delay_ms, 1),
+            )
+        }))
+    }
+}
+
+/// Returns the default color of a model, see [`crate::palette_color`]
+fn palette_color(model_index: ModelIndex) -> Rgba<u8> {
+    let [r, g, b] = crate::palette_color(model_index);
+    let channel = |value: f32| (value * 255.) as u8;
+    Rgba([channel(r), channel(g), channel(b), 255])
+}
@@ -4,52 +4,124 @@ use std::{collections::HashMap, sync::Arc};
 #[cfg(feature = "bevy")]
 use bevy::ecs::component::Component;
 
+use bitvec::vec::BitVec;
 use ghx_grid::{
     coordinate_system::{Cartesian2D, CoordinateSystem},
     grid::{GridData, GridDefinition, NodeRef},
 };
+use rand::{Rng, RngCore, SeedableRng};
 
 use crate::{GeneratorError, NodeIndex, NodeSetError};
 
 use self::{
     builder::{GeneratorBuilder, Unset},
-    internal_generator::{InternalGenerator, InternalGeneratorStatus},
+    internal_generator::{InitialBans, InternalGenerator, InternalGeneratorStatus},
     model::{ModelIndex, ModelInstance, ModelRotation, ModelVariantIndex},
-    node_heuristic::NodeSelectionHeuristic,
-    observer::GenerationUpdate,
+    node_heuristic::{HeuristicPhase, NodeSelectionHeuristic},
+    node_weights::NodeWeights,
+    observer::{GenerationUpdate, NodeRegion, ObserverSender},
     rules::{ModelInfo, ModelVariantRef, Rules},
 };
 
+/// Type-erased [`Generator`] hiding its coordinate system, see [`crate::generator::any_generator::AnyGenerator`]
+pub mod any_generator;
 /// Defines a [`GeneratorBuilder`] used to create a generator
 pub mod builder;
+/// Chunked generation of large grids, see [`Generator::generate_chunked`]
+pub mod chunked;
+/// Defines the [`crate::generator::diagonal::Diagonal`] adjacencies of 2d rules and their sockets
+pub mod diagonal;
+/// Defines the [`crate::generator::direction_set::DirectionSet`] of additional adjacencies (diagonals, 26-connected neighbours, ...) used by some rules
+pub mod direction_set;
+/// Dungeon constraints (rooms, doors, corridor budget and connectivity) on top of the generator, see [`crate::generator::dungeon::DungeonConstraints`]
+pub mod dungeon;
+/// Support rules pruning unsupported "heavy" models before a 3d generation, see [`crate::generator::gravity::GravityPruning`]
+pub mod gravity;
+/// Helpers to constrain a 3d generation with a heightmap
+pub mod heightmap;
+/// Coarse-to-fine generation, see [`crate::generator::hierarchical::HierarchicalGenerator`]
+pub mod hierarchical;
 /// Defines [`crate::generator::model::Model`] and their associated type & utilities
 pub mod model;
 /// Defines the different possible [`NodeSelectionHeuristic`]
 pub mod node_heuristic;
+/// Per-node multipliers of the models weights, see [`crate::generator::node_weights::NodeWeights`]
+pub mod node_weights;
 /// Defines different possible observers to view the results:execution of a [`Generator`]
 pub mod observer;
+/// Namespaced string keys (`"base:grass"`, `"mod:lava"`) for sockets and models
+pub mod registry;
+/// Repair of hand-edited grids, by regenerating the nodes around the ones not respecting the rules
+pub mod repair;
 /// Defines the [`Rules`] used by a [`Generator`]
 pub mod rules;
+/// Two-pass generation with some skeleton models frozen before the full generation
+pub mod skeleton;
 /// Defines [`crate::generator::socket::Socket`] and their associated type & utilities
 pub mod socket;
 
+pub(crate) mod callbacks;
+pub(crate) mod candidates;
 pub(crate) mod internal_generator;
+pub(crate) mod journal;
 
 /// Defines a heuristic for the choice of a model among the possible ones when a node has been selected for generation.
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ModelSelectionHeuristic {
     /// Choses a random model among the possible ones, weighted by each model weight.
     #[default]
     WeightedProbability,
 }
 
+/// Defines how a [`Generator`] stores the models still possible on each node, as well as their supports counts (used to propagate the constraints).
+///
+/// ### Memory
+///
+/// For a grid of `N` nodes, `M` model variations (rotations included) and `D` directions, a node needs `M` bits for its possible models and `M * D` `usize` for its supports counts. For a 512x512 2d grid with 1000 model variations, this is about 8 GB of supports counts on a 64 bits platform.
+#[derive(Default, Clone, Copy, Debug)]
+pub enum CandidateStorage {
+    /// All the nodes data is allocated when the generator is created, and fully reset on each reinitialization.
+    #[default]
+    Dense,
+    /// Nodes are grouped in pages of `page_size` nodes. The data of a page is only allocated once a model is banned from one of its nodes. Until then, all the models are considered possible on the page nodes, with their initial supports counts.
+    ///
+    /// The memory used is proportional to the number of allocated pages, which can be much lower than the full grid when the generation is done step by step, or only on a part of the grid (with [`Generator::collapse_node`] for example). Since models with no possible neighbour in a direction are banned from all the nodes on initialization, such rules allocate all the pages anyway.
+    ///
+    /// Accessing a node data is a bit slower than with [`CandidateStorage::Dense`].
+    Paged {
+        /// Number of nodes in a page
+        page_size: usize,
+    },
+}
+
+/// Defines how strictly the parallel generations of a [`Generator`] (such as [`Generator::generate_n_parallel`]) are kept deterministic.
+///
+/// In all levels, each partition of the work (each output of [`Generator::generate_n_parallel`]) gets its own RNG stream, seeded from a seed drawn from the generator master seed before any work is distributed, and the results are merged in partition order: successful outputs never depend on the number of threads or on their scheduling.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeterminismLevel {
+    /// All the partitions are run to completion, and the returned error is the one of the first failing partition, in partition order. Results and errors are fully reproducible.
+    #[default]
+    Strict,
+    /// The parallel work is stopped as soon as a partition fails, and the returned error is the one of the first failure detected, which may depend on the threads scheduling. Faster when failures are expected.
+    Relaxed,
+}
+
 /// Different ways to seed the RNG of the generator.
 ///
 /// Note: No matter the selected mode, on each failed generation/reset, the generator will generate and use a new `u64` seed using the previous `u64` seed.
 ///
 /// As an example: if a generation with 50 retries is requested with a seed `s1`, but the generations fails 14 times before finally succeeding with seed `s15`, requesting the generation with any of the seeds `s1`, `s2`, ... to `s15` will give the exact same final successful result. However, while `s1` will need to redo the 14 failed generations before succeeding,`s15` will directly generate the successfull result.
-#[derive(Default, Clone, Copy)]
+///
+/// ### Determinism
+///
+/// For a given version of this crate, the same seed used with the same [`Rules`] (built from the same models & sockets, declared in the same order), the same [`GridDefinition`], the same heuristics and the same initial nodes will always give the same output, on any platform. This is also true for the successive seeds used on retries.
+///
+/// - Building [`Rules`] never depends on the iteration order of a hash-based container: models variations and their allowed neighbours are ordered by index, socket connections are kept in their declaration order.
+/// - [`NodeSelectionHeuristic::MinimumEntropy`] computes entropies with `f64::ln`, whose precision may vary between platforms math libraries. In rare cases of near-equal entropies, this heuristic could select different nodes on different platforms. The other heuristics do not have this limitation.
+/// - Outputs are not guaranteed to be identical between different versions of this crate (or of `rand`). See the `golden` module (with the `test-utils` feature) to detect such changes in your rules.
+/// - With [`RngMode::External`], determinism also depends on the external RNG implementation.
+#[derive(Default, Clone)]
 pub enum RngMode {
     /// The generator will use the given seed for its random source.
     ///
@@ -59,6 +131,92 @@ pub enum RngMode {
     /// The randomly generated seed can still be retrieved on the generator once created.
     #[default]
     RandomSeed,
+    /// The generator will use an external RNG implementation (such as a PCG or Xoshiro RNG) as its random source. See [`ExternalRng`].
+    ///
+    /// The external RNG is still created from a `u64` seed, so that the seeds behaviour described above is the same with any RNG implementation.
+    External(ExternalRng),
+}
+
+impl RngMode {
+    /// Returns the `u64` seed described by this mode, generating a random one if needed.
+    pub(crate) fn seed(&self) -> u64 {
+        match self {
+            RngMode::Seeded(seed) => *seed,
+            RngMode::RandomSeed => rand::thread_rng().gen::<u64>(),
+            RngMode::External(external_rng) => match external_rng.seed {
+                Some(seed) => seed,
+                None => rand::thread_rng().gen::<u64>(),
+            },
+        }
+    }
+}
+
+/// Function creating an external RNG from a `u64` seed
+pub type RngFactory = dyn Fn(u64) -> Box<dyn CloneableRng> + Send + Sync;
+
+/// An RNG which can be cloned behind a `Box`, so that a [`Generator`] can save its state in its undo journal. Implemented for any `Clone` RNG. The RNG does not need to be `Sync`.
+pub trait CloneableRng: RngCore + Send {
+    /// Returns a boxed copy of this RNG, in the same state
+    fn clone_boxed(&self) -> Box<dyn CloneableRng>;
+}
+
+impl<R: RngCore + Clone + Send + 'static> CloneableRng for R {
+    fn clone_boxed(&self) -> Box<dyn CloneableRng> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn CloneableRng> {
+    fn clone(&self) -> Self {
+        (**self).clone_boxed()
+    }
+}
+
+/// Describes an external RNG to be used by a [`Generator`], see [`RngMode::External`].
+///
+/// ### Example
+///
+/// Any RNG implementing [`SeedableRng`] can be used directly:
+/// ```
+/// use ghx_proc_gen::generator::{ExternalRng, RngMode};
+/// use rand::rngs::StdRng;
+///
+/// // A `rand_pcg::Pcg64` or `rand_xoshiro::Xoshiro256PlusPlus` could be used the same way.
+/// let rng_mode = RngMode::External(ExternalRng::new::<StdRng>(Some(42)));
+/// ```
+#[derive(Clone)]
+pub struct ExternalRng {
+    seed: Option<u64>,
+    factory: Arc<RngFactory>,
+}
+
+impl ExternalRng {
+    /// Creates an [`ExternalRng`] of type `R`, seeded through [`SeedableRng::seed_from_u64`] with `seed`, or with a random seed if `None`.
+    pub fn new<R: RngCore + SeedableRng + Clone + Send + 'static>(seed: Option<u64>) -> Self {
+        Self::from_factory(seed, |seed| Box::new(R::seed_from_u64(seed)))
+    }
+
+    /// Creates an [`ExternalRng`] using `factory` to create an RNG from a `u64` seed. The seed is `seed`, or a random seed if `None`.
+    ///
+    /// For the generations to be reproducible, `factory` should always create the same RNG for the same seed.
+    pub fn from_factory<F>(seed: Option<u64>, factory: F) -> Self
+    where
+        F: Fn(u64) -> Box<dyn CloneableRng> + Send + Sync + 'static,
+    {
+        Self {
+            seed,
+            factory: Arc::new(factory),
+        }
+    }
+
+    /// Returns the seed of this [`ExternalRng`], `None` meaning that a random seed will be used.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub(crate) fn create(&self, seed: u64) -> Box<dyn CloneableRng> {
+        (self.factory)(seed)
+    }
 }
 
 /// Represents the current generation state, if not failed.
@@ -87,6 +245,30 @@ pub struct GenInfo {
     pub try_count: u32,
 }
 
+/// Statistics of a generation step, see [`Generator::last_step_stats`]
+#[cfg(feature = "step-stats")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StepStats {
+    /// Node selected by the step. `None` if there was no node left to select.
+    pub node_index: Option<NodeIndex>,
+    /// Time spent in the step: node selection, model selection and propagation
+    pub elapsed: std::time::Duration,
+    /// Number of bans propagated by the step
+    pub propagated_bans: usize,
+    /// Number of nodes generated by the step: the selected node and the nodes forced by the propagation
+    pub generated_nodes: usize,
+}
+
+/// One of the outputs of [`Generator::generate_n`]
+pub struct GeneratedGrid<C: CoordinateSystem> {
+    /// Seed of the successful generation
+    pub seed: u64,
+    /// Information about the generation
+    pub gen_info: GenInfo,
+    /// Generated nodes
+    pub grid_data: GridData<C, ModelInstance>,
+}
+
 enum NodeSetStatus {
     AlreadySet,
     CanBeSet,
@@ -100,6 +282,7 @@ type Collector<'a> = Option<&'a mut Vec<GeneratedNode>>;
 pub struct Generator<C: CoordinateSystem> {
     // === Dynamic configuration ===
     max_retry_count: u32,
+    determinism_level: DeterminismLevel,
     initial_nodes: Vec<(NodeIndex, ModelVariantIndex)>,
 
     // === Internal state ===
@@ -116,23 +299,39 @@ impl<C: CoordinateSystem> Generator<C> {
         rules: Arc<Rules<C>>,
         grid: GridDefinition<C>,
         initial_nodes: Vec<(NodeIndex, ModelVariantIndex)>,
+        initial_bans: InitialBans,
         max_retry_count: u32,
+        determinism_level: DeterminismLevel,
         node_selection_heuristic: NodeSelectionHeuristic,
         model_selection_heuristic: ModelSelectionHeuristic,
+        heuristic_schedule: Vec<HeuristicPhase>,
         rng_mode: RngMode,
-        observers: Vec<crossbeam_channel::Sender<GenerationUpdate>>,
+        observers: Vec<ObserverSender>,
+        candidate_storage: CandidateStorage,
+        selection_noise: f32,
+        active_nodes: Option<Arc<BitVec<usize>>>,
+        anchors: Vec<NodeIndex>,
+        node_weights: Arc<NodeWeights>,
         collector: &mut Collector,
     ) -> Result<Self, NodeSetError> {
         let mut generator = Self {
             max_retry_count,
+            determinism_level,
             initial_nodes,
             internal: InternalGenerator::new(
                 rules,
                 grid,
                 node_selection_heuristic,
                 model_selection_heuristic,
+                heuristic_schedule,
                 rng_mode,
                 observers,
+                initial_bans,
+                candidate_storage,
+                selection_noise,
+                active_nodes,
+                anchors,
+                node_weights,
             ),
         };
         match generator
@@ -144,6 +343,109 @@ impl<C: CoordinateSystem> Generator<C> {
         }
     }
 
+    /// Registers a callback called when [`Generator::generate_grid`] or [`Generator::generate`] retries a generation after a contradiction, with the number of the new try (starting at 2) and its seed.
+    ///
+    /// Callbacks are called synchronously by the generator, on its thread. Multiple callbacks can be registered for a same event, they are called in registration order.
+    pub fn on_retry<F: FnMut(u32, u64) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.internal.callbacks.add_on_retry(Box::new(callback));
+    }
+
+    /// Registers a callback called when a generation succeeds, with the seed of the generation. See [`Generator::on_retry`] for the calling conventions.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use std::sync::{atomic::{AtomicU32, Ordering}, Arc};
+    /// use ghx_proc_gen::generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::grid::GridDefinition;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (white, black) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(white, vec![black]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(white));
+    /// models.create(SocketsCartesian2D::Mono(black));
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    ///
+    /// let mut generator = GeneratorBuilder::new()
+    ///    .with_rules(rules)
+    ///    .with_grid(GridDefinition::new_cartesian_2d(8, 8, false, false))
+    ///    .build()
+    ///    .unwrap();
+    /// let done_count = Arc::new(AtomicU32::new(0));
+    /// let counter = Arc::clone(&done_count);
+    /// generator.on_done(move |_seed| {
+    ///     counter.fetch_add(1, Ordering::Relaxed);
+    /// });
+    /// generator.generate().unwrap();
+    /// assert_eq!(done_count.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn on_done<F: FnMut(u64) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.internal.callbacks.add_on_done(Box::new(callback));
+    }
+
+    /// Registers a callback called when the generation encounters a contradiction, with the [`GeneratorError`] and the seed of the failed generation. See [`Generator::on_retry`] for the calling conventions.
+    pub fn on_contradiction<F: FnMut(&GeneratorError, u64) + Send + Sync + 'static>(
+        &mut self,
+        callback: F,
+    ) {
+        self.internal
+            .callbacks
+            .add_on_contradiction(Box::new(callback));
+    }
+
+    /// Registers a callback called after each generation step (see [`Generator::select_and_propagate`]) taking longer than `threshold`, with the [`StepStats`] of the step and the seed of the generation. See [`Generator::on_retry`] for the calling conventions.
+    ///
+    /// Useful to identify the sources of hitches when generating step by step in a frame loop.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use ghx_proc_gen::generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::grid::GridDefinition;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (white, black) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(white, vec![black]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(white));
+    /// models.create(SocketsCartesian2D::Mono(black));
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    ///
+    /// let mut generator = GeneratorBuilder::new()
+    ///    .with_rules(rules)
+    ///    .with_grid(GridDefinition::new_cartesian_2d(8, 8, false, false))
+    ///    .build()
+    ///    .unwrap();
+    /// generator.on_slow_step(Duration::from_millis(5), |stats, seed| {
+    ///     println!("Slow step with seed {}: {:?}", seed, stats);
+    /// });
+    /// generator.select_and_propagate().unwrap();
+    /// assert_eq!(generator.last_step_stats().unwrap().generated_nodes, 64);
+    /// ```
+    #[cfg(feature = "step-stats")]
+    pub fn on_slow_step<F: FnMut(&StepStats, u64) + Send + Sync + 'static>(
+        &mut self,
+        threshold: std::time::Duration,
+        callback: F,
+    ) {
+        self.internal
+            .callbacks
+            .add_on_slow_step(threshold, Box::new(callback));
+    }
+
+    /// Returns the [`StepStats`] of the last step executed by the generator (by [`Generator::select_and_propagate`] or by a full generation), if any
+    #[cfg(feature = "step-stats")]
+    pub fn last_step_stats(&self) -> Option<StepStats> {
+        self.internal.last_step_stats
+    }
+
+    /// Removes all the callbacks registered with [`Generator::on_retry`], [`Generator::on_done`], [`Generator::on_contradiction`] and [`Generator::on_slow_step`]
+    pub fn clear_callbacks(&mut self) {
+        self.internal.callbacks.clear();
+    }
+
     /// Returns the `max_retry_count`: how many time the [`Generator`] should retry to generate the [`GridDefinition`] when a contradiction is encountered
     pub fn max_retry_count(&self) -> u32 {
         self.max_retry_count
@@ -154,6 +456,48 @@ impl<C: CoordinateSystem> Generator<C> {
         self.max_retry_count = max_retry_count;
     }
 
+    /// Returns the [`DeterminismLevel`] of the parallel generations of this generator
+    pub fn determinism_level(&self) -> DeterminismLevel {
+        self.determinism_level
+    }
+
+    /// Specifies the [`DeterminismLevel`] of the parallel generations of this generator
+    pub fn set_determinism_level(&mut self, determinism_level: DeterminismLevel) {
+        self.determinism_level = determinism_level;
+    }
+
+    /// Returns the [`NodeSelectionHeuristic`] currently used by the generator
+    pub fn node_heuristic(&self) -> NodeSelectionHeuristic {
+        self.internal.node_selection_heuristic()
+    }
+
+    /// Specifies the [`NodeSelectionHeuristic`] used by the generator.
+    ///
+    /// Can be changed during a generation, the new heuristic will be used for the next node selections. With a heuristic schedule (see [`GeneratorBuilder::with_heuristic_schedule`]), this is the heuristic used before the first phase, and it is replaced by the heuristic of the next phase started.
+    pub fn set_node_heuristic(&mut self, heuristic: NodeSelectionHeuristic) {
+        self.internal.set_base_node_selection_heuristic(heuristic);
+    }
+
+    /// Returns the heuristic schedule of the generator, see [`GeneratorBuilder::with_heuristic_schedule`]
+    pub fn heuristic_schedule(&self) -> &[HeuristicPhase] {
+        &self.internal.heuristic_schedule
+    }
+
+    /// Returns the index in the heuristic schedule of the current [`HeuristicPhase`], or `None` if no phase has started
+    pub fn current_heuristic_phase(&self) -> Option<usize> {
+        self.internal.current_phase
+    }
+
+    /// Returns the magnitude of the noise breaking the ties in the node selection, see [`GeneratorBuilder::with_selection_noise`]
+    pub fn selection_noise(&self) -> f32 {
+        self.internal.selection_noise
+    }
+
+    /// Specifies the magnitude of the noise breaking the ties in the node selection, see [`GeneratorBuilder::with_selection_noise`]
+    pub fn set_selection_noise(&mut self, noise: f32) {
+        self.internal.selection_noise = node_heuristic::sanitize_selection_noise(noise);
+    }
+
     /// Returns the seed that was used to initialize the generator RNG for this generation. See [`RngMode`] for more information.
     pub fn seed(&self) -> u64 {
         self.internal.seed
@@ -185,6 +529,30 @@ impl<C: CoordinateSystem> Generator<C> {
         }
     }
 
+    /// Same as [`Generator::to_grid_data`] but the nodes masked out of the generation (see [`GeneratorBuilder::with_active_nodes`]) are `None`.
+    ///
+    /// [`Generator::to_grid_data`] gives the masked-out nodes the first model variant of the rules.
+    pub fn to_active_grid_data(&self) -> Option<GridData<C, Option<ModelInstance>>> {
+        let grid_data = self.to_grid_data()?;
+        let nodes = grid_data
+            .nodes()
+            .iter()
+            .enumerate()
+            .map(
+                |(node_index, model_instance)| match self.internal.is_active_node(node_index) {
+                    true => Some(*model_instance),
+                    false => None,
+                },
+            )
+            .collect();
+        Some(GridData::new(self.grid().clone(), nodes))
+    }
+
+    /// Returns `true` if the node at `node_index` is generated, `false` if it is masked out of the generation (see [`GeneratorBuilder::with_active_nodes`]) or does not exist.
+    pub fn is_active_node(&self, node_index: NodeIndex) -> bool {
+        self.internal.is_valid_node_index(node_index) && self.internal.is_active_node(node_index)
+    }
+
     /// Tries to generate the whole grid. If the generation fails due to a contradiction, it will retry `max_retry_count` times before returning the last encountered [`GeneratorError`]
     ///
     /// If the generation is currently done or failed, calling this method will reinitialize the generator with the next seed before starting the generation.
@@ -209,6 +577,89 @@ impl<C: CoordinateSystem> Generator<C> {
         Ok(gen_info)
     }
 
+    /// Generates `n` independent outputs, each one with a distinct seed, by reusing the rules and the internal buffers of this generator. Useful to bake content in bulk (many levels from the same rules for example).
+    ///
+    /// The `n` seeds are drawn from the generator RNG before the first generation: the outputs only depend on the current seed of the generator, and are the same as the ones of [`Generator::generate_n_parallel`]. Each output can retry up to `max_retry_count` times. Any ongoing generation is discarded, and the generator is left in the state of the last generation.
+    ///
+    /// Returns the first [`GeneratorError`] if a generation fails after all its retries.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::grid::GridDefinition;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (white, black) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(white, vec![black]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(white));
+    /// models.create(SocketsCartesian2D::Mono(black));
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    ///
+    /// let mut generator = GeneratorBuilder::new()
+    ///    .with_rules(rules)
+    ///    .with_grid(GridDefinition::new_cartesian_2d(8, 8, false, false))
+    ///    .build()
+    ///    .unwrap();
+    /// let outputs = generator.generate_n(10).unwrap();
+    /// assert_eq!(outputs.len(), 10);
+    /// ```
+    pub fn generate_n(&mut self, n: usize) -> Result<Vec<GeneratedGrid<C>>, GeneratorError> {
+        let seeds = self.internal.next_seeds(n);
+        let mut outputs = Vec::with_capacity(n);
+        for seed in seeds {
+            let gen_info = self.internal.generate_with_seed(
+                seed,
+                self.max_retry_count,
+                &self.initial_nodes,
+            )?;
+            outputs.push(GeneratedGrid {
+                seed: self.internal.seed,
+                gen_info,
+                grid_data: self.internal.to_grid_data(),
+            });
+        }
+        Ok(outputs)
+    }
+
+    /// Same as [`Generator::generate_n`] but runs the generations in parallel, on the `rayon` thread pool. Each worker thread uses its own copy of the generator (sharing the same rules), reused for all its generations.
+    ///
+    /// The outputs are returned in seed order and are identical to the ones of [`Generator::generate_n`]: each generation uses its own RNG stream, seeded from the seeds drawn from the generator RNG before the work is distributed. Observers and callbacks of this generator are not called by the parallel generations, and its state is left unchanged (except for its RNG, used to draw the seeds).
+    ///
+    /// When some generations fail, the returned [`GeneratorError`] depends on the [`DeterminismLevel`] of the generator: with [`DeterminismLevel::Strict`] it is the one of the first failing seed, as with [`Generator::generate_n`], with [`DeterminismLevel::Relaxed`] it is the first one detected.
+    #[cfg(feature = "parallel")]
+    pub fn generate_n_parallel(&mut self, n: usize) -> Result<Vec<GeneratedGrid<C>>, GeneratorError>
+    where
+        C: Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        let seeds = self.internal.next_seeds(n);
+        let internal = &self.internal;
+        let (max_retry_count, initial_nodes) = (self.max_retry_count, &self.initial_nodes);
+        let outputs = seeds.into_par_iter().map_init(
+            || internal.fork(),
+            |generator, seed| {
+                let gen_info =
+                    generator.generate_with_seed(seed, max_retry_count, initial_nodes)?;
+                Ok(GeneratedGrid {
+                    seed: generator.seed,
+                    gen_info,
+                    grid_data: generator.to_grid_data(),
+                })
+            },
+        );
+        match self.determinism_level {
+            DeterminismLevel::Strict => {
+                // Collecting all the results keeps them in seed order, even the errors
+                let results: Vec<Result<GeneratedGrid<C>, GeneratorError>> = outputs.collect();
+                results.into_iter().collect()
+            }
+            DeterminismLevel::Relaxed => outputs.collect(),
+        }
+    }
+
     /// Advances the generation by one "step": select a node and a model via the heuristics and propagate the changes.
     /// - Returns the [`GenerationStatus`] if the step executed successfully
     /// - Returns a [`GeneratorError`] if the generation fails due to a contradiction.
@@ -276,6 +727,122 @@ impl<C: CoordinateSystem> Generator<C> {
         Ok((status, generated_nodes))
     }
 
+    /// Forces the node referenced by `node_ref` to collapse right now to the model referenced by `model_variant_ref`, then propagates the change. Made for interactive workflows (such as painting some nodes in an editor and letting the generator complete their surroundings).
+    ///
+    /// Same as [`Generator::set_and_propagate`] without memorization, except when the generation is done: the parameters are still checked, and a model already banned from the node returns a [`NodeSetError::IllegalModel`].
+    pub fn collapse_node<N: NodeRef<C>, M: ModelVariantRef<C>>(
+        &mut self,
+        node_ref: N,
+        model_variant_ref: M,
+    ) -> Result<GenerationStatus, NodeSetError> {
+        let node_index = node_ref.to_index(&self.internal.grid);
+        let model_variant_index = model_variant_ref.to_index(&self.internal.rules)?;
+        self.internal
+            .collapse_node(node_index, model_variant_index, &mut None)
+    }
+
+    /// Same as [`Generator::collapse_node`] but also returns all the [`GeneratedNode`] generated by this operation if successful.
+    pub fn collapse_node_collected<N: NodeRef<C>, M: ModelVariantRef<C>>(
+        &mut self,
+        node_ref: N,
+        model_variant_ref: M,
+    ) -> Result<(GenerationStatus, Vec<GeneratedNode>), NodeSetError> {
+        let mut generated_nodes = Vec::new();
+        let node_index = node_ref.to_index(&self.internal.grid);
+        let model_variant_index = model_variant_ref.to_index(&self.internal.rules)?;
+        let status = self.internal.collapse_node(
+            node_index,
+            model_variant_index,
+            &mut Some(&mut generated_nodes),
+        )?;
+        Ok((status, generated_nodes))
+    }
+
+    /// Returns the maximum number of actions that can be undone, see [`Generator::set_undo_limit`]
+    pub fn undo_limit(&self) -> usize {
+        self.internal.undo_limit()
+    }
+
+    /// Enables the undo journal by setting the maximum number of actions that can be undone. `0` (the default) disables the journal.
+    ///
+    /// Each call to [`Generator::collapse_node`], [`Generator::set_and_propagate`] and [`Generator::select_and_propagate`] (and their `_collected` variants) is journaled as one action. For each of them, the journal stores the previous state of the nodes modified by the action (and by its propagation), as well as the state of the RNG and of the heuristics, so that it can be restored exactly with [`Generator::undo`], without running the generation again.
+    ///
+    /// The journal is cleared when the generator reinitializes, when it generates the whole grid (see [`Generator::generate`]) or when its [`NodeSelectionHeuristic`] is changed.
+    pub fn set_undo_limit(&mut self, limit: usize) {
+        self.internal.set_undo_limit(limit);
+    }
+
+    /// Returns `true` if there is a journaled action to undo
+    pub fn can_undo(&self) -> bool {
+        self.internal.can_undo()
+    }
+
+    /// Returns `true` if there is an undone action to redo
+    pub fn can_redo(&self) -> bool {
+        self.internal.can_redo()
+    }
+
+    /// Reverts the last journaled action (see [`Generator::set_undo_limit`]), restoring the possible models of all the nodes it modified. Does nothing if there is no action to undo.
+    ///
+    /// The RNG is restored too: stepping again after an undo makes the same choices as the undone action.
+    ///
+    /// Observers receive a [`GenerationUpdate::Reverted`] for each node that is not generated anymore. If the action led to a contradiction, the generation is back to its previous (ongoing) status.
+    ///
+    /// Returns the [`GenerationStatus`] after the undo, or a [`GeneratorError`] if the generation is currently failed.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::{ModelCollection, ModelVariantIndex}};
+    /// use ghx_grid::grid::GridDefinition;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let ground = sockets.create();
+    /// sockets.add_connection(ground, vec![ground]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(ground));
+    /// models.create(SocketsCartesian2D::Mono(ground));
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    ///
+    /// let mut generator = GeneratorBuilder::new()
+    ///    .with_rules(rules)
+    ///    .with_grid(GridDefinition::new_cartesian_2d(4, 4, false, false))
+    ///    .build()
+    ///    .unwrap();
+    /// generator.set_undo_limit(8);
+    ///
+    /// // Paint a node, then let the generator take a step
+    /// let (node, model): (usize, ModelVariantIndex) = (5, 1);
+    /// generator.collapse_node(node, model).unwrap();
+    /// generator.select_and_propagate().unwrap();
+    /// assert_eq!(generator.nodes_left(), 14);
+    /// let models: Vec<_> = (0..16).map(|node| generator.get_models_on(node)).collect();
+    ///
+    /// // Stepping again after an undo makes the same choices
+    /// generator.undo().unwrap();
+    /// generator.select_and_propagate().unwrap();
+    /// assert!((0..16).all(|node| generator.get_models_on(node) == models[node]));
+    ///
+    /// // The step is undone first, then the paint
+    /// generator.undo().unwrap();
+    /// assert_eq!(generator.nodes_left(), 15);
+    /// assert_eq!(generator.get_models_on(node).len(), 1);
+    /// generator.undo().unwrap();
+    /// assert_eq!(generator.nodes_left(), 16);
+    /// assert_eq!(generator.get_models_on(node).len(), 2);
+    /// assert!(!generator.can_undo());
+    /// ```
+    pub fn undo(&mut self) -> Result<GenerationStatus, GeneratorError> {
+        self.internal.undo()
+    }
+
+    /// Re-applies the last action reverted by [`Generator::undo`]. Does nothing if there is no action to redo. Any new journaled action clears the actions to redo.
+    ///
+    /// Returns the [`GenerationStatus`] after the redo, or a [`GeneratorError`] if the generation is currently failed.
+    pub fn redo(&mut self) -> Result<GenerationStatus, GeneratorError> {
+        self.internal.redo()
+    }
+
     /// Reinitalizes the generator with the next seed (a seed is generated from the current seed)
     pub fn reinitialize(&mut self) -> GenerationStatus {
         self.internal.reinitialize(&mut None, &self.initial_nodes)
@@ -290,6 +857,35 @@ impl<C: CoordinateSystem> Generator<C> {
         (res, generated_nodes)
     }
 
+    /// Reinitalizes the generator with a new seed given by `rng_mode`, instead of the next seed derived from the current one.
+    ///
+    /// Can be used to restart a generation from scratch, for example with [`RngMode::RandomSeed`] to reroll the results.
+    ///
+    /// The new `rng_mode` is kept for the next reinitializations: an [`RngMode::External`] RNG replaces the current RNG of the generator.
+    pub fn reinitialize_with_rng(&mut self, rng_mode: RngMode) -> GenerationStatus {
+        self.internal
+            .reinitialize_with_rng(&rng_mode, &mut None, &self.initial_nodes)
+    }
+
+    /// Same as [`Generator::reinitialize_with_rng`] but also returns all the [`GeneratedNode`] generated by this generation operation.
+    pub fn reinitialize_with_rng_collected(
+        &mut self,
+        rng_mode: RngMode,
+    ) -> (GenerationStatus, Vec<GeneratedNode>) {
+        let mut generated_nodes = Vec::new();
+        let res = self.internal.reinitialize_with_rng(
+            &rng_mode,
+            &mut Some(&mut generated_nodes),
+            &self.initial_nodes,
+        );
+        (res, generated_nodes)
+    }
+
+    /// Returns the number of candidates pages currently allocated by the generator and the total number of pages. See [`CandidateStorage`]
+    pub fn candidates_pages_usage(&self) -> (usize, usize) {
+        self.internal.candidates_pages_usage()
+    }
+
     /// Returns all the current possible model instances on `node_index`
     pub fn get_models_on(&self, node_index: NodeIndex) -> Vec<ModelInstance> {
         let mut models = Vec::new();
@@ -336,7 +932,20 @@ impl<C: CoordinateSystem> Generator<C> {
     fn create_observer_queue(&mut self) -> crossbeam_channel::Receiver<GenerationUpdate> {
         // We can't simply bound to the number of nodes since we might retry some generations. (and send more than number_of_nodes updates)
         let (sender, receiver) = crossbeam_channel::unbounded();
-        self.internal.observers.push(sender);
+        self.internal
+            .observers
+            .push(ObserverSender::new(sender, None));
+        receiver
+    }
+
+    fn create_region_observer_queue(
+        &mut self,
+        region: NodeRegion,
+    ) -> crossbeam_channel::Receiver<GenerationUpdate> {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        self.internal
+            .observers
+            .push(ObserverSender::new(sender, Some(region)));
         receiver
     }
 }
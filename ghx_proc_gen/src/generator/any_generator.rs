@@ -0,0 +1,234 @@
+use std::any::Any;
+
+use ghx_grid::{coordinate_system::CoordinateSystem, grid::GridPosition};
+
+use crate::{GeneratorError, NodeIndex};
+
+use super::{
+    model::ModelInstance,
+    node_heuristic::NodeSelectionHeuristic,
+    observer::{NodeRegion, QueuedObserver},
+    GenInfo, GeneratedNode, GenerationStatus, Generator, RngMode,
+};
+
+/// Type-erased [`Generator`]: exposes the stepping and observing API of a generator without its [`CoordinateSystem`] generic.
+///
+/// Allows applications supporting multiple coordinate systems (2d & 3d generations for example) to store their generators together (as `Box<dyn AnyGenerator>`) and drive them with the same code. The concrete generator can be retrieved with [`AnyGenerator::downcast_ref`] or [`AnyGenerator::downcast_mut`].
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::generator::{any_generator::AnyGenerator, builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketsCartesian3D, SocketCollection}, model::ModelCollection, Generator};
+/// use ghx_grid::{coordinate_system::Cartesian2D, grid::GridDefinition};
+///
+/// let mut sockets = SocketCollection::new();
+/// let socket = sockets.create();
+/// sockets.add_connection(socket, vec![socket]);
+/// let mut models_2d = ModelCollection::new();
+/// models_2d.create(SocketsCartesian2D::Mono(socket));
+/// let mut models_3d = ModelCollection::new();
+/// models_3d.create(SocketsCartesian3D::Mono(socket));
+///
+/// let mut generators: Vec<Box<dyn AnyGenerator>> = vec![
+///     Box::new(GeneratorBuilder::new()
+///         .with_rules(RulesBuilder::new_cartesian_2d(models_2d, sockets.clone()).build().unwrap())
+///         .with_grid(GridDefinition::new_cartesian_2d(4, 4, false, false))
+///         .build()
+///         .unwrap()),
+///     Box::new(GeneratorBuilder::new()
+///         .with_rules(RulesBuilder::new_cartesian_3d(models_3d, sockets).build().unwrap())
+///         .with_grid(GridDefinition::new_cartesian_3d(4, 4, 4, false, false, false))
+///         .build()
+///         .unwrap()),
+/// ];
+/// for generator in generators.iter_mut() {
+///     generator.generate().unwrap();
+///     assert_eq!(generator.nodes_left(), 0);
+/// }
+/// assert!(generators[0].downcast_ref::<Cartesian2D>().is_some());
+/// ```
+pub trait AnyGenerator: Send + Sync {
+    /// Returns the seed of the current generation, see [`Generator::seed`]
+    fn seed(&self) -> u64;
+
+    /// Returns the size of the grid of the generator on each axis, `(x, y, z)`. `z` is `1` for 2d grids.
+    fn grid_size(&self) -> (u32, u32, u32);
+
+    /// Returns the number of nodes in the grid of the generator
+    fn nodes_count(&self) -> usize;
+
+    /// Returns the position in the grid of the node at `node_index`
+    fn node_position(&self, node_index: NodeIndex) -> GridPosition;
+
+    /// Returns how many nodes are left to generate, see [`Generator::nodes_left`]
+    fn nodes_left(&self) -> usize;
+
+    /// See [`Generator::max_retry_count`]
+    fn max_retry_count(&self) -> u32;
+
+    /// See [`Generator::set_max_retry_count`]
+    fn set_max_retry_count(&mut self, max_retry_count: u32);
+
+    /// See [`Generator::node_heuristic`]
+    fn node_heuristic(&self) -> NodeSelectionHeuristic;
+
+    /// See [`Generator::set_node_heuristic`]
+    fn set_node_heuristic(&mut self, heuristic: NodeSelectionHeuristic);
+
+    /// See [`Generator::generate`]
+    fn generate(&mut self) -> Result<GenInfo, GeneratorError>;
+
+    /// See [`Generator::select_and_propagate`]
+    fn select_and_propagate(&mut self) -> Result<GenerationStatus, GeneratorError>;
+
+    /// See [`Generator::select_and_propagate_collected`]
+    fn select_and_propagate_collected(
+        &mut self,
+    ) -> Result<(GenerationStatus, Vec<GeneratedNode>), GeneratorError>;
+
+    /// See [`Generator::undo`]
+    fn undo(&mut self) -> Result<GenerationStatus, GeneratorError>;
+
+    /// See [`Generator::redo`]
+    fn redo(&mut self) -> Result<GenerationStatus, GeneratorError>;
+
+    /// See [`Generator::reinitialize`]
+    fn reinitialize(&mut self) -> GenerationStatus;
+
+    /// See [`Generator::reinitialize_collected`]
+    fn reinitialize_collected(&mut self) -> (GenerationStatus, Vec<GeneratedNode>);
+
+    /// See [`Generator::reinitialize_with_rng`]
+    fn reinitialize_with_rng(&mut self, rng_mode: RngMode) -> GenerationStatus;
+
+    /// See [`Generator::get_models_on`]
+    fn get_models_on(&self, node_index: NodeIndex) -> Vec<ModelInstance>;
+
+    /// Returns the generated [`ModelInstance`] of all the nodes, in node index order, if the generation is done. See [`Generator::to_grid_data`]
+    fn to_model_instances(&self) -> Option<Vec<ModelInstance>>;
+
+    /// Creates a [`QueuedObserver`] of the generator, see [`QueuedObserver::new`]
+    fn create_observer(&mut self) -> QueuedObserver;
+
+    /// Creates a [`QueuedObserver`] of the generator for the nodes in `region`, see [`QueuedObserver::for_region`]
+    fn create_region_observer(&mut self, region: NodeRegion) -> QueuedObserver;
+
+    /// Returns the generator as [`Any`], used by [`AnyGenerator::downcast_ref`]
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the generator as [`Any`], used by [`AnyGenerator::downcast_mut`]
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl dyn AnyGenerator {
+    /// Returns the concrete [`Generator`] if its coordinate system is `C`
+    pub fn downcast_ref<C: CoordinateSystem + 'static>(&self) -> Option<&Generator<C>> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Returns the concrete [`Generator`] if its coordinate system is `C`
+    pub fn downcast_mut<C: CoordinateSystem + 'static>(&mut self) -> Option<&mut Generator<C>> {
+        self.as_any_mut().downcast_mut()
+    }
+}
+
+impl<C: CoordinateSystem + 'static> AnyGenerator for Generator<C>
+where
+    Generator<C>: Send + Sync,
+{
+    fn seed(&self) -> u64 {
+        Generator::seed(self)
+    }
+
+    fn grid_size(&self) -> (u32, u32, u32) {
+        let grid = self.grid();
+        (grid.size_x(), grid.size_y(), grid.size_z())
+    }
+
+    fn nodes_count(&self) -> usize {
+        self.grid().total_size()
+    }
+
+    fn node_position(&self, node_index: NodeIndex) -> GridPosition {
+        self.grid().pos_from_index(node_index)
+    }
+
+    fn nodes_left(&self) -> usize {
+        Generator::nodes_left(self)
+    }
+
+    fn max_retry_count(&self) -> u32 {
+        Generator::max_retry_count(self)
+    }
+
+    fn set_max_retry_count(&mut self, max_retry_count: u32) {
+        Generator::set_max_retry_count(self, max_retry_count)
+    }
+
+    fn node_heuristic(&self) -> NodeSelectionHeuristic {
+        Generator::node_heuristic(self)
+    }
+
+    fn set_node_heuristic(&mut self, heuristic: NodeSelectionHeuristic) {
+        Generator::set_node_heuristic(self, heuristic)
+    }
+
+    fn generate(&mut self) -> Result<GenInfo, GeneratorError> {
+        Generator::generate(self)
+    }
+
+    fn select_and_propagate(&mut self) -> Result<GenerationStatus, GeneratorError> {
+        Generator::select_and_propagate(self)
+    }
+
+    fn select_and_propagate_collected(
+        &mut self,
+    ) -> Result<(GenerationStatus, Vec<GeneratedNode>), GeneratorError> {
+        Generator::select_and_propagate_collected(self)
+    }
+
+    fn undo(&mut self) -> Result<GenerationStatus, GeneratorError> {
+        Generator::undo(self)
+    }
+
+    fn redo(&mut self) -> Result<GenerationStatus, GeneratorError> {
+        Generator::redo(self)
+    }
+
+    fn reinitialize(&mut self) -> GenerationStatus {
+        Generator::reinitialize(self)
+    }
+
+    fn reinitialize_collected(&mut self) -> (GenerationStatus, Vec<GeneratedNode>) {
+        Generator::reinitialize_collected(self)
+    }
+
+    fn reinitialize_with_rng(&mut self, rng_mode: RngMode) -> GenerationStatus {
+        Generator::reinitialize_with_rng(self, rng_mode)
+    }
+
+    fn get_models_on(&self, node_index: NodeIndex) -> Vec<ModelInstance> {
+        Generator::get_models_on(self, node_index)
+    }
+
+    fn to_model_instances(&self) -> Option<Vec<ModelInstance>> {
+        self.to_grid_data()
+            .map(|grid_data| grid_data.nodes().to_vec())
+    }
+
+    fn create_observer(&mut self) -> QueuedObserver {
+        QueuedObserver::new(self)
+    }
+
+    fn create_region_observer(&mut self, region: NodeRegion) -> QueuedObserver {
+        QueuedObserver::for_region(self, region)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -1,18 +1,27 @@
 use std::{marker::PhantomData, sync::Arc};
 
+use bitvec::vec::BitVec;
 use ghx_grid::{
     coordinate_system::CoordinateSystem,
+    direction::Direction,
     grid::{GridData, GridDefinition, NodeRef},
 };
 
-use crate::{GeneratorBuilderError, NodeIndex};
+use crate::{GeneratorBuilderError, NodeIndex, NodeSetError};
 
 use super::{
-    model::ModelVariantIndex,
-    node_heuristic::NodeSelectionHeuristic,
-    observer::{GenerationUpdate, QueuedObserver, QueuedStatefulObserver},
+    internal_generator::InitialBans,
+    model::{ModelIndex, ModelRotation, ModelVariantIndex},
+    node_heuristic::{
+        sanitize_selection_noise, HeuristicPhase, NodeSelectionHeuristic, DEFAULT_SELECTION_NOISE,
+    },
+    node_weights::{gradient, NodeWeights},
+    observer::{
+        BatchedObserver, NodeRegion, ObserverSender, QueuedObserver, QueuedStatefulObserver,
+    },
     rules::{ModelVariantRef, Rules},
-    Collector, GeneratedNode, Generator, ModelSelectionHeuristic, RngMode,
+    CandidateStorage, Collector, DeterminismLevel, GeneratedNode, Generator,
+    ModelSelectionHeuristic, RngMode,
 };
 
 /// Default retry count for the generator
@@ -56,11 +65,19 @@ pub struct GeneratorBuilder<G, R, C: CoordinateSystem> {
     rules: Option<Arc<Rules<C>>>,
     grid: Option<GridDefinition<C>>,
     max_retry_count: u32,
+    determinism_level: DeterminismLevel,
     node_selection_heuristic: NodeSelectionHeuristic,
     model_selection_heuristic: ModelSelectionHeuristic,
+    heuristic_schedule: Vec<HeuristicPhase>,
     rng_mode: RngMode,
-    observers: Vec<crossbeam_channel::Sender<GenerationUpdate>>,
+    observers: Vec<ObserverSender>,
     initial_nodes: Vec<(NodeIndex, ModelVariantIndex)>,
+    initial_bans: InitialBans,
+    candidate_storage: CandidateStorage,
+    selection_noise: f32,
+    active_nodes: Option<Arc<BitVec<usize>>>,
+    anchors: Vec<NodeIndex>,
+    node_weights: NodeWeights,
     typestate: PhantomData<(G, R)>,
 }
 
@@ -71,11 +88,19 @@ impl<C: CoordinateSystem> GeneratorBuilder<Unset, Unset, C> {
             rules: None,
             grid: None,
             max_retry_count: DEFAULT_RETRY_COUNT,
+            determinism_level: DeterminismLevel::Strict,
             node_selection_heuristic: NodeSelectionHeuristic::MinimumRemainingValue,
             model_selection_heuristic: ModelSelectionHeuristic::WeightedProbability,
+            heuristic_schedule: Vec::new(),
             rng_mode: RngMode::RandomSeed,
             observers: Vec::new(),
             initial_nodes: Vec::new(),
+            initial_bans: InitialBans::default(),
+            candidate_storage: CandidateStorage::Dense,
+            selection_noise: DEFAULT_SELECTION_NOISE,
+            active_nodes: None,
+            anchors: Vec::new(),
+            node_weights: NodeWeights::new(),
             typestate: PhantomData,
         }
     }
@@ -89,11 +114,19 @@ impl<C: CoordinateSystem> GeneratorBuilder<Unset, Unset, C> {
 
             grid: self.grid,
             max_retry_count: self.max_retry_count,
+            determinism_level: self.determinism_level,
             node_selection_heuristic: self.node_selection_heuristic,
             model_selection_heuristic: self.model_selection_heuristic,
+            heuristic_schedule: self.heuristic_schedule,
             rng_mode: self.rng_mode,
             observers: self.observers,
             initial_nodes: self.initial_nodes,
+            initial_bans: self.initial_bans,
+            candidate_storage: self.candidate_storage,
+            selection_noise: self.selection_noise,
+            active_nodes: self.active_nodes,
+            anchors: self.anchors,
+            node_weights: self.node_weights,
 
             typestate: PhantomData,
         }
@@ -106,11 +139,19 @@ impl<C: CoordinateSystem> GeneratorBuilder<Unset, Unset, C> {
 
             grid: self.grid,
             max_retry_count: self.max_retry_count,
+            determinism_level: self.determinism_level,
             node_selection_heuristic: self.node_selection_heuristic,
             model_selection_heuristic: self.model_selection_heuristic,
+            heuristic_schedule: self.heuristic_schedule,
             rng_mode: self.rng_mode,
             observers: self.observers,
             initial_nodes: self.initial_nodes,
+            initial_bans: self.initial_bans,
+            candidate_storage: self.candidate_storage,
+            selection_noise: self.selection_noise,
+            active_nodes: self.active_nodes,
+            anchors: self.anchors,
+            node_weights: self.node_weights,
 
             typestate: PhantomData,
         }
@@ -125,11 +166,19 @@ impl<C: CoordinateSystem> GeneratorBuilder<Unset, Set, C> {
 
             rules: self.rules,
             max_retry_count: self.max_retry_count,
+            determinism_level: self.determinism_level,
             node_selection_heuristic: self.node_selection_heuristic,
             model_selection_heuristic: self.model_selection_heuristic,
+            heuristic_schedule: self.heuristic_schedule,
             rng_mode: self.rng_mode,
             observers: self.observers,
             initial_nodes: self.initial_nodes,
+            initial_bans: self.initial_bans,
+            candidate_storage: self.candidate_storage,
+            selection_noise: self.selection_noise,
+            active_nodes: self.active_nodes,
+            anchors: self.anchors,
+            node_weights: self.node_weights,
 
             typestate: PhantomData,
         }
@@ -142,6 +191,11 @@ impl<G, R, C: CoordinateSystem> GeneratorBuilder<G, R, C> {
         self.max_retry_count = max_retry_count;
         self
     }
+    /// Specifies the [`DeterminismLevel`] of the parallel generations of the [`Generator`]. Defaults to [`DeterminismLevel::Strict`].
+    pub fn with_determinism_level(mut self, determinism_level: DeterminismLevel) -> Self {
+        self.determinism_level = determinism_level;
+        self
+    }
     /// Specifies the [`NodeSelectionHeuristic`] to be used by the [`Generator`]. Defaults to [`NodeSelectionHeuristic::MinimumRemainingValue`].
     pub fn with_node_heuristic(mut self, heuristic: NodeSelectionHeuristic) -> Self {
         self.node_selection_heuristic = heuristic;
@@ -152,11 +206,47 @@ impl<G, R, C: CoordinateSystem> GeneratorBuilder<G, R, C> {
         self.model_selection_heuristic = heuristic;
         self
     }
+    /// Specifies an ordered list of [`HeuristicPhase`]: the heuristics given to [`GeneratorBuilder::with_node_heuristic`] and [`GeneratorBuilder::with_model_heuristic`] are used until the condition of a phase is met, then the heuristics of the last phase whose condition is met are used.
+    ///
+    /// Conditions are checked before each node selection. Switching to a new node heuristic clears the undo/redo history of the generator.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{builder::GeneratorBuilder, node_heuristic::{HeuristicPhase, NodeSelectionHeuristic, PhaseCondition}};
+    ///
+    /// // MinimumRemainingValue for the first 80% of the nodes, Random for the rest, and MinimumEntropy (for all the nodes) after the first contradiction.
+    /// let builder = GeneratorBuilder::new()
+    ///     .with_node_heuristic(NodeSelectionHeuristic::MinimumRemainingValue)
+    ///     .with_heuristic_schedule(vec![
+    ///         HeuristicPhase::new(PhaseCondition::GeneratedRatio(0.8), NodeSelectionHeuristic::Random),
+    ///         HeuristicPhase::new(PhaseCondition::Contradictions(1), NodeSelectionHeuristic::MinimumEntropy),
+    ///     ]);
+    /// ```
+    pub fn with_heuristic_schedule(mut self, phases: Vec<HeuristicPhase>) -> Self {
+        self.heuristic_schedule = phases;
+        self
+    }
     /// Specifies the [`RngMode`] to be used by the [`Generator`]. Defaults to [`RngMode::RandomSeed`].
     pub fn with_rng(mut self, rng_mode: RngMode) -> Self {
         self.rng_mode = rng_mode;
         self
     }
+    /// Specifies the [`CandidateStorage`] to be used by the [`Generator`]. Defaults to [`CandidateStorage::Dense`].
+    ///
+    /// [`CandidateStorage::Paged`] can greatly reduce the memory usage on large grids.
+    pub fn with_candidate_storage(mut self, candidate_storage: CandidateStorage) -> Self {
+        self.candidate_storage = candidate_storage;
+        self
+    }
+
+    /// Specifies the magnitude of the random noise added to the value of each node (models count or entropy) by the [`NodeSelectionHeuristic`], to break the ties between nodes with the same value. Defaults to [`DEFAULT_SELECTION_NOISE`].
+    ///
+    /// The noise is drawn from the generator RNG: the same seed always breaks the ties the same way. With a noise of `0`, ties are broken by taking the node with the lowest index. Bigger values make the selection less strict (a node with a slightly higher value can be selected). Negative or non-finite values are replaced by `0`.
+    pub fn with_selection_noise(mut self, noise: f32) -> Self {
+        self.selection_noise = sanitize_selection_noise(noise);
+        self
+    }
 
     /// Registers some [`NodeIndex`] [`ModelVariantIndex`] pairs to be spawned initially by the [`Generator`]. These nodes will be spawned when the generator reinitializes too.
     ///
@@ -172,14 +262,18 @@ impl<G, R, C: CoordinateSystem> GeneratorBuilder<G, R, C> {
 
 // For functions in this impl, we know that self.grid is `Some` thanks to the typing.
 impl<C: CoordinateSystem, R> GeneratorBuilder<Set, R, C> {
+    pub(crate) fn grid(&self) -> &GridDefinition<C> {
+        self.grid.as_ref().unwrap()
+    }
+
     /// Adds a [`QueuedStatefulObserver`] to the [`Generator`] that will be built, and returns it.
     ///
-    /// Adding the observer before building the generator allows the observer to see the nodes than *can* be generated during a generator's initialization.
+    /// Adding the observer before building the generator allows the observer to see the nodes than *can* be generated during a generator's initialization. The void models are only known by the observer if the rules were set before adding it.
     pub fn add_queued_stateful_observer(&mut self) -> QueuedStatefulObserver<C> {
         let (sender, receiver) = crossbeam_channel::unbounded();
-        self.observers.push(sender);
+        self.observers.push(ObserverSender::new(sender, None));
         let grid = self.grid.clone().unwrap();
-        QueuedStatefulObserver::create(receiver, &grid)
+        QueuedStatefulObserver::create(receiver, &grid, self.rules.as_deref())
     }
 
     /// Adds a [`QueuedObserver`] to the [`Generator`] that will be built, and returns it.
@@ -187,10 +281,72 @@ impl<C: CoordinateSystem, R> GeneratorBuilder<Set, R, C> {
     /// Adding the observer before building the generator allows the observer to see the nodes than *can* be generated during a generator's initialization.
     pub fn add_queued_observer(&mut self) -> QueuedObserver {
         let (sender, receiver) = crossbeam_channel::unbounded();
-        self.observers.push(sender);
+        self.observers.push(ObserverSender::new(sender, None));
         QueuedObserver::create(receiver)
     }
 
+    /// Adds a [`QueuedObserver`] to the [`Generator`] that will be built, and returns it. The observer only receives the updates of the nodes inside `region`, see [`QueuedObserver::for_region`].
+    ///
+    /// Adding the observer before building the generator allows the observer to see the nodes than *can* be generated during a generator's initialization.
+    pub fn add_queued_observer_for_region(&mut self, region: NodeRegion) -> QueuedObserver {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        self.observers
+            .push(ObserverSender::new(sender, Some(region)));
+        QueuedObserver::create(receiver)
+    }
+
+    /// Adds a [`BatchedObserver`] to the [`Generator`] that will be built, and returns it.
+    ///
+    /// Adding the observer before building the generator allows the observer to see the nodes than *can* be generated during a generator's initialization.
+    pub fn add_batched_observer(&mut self) -> BatchedObserver {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        self.observers.push(ObserverSender::new(sender, None));
+        BatchedObserver::create(receiver)
+    }
+
+    /// Restricts the generation to the nodes set to `true` in `mask`, to generate non-rectangular regions (islands, caves outlines, ...). By default, all the nodes of the grid are generated.
+    ///
+    /// The masked-out nodes are never generated nor signaled to the observers, and are treated as nonexistent neighbours by the active nodes (like a non-looping border). Initial nodes cannot be set on masked-out nodes. See [`Generator::to_active_grid_data`] to get the output without the masked-out nodes.
+    ///
+    /// Returns a [`GeneratorBuilderError::InvalidGridSize`] if the mask size does not match the grid size.
+    pub fn with_active_nodes(
+        mut self,
+        mask: GridData<C, bool>,
+    ) -> Result<Self, GeneratorBuilderError> {
+        let grid = self.grid.as_ref().unwrap();
+        if grid.size() != mask.grid().size() {
+            return Err(GeneratorBuilderError::InvalidGridSize(
+                mask.grid().size(),
+                grid.size(),
+            ));
+        }
+        self.active_nodes = Some(Arc::new(mask.nodes().iter().copied().collect()));
+        Ok(self)
+    }
+
+    /// Registers some anchor nodes, from which the generation grows when using [`NodeSelectionHeuristic::GrowFromAnchors`]. Anchors are ignored by the other heuristics.
+    ///
+    /// Returns a [`NodeSetError::InvalidNodeIndex`] if an anchor is not in the grid, and a [`NodeSetError::InactiveNodeIndex`] if an anchor is masked-out by [`GeneratorBuilder::with_active_nodes`] (which must then be called before this method).
+    pub fn with_anchors<N: NodeRef<C>>(
+        mut self,
+        anchors: Vec<N>,
+    ) -> Result<Self, GeneratorBuilderError> {
+        let grid = self.grid.as_ref().unwrap();
+        for node_ref in anchors {
+            let node_index = node_ref.to_index(grid);
+            if node_index >= grid.total_size() {
+                return Err(NodeSetError::InvalidNodeIndex(node_index).into());
+            }
+            if let Some(active_nodes) = &self.active_nodes {
+                if !active_nodes[node_index] {
+                    return Err(NodeSetError::InactiveNodeIndex(node_index).into());
+                }
+            }
+            self.anchors.push(node_index);
+        }
+        Ok(self)
+    }
+
     /// Registers [`ModelVariantRef`] from a [`GridData`] to be spawned initially by the [`Generator`]. These nodes will be spawned when the generator reinitializes too.
     ///
     /// See [`GeneratorBuilder::with_initial_grid`] for a more versatile and easy to use method (at the price of a bit of performances during the method call).
@@ -220,6 +376,86 @@ impl<C: CoordinateSystem, R> GeneratorBuilder<Set, R, C> {
 
 // For functions in this impl, we know that self.rules and self.grid are `Some` thanks to the typing.
 impl<C: CoordinateSystem> GeneratorBuilder<Set, Set, C> {
+    pub(crate) fn rules(&self) -> &Rules<C> {
+        self.rules.as_ref().unwrap()
+    }
+
+    /// Multiplies the weight of the original model `model_index` on each node by the value of this node in `multipliers`, to locally change the density of a model without changing the rules. Multipliers given for a same model add up multiplicatively, see [`NodeWeights`].
+    ///
+    /// Returns a [`GeneratorBuilderError::InvalidModelIndex`] if `model_index` is not an original model of the rules, or a [`GeneratorBuilderError::InvalidGridSize`] if the size of `multipliers` does not match the grid size.
+    pub fn with_node_weights(
+        mut self,
+        model_index: ModelIndex,
+        multipliers: GridData<C, f32>,
+    ) -> Result<Self, GeneratorBuilderError> {
+        self.check_original_model(model_index)?;
+        let grid = self.grid.as_ref().unwrap();
+        if grid.size() != multipliers.grid().size() {
+            return Err(GeneratorBuilderError::InvalidGridSize(
+                multipliers.grid().size(),
+                grid.size(),
+            ));
+        }
+        self.node_weights
+            .multiply(model_index, grid.total_size(), |node_index| {
+                *multipliers.get(node_index)
+            });
+        Ok(self)
+    }
+
+    /// Multiplies the weight of the original model `model_index` by a linear gradient, going from `from` on the first layer of nodes along `direction` to `to` on the last one. Built on top of [`GeneratorBuilder::with_node_weights`].
+    ///
+    /// Returns a [`GeneratorBuilderError::InvalidModelIndex`] if `model_index` is not an original model of the rules.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian3D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::{direction::Direction, grid::GridDefinition};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let a = sockets.create();
+    /// sockets.add_connection(a, vec![a]);
+    /// let mut models = ModelCollection::new();
+    /// let rock = models.create(SocketsCartesian3D::Mono(a)).index();
+    /// let void = models.create(SocketsCartesian3D::Mono(a)).index();
+    /// let rules = RulesBuilder::new_cartesian_3d(models, sockets).build().unwrap();
+    ///
+    /// // More rock at the bottom, more void at the top
+    /// let generator = GeneratorBuilder::new()
+    ///     .with_rules(rules)
+    ///     .with_grid(GridDefinition::new_cartesian_3d(10, 10, 10, false, false, false))
+    ///     .with_weight_gradient(rock, Direction::YForward, 4., 0.25)
+    ///     .unwrap()
+    ///     .with_weight_gradient(void, Direction::YForward, 0.25, 4.)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn with_weight_gradient(
+        mut self,
+        model_index: ModelIndex,
+        direction: Direction,
+        from: f32,
+        to: f32,
+    ) -> Result<Self, GeneratorBuilderError> {
+        self.check_original_model(model_index)?;
+        let grid = self.grid.as_ref().unwrap();
+        self.node_weights.multiply(
+            model_index,
+            grid.total_size(),
+            gradient(grid, direction, from, to),
+        );
+        Ok(self)
+    }
+
+    fn check_original_model(&self, model_index: ModelIndex) -> Result<(), GeneratorBuilderError> {
+        match model_index < self.rules().original_models_count() {
+            true => Ok(()),
+            false => Err(GeneratorBuilderError::InvalidModelIndex(model_index)),
+        }
+    }
+
     /// Registers some [`NodeRef`] [`ModelVariantRef`] pairs to be spawned initially by the [`Generator`]. These nodes will be spawned when the generator reinitializes too.
     ///
     /// See [`GeneratorBuilder::with_initial_nodes_raw`] for a bit more performant but more constrained method. The performance difference only matters during this method call in the `GeneratorBuilder`, during generation all the initial nodes are already converted to their raw format.
@@ -236,6 +472,106 @@ impl<C: CoordinateSystem> GeneratorBuilder<Set, Set, C> {
         Ok(self)
     }
 
+    /// Registers some [`NodeRef`] [`ModelVariantRef`] pairs to be banned initially by the [`Generator`]: the model will never be generated on the node. These bans are applied again when the generator reinitializes.
+    ///
+    /// Bans are applied before the initial nodes. See [`crate::generator::heightmap::Heightmap`] for an helper creating bans from a heightmap.
+    pub fn with_initial_bans<N: NodeRef<C>, M: ModelVariantRef<C>>(
+        mut self,
+        initial_bans: Vec<(N, M)>,
+    ) -> Result<Self, GeneratorBuilderError> {
+        let grid = self.grid.as_ref().unwrap();
+        let rules = self.rules.as_ref().unwrap();
+        for (node_ref, model_ref) in initial_bans {
+            let node_index = node_ref.to_index(grid);
+            if node_index >= grid.total_size() {
+                return Err(NodeSetError::InvalidNodeIndex(node_index).into());
+            }
+            let model_index = model_ref.to_index(rules)?;
+            if model_index >= rules.models_count() {
+                return Err(NodeSetError::InvalidModelIndex(model_index).into());
+            }
+            self.initial_bans.nodes.push((node_index, model_index));
+        }
+        Ok(self)
+    }
+
+    /// Registers some layer [`ModelVariantRef`] pairs to be banned initially by the [`Generator`]: the model will never be generated on the nodes of the layer (the nodes with the same `y` coordinate). These bans are applied again when the generator reinitializes.
+    ///
+    /// Same as [`GeneratorBuilder::with_initial_bans`] on all the nodes of the layers, without listing them. See [`crate::generator::gravity::GravityPruning`] for an helper creating layer bans.
+    ///
+    /// Returns a [`GeneratorBuilderError::InvalidLayer`] if a layer is not in the grid.
+    pub fn with_layer_bans<M: ModelVariantRef<C>>(
+        mut self,
+        layer_bans: Vec<(u32, M)>,
+    ) -> Result<Self, GeneratorBuilderError> {
+        let grid = self.grid.as_ref().unwrap();
+        let rules = self.rules.as_ref().unwrap();
+        for (y, model_ref) in layer_bans {
+            if y >= grid.size_y() {
+                return Err(GeneratorBuilderError::InvalidLayer(y, grid.size_y()));
+            }
+            let model_index = model_ref.to_index(rules)?;
+            if model_index >= rules.models_count() {
+                return Err(NodeSetError::InvalidModelIndex(model_index).into());
+            }
+            self.initial_bans.layers.push((y, model_index));
+        }
+        Ok(self)
+    }
+
+    /// Constrains a whole layer of nodes (the nodes with the same `y` coordinate, Y being the up axis) to the original models of `allowed_models`, in any of their rotations. For example, the bottom layer `0` of a terrain only made of dirt or rock.
+    ///
+    /// All the other models are banned from the layer, as with [`GeneratorBuilder::with_layer_bans`]. Calling this method again on the same layer only keeps the models allowed by both calls.
+    ///
+    /// Returns a [`GeneratorBuilderError::InvalidLayer`] if `y` is not in the grid, and a [`NodeSetError::InvalidModelRef`] if a model of `allowed_models` does not exist in the rules.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian3D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::grid::GridDefinition;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let a = sockets.create();
+    /// sockets.add_connection(a, vec![a]);
+    /// let mut models = ModelCollection::new();
+    /// let dirt = models.create(SocketsCartesian3D::Mono(a)).index();
+    /// let rock = models.create(SocketsCartesian3D::Mono(a)).index();
+    /// let _air = models.create(SocketsCartesian3D::Mono(a)).index();
+    /// let rules = RulesBuilder::new_cartesian_3d(models, sockets).build().unwrap();
+    ///
+    /// let generator = GeneratorBuilder::new()
+    ///     .with_rules(rules)
+    ///     .with_grid(GridDefinition::new_cartesian_3d(10, 10, 10, false, false, false))
+    ///     .with_ground_level(0, vec![dirt, rock])
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn with_ground_level(
+        mut self,
+        y: u32,
+        allowed_models: Vec<ModelIndex>,
+    ) -> Result<Self, GeneratorBuilderError> {
+        let grid = self.grid.as_ref().unwrap();
+        let rules = self.rules.as_ref().unwrap();
+        if y >= grid.size_y() {
+            return Err(GeneratorBuilderError::InvalidLayer(y, grid.size_y()));
+        }
+        if let Some(&model_index) = allowed_models
+            .iter()
+            .find(|&&model_index| model_index >= rules.original_models_count())
+        {
+            return Err(NodeSetError::InvalidModelRef(model_index, ModelRotation::Rot0).into());
+        }
+        self.initial_bans.layers.extend(
+            (0..rules.models_count())
+                .filter(|&model| !allowed_models.contains(&rules.model(model).model_index))
+                .map(|model| (y, model)),
+        );
+        Ok(self)
+    }
+
     /// Registers [`ModelVariantRef`] from a [`GridData`] to be spawned initially by the [`Generator`]. These nodes will be spawned when the generator reinitializes too.
     ///
     /// See [`GeneratorBuilder::with_initial_grid_raw`] for a bit more performant but more constrained method. The performance difference only matters during this method call in the `GeneratorBuilder`, during generation all the initial nodes are already converted to their raw format.
@@ -284,15 +620,26 @@ impl<C: CoordinateSystem> GeneratorBuilder<Set, Set, C> {
         // We know that self.rules and self.grid are `Some` thanks to the typing.
         let rules = self.rules.unwrap();
         let grid = self.grid.unwrap();
+        if grid.total_size() == 0 {
+            return Err(GeneratorBuilderError::EmptyGrid(grid.size()));
+        }
         Ok(Generator::create(
             rules,
             grid,
             self.initial_nodes,
+            self.initial_bans,
             self.max_retry_count,
+            self.determinism_level,
             self.node_selection_heuristic,
             self.model_selection_heuristic,
+            self.heuristic_schedule,
             self.rng_mode,
             self.observers,
+            self.candidate_storage,
+            self.selection_noise,
+            self.active_nodes,
+            self.anchors,
+            Arc::new(self.node_weights),
             collector,
         )?)
     }
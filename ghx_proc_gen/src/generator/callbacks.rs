@@ -0,0 +1,82 @@
+#[cfg(feature = "step-stats")]
+use std::time::Duration;
+
+use crate::GeneratorError;
+
+#[cfg(feature = "step-stats")]
+use super::StepStats;
+
+type RetryCallback = Box<dyn FnMut(u32, u64) + Send + Sync>;
+type DoneCallback = Box<dyn FnMut(u64) + Send + Sync>;
+type ContradictionCallback = Box<dyn FnMut(&GeneratorError, u64) + Send + Sync>;
+#[cfg(feature = "step-stats")]
+type SlowStepCallback = Box<dyn FnMut(&StepStats, u64) + Send + Sync>;
+
+/// Callbacks registered on a generator, called synchronously on its phase transitions
+#[derive(Default)]
+pub(crate) struct Callbacks {
+    on_retry: Vec<RetryCallback>,
+    on_done: Vec<DoneCallback>,
+    on_contradiction: Vec<ContradictionCallback>,
+    /// Callbacks called when a step takes longer than their threshold
+    #[cfg(feature = "step-stats")]
+    on_slow_step: Vec<(Duration, SlowStepCallback)>,
+}
+
+impl Callbacks {
+    pub(crate) fn add_on_retry(&mut self, callback: RetryCallback) {
+        self.on_retry.push(callback);
+    }
+
+    pub(crate) fn add_on_done(&mut self, callback: DoneCallback) {
+        self.on_done.push(callback);
+    }
+
+    pub(crate) fn add_on_contradiction(&mut self, callback: ContradictionCallback) {
+        self.on_contradiction.push(callback);
+    }
+
+    #[cfg(feature = "step-stats")]
+    pub(crate) fn add_on_slow_step(&mut self, threshold: Duration, callback: SlowStepCallback) {
+        self.on_slow_step.push((threshold, callback));
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.on_retry.clear();
+        self.on_done.clear();
+        self.on_contradiction.clear();
+        #[cfg(feature = "step-stats")]
+        self.on_slow_step.clear();
+    }
+
+    #[inline]
+    pub(crate) fn retry(&mut self, try_count: u32, seed: u64) {
+        for callback in self.on_retry.iter_mut() {
+            callback(try_count, seed);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn done(&mut self, seed: u64) {
+        for callback in self.on_done.iter_mut() {
+            callback(seed);
+        }
+    }
+
+    #[cfg(feature = "step-stats")]
+    #[inline]
+    pub(crate) fn step(&mut self, stats: &StepStats, seed: u64) {
+        for (threshold, callback) in self.on_slow_step.iter_mut() {
+            if stats.elapsed > *threshold {
+                callback(stats, seed);
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn contradiction(&mut self, error: &GeneratorError, seed: u64) {
+        for callback in self.on_contradiction.iter_mut() {
+            callback(error, seed);
+        }
+    }
+}
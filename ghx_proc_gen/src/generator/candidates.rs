@@ -0,0 +1,372 @@
+use std::{ops::Range, sync::Arc};
+
+use bitvec::{bitvec, order::Lsb0, slice::IterOnes, vec::BitVec};
+use ghx_grid::{
+    coordinate_system::CoordinateSystem,
+    grid::{GridDefinition, GridPosition},
+};
+use ndarray::{s, Array, Ix2, Ix3};
+
+use crate::NodeIndex;
+
+use super::{
+    direction_set::{adjacent_node_index, opposite_adjacency, DirectionSet},
+    model::ModelVariantIndex,
+    rules::Rules,
+    CandidateStorage,
+};
+
+/// Iterator over the possible models of a node, see [`Candidates::possible_models`]
+pub(crate) enum PossibleModels<'a> {
+    /// Models still possible on a node of a materialized page
+    Page(IterOnes<'a, usize, Lsb0>),
+    /// All the models, on a node of a page which is not materialized yet
+    All(Range<ModelVariantIndex>),
+}
+
+impl Iterator for PossibleModels<'_> {
+    type Item = ModelVariantIndex;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            PossibleModels::Page(models) => models.next(),
+            PossibleModels::All(models) => models.next(),
+        }
+    }
+}
+
+/// Possible models and supports counts of a range of nodes
+struct Page {
+    /// `models[local_node_index * stride + model_index]` is true (1) if model with index `model_index` is still allowed on the node
+    models: BitVec<usize>,
+    /// The value at `supports[local_node_index][model_index][adjacency]` represents the number of supports of a `model_index` at the node from `adjacency` (a direction, or an offset of the direction set if the rules have one)
+    supports: Array<usize, Ix3>,
+}
+
+/// Per-node candidates storage of a generator: the models still possible on each node and their supports counts.
+///
+/// Nodes are grouped in pages. A page which is not materialized represents nodes on which nothing was banned yet: all the models are possible and the supports counts are their initial values.
+pub(crate) struct Candidates<C: CoordinateSystem> {
+    grid: GridDefinition<C>,
+    /// Direction set of the rules, if any
+    direction_set: Option<DirectionSet>,
+    storage: CandidateStorage,
+    models_count: usize,
+    /// Number of bits used by a node in a page: `models_count` rounded up to a multiple of the word size, so that the models of each node start on a new word.
+    stride: usize,
+    adjacencies_count: usize,
+    /// Number of nodes per page
+    page_size: usize,
+    pages: Vec<Option<Page>>,
+    /// `initial_supports[model_index][adjacency]` is the initial supports count of a model from `adjacency`, for a node with a neighbour through the opposite adjacency (else it is 0).
+    initial_supports: Array<usize, Ix2>,
+    /// Models with no allowed neighbours through at least one adjacency
+    zero_supports_models: Vec<ModelVariantIndex>,
+    /// Nodes to generate, all the nodes of the grid if `None`. Inactive nodes are never generated and are not neighbours of any node.
+    active_nodes: Option<Arc<BitVec<usize>>>,
+}
+
+impl<C: CoordinateSystem> Candidates<C> {
+    pub(crate) fn new(
+        grid: &GridDefinition<C>,
+        rules: &Rules<C>,
+        storage: CandidateStorage,
+        active_nodes: Option<Arc<BitVec<usize>>>,
+    ) -> Self {
+        let models_count = rules.models_count();
+        let adjacencies_count = rules.adjacencies_count();
+        let nodes_count = grid.total_size();
+
+        let mut initial_supports = Array::zeros((models_count, adjacencies_count));
+        for model in 0..models_count {
+            for adjacency in 0..adjacencies_count {
+                initial_supports[(model, adjacency)] = rules
+                    .allowed_models(
+                        model,
+                        opposite_adjacency(grid.directions(), rules.direction_set(), adjacency),
+                    )
+                    .len();
+            }
+        }
+
+        let zero_supports_models = (0..models_count)
+            .filter(|&model| {
+                initial_supports
+                    .slice(s![model, ..])
+                    .iter()
+                    .any(|&s| s == 0)
+            })
+            .collect();
+
+        let page_size = match storage {
+            CandidateStorage::Dense => nodes_count,
+            CandidateStorage::Paged { page_size } => page_size,
+        }
+        .max(1);
+        let mut candidates = Self {
+            grid: grid.clone(),
+            direction_set: rules.direction_set().cloned(),
+            storage,
+            models_count,
+            stride: models_count.div_ceil(usize::BITS as usize) * usize::BITS as usize,
+            adjacencies_count,
+            page_size,
+            pages: Vec::new(),
+            initial_supports,
+            zero_supports_models,
+            active_nodes,
+        };
+        candidates.reset();
+        candidates
+    }
+
+    /// Resets all the nodes to their initial state: all models possible with their initial supports counts.
+    pub(crate) fn reset(&mut self) {
+        let pages_count = self.grid.total_size().div_ceil(self.page_size);
+        self.pages.clear();
+        self.pages.resize_with(pages_count, || None);
+        if let CandidateStorage::Dense = self.storage {
+            for page_index in 0..pages_count {
+                self.materialize(page_index);
+            }
+        }
+    }
+
+    /// Returns the storage mode of the candidates
+    pub(crate) fn storage(&self) -> CandidateStorage {
+        self.storage
+    }
+
+    /// Returns the active nodes mask of the candidates, `None` if all the nodes are active
+    pub(crate) fn active_nodes(&self) -> Option<Arc<BitVec<usize>>> {
+        self.active_nodes.clone()
+    }
+
+    /// Returns `true` if the node is to be generated (not masked out)
+    #[inline]
+    pub(crate) fn is_active(&self, node_index: NodeIndex) -> bool {
+        match &self.active_nodes {
+            Some(active_nodes) => active_nodes[node_index],
+            None => true,
+        }
+    }
+
+    /// Returns the index of the active node adjacent to `position` through `adjacency`, if any. Inactive nodes are treated as nonexistent.
+    #[inline]
+    pub(crate) fn adjacent_active_node(
+        &self,
+        position: &GridPosition,
+        adjacency: usize,
+    ) -> Option<NodeIndex> {
+        adjacent_node_index(&self.grid, self.direction_set.as_ref(), position, adjacency)
+            .filter(|&node_index| self.is_active(node_index))
+    }
+
+    /// Returns the number of currently materialized pages and the total number of pages
+    pub(crate) fn pages_usage(&self) -> (usize, usize) {
+        (
+            self.pages.iter().filter(|page| page.is_some()).count(),
+            self.pages.len(),
+        )
+    }
+
+    #[inline]
+    fn page(&self, node_index: NodeIndex) -> (Option<&Page>, usize) {
+        (
+            self.pages[node_index / self.page_size].as_ref(),
+            node_index % self.page_size,
+        )
+    }
+
+    #[inline]
+    fn page_mut(&mut self, node_index: NodeIndex) -> (&mut Page, usize) {
+        let page_index = node_index / self.page_size;
+        if self.pages[page_index].is_none() {
+            self.materialize(page_index);
+        }
+        // Unwrap is safe, the page was just materialized
+        (
+            self.pages[page_index].as_mut().unwrap(),
+            node_index % self.page_size,
+        )
+    }
+
+    fn materialize(&mut self, page_index: usize) {
+        let first_node = page_index * self.page_size;
+        let page_nodes = self.page_size.min(self.grid.total_size() - first_node);
+        let mut supports = Array::zeros((page_nodes, self.models_count, self.adjacencies_count));
+        for local_node in 0..page_nodes {
+            supports
+                .slice_mut(s![local_node, .., ..])
+                .assign(&self.initial_node_supports(first_node + local_node));
+        }
+        // Padding bits between the nodes are left at 0
+        let mut models = bitvec![0; page_nodes * self.stride];
+        for local_node in 0..page_nodes {
+            models[local_node * self.stride..local_node * self.stride + self.models_count]
+                .fill(true);
+        }
+        self.pages[page_index] = Some(Page { models, supports });
+    }
+
+    /// Initial supports counts of a node: a node on a non-looping border (or next to an inactive node) has no supports from the direction of the border.
+    fn initial_node_supports(&self, node_index: NodeIndex) -> Array<usize, Ix2> {
+        let mut supports = self.initial_supports.clone();
+        let position = self.grid.pos_from_index(node_index);
+        for adjacency in 0..self.adjacencies_count {
+            if !self.has_neighbour_from(&position, adjacency) {
+                supports.slice_mut(s![.., adjacency]).fill(0);
+            }
+        }
+        supports
+    }
+
+    /// Returns `true` if the node at `position` has a neighbour which can support it from `adjacency`
+    #[inline]
+    fn has_neighbour_from(&self, position: &GridPosition, adjacency: usize) -> bool {
+        self.adjacent_active_node(
+            position,
+            opposite_adjacency(
+                self.grid.directions(),
+                self.direction_set.as_ref(),
+                adjacency,
+            ),
+        )
+        .is_some()
+    }
+
+    /// Returns the models that can never be supported on the node: their initial supports count is 0 from an adjacency where the node has a neighbour.
+    pub(crate) fn unsupported_models(&self, node_index: NodeIndex) -> Vec<ModelVariantIndex> {
+        if self.zero_supports_models.is_empty() {
+            return Vec::new();
+        }
+        let position = self.grid.pos_from_index(node_index);
+        let supported_adjacencies: Vec<usize> = (0..self.adjacencies_count)
+            .filter(|&adjacency| self.has_neighbour_from(&position, adjacency))
+            .collect();
+        self.zero_supports_models
+            .iter()
+            .copied()
+            .filter(|&model| {
+                supported_adjacencies
+                    .iter()
+                    .any(|&adjacency| self.initial_supports[(model, adjacency)] == 0)
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub(crate) fn is_possible(&self, node_index: NodeIndex, model: ModelVariantIndex) -> bool {
+        match self.page(node_index) {
+            (Some(page), local_node) => page.models[local_node * self.stride + model],
+            (None, _) => true,
+        }
+    }
+
+    /// Returns the word at `word_index` of the possible models bits of the node: bit `i` of the word is set if model `word_index * usize::BITS + i` is possible.
+    #[cfg(feature = "simd-propagation")]
+    #[inline]
+    pub(crate) fn possible_models_word(&self, node_index: NodeIndex, word_index: usize) -> usize {
+        match self.page(node_index) {
+            (Some(page), local_node) => page.models.as_raw_slice()
+                [local_node * self.stride / usize::BITS as usize + word_index],
+            (None, _) => usize::MAX,
+        }
+    }
+
+    /// Returns the first possible model on the node, or `0` if there is none.
+    #[inline]
+    pub(crate) fn first_possible(&self, node_index: NodeIndex) -> ModelVariantIndex {
+        match self.page(node_index) {
+            (Some(page), local_node) => page.models
+                [local_node * self.stride..local_node * self.stride + self.models_count]
+                .first_one()
+                .unwrap_or(0),
+            (None, _) => 0,
+        }
+    }
+
+    pub(crate) fn possible_models(&self, node_index: NodeIndex) -> PossibleModels<'_> {
+        match self.page(node_index) {
+            (Some(page), local_node) => PossibleModels::Page(
+                page.models[local_node * self.stride..local_node * self.stride + self.models_count]
+                    .iter_ones(),
+            ),
+            (None, _) => PossibleModels::All(0..self.models_count),
+        }
+    }
+
+    /// Decrements the supports count of `model` on the node from `adjacency` if it is not 0 already, and returns the new supports count (`None` if it was already 0).
+    #[inline]
+    pub(crate) fn decrement_supports(
+        &mut self,
+        node_index: NodeIndex,
+        model: ModelVariantIndex,
+        adjacency: usize,
+    ) -> Option<usize> {
+        let (page, local_node) = self.page_mut(node_index);
+        let supports_count = &mut page.supports[(local_node, model, adjacency)];
+        if *supports_count > 0 {
+            *supports_count -= 1;
+            Some(*supports_count)
+        } else {
+            None
+        }
+    }
+
+    /// Removes `model` from the possible models of the node and sets its supports counts to 0
+    pub(crate) fn ban(&mut self, node_index: NodeIndex, model: ModelVariantIndex) {
+        let stride = self.stride;
+        let (page, local_node) = self.page_mut(node_index);
+        page.supports.slice_mut(s![local_node, model, ..]).fill(0);
+        page.models.set(local_node * stride + model, false);
+    }
+
+    /// Removes all the models but `model` from the possible models of the node, and sets their supports counts to 0
+    pub(crate) fn select(&mut self, node_index: NodeIndex, model: ModelVariantIndex) {
+        let (stride, models_count) = (self.stride, self.models_count);
+        let (page, local_node) = self.page_mut(node_index);
+        let node_models = &mut page.models[local_node * stride..local_node * stride + models_count];
+        for other_model in node_models.iter_ones() {
+            if other_model != model {
+                page.supports
+                    .slice_mut(s![local_node, other_model, ..])
+                    .fill(0);
+            }
+        }
+        node_models.fill(false);
+        node_models.set(model, true);
+    }
+
+    /// Returns a copy of the possible models and of the supports counts of the node
+    pub(crate) fn node_state(&self, node_index: NodeIndex) -> (BitVec<usize>, Array<usize, Ix2>) {
+        match self.page(node_index) {
+            (Some(page), local_node) => (
+                page.models[local_node * self.stride..local_node * self.stride + self.models_count]
+                    .to_bitvec(),
+                page.supports.slice(s![local_node, .., ..]).to_owned(),
+            ),
+            (None, _) => (
+                bitvec![1; self.models_count],
+                self.initial_node_supports(node_index),
+            ),
+        }
+    }
+
+    /// Restores a state given by [`Candidates::node_state`]
+    pub(crate) fn set_node_state(
+        &mut self,
+        node_index: NodeIndex,
+        models: &BitVec<usize>,
+        supports: &Array<usize, Ix2>,
+    ) {
+        let (stride, models_count) = (self.stride, self.models_count);
+        let (page, local_node) = self.page_mut(node_index);
+        page.models[local_node * stride..local_node * stride + models_count]
+            .copy_from_bitslice(models);
+        page.supports
+            .slice_mut(s![local_node, .., ..])
+            .assign(supports);
+    }
+}
@@ -0,0 +1,415 @@
+use std::cmp::Ordering;
+
+use ghx_grid::{
+    coordinate_system::CoordinateSystem,
+    direction::Direction,
+    grid::{GridData, GridDefinition, GridPosition},
+};
+
+use crate::{GeneratorError, NodeIndex};
+
+use super::{
+    model::{ModelInstance, ModelVariantIndex},
+    observer::NodeRegion,
+    GenInfo, GenerationStatus, Generator,
+};
+
+/// Splits the grid of a [`Generator`] into chunks separated by seam strips, see [`Generator::generate_chunked`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chunking {
+    /// Size of the chunks on each axis, in nodes. Axes smaller than their chunk size are not split.
+    ///
+    /// On a looping axis, the last chunk would be adjacent to the first one through the wrap-around: a seam is always reserved at the end of the axis, even if the axis is not split.
+    pub chunk_size: (u32, u32, u32),
+    /// Width of the seam strips between two chunks, in nodes. A width of `0` is replaced by `1`: chunks are never adjacent.
+    pub seam_width: u32,
+}
+
+impl Chunking {
+    /// Creates a [`Chunking`] with chunks of `chunk_size` nodes on each axis, separated by seams of `seam_width` nodes
+    pub fn new(chunk_size: (u32, u32, u32), seam_width: u32) -> Self {
+        Self {
+            chunk_size,
+            seam_width,
+        }
+    }
+
+    /// Returns the regions of the chunks of `grid`, ordered by their lowest node index
+    pub fn chunks<C: CoordinateSystem>(&self, grid: &GridDefinition<C>) -> Vec<NodeRegion> {
+        let xs = self.axis_chunks(
+            grid.size_x(),
+            self.chunk_size.0,
+            is_looping(grid, Direction::XForward),
+        );
+        let ys = self.axis_chunks(
+            grid.size_y(),
+            self.chunk_size.1,
+            is_looping(grid, Direction::YForward),
+        );
+        let zs = self.axis_chunks(
+            grid.size_z(),
+            self.chunk_size.2,
+            is_looping(grid, Direction::ZForward),
+        );
+        let mut chunks = Vec::with_capacity(xs.len() * ys.len() * zs.len());
+        for &(min_z, max_z) in zs.iter() {
+            for &(min_y, max_y) in ys.iter() {
+                for &(min_x, max_x) in xs.iter() {
+                    chunks.push(NodeRegion::new(
+                        GridPosition::new(min_x, min_y, min_z),
+                        GridPosition::new(max_x, max_y, max_z),
+                    ));
+                }
+            }
+        }
+        chunks
+    }
+
+    /// Returns the regions of the seam strips of `grid`, in their generation order.
+    ///
+    /// The seams along the x axis (spanning the chunks on the other axes) come first, then the seams along the y axis (spanning the whole x axis and the chunks on the z axis), then the seams along the z axis (spanning the whole x and y axes). Each seam is only adjacent to chunks and to the seams coming before it, except at its ends.
+    pub fn seams<C: CoordinateSystem>(&self, grid: &GridDefinition<C>) -> Vec<NodeRegion> {
+        let axes = [
+            (grid.size_x(), Direction::XForward, self.chunk_size.0),
+            (grid.size_y(), Direction::YForward, self.chunk_size.1),
+            (grid.size_z(), Direction::ZForward, self.chunk_size.2),
+        ]
+        .map(|(size, direction, chunk_size)| {
+            let chunks = self.axis_chunks(size, chunk_size, is_looping(grid, direction));
+            (size, axis_seams(size, &chunks), chunks)
+        });
+        let mut seams = Vec::new();
+        for seam_axis in 0..3 {
+            let [xs, ys, zs] = [0, 1, 2].map(|axis| {
+                let (size, axis_seams, axis_chunks) = &axes[axis];
+                match axis.cmp(&seam_axis) {
+                    Ordering::Less => vec![(0, size - 1)],
+                    Ordering::Equal => axis_seams.clone(),
+                    Ordering::Greater => axis_chunks.clone(),
+                }
+            });
+            for &(min_z, max_z) in zs.iter() {
+                for &(min_y, max_y) in ys.iter() {
+                    for &(min_x, max_x) in xs.iter() {
+                        seams.push(NodeRegion::new(
+                            GridPosition::new(min_x, min_y, min_z),
+                            GridPosition::new(max_x, max_y, max_z),
+                        ));
+                    }
+                }
+            }
+        }
+        seams
+    }
+
+    /// Returns the `(min, max)` coordinates (both inclusive) of the chunks of an axis of size `size`. On a `looping` axis, the last `seam_width` nodes are left to the wrap-around seam, and there may be no chunk at all if the axis is not larger than a seam.
+    fn axis_chunks(&self, size: u32, chunk_size: u32, looping: bool) -> Vec<(u32, u32)> {
+        let chunk_size = chunk_size.max(1);
+        let seam_width = self.seam_width.max(1);
+        if !looping && chunk_size >= size {
+            return vec![(0, size.saturating_sub(1))];
+        }
+        let chunked_size = match looping {
+            true => size.saturating_sub(seam_width),
+            false => size,
+        };
+        let period = chunk_size + seam_width;
+        (0..chunked_size)
+            .step_by(period as usize)
+            .map(|min| (min, (min + chunk_size).min(chunked_size) - 1))
+            .collect()
+    }
+}
+
+/// Returns the `(min, max)` coordinates (both inclusive) of the seams of an axis of size `size`: the intervals between the `chunks` of the axis, and after the last one on a looping axis.
+fn axis_seams(size: u32, chunks: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut seams = Vec::new();
+    let mut next = 0;
+    for &(min, max) in chunks.iter() {
+        if min > next {
+            seams.push((next, min - 1));
+        }
+        next = max + 1;
+    }
+    if next < size {
+        seams.push((next, size - 1));
+    }
+    seams
+}
+
+/// Returns `true` if the grid loops on the axis of `direction`: the last node of the axis has a neighbour in `direction`
+fn is_looping<C: CoordinateSystem>(grid: &GridDefinition<C>, direction: Direction) -> bool {
+    if !grid.directions().contains(&direction) {
+        return false;
+    }
+    let (size_x, size_y, size_z) = grid.size();
+    let last = GridPosition::new(
+        size_x.saturating_sub(1),
+        size_y.saturating_sub(1),
+        size_z.saturating_sub(1),
+    );
+    grid.get_next_index_in_direction(&last, direction).is_some()
+}
+
+/// Returns the grid of a sub-generation on `region` extended by `margin` nodes on each side (where the grid has nodes), and the index in `grid` of each node of this sub-grid.
+///
+/// The sub-grid loops on the axes it fully spans if `grid` loops on them. On the other axes, the margin follows the looping of `grid`.
+fn sub_grid<C: CoordinateSystem>(
+    grid: &GridDefinition<C>,
+    coord_system: &C,
+    region: &NodeRegion,
+    margin: u32,
+) -> (GridDefinition<C>, Vec<NodeIndex>) {
+    let spans = [
+        (
+            grid.size_x(),
+            region.min.x,
+            region.max.x,
+            Direction::XForward,
+        ),
+        (
+            grid.size_y(),
+            region.min.y,
+            region.max.y,
+            Direction::YForward,
+        ),
+        (
+            grid.size_z(),
+            region.min.z,
+            region.max.z,
+            Direction::ZForward,
+        ),
+    ]
+    .map(|(size, min, max, direction)| {
+        axis_span(size, min, max, margin, is_looping(grid, direction))
+    });
+    let [(start_x, len_x, loop_x), (start_y, len_y, loop_y), (start_z, len_z, loop_z)] = spans;
+    let sub_grid = GridDefinition::new(
+        len_x,
+        len_y,
+        len_z,
+        loop_x,
+        loop_y,
+        loop_z,
+        coord_system.clone(),
+    );
+    let grid_indexes = (0..sub_grid.total_size())
+        .map(|sub_index| {
+            let position = sub_grid.pos_from_index(sub_index);
+            grid.index_from_coords(
+                (start_x + position.x) % grid.size_x(),
+                (start_y + position.y) % grid.size_y(),
+                (start_z + position.z) % grid.size_z(),
+            )
+        })
+        .collect();
+    (sub_grid, grid_indexes)
+}
+
+/// Returns the `(start, length, looping)` of the span of a sub-grid on an axis of size `size`, for a region from `min` to `max` (inclusive) extended by `margin` nodes on each side
+fn axis_span(size: u32, min: u32, max: u32, margin: u32, looping: bool) -> (u32, u32, bool) {
+    let len = max - min + 1;
+    if len == size || (looping && len + 2 * margin >= size) {
+        return (0, size, looping);
+    }
+    match looping {
+        true => ((min + size - margin) % size, len + 2 * margin, false),
+        false => {
+            let start = min.saturating_sub(margin);
+            (start, (max + margin).min(size - 1) - start + 1, false)
+        }
+    }
+}
+
+impl<C: CoordinateSystem> Generator<C> {
+    /// Generates the grid chunk by chunk: the chunks given by `chunking` are generated independently, each by a generator on a grid of the size of the chunk (sharing the same rules). Then the seam strips between them (see [`Chunking::seams`]) are generated one by one, each by a generator on a sub-grid made of the seam and of its borders: the already generated nodes of the borders (from the chunks and the previous seams) constrain the seam.
+    ///
+    /// Since the chunks are never adjacent, they can be generated in any order, see [`Generator::generate_chunked_parallel`]. On looping axes, a seam also separates the last chunk from the first one. A contradiction in a chunk or in a seam is retried up to `max_retry_count` times in this chunk or seam only. The seams must be wide enough for the rules to be able to connect any two chunks borders: a contradiction caused by the borders constraints fails the generation.
+    ///
+    /// The chunks and the seams only search and propagate on their own sub-grid. Once all of them are generated, this generator is reinitialized with all their nodes, so that it holds the output and signals it to its observers: this last pass sets every node once, but does not search nor retry.
+    ///
+    /// The seeds of the chunks and of the seams are drawn from the generator RNG before the first chunk is generated: the output only depends on the current seed of the generator, and is the same as the one of [`Generator::generate_chunked_parallel`]. The initial nodes, initial bans, active nodes, anchors and node weights of the generator are restricted to the sub-grid of each chunk and seam. Observers and callbacks are only called by the last pass, which sets all the nodes. The returned [`GenInfo`] holds the largest try count of the chunks and seams.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{builder::GeneratorBuilder, chunked::Chunking, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::grid::GridDefinition;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (grass, water) = (sockets.create(), sockets.create());
+    /// sockets.add_connections(vec![(grass, vec![grass, water]), (water, vec![water])]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(grass));
+    /// models.create(SocketsCartesian2D::Mono(water));
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    ///
+    /// let mut generator = GeneratorBuilder::new()
+    ///    .with_rules(rules)
+    ///    .with_grid(GridDefinition::new_cartesian_2d(66, 66, false, false))
+    ///    .build()
+    ///    .unwrap();
+    /// // 4 chunks of 32x32 nodes, separated by seams of 2 nodes
+    /// let chunking = Chunking::new((32, 32, 1), 2);
+    /// assert_eq!(chunking.chunks(generator.grid()).len(), 4);
+    /// // 2 seams along x between the chunks, then 1 seam along y crossing the whole grid
+    /// assert_eq!(chunking.seams(generator.grid()).len(), 3);
+    /// let (_gen_info, output) = generator.generate_chunked(chunking).unwrap();
+    /// assert_eq!(output.nodes().len(), 66 * 66);
+    /// ```
+    pub fn generate_chunked(
+        &mut self,
+        chunking: Chunking,
+    ) -> Result<(GenInfo, GridData<C, ModelInstance>), GeneratorError> {
+        let chunks = chunking.chunks(self.grid());
+        let seams = chunking.seams(self.grid());
+        let mut seeds = self.internal.next_seeds(chunks.len() + seams.len() + 1);
+        let output_seed = seeds.pop().unwrap();
+        let seams_seeds = seeds.split_off(chunks.len());
+        let mut nodes = self.initial_nodes_map();
+        let mut try_count = 1;
+        for (chunk, seed) in chunks.iter().zip(seeds) {
+            let (gen_info, chunk_nodes) = self.generate_region(chunk, 0, seed, &nodes)?;
+            try_count = try_count.max(gen_info.try_count);
+            for (node_index, model_variant_index) in chunk_nodes {
+                nodes[node_index] = Some(model_variant_index);
+            }
+        }
+        self.generate_seams(&seams, seams_seeds, output_seed, nodes, try_count)
+    }
+
+    /// Same as [`Generator::generate_chunked`] but generates the chunks in parallel, on the `rayon` thread pool. The output is identical to the one of [`Generator::generate_chunked`]. The seams are then generated on the calling thread.
+    ///
+    /// When some chunks fail, the returned [`GeneratorError`] depends on the [`super::DeterminismLevel`] of the generator: with [`super::DeterminismLevel::Strict`] it is the one of the first failing chunk, as with [`Generator::generate_chunked`], with [`super::DeterminismLevel::Relaxed`] it is the first one detected.
+    #[cfg(feature = "parallel")]
+    pub fn generate_chunked_parallel(
+        &mut self,
+        chunking: Chunking,
+    ) -> Result<(GenInfo, GridData<C, ModelInstance>), GeneratorError>
+    where
+        C: Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        let chunks = chunking.chunks(self.grid());
+        let seams = chunking.seams(self.grid());
+        let mut seeds = self.internal.next_seeds(chunks.len() + seams.len() + 1);
+        let output_seed = seeds.pop().unwrap();
+        let seams_seeds = seeds.split_off(chunks.len());
+        let mut nodes = self.initial_nodes_map();
+        let generator = &*self;
+        let initial_nodes = &nodes;
+        let outputs = chunks
+            .par_iter()
+            .zip(seeds)
+            .map(|(chunk, seed)| generator.generate_region(chunk, 0, seed, initial_nodes));
+        let chunks_outputs: Vec<(GenInfo, Vec<(NodeIndex, ModelVariantIndex)>)> =
+            match self.determinism_level {
+                super::DeterminismLevel::Strict => {
+                    // Collecting all the results keeps them in chunk order, even the errors
+                    let results: Vec<Result<_, GeneratorError>> = outputs.collect();
+                    results.into_iter().collect::<Result<_, _>>()?
+                }
+                super::DeterminismLevel::Relaxed => outputs.collect::<Result<_, _>>()?,
+            };
+        let mut try_count = 1;
+        for (gen_info, chunk_nodes) in chunks_outputs {
+            try_count = try_count.max(gen_info.try_count);
+            for (node_index, model_variant_index) in chunk_nodes {
+                nodes[node_index] = Some(model_variant_index);
+            }
+        }
+        self.generate_seams(&seams, seams_seeds, output_seed, nodes, try_count)
+    }
+
+    /// Returns the model set on each node by the initial nodes of the generator, if any
+    fn initial_nodes_map(&self) -> Vec<Option<ModelVariantIndex>> {
+        let mut nodes = vec![None; self.grid().total_size()];
+        for &(node_index, model_variant_index) in self.initial_nodes.iter() {
+            nodes[node_index] = Some(model_variant_index);
+        }
+        nodes
+    }
+
+    /// Generates the nodes of `region` with their own generator seeded with `seed`, on a sub-grid made of `region` and of `margin` nodes around it. The nodes of the sub-grid already set in `nodes` are its initial nodes.
+    ///
+    /// Returns the generated nodes of `region`, with their index in the whole grid.
+    fn generate_region(
+        &self,
+        region: &NodeRegion,
+        margin: u32,
+        seed: u64,
+        nodes: &[Option<ModelVariantIndex>],
+    ) -> Result<(GenInfo, Vec<(NodeIndex, ModelVariantIndex)>), GeneratorError> {
+        let grid = self.grid();
+        let (region_grid, grid_indexes) =
+            sub_grid(grid, self.rules().coord_system(), region, margin);
+        let initial_nodes = grid_indexes
+            .iter()
+            .enumerate()
+            .filter_map(|(sub_index, &node_index)| {
+                nodes[node_index].map(|model_variant_index| (sub_index, model_variant_index))
+            })
+            .collect();
+
+        let mut region_generator = self.internal.fork_on_sub_grid(region_grid, &grid_indexes);
+        let gen_info = region_generator
+            .generate_with_checked_nodes(seed, self.max_retry_count, &initial_nodes)
+            .map_err(|err| GeneratorError {
+                node_index: grid_indexes[err.node_index],
+            })?;
+        let region_nodes = grid_indexes
+            .iter()
+            .enumerate()
+            .filter(|&(_, &node_index)| region.contains(&grid.pos_from_index(node_index)))
+            .filter_map(|(sub_index, &node_index)| {
+                region_generator
+                    .generated_model_index(sub_index)
+                    .map(|model_variant_index| (node_index, model_variant_index))
+            })
+            .collect();
+        Ok((gen_info, region_nodes))
+    }
+
+    /// Generates the `seams` one by one (each with its seed from `seeds`), bounded by the `nodes` already generated, then reinitializes this generator with `output_seed` and all the nodes.
+    fn generate_seams(
+        &mut self,
+        seams: &[NodeRegion],
+        seeds: Vec<u64>,
+        output_seed: u64,
+        mut nodes: Vec<Option<ModelVariantIndex>>,
+        mut try_count: u32,
+    ) -> Result<(GenInfo, GridData<C, ModelInstance>), GeneratorError> {
+        // The borders of a seam must hold every neighbour of its nodes, including through the offsets of the direction set
+        let margin = self
+            .rules()
+            .direction_set()
+            .and_then(|direction_set| {
+                direction_set
+                    .offsets()
+                    .iter()
+                    .flat_map(|offset| offset.steps())
+                    .map(|(_, count)| count)
+                    .max()
+            })
+            .unwrap_or(1)
+            .max(1);
+        for (seam, seed) in seams.iter().zip(seeds) {
+            let (gen_info, seam_nodes) = self.generate_region(seam, margin, seed, &nodes)?;
+            try_count = try_count.max(gen_info.try_count);
+            for (node_index, model_variant_index) in seam_nodes {
+                nodes[node_index] = Some(model_variant_index);
+            }
+        }
+        let constraints = nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(node_index, model_variant_index)| {
+                model_variant_index.map(|model_variant_index| (node_index, model_variant_index))
+            })
+            .collect();
+        self.internal
+            .reinitialize_with_checked_nodes(output_seed, &constraints)?;
+        Ok((GenInfo { try_count }, self.internal.to_grid_data()))
+    }
+}
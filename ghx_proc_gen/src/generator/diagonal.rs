@@ -0,0 +1,117 @@
+use ghx_grid::direction::Direction;
+
+#[cfg(feature = "bevy")]
+use bevy::ecs::component::Component;
+#[cfg(feature = "reflect")]
+use bevy::{ecs::reflect::ReflectComponent, reflect::Reflect};
+
+use super::{direction_set::GridOffset, socket::Socket};
+
+/// Number of diagonals in a 2d cartesian grid
+pub const DIAGONALS_COUNT: usize = 4;
+
+/// Diagonal adjacency of a node in a 2d cartesian grid, used by rules built with [`super::rules::RulesBuilder::with_diagonals`].
+///
+/// Diagonals are ordered counter-clockwise, starting from the x+/y+ corner, like the offsets of [`super::direction_set::DirectionSet::diagonals_2d`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "bevy", derive(Component))]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub enum Diagonal {
+    /// Corner on the x+ and y+ sides
+    XPosYPos = 0,
+    /// Corner on the x- and y+ sides
+    XNegYPos = 1,
+    /// Corner on the x- and y- sides
+    XNegYNeg = 2,
+    /// Corner on the x+ and y- sides
+    XPosYNeg = 3,
+}
+
+impl Diagonal {
+    /// All the diagonals, ordered by index
+    pub const ALL: [Diagonal; DIAGONALS_COUNT] = [
+        Diagonal::XPosYPos,
+        Diagonal::XNegYPos,
+        Diagonal::XNegYNeg,
+        Diagonal::XPosYNeg,
+    ];
+
+    /// Returns the x and y directions composing this diagonal
+    pub fn directions(&self) -> (Direction, Direction) {
+        match self {
+            Diagonal::XPosYPos => (Direction::XForward, Direction::YForward),
+            Diagonal::XNegYPos => (Direction::XBackward, Direction::YForward),
+            Diagonal::XNegYNeg => (Direction::XBackward, Direction::YBackward),
+            Diagonal::XPosYNeg => (Direction::XForward, Direction::YBackward),
+        }
+    }
+
+    /// Returns the opposite diagonal
+    pub fn opposite(&self) -> Diagonal {
+        Diagonal::ALL[(*self as usize + 2) % DIAGONALS_COUNT]
+    }
+
+    /// Returns the [`GridOffset`] from a node to its neighbour on this diagonal
+    pub fn offset(&self) -> GridOffset {
+        let (x, y) = self.directions();
+        GridOffset::from_direction(x) + GridOffset::from_direction(y)
+    }
+}
+
+/// Sockets of a model on its diagonals, see [`super::model::ModelTemplate::with_diagonal_sockets`].
+pub enum DiagonalSockets {
+    /// The model has only 1 socket, and it is the same on all diagonals.
+    Mono(Socket),
+    /// The model has 1 socket per diagonal.
+    Simple {
+        /// socket on the x+/y+ corner
+        x_pos_y_pos: Socket,
+        /// socket on the x-/y+ corner
+        x_neg_y_pos: Socket,
+        /// socket on the x-/y- corner
+        x_neg_y_neg: Socket,
+        /// socket on the x+/y- corner
+        x_pos_y_neg: Socket,
+    },
+    /// The model has multiple sockets per diagonal.
+    Multiple {
+        /// sockets on the x+/y+ corner
+        x_pos_y_pos: Vec<Socket>,
+        /// sockets on the x-/y+ corner
+        x_neg_y_pos: Vec<Socket>,
+        /// sockets on the x-/y- corner
+        x_neg_y_neg: Vec<Socket>,
+        /// sockets on the x+/y- corner
+        x_pos_y_neg: Vec<Socket>,
+    },
+}
+
+impl Into<Vec<(GridOffset, Vec<Socket>)>> for DiagonalSockets {
+    fn into(self) -> Vec<(GridOffset, Vec<Socket>)> {
+        let sockets: Vec<Vec<Socket>> = match self {
+            DiagonalSockets::Mono(socket) => vec![vec![socket]; DIAGONALS_COUNT],
+            DiagonalSockets::Simple {
+                x_pos_y_pos,
+                x_neg_y_pos,
+                x_neg_y_neg,
+                x_pos_y_neg,
+            } => vec![
+                vec![x_pos_y_pos],
+                vec![x_neg_y_pos],
+                vec![x_neg_y_neg],
+                vec![x_pos_y_neg],
+            ],
+            DiagonalSockets::Multiple {
+                x_pos_y_pos,
+                x_neg_y_pos,
+                x_neg_y_neg,
+                x_pos_y_neg,
+            } => vec![x_pos_y_pos, x_neg_y_pos, x_neg_y_neg, x_pos_y_neg],
+        };
+        Diagonal::ALL
+            .iter()
+            .map(|diagonal| diagonal.offset())
+            .zip(sockets)
+            .collect()
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    fmt,
+    ops::{Add, Mul},
+};
+
+use ghx_grid::{
+    coordinate_system::CoordinateSystem,
+    direction::Direction,
+    grid::{GridDefinition, GridPosition},
+};
+
+#[cfg(feature = "bevy")]
+use bevy::ecs::component::Component;
+#[cfg(feature = "reflect")]
+use bevy::{ecs::reflect::ReflectComponent, reflect::Reflect};
+
+use crate::{DirectionSetError, NodeIndex};
+
+use super::{diagonal::Diagonal, model::ModelRotation};
+
+/// Offset from a node to one of its neighbours, in grid coordinates. Used as the adjacencies of a [`DirectionSet`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "bevy", derive(Component))]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct GridOffset {
+    /// Offset on the x axis
+    pub dx: i32,
+    /// Offset on the y axis
+    pub dy: i32,
+    /// Offset on the z axis
+    pub dz: i32,
+}
+
+impl GridOffset {
+    /// Creates a new offset
+    pub const fn new(dx: i32, dy: i32, dz: i32) -> Self {
+        Self { dx, dy, dz }
+    }
+
+    /// Returns the offset of a neighbour at one node in `direction`
+    pub fn from_direction(direction: Direction) -> Self {
+        match direction {
+            Direction::XForward => Self::new(1, 0, 0),
+            Direction::XBackward => Self::new(-1, 0, 0),
+            Direction::YForward => Self::new(0, 1, 0),
+            Direction::YBackward => Self::new(0, -1, 0),
+            Direction::ZForward => Self::new(0, 0, 1),
+            Direction::ZBackward => Self::new(0, 0, -1),
+        }
+    }
+
+    /// Returns `true` if the offset is `(0, 0, 0)`
+    pub fn is_zero(&self) -> bool {
+        *self == Self::new(0, 0, 0)
+    }
+
+    /// Returns the opposite offset
+    pub fn opposite(&self) -> Self {
+        Self::new(-self.dx, -self.dy, -self.dz)
+    }
+
+    /// Returns the steps composing this offset: a direction and a number of nodes for each non-zero axis, in the x, y, z order.
+    pub fn steps(&self) -> impl Iterator<Item = (Direction, u32)> {
+        [
+            (self.dx, Direction::XForward, Direction::XBackward),
+            (self.dy, Direction::YForward, Direction::YBackward),
+            (self.dz, Direction::ZForward, Direction::ZBackward),
+        ]
+        .into_iter()
+        .filter(|(delta, _, _)| *delta != 0)
+        .map(|(delta, forward, backward)| match delta > 0 {
+            true => (forward, delta.unsigned_abs()),
+            false => (backward, delta.unsigned_abs()),
+        })
+    }
+
+    /// Returns the offset reached after a rotation of `rotation` around `rot_axis` (counter-clockwise, like the sockets of a model).
+    pub fn rotated(&self, rotation: ModelRotation, rot_axis: Direction) -> Self {
+        let basis = rot_axis.rotation_basis();
+        self.steps()
+            .map(|(direction, count)| {
+                let direction = match basis.iter().position(|&d| d == direction) {
+                    Some(i) => basis[(i + rotation.index() as usize) % basis.len()],
+                    None => direction,
+                };
+                GridOffset::from_direction(direction) * count as i32
+            })
+            .fold(GridOffset::new(0, 0, 0), |rotated, step| rotated + step)
+    }
+}
+
+impl Add for GridOffset {
+    type Output = GridOffset;
+
+    fn add(self, rhs: GridOffset) -> GridOffset {
+        GridOffset::new(self.dx + rhs.dx, self.dy + rhs.dy, self.dz + rhs.dz)
+    }
+}
+
+impl Mul<i32> for GridOffset {
+    type Output = GridOffset;
+
+    fn mul(self, rhs: i32) -> GridOffset {
+        GridOffset::new(self.dx * rhs, self.dy * rhs, self.dz * rhs)
+    }
+}
+
+impl fmt::Display for GridOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.dx, self.dy, self.dz)
+    }
+}
+
+/// Additional adjacencies of some [`super::rules::Rules`], on top of the directions of their coordinate system: each adjacency is a [`GridOffset`] from a node to a neighbour. See [`super::rules::RulesBuilder::with_direction_set`].
+///
+/// Each offset of the set must have its opposite in the set, since an adjacency constrains the two nodes it links. The offsets of the directions of the coordinate system are not allowed, they are already adjacencies of the rules.
+///
+/// ### Example
+///
+/// 8-connected 2d grids use the 4 diagonals, and 26-connected 3d grids the 20 edges and corners offsets:
+/// ```
+/// use ghx_proc_gen::generator::direction_set::{DirectionSet, GridOffset};
+///
+/// assert_eq!(DirectionSet::diagonals_2d().len(), 4);
+/// assert_eq!(DirectionSet::diagonals_3d().len(), 20);
+///
+/// // Knight moves
+/// let knight_moves = DirectionSet::new([
+///     GridOffset::new(2, 1, 0), GridOffset::new(-1, 2, 0), GridOffset::new(-2, -1, 0), GridOffset::new(1, -2, 0),
+///     GridOffset::new(1, 2, 0), GridOffset::new(-2, 1, 0), GridOffset::new(-1, -2, 0), GridOffset::new(2, -1, 0),
+/// ]).unwrap();
+/// assert_eq!(knight_moves.opposite(0), 2);
+///
+/// assert!(DirectionSet::new([GridOffset::new(1, 1, 0)]).is_err());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DirectionSet {
+    offsets: Vec<GridOffset>,
+    /// `opposites[index]` is the index of the opposite of `offsets[index]`
+    opposites: Vec<usize>,
+}
+
+impl DirectionSet {
+    /// Creates a set from its `offsets`, in order.
+    ///
+    /// Returns a [`DirectionSetError`] if an offset is zero, is the offset of a direction, appears twice, or does not have its opposite in the set.
+    pub fn new(offsets: impl IntoIterator<Item = GridOffset>) -> Result<Self, DirectionSetError> {
+        let offsets: Vec<GridOffset> = offsets.into_iter().collect();
+        let mut opposites = Vec::with_capacity(offsets.len());
+        for (index, offset) in offsets.iter().enumerate() {
+            if offset.is_zero() {
+                return Err(DirectionSetError::ZeroOffset);
+            }
+            if offset.steps().count() == 1 && offset.steps().all(|(_, count)| count == 1) {
+                return Err(DirectionSetError::DirectionOffset(*offset));
+            }
+            if offsets[..index].contains(offset) {
+                return Err(DirectionSetError::DuplicateOffset(*offset));
+            }
+            match offsets.iter().position(|o| *o == offset.opposite()) {
+                Some(opposite) => opposites.push(opposite),
+                None => return Err(DirectionSetError::MissingOpposite(*offset)),
+            }
+        }
+        Ok(Self { offsets, opposites })
+    }
+
+    /// The 4 diagonals of a 2d grid, ordered like [`Diagonal::ALL`]
+    pub fn diagonals_2d() -> Self {
+        Self::new(Diagonal::ALL.iter().map(|diagonal| diagonal.offset()))
+            .expect("diagonals form a valid direction set")
+    }
+
+    /// The 20 edges and corners neighbours of a 3d grid, which make it 26-connected with its 6 directions. Ordered by z, then y, then x.
+    pub fn diagonals_3d() -> Self {
+        let mut offsets = Vec::new();
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let offset = GridOffset::new(dx, dy, dz);
+                    if offset.steps().count() >= 2 {
+                        offsets.push(offset);
+                    }
+                }
+            }
+        }
+        Self::new(offsets).expect("edges and corners form a valid direction set")
+    }
+
+    /// Returns the offsets of the set, in adjacency order
+    pub fn offsets(&self) -> &[GridOffset] {
+        &self.offsets
+    }
+
+    /// Returns the number of offsets in the set
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns `true` if the set has no offsets
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Returns the index of `offset` in the set, if any
+    pub fn index_of(&self, offset: GridOffset) -> Option<usize> {
+        self.offsets.iter().position(|o| *o == offset)
+    }
+
+    /// Returns the index of the opposite of the offset at `index`
+    pub fn opposite(&self, index: usize) -> usize {
+        self.opposites[index]
+    }
+
+    /// Returns the first offset which uses an axis not in `directions`, if any
+    pub(crate) fn offset_outside(&self, directions: &[Direction]) -> Option<GridOffset> {
+        self.offsets.iter().copied().find(|offset| {
+            offset
+                .steps()
+                .any(|(direction, _)| !directions.contains(&direction))
+        })
+    }
+
+    /// Returns the first offset which is not in the set once rotated by `rotation` around `rot_axis`, if any
+    pub(crate) fn offset_not_rotating(
+        &self,
+        rotation: ModelRotation,
+        rot_axis: Direction,
+    ) -> Option<GridOffset> {
+        self.offsets
+            .iter()
+            .copied()
+            .find(|offset| self.index_of(offset.rotated(rotation, rot_axis)).is_none())
+    }
+}
+
+/// Returns the index of the node adjacent to `position` through `adjacency`, if any.
+///
+/// Adjacencies `0..directions_count` are the directions of the grid, and the following ones are the offsets of `direction_set` (in index order). A neighbour through an offset is reached step by step on each of its axes, so it follows the looping of the grid.
+#[inline]
+pub(crate) fn adjacent_node_index<C: CoordinateSystem>(
+    grid: &GridDefinition<C>,
+    direction_set: Option<&DirectionSet>,
+    position: &GridPosition,
+    adjacency: usize,
+) -> Option<NodeIndex> {
+    let directions = grid.directions();
+    if let Some(&direction) = directions.get(adjacency) {
+        return grid.get_next_index_in_direction(position, direction);
+    }
+    let offset = direction_set?.offsets[adjacency - directions.len()];
+    let mut index = None;
+    for (direction, count) in offset.steps() {
+        for _ in 0..count {
+            index = Some(match index {
+                None => grid.get_next_index_in_direction(position, direction)?,
+                Some(index) => {
+                    grid.get_next_index_in_direction(&grid.pos_from_index(index), direction)?
+                }
+            });
+        }
+    }
+    index
+}
+
+/// Returns the adjacency opposite to `adjacency` (see [`adjacent_node_index`])
+#[inline]
+pub(crate) fn opposite_adjacency(
+    directions: &[Direction],
+    direction_set: Option<&DirectionSet>,
+    adjacency: usize,
+) -> usize {
+    match (directions.get(adjacency), direction_set) {
+        (Some(direction), _) => direction.opposite() as usize,
+        (None, Some(direction_set)) => {
+            directions.len() + direction_set.opposite(adjacency - directions.len())
+        }
+        (None, None) => adjacency,
+    }
+}
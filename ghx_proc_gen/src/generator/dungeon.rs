@@ -0,0 +1,300 @@
+use std::collections::VecDeque;
+
+use ghx_grid::{coordinate_system::CoordinateSystem, grid::GridData};
+
+use crate::{DungeonGenerationError, GeneratorBuilderError, NodeIndex};
+
+use super::{
+    builder::{GeneratorBuilder, Set},
+    model::{ModelIndex, ModelInstance, ModelVariantIndex, ALL_MODEL_ROTATIONS},
+    rules::Rules,
+    socket::Socket,
+    Generator,
+};
+
+/// Default value of [`DungeonConstraints::with_max_attempts`]
+pub const DEFAULT_DUNGEON_MAX_ATTEMPTS: u32 = 32;
+
+/// Measures of a dungeon generation output, see [`DungeonConstraints::measure`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DungeonStats {
+    /// Number of rooms: groups of adjacent nodes with a room model
+    pub rooms: u32,
+    /// Number of nodes with a door model
+    pub doors: u32,
+    /// Number of nodes with a corridor model
+    pub corridor_nodes: u32,
+    /// `true` if all the walkable nodes (with a room, corridor or door model) are connected
+    pub connected: bool,
+}
+
+/// High-level constraints of a dungeon generation: which models are rooms, corridors and doors, how many rooms are expected, how many corridor nodes are allowed, and whether all the walkable nodes must be connected.
+///
+/// A model synthesis generator only enforces local adjacencies, so those global constraints are assembled in two parts:
+/// - [`DungeonConstraints::configure`] adds what can be given to the generator beforehand (bans and weights) to a [`GeneratorBuilder`].
+/// - [`DungeonConstraints::generate`] runs the generator until an output satisfies the room count, the corridor budget and the connectivity requirement, see [`DungeonConstraints::measure`].
+///
+/// ### Example
+/// ```
+/// use ghx_proc_gen::generator::{builder::GeneratorBuilder, dungeon::DungeonConstraints, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection, RngMode};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (rock, floor, door) = (sockets.create(), sockets.create(), sockets.create());
+/// sockets.add_connections(vec![(rock, vec![rock, floor]), (floor, vec![floor, door])]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(rock)).with_weight(4.);
+/// let room = models.create(SocketsCartesian2D::Mono(floor)).index();
+/// models.create(SocketsCartesian2D::Simple { x_pos: door, x_neg: door, y_pos: rock, y_neg: rock });
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+///
+/// let constraints = DungeonConstraints::new()
+///     .with_room_models(vec![room])
+///     .with_door_sockets(vec![door])
+///     .with_room_count(1, u32::MAX)
+///     .with_connectivity(false);
+/// let builder = GeneratorBuilder::new()
+///     .with_rules(rules)
+///     .with_grid(GridDefinition::new_cartesian_2d(10, 10, false, false))
+///     .with_rng(RngMode::Seeded(42));
+/// let mut generator = constraints.configure(builder).unwrap().build().unwrap();
+/// let (stats, output) = constraints.generate(&mut generator).unwrap();
+/// assert!(stats.rooms >= 1);
+/// assert_eq!(stats, constraints.measure(generator.rules(), &output));
+/// ```
+#[derive(Clone, Debug)]
+pub struct DungeonConstraints {
+    room_models: Vec<ModelIndex>,
+    corridor_models: Vec<ModelIndex>,
+    door_sockets: Vec<Socket>,
+    min_rooms: u32,
+    max_rooms: u32,
+    corridor_budget: Option<u32>,
+    connected: bool,
+    max_attempts: u32,
+}
+
+impl Default for DungeonConstraints {
+    fn default() -> Self {
+        Self {
+            room_models: Vec::new(),
+            corridor_models: Vec::new(),
+            door_sockets: Vec::new(),
+            min_rooms: 0,
+            max_rooms: u32::MAX,
+            corridor_budget: None,
+            connected: true,
+            max_attempts: DEFAULT_DUNGEON_MAX_ATTEMPTS,
+        }
+    }
+}
+
+impl DungeonConstraints {
+    /// Creates [`DungeonConstraints`] without any room, corridor or door model, requiring all the walkable nodes to be connected
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares the original models used as the floors of the rooms. Adjacent nodes with room models belong to the same room.
+    pub fn with_room_models(mut self, room_models: Vec<ModelIndex>) -> Self {
+        self.room_models = room_models;
+        self
+    }
+
+    /// Declares the original models used as corridors
+    pub fn with_corridor_models(mut self, corridor_models: Vec<ModelIndex>) -> Self {
+        self.corridor_models = corridor_models;
+        self
+    }
+
+    /// Declares the sockets of the doors: every original model with one of those sockets (in any direction and rotation) is a door model, see [`DungeonConstraints::door_models`]
+    pub fn with_door_sockets(mut self, door_sockets: Vec<Socket>) -> Self {
+        self.door_sockets = door_sockets;
+        self
+    }
+
+    /// Sets the minimum and maximum (both inclusive) number of rooms of the dungeon. Defaults to any number of rooms.
+    pub fn with_room_count(mut self, min: u32, max: u32) -> Self {
+        self.min_rooms = min;
+        self.max_rooms = max.max(min);
+        self
+    }
+
+    /// Sets the maximum number of corridor nodes of the dungeon. Defaults to unlimited.
+    pub fn with_corridor_budget(mut self, budget: u32) -> Self {
+        self.corridor_budget = Some(budget);
+        self
+    }
+
+    /// Sets whether or not all the walkable nodes (with a room, corridor or door model) must be connected. Defaults to `true`.
+    pub fn with_connectivity(mut self, connected: bool) -> Self {
+        self.connected = connected;
+        self
+    }
+
+    /// Sets how many outputs [`DungeonConstraints::generate`] tries at most before giving up. Defaults to [`DEFAULT_DUNGEON_MAX_ATTEMPTS`].
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Returns the original models of `rules` having one of the door sockets in any direction
+    pub fn door_models<C: CoordinateSystem>(&self, rules: &Rules<C>) -> Vec<ModelIndex> {
+        (0..rules.original_models_count())
+            .filter(|&model_index| {
+                variants(rules, model_index).any(|variant| {
+                    rules.coord_system().directions().iter().any(|&direction| {
+                        rules.sockets_in(variant, direction).iter().any(|socket| {
+                            self.door_sockets
+                                .iter()
+                                .any(|door| door.index() == socket.index())
+                        })
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Adds the constraints which can be given to the generator beforehand to `builder`:
+    /// - The room models are banned everywhere if at most 0 rooms are allowed, and the corridor models if the corridor budget is 0.
+    /// - With a corridor budget, the weights of the corridor models are multiplied by the ratio of the budget to the number of nodes (capped at `1`) on all the nodes, to make outputs within the budget more likely.
+    ///
+    /// The room count and connectivity requirements can only be checked on the outputs, see [`DungeonConstraints::generate`].
+    pub fn configure<C: CoordinateSystem>(
+        &self,
+        builder: GeneratorBuilder<Set, Set, C>,
+    ) -> Result<GeneratorBuilder<Set, Set, C>, GeneratorBuilderError> {
+        let (nodes_count, layers_count) = (builder.grid().total_size(), builder.grid().size_y());
+        let mut banned_models = Vec::new();
+        if self.max_rooms == 0 {
+            banned_models.extend(self.room_models.iter().copied());
+        }
+        if self.corridor_budget == Some(0) {
+            banned_models.extend(self.corridor_models.iter().copied());
+        }
+        let banned_variants: Vec<ModelVariantIndex> = banned_models
+            .iter()
+            .flat_map(|&model_index| variants(builder.rules(), model_index))
+            .collect();
+        let bans: Vec<(u32, ModelVariantIndex)> = (0..layers_count)
+            .flat_map(|y| banned_variants.iter().map(move |&variant| (y, variant)))
+            .collect();
+        let mut builder = builder.with_layer_bans(bans)?;
+
+        if let Some(budget) = self.corridor_budget.filter(|budget| *budget > 0) {
+            let multiplier = (budget as f32 / nodes_count as f32).min(1.);
+            for &model_index in self.corridor_models.iter() {
+                let multipliers =
+                    GridData::new(builder.grid().clone(), vec![multiplier; nodes_count]);
+                builder = builder.with_node_weights(model_index, multipliers)?;
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Measures the rooms, doors, corridor nodes and connectivity of a generation `output` made with `rules`
+    pub fn measure<C: CoordinateSystem>(
+        &self,
+        rules: &Rules<C>,
+        output: &GridData<C, ModelInstance>,
+    ) -> DungeonStats {
+        let door_models = self.door_models(rules);
+        let is_room = |node_index: NodeIndex| {
+            self.room_models
+                .contains(&output.get(node_index).model_index)
+        };
+        let is_walkable = |node_index: NodeIndex| {
+            let model_index = output.get(node_index).model_index;
+            self.room_models.contains(&model_index)
+                || self.corridor_models.contains(&model_index)
+                || door_models.contains(&model_index)
+        };
+        let count = |models: &Vec<ModelIndex>| {
+            output
+                .nodes()
+                .iter()
+                .filter(|instance| models.contains(&instance.model_index))
+                .count() as u32
+        };
+        DungeonStats {
+            rooms: count_components(output, is_room),
+            doors: count(&door_models),
+            corridor_nodes: count(&self.corridor_models),
+            connected: count_components(output, is_walkable) <= 1,
+        }
+    }
+
+    /// Returns `true` if `stats` satisfy the room count, the corridor budget and the connectivity requirement
+    pub fn is_satisfied(&self, stats: &DungeonStats) -> bool {
+        stats.rooms >= self.min_rooms
+            && stats.rooms <= self.max_rooms
+            && self
+                .corridor_budget
+                .map_or(true, |budget| stats.corridor_nodes <= budget)
+            && (stats.connected || !self.connected)
+    }
+
+    /// Runs `generator` (configured with [`DungeonConstraints::configure`]) until one of its outputs satisfies these constraints, trying at most `max_attempts` outputs (each with its own seed).
+    ///
+    /// Returns the [`DungeonStats`] and the output if successful. Returns a [`DungeonGenerationError`] if a generation fails or if no output satisfied the constraints.
+    pub fn generate<C: CoordinateSystem>(
+        &self,
+        generator: &mut Generator<C>,
+    ) -> Result<(DungeonStats, GridData<C, ModelInstance>), DungeonGenerationError> {
+        let mut last_stats = DungeonStats::default();
+        for _ in 0..self.max_attempts {
+            let (_gen_info, output) = generator
+                .generate_grid()
+                .map_err(DungeonGenerationError::GenerationFailed)?;
+            last_stats = self.measure(generator.rules(), &output);
+            if self.is_satisfied(&last_stats) {
+                return Ok((last_stats, output));
+            }
+        }
+        Err(DungeonGenerationError::Unsatisfied(
+            self.max_attempts,
+            last_stats,
+        ))
+    }
+}
+
+/// Returns the variants of the original model `model_index` in `rules`
+fn variants<C: CoordinateSystem>(
+    rules: &Rules<C>,
+    model_index: ModelIndex,
+) -> impl Iterator<Item = ModelVariantIndex> + '_ {
+    ALL_MODEL_ROTATIONS
+        .iter()
+        .filter_map(move |&rotation| rules.variant_index(model_index, rotation))
+}
+
+/// Counts the groups of adjacent nodes of `output` matching `included`
+fn count_components<C: CoordinateSystem>(
+    output: &GridData<C, ModelInstance>,
+    included: impl Fn(NodeIndex) -> bool,
+) -> u32 {
+    let grid = output.grid();
+    let mut visited = vec![false; grid.total_size()];
+    let mut queue = VecDeque::new();
+    let mut components = 0;
+    for start in 0..grid.total_size() {
+        if visited[start] || !included(start) {
+            continue;
+        }
+        components += 1;
+        visited[start] = true;
+        queue.push_back(start);
+        while let Some(node_index) = queue.pop_front() {
+            let position = grid.pos_from_index(node_index);
+            for &direction in grid.directions() {
+                if let Some(neighbour) = grid.get_next_index_in_direction(&position, direction) {
+                    if !visited[neighbour] && included(neighbour) {
+                        visited[neighbour] = true;
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+        }
+    }
+    components
+}
@@ -0,0 +1,103 @@
+use std::borrow::Cow;
+
+use bitvec::{bitvec, vec::BitVec};
+use ghx_grid::{coordinate_system::Cartesian3D, direction::Direction, grid::GridDefinition};
+
+use super::{model::ModelVariantIndex, rules::Rules};
+
+/// Simple support rules for 3d grids with Y as the vertical axis: a "heavy" tagged model must rest on a model which is not "void" (tagged with the void tag, or flagged with [`super::model::ModelTemplate::with_void`]).
+///
+/// Used to create initial layer bans (see [`super::builder::GeneratorBuilder::with_layer_bans`]) removing, across the whole grid and before the generation starts, the heavy models which could never be supported on their layer: the ones whose allowed neighbours below (given by the rules) are all void, or all heavy models which cannot be supported on the layer below. This reduces the contradictions in tall 3d grids. The support rules are not enforced during the generation itself.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::generator::{builder::GeneratorBuilder, gravity::GravityPruning, rules::RulesBuilder, socket::{SocketsCartesian3D, SocketCollection}, model::ModelCollection};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (air, block) = (sockets.create(), sockets.create());
+/// sockets.add_connections(vec![(air, vec![air, block]), (block, vec![block])]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian3D::Mono(air)).with_tag("void");
+/// models.create(SocketsCartesian3D::Mono(block)).with_tag("heavy");
+/// let rules = RulesBuilder::new_cartesian_3d(models, sockets).build().unwrap();
+/// let grid = GridDefinition::new_cartesian_3d(8, 16, 8, false, false, false);
+///
+/// let bans = GravityPruning::new("heavy", "void").bans(&grid, &rules);
+/// let generator = GeneratorBuilder::new()
+///     .with_rules(rules)
+///     .with_grid(grid)
+///     .with_layer_bans(bans)
+///     .unwrap()
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct GravityPruning {
+    heavy_tag: Cow<'static, str>,
+    void_tag: Cow<'static, str>,
+    grounded: bool,
+}
+
+impl GravityPruning {
+    /// Creates support rules for the models tagged with `heavy_tag`, which cannot rest on the models tagged with `void_tag`. The bottom layer of the grid rests on the ground: any heavy model is supported there.
+    pub fn new(
+        heavy_tag: impl Into<Cow<'static, str>>,
+        void_tag: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Self {
+            heavy_tag: heavy_tag.into(),
+            void_tag: void_tag.into(),
+            grounded: true,
+        }
+    }
+
+    /// The bottom layer of the grid does not rest on the ground: heavy models are not supported there, as if the grid was floating above the void.
+    pub fn without_ground(mut self) -> Self {
+        self.grounded = false;
+        self
+    }
+
+    /// Returns initial layer bans for a generation on `grid` with `rules`, to be given to [`super::builder::GeneratorBuilder::with_layer_bans`]: each heavy model is banned from the layers on which it can never be supported.
+    pub fn bans(
+        &self,
+        grid: &GridDefinition<Cartesian3D>,
+        rules: &Rules<Cartesian3D>,
+    ) -> Vec<(u32, ModelVariantIndex)> {
+        let models_count = rules.models_count();
+        let original_model = |model: ModelVariantIndex| rules.model(model).model_index;
+        let heavy_models: Vec<ModelVariantIndex> = (0..models_count)
+            .filter(|&model| rules.has_tag(original_model(model), &self.heavy_tag))
+            .collect();
+        let supporting: BitVec<usize> = (0..models_count)
+            .map(|model| {
+                let model_index = original_model(model);
+                !rules.has_tag(model_index, &self.void_tag) && !rules.is_void(model_index)
+            })
+            .collect();
+
+        let mut bans = Vec::new();
+        // Models possible on the previous layer, all of them below the bottom layer when it rests on the ground
+        let mut possible_below = bitvec![self.grounded as usize; models_count];
+        let mut ground = self.grounded;
+        for y in 0..grid.size_y() {
+            let mut possible = bitvec![1; models_count];
+            for &heavy in heavy_models.iter() {
+                let supported = ground
+                    || rules
+                        .allowed_mask(heavy, Direction::YBackward)
+                        .iter()
+                        .any(|below| possible_below[below] && supporting[below]);
+                if supported {
+                    continue;
+                }
+                possible.set(heavy, false);
+                bans.push((y, heavy));
+            }
+            possible_below = possible;
+            ground = false;
+        }
+        bans
+    }
+}
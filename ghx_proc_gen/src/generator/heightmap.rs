@@ -0,0 +1,82 @@
+use ghx_grid::{
+    coordinate_system::{Cartesian2D, Cartesian3D},
+    grid::{GridData, GridDefinition},
+};
+
+use crate::NodeIndex;
+
+use super::{model::ModelVariantIndex, rules::Rules};
+
+/// A heightmap giving a height (in nodes) for each `(x, z)` column of a 3d grid, with Y as the vertical axis.
+///
+/// Used to create initial bans (see [`super::builder::GeneratorBuilder::with_initial_bans`]) limiting the Y layers in which "solid" tagged models can be generated, to control the macro shape of a generated terrain.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::generator::heightmap::Heightmap;
+///
+/// // A slope going up along the x axis
+/// let heightmap = Heightmap::from_fn(|x, _z| x / 2);
+/// ```
+pub struct Heightmap<F: Fn(u32, u32) -> u32> {
+    height: F,
+}
+
+impl<F: Fn(u32, u32) -> u32> Heightmap<F> {
+    /// Creates a [`Heightmap`] from a closure returning the height of the `(x, z)` column
+    pub fn from_fn(height: F) -> Self {
+        Self { height }
+    }
+
+    /// Returns the height of the `(x, z)` column
+    pub fn height(&self, x: u32, z: u32) -> u32 {
+        (self.height)(x, z)
+    }
+
+    /// Returns initial bans for a generation on `grid` with `rules`:
+    /// - models tagged with `solid_tag` are banned from all the nodes at or above the height of their column.
+    /// - if `fill_below` is `true`, all the other models are banned from the nodes below the height of their column, which are then only made of solid models.
+    pub fn bans(
+        &self,
+        grid: &GridDefinition<Cartesian3D>,
+        rules: &Rules<Cartesian3D>,
+        solid_tag: &str,
+        fill_below: bool,
+    ) -> Vec<(NodeIndex, ModelVariantIndex)> {
+        let (solid_models, other_models): (Vec<ModelVariantIndex>, Vec<ModelVariantIndex>) = (0
+            ..rules.models_count())
+            .partition(|&model| rules.has_tag(rules.model(model).model_index, solid_tag));
+
+        let (size_x, size_y, size_z) = grid.size();
+        let mut bans = Vec::new();
+        for z in 0..size_z {
+            for x in 0..size_x {
+                let height = self.height(x, z).min(size_y);
+                for y in 0..size_y {
+                    let banned_models = match y >= height {
+                        true => &solid_models,
+                        false if fill_below => &other_models,
+                        false => continue,
+                    };
+                    let node_index = grid.index_from_coords(x, y, z);
+                    bans.extend(banned_models.iter().map(|&model| (node_index, model)));
+                }
+            }
+        }
+        bans
+    }
+}
+
+impl<'a> Heightmap<Box<dyn Fn(u32, u32) -> u32 + 'a>> {
+    /// Creates a [`Heightmap`] from a 2d [`GridData`], where the node `(x, y)` of `data` gives the height of the `(x, z = y)` column of the 3d grid.
+    ///
+    /// Columns outside of `data` have a height of `0`.
+    pub fn from_grid_data(data: &'a GridData<Cartesian2D, u32>) -> Self {
+        let (size_x, size_y, _) = data.grid().size();
+        Self::from_fn(Box::new(move |x, z| match x < size_x && z < size_y {
+            true => data.nodes()[data.grid().index_from_coords(x, z, 0)],
+            false => 0,
+        }))
+    }
+}
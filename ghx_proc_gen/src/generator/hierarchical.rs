@@ -0,0 +1,200 @@
+use ghx_grid::{coordinate_system::CoordinateSystem, grid::GridData};
+
+use crate::{GeneratorBuilderError, HierarchicalGenerationError, NodeIndex};
+
+use super::{
+    builder::{GeneratorBuilder, Set},
+    model::{ModelIndex, ModelInstance, ModelVariantIndex},
+    rules::Rules,
+    Generator,
+};
+
+/// Maps the models of some coarse [`Rules`] to the fine models allowed in their upscaled blocks, see [`HierarchicalGenerator`].
+///
+/// Coarse models without a mapping do not constrain their block: the fine generation is free to generate any model on it.
+#[derive(Clone, Debug)]
+pub struct CoarseMapping {
+    /// `mapping[coarse_model_index]` holds the optional fine models allowed in the blocks of this coarse model.
+    mapping: Vec<Option<Vec<ModelIndex>>>,
+}
+
+impl CoarseMapping {
+    /// Creates a mapping from a closure returning the fine models allowed for each coarse model
+    pub fn from_fn<C: CoordinateSystem, F: Fn(ModelIndex) -> Option<Vec<ModelIndex>>>(
+        coarse_rules: &Rules<C>,
+        f: F,
+    ) -> Self {
+        Self {
+            mapping: (0..coarse_rules.original_models_count()).map(f).collect(),
+        }
+    }
+
+    /// Maps each coarse model to all the fine models sharing at least one of the given `tags` with it
+    pub fn by_tags<C: CoordinateSystem>(
+        coarse_rules: &Rules<C>,
+        fine_rules: &Rules<C>,
+        tags: &[&str],
+    ) -> Self {
+        Self::from_fn(coarse_rules, |coarse_model| {
+            let mut fine_models: Vec<ModelIndex> = tags
+                .iter()
+                .filter(|tag| coarse_rules.has_tag(coarse_model, tag))
+                .flat_map(|tag| fine_rules.models_with_tag(tag))
+                .collect();
+            fine_models.sort_unstable();
+            fine_models.dedup();
+            match fine_models.is_empty() {
+                true => None,
+                false => Some(fine_models),
+            }
+        })
+    }
+
+    /// Returns the fine models allowed in the blocks of `coarse_model`, if any
+    pub fn get(&self, coarse_model: ModelIndex) -> Option<&[ModelIndex]> {
+        self.mapping.get(coarse_model)?.as_deref()
+    }
+}
+
+/// Coarse-to-fine generation: generates a grid at 1/Nth of the resolution with some coarse [`Rules`] (biomes, districts, ...), then upscales each coarse node into a block of N nodes per axis, constrained to the fine models mapped to its coarse model, and runs a fine generator on the full resolution grid.
+///
+/// The size of the fine grid must be N times the size of the coarse grid on each axis (axes of size 1 on both grids are kept). The constraints are registered as initial bans of the fine generator: they are applied again when it reinitializes.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::generator::{builder::GeneratorBuilder, hierarchical::{CoarseMapping, HierarchicalGenerator}, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (land, sea) = (sockets.create(), sockets.create());
+/// sockets.add_connections(vec![(land, vec![land, sea]), (sea, vec![sea])]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(land)).with_tag("land");
+/// models.create(SocketsCartesian2D::Mono(sea)).with_tag("sea");
+/// let coarse_rules = RulesBuilder::new_cartesian_2d(models.clone(), sockets.clone()).build().unwrap();
+/// let fine_rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+///
+/// let mapping = CoarseMapping::by_tags(&coarse_rules, &fine_rules, &["land", "sea"]);
+/// let coarse_generator = GeneratorBuilder::new()
+///     .with_rules(coarse_rules)
+///     .with_grid(GridDefinition::new_cartesian_2d(4, 4, false, false))
+///     .build()
+///     .unwrap();
+/// let fine_builder = GeneratorBuilder::new()
+///     .with_rules(fine_rules)
+///     .with_grid(GridDefinition::new_cartesian_2d(16, 16, false, false));
+///
+/// let mut generator = HierarchicalGenerator::new(coarse_generator, fine_builder, mapping, 4).unwrap();
+/// let (_fine_generator, output) = generator.generate().unwrap();
+/// assert_eq!(output.nodes().len(), 16 * 16);
+/// ```
+pub struct HierarchicalGenerator<C: CoordinateSystem> {
+    coarse_generator: Generator<C>,
+    fine_builder: GeneratorBuilder<Set, Set, C>,
+    mapping: CoarseMapping,
+    factor: u32,
+}
+
+impl<C: CoordinateSystem> HierarchicalGenerator<C> {
+    /// Creates a [`HierarchicalGenerator`] upscaling the output of `coarse_generator` by `factor` on each axis, and running a fine generator built from `fine_builder`.
+    ///
+    /// Returns a [`GeneratorBuilderError::InvalidGridSize`] if the size of the fine grid is not the size of the coarse grid multiplied by `factor`.
+    pub fn new(
+        coarse_generator: Generator<C>,
+        fine_builder: GeneratorBuilder<Set, Set, C>,
+        mapping: CoarseMapping,
+        factor: u32,
+    ) -> Result<Self, GeneratorBuilderError> {
+        let factor = factor.max(1);
+        let coarse_size = coarse_generator.grid().size();
+        let fine_size = fine_builder.grid().size();
+        let upscaled = |coarse: u32, fine: u32| match (coarse, fine) {
+            (1, 1) => 1,
+            _ => coarse * factor,
+        };
+        let expected_size = (
+            upscaled(coarse_size.0, fine_size.0),
+            upscaled(coarse_size.1, fine_size.1),
+            upscaled(coarse_size.2, fine_size.2),
+        );
+        if fine_size != expected_size {
+            return Err(GeneratorBuilderError::InvalidGridSize(
+                fine_size,
+                expected_size,
+            ));
+        }
+        Ok(Self {
+            coarse_generator,
+            fine_builder,
+            mapping,
+            factor,
+        })
+    }
+
+    /// Returns the coarse [`Generator`]
+    pub fn coarse_generator(&self) -> &Generator<C> {
+        &self.coarse_generator
+    }
+
+    /// Returns the coarse [`Generator`] as mutable, to reseed it for example
+    pub fn coarse_generator_mut(&mut self) -> &mut Generator<C> {
+        &mut self.coarse_generator
+    }
+
+    /// Returns the upscaling factor of this [`HierarchicalGenerator`]
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    /// Converts a coarse output into constraints for the fine generation: on each node of the block of a coarse node with a mapped coarse model, all the fine models which are not mapped to it are banned.
+    ///
+    /// The result can be given to [`GeneratorBuilder::with_initial_bans`].
+    pub fn upscale(
+        &self,
+        coarse_output: &GridData<C, ModelInstance>,
+    ) -> Vec<(NodeIndex, ModelVariantIndex)> {
+        let fine_grid = self.fine_builder.grid();
+        let fine_rules = self.fine_builder.rules();
+        let coarse_grid = coarse_output.grid();
+        let mut bans = Vec::new();
+        for fine_index in 0..fine_grid.total_size() {
+            let pos = fine_grid.pos_from_index(fine_index);
+            let coarse_index = coarse_grid.index_from_coords(
+                pos.x / self.factor,
+                pos.y / self.factor,
+                pos.z / self.factor,
+            );
+            let Some(allowed) = self
+                .mapping
+                .get(coarse_output.get(coarse_index).model_index)
+            else {
+                continue;
+            };
+            for variant_index in 0..fine_rules.models_count() {
+                if !allowed.contains(&fine_rules.model(variant_index).model_index) {
+                    bans.push((fine_index, variant_index));
+                }
+            }
+        }
+        bans
+    }
+
+    /// Runs the coarse generation, upscales its output into constraints (see [`HierarchicalGenerator::upscale`]), then builds a fine generator with those constraints and runs it.
+    ///
+    /// Returns the fine [`Generator`] and its output if successful.
+    pub fn generate(
+        &mut self,
+    ) -> Result<(Generator<C>, GridData<C, ModelInstance>), HierarchicalGenerationError> {
+        let (_gen_info, coarse_output) = self
+            .coarse_generator
+            .generate_grid()
+            .map_err(HierarchicalGenerationError::CoarseFailed)?;
+        let bans = self.upscale(&coarse_output);
+        let mut fine_generator = self.fine_builder.clone().with_initial_bans(bans)?.build()?;
+        let (_gen_info, fine_output) = fine_generator
+            .generate_grid()
+            .map_err(HierarchicalGenerationError::FineFailed)?;
+        Ok((fine_generator, fine_output))
+    }
+}
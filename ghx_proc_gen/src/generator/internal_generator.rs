@@ -1,29 +1,40 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, PoisonError},
+};
 
-use bitvec::{bitvec, order::LocalBits, slice::IterOnes, vec::BitVec};
+use bitvec::vec::BitVec;
 use ghx_grid::{
     coordinate_system::CoordinateSystem,
     grid::{GridData, GridDefinition},
 };
-use ndarray::{Array, Ix3};
 use rand::{
     distributions::{Distribution, WeightedIndex},
     rngs::StdRng,
-    Rng, SeedableRng,
+    Rng, RngCore, SeedableRng,
 };
 
 #[cfg(feature = "debug-traces")]
-use tracing::{debug, info, trace};
+use tracing::{debug, debug_span, field, info, info_span, trace};
+
+#[cfg(feature = "step-stats")]
+use std::time::Instant;
 
+#[cfg(feature = "step-stats")]
+use super::StepStats;
 use crate::{GeneratorError, NodeIndex, NodeSetError};
 
 use super::{
-    model::{ModelInstance, ModelVariantIndex},
-    node_heuristic::{InternalNodeSelectionHeuristic, NodeSelectionHeuristic},
-    observer::GenerationUpdate,
+    callbacks::Callbacks,
+    candidates::Candidates,
+    journal::{GeneratorSnapshot, Journal, JournalEntry, NodeSnapshot},
+    model::{CountLimits, ModelIndex, ModelInstance, ModelVariantIndex},
+    node_heuristic::{HeuristicPhase, InternalNodeSelectionHeuristic, NodeSelectionHeuristic},
+    node_weights::NodeWeights,
+    observer::{GenerationUpdate, ObserverSender},
     rules::Rules,
-    Collector, GenInfo, GeneratedNode, GenerationStatus, ModelSelectionHeuristic, NodeSetStatus,
-    RngMode,
+    CandidateStorage, CloneableRng, Collector, ExternalRng, GenInfo, GeneratedNode,
+    GenerationStatus, ModelSelectionHeuristic, NodeSetStatus, RngMode,
 };
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -37,6 +48,193 @@ pub(crate) enum InternalGeneratorStatus {
     Failed(GeneratorError),
 }
 
+/// Models banned from some nodes at each initialization, see [`super::builder::GeneratorBuilder::with_initial_bans`] and [`super::builder::GeneratorBuilder::with_layer_bans`]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InitialBans {
+    /// Models banned from a single node
+    pub(crate) nodes: Vec<(NodeIndex, ModelVariantIndex)>,
+    /// Models banned from all the nodes of a layer (the nodes with the same `y` coordinate)
+    pub(crate) layers: Vec<(u32, ModelVariantIndex)>,
+}
+
+/// Random source of a generator, either the default [`StdRng`] or an external RNG given by the user.
+///
+/// The external RNG is only required to be `Send`: it is stored in a [`Mutex`] so that the generator stays `Sync`. The generator always has a mutable access to it, so the lock is never taken.
+pub(crate) enum GeneratorRng {
+    Std(StdRng),
+    External(ExternalRng, Mutex<Box<dyn CloneableRng>>),
+}
+
+impl Clone for GeneratorRng {
+    fn clone(&self) -> Self {
+        match self {
+            GeneratorRng::Std(rng) => GeneratorRng::Std(rng.clone()),
+            GeneratorRng::External(external_rng, rng) => {
+                let rng = rng.lock().unwrap_or_else(PoisonError::into_inner);
+                GeneratorRng::External(external_rng.clone(), Mutex::new(rng.clone_boxed()))
+            }
+        }
+    }
+}
+
+impl GeneratorRng {
+    fn new(rng_mode: &RngMode, seed: u64) -> Self {
+        match rng_mode {
+            RngMode::External(external_rng) => {
+                GeneratorRng::External(external_rng.clone(), Mutex::new(external_rng.create(seed)))
+            }
+            _ => GeneratorRng::Std(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        match self {
+            GeneratorRng::Std(rng) => *rng = StdRng::seed_from_u64(seed),
+            GeneratorRng::External(external_rng, rng) => {
+                *rng = Mutex::new(external_rng.create(seed))
+            }
+        }
+    }
+
+    fn external(rng: &mut Mutex<Box<dyn CloneableRng>>) -> &mut dyn CloneableRng {
+        rng.get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+    }
+}
+
+impl RngCore for GeneratorRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            GeneratorRng::Std(rng) => rng.next_u32(),
+            GeneratorRng::External(_, rng) => Self::external(rng).next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            GeneratorRng::Std(rng) => rng.next_u64(),
+            GeneratorRng::External(_, rng) => Self::external(rng).next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            GeneratorRng::Std(rng) => rng.fill_bytes(dest),
+            GeneratorRng::External(_, rng) => Self::external(rng).fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            GeneratorRng::Std(rng) => rng.try_fill_bytes(dest),
+            GeneratorRng::External(_, rng) => Self::external(rng).try_fill_bytes(dest),
+        }
+    }
+}
+
+/// Number of generated nodes of each original model in each column and layer of the grid, used to enforce the [`CountLimits`] of the rules without scanning the grid.
+///
+/// Kept in sync with the nodes state: a node is counted as soon as a single model is left on it (selected, forced by the propagation or restored by an undo/redo), and uncounted when it has more or no models left.
+#[derive(Default)]
+struct GeneratedModelCounts {
+    /// Number of columns of the grid (`size_x * size_z`)
+    columns: usize,
+    /// Number of layers of the grid (`size_y`)
+    layers: usize,
+    /// `per_column[model_index * columns + column]`
+    per_column: Vec<u32>,
+    /// `per_layer[model_index * layers + layer]`
+    per_layer: Vec<u32>,
+    /// Original model counted on each node, if any
+    counted: Vec<Option<ModelIndex>>,
+}
+
+impl GeneratedModelCounts {
+    /// Returns empty counts if the `rules` do not have any [`CountLimits`], in which case the counts are never read nor updated.
+    fn new<C: CoordinateSystem>(rules: &Rules<C>, grid: &GridDefinition<C>) -> Self {
+        if !rules.has_count_limits() {
+            return Self::default();
+        }
+        let columns = (grid.size_x() * grid.size_z()) as usize;
+        let layers = grid.size_y() as usize;
+        Self {
+            columns,
+            layers,
+            per_column: vec![0; rules.original_models_count() * columns],
+            per_layer: vec![0; rules.original_models_count() * layers],
+            counted: vec![None; grid.total_size()],
+        }
+    }
+
+    /// Returns `true` if the counts are maintained
+    #[inline]
+    fn is_enabled(&self) -> bool {
+        !self.counted.is_empty()
+    }
+
+    fn reset(&mut self) {
+        self.per_column.fill(0);
+        self.per_layer.fill(0);
+        self.counted.fill(None);
+    }
+
+    #[inline]
+    fn slots<C: CoordinateSystem>(
+        &self,
+        grid: &GridDefinition<C>,
+        node_index: NodeIndex,
+        model_index: ModelIndex,
+    ) -> (usize, usize) {
+        let position = grid.pos_from_index(node_index);
+        let column = (position.x + position.z * grid.size_x()) as usize;
+        (
+            model_index * self.columns + column,
+            model_index * self.layers + position.y as usize,
+        )
+    }
+
+    /// Counts `node_index` as generated with the original model `model_index`, replacing its previous count if any
+    fn count<C: CoordinateSystem>(
+        &mut self,
+        grid: &GridDefinition<C>,
+        node_index: NodeIndex,
+        model_index: ModelIndex,
+    ) {
+        self.uncount(grid, node_index);
+        let (column_slot, layer_slot) = self.slots(grid, node_index, model_index);
+        self.per_column[column_slot] += 1;
+        self.per_layer[layer_slot] += 1;
+        self.counted[node_index] = Some(model_index);
+    }
+
+    /// Removes the count of `node_index`, if it was counted
+    fn uncount<C: CoordinateSystem>(&mut self, grid: &GridDefinition<C>, node_index: NodeIndex) {
+        if let Some(model_index) = self.counted[node_index].take() {
+            let (column_slot, layer_slot) = self.slots(grid, node_index, model_index);
+            self.per_column[column_slot] -= 1;
+            self.per_layer[layer_slot] -= 1;
+        }
+    }
+
+    /// Returns `true` if the column or the layer of `node_index` holds as many nodes generated with the original model `model_index` as allowed by `limits`
+    fn limit_reached<C: CoordinateSystem>(
+        &self,
+        grid: &GridDefinition<C>,
+        node_index: NodeIndex,
+        model_index: ModelIndex,
+        limits: CountLimits,
+    ) -> bool {
+        let (column_slot, layer_slot) = self.slots(grid, node_index, model_index);
+        limits
+            .per_column
+            .is_some_and(|max| self.per_column[column_slot] >= max)
+            || limits
+                .per_layer
+                .is_some_and(|max| self.per_layer[layer_slot] >= max)
+    }
+}
+
 struct PropagationEntry {
     node_index: NodeIndex,
     model_index: ModelVariantIndex,
@@ -46,26 +244,59 @@ pub(crate) struct InternalGenerator<C: CoordinateSystem> {
     // === Read-only configuration ===
     pub(crate) grid: GridDefinition<C>,
     pub(crate) rules: Arc<Rules<C>>,
+    /// Number of nodes to generate (nodes not masked out)
+    active_nodes_count: usize,
+    /// Nodes from which the generation grows with [`NodeSelectionHeuristic::GrowFromAnchors`]
+    anchors: Vec<NodeIndex>,
+    /// Distance (in grid steps) from each node to the closest anchor
+    anchor_distances: Arc<Vec<u32>>,
+    /// Per-node multipliers of the models weights
+    node_weights: Arc<NodeWeights>,
 
     // === Generation state ===
     pub(crate) status: InternalGeneratorStatus,
     pub(crate) nodes_left_to_generate: usize,
     /// Observers signaled with updates of the nodes.
-    pub(crate) observers: Vec<crossbeam_channel::Sender<GenerationUpdate>>,
+    pub(crate) observers: Vec<ObserverSender>,
+    /// Callbacks called on the phase transitions of the generation
+    pub(crate) callbacks: Callbacks,
+    /// Models banned from some nodes at each initialization
+    initial_bans: InitialBans,
     pub(crate) seed: u64,
-    rng: StdRng,
-    /// `nodes[node_index * self.rules.models_count() + model_index]` is true (1) if model with index `model_index` is still allowed on node with index `node_index`
-    nodes: BitVec<usize>,
+    rng: GeneratorRng,
+    /// Models still allowed on each node, and their supports counts
+    candidates: Candidates<C>,
     /// Stores how many models are still possible for a given node
     possible_models_counts: Vec<usize>,
+    /// Generated nodes of each model in each column and layer, for the count limits of the models
+    generated_counts: GeneratedModelCounts,
     node_selection_heuristic: InternalNodeSelectionHeuristic,
     model_selection_heuristic: ModelSelectionHeuristic,
+    /// Heuristics used before the first phase of the heuristic schedule
+    base_heuristics: (NodeSelectionHeuristic, ModelSelectionHeuristic),
+    /// Ordered phases switching the heuristics during a generation
+    pub(crate) heuristic_schedule: Vec<HeuristicPhase>,
+    /// Index of the current phase of the heuristic schedule, if any
+    pub(crate) current_phase: Option<usize>,
+    /// Number of contradictions since the last successful generation
+    contradictions_count: u32,
+    /// Magnitude of the noise breaking the ties in the node selection
+    pub(crate) selection_noise: f32,
 
     // === Constraint satisfaction algorithm data ===
     /// Stack of bans to propagate
     propagation_stack: Vec<PropagationEntry>,
-    /// The value at `support_count[node_index][model_index][direction]` represents the number of supports of a `model_index` at `node_index` from `direction`
-    supports_count: Array<usize, Ix3>,
+
+    // === Undo/redo ===
+    journal: Journal,
+
+    // === Step statistics ===
+    /// Statistics of the step being executed
+    #[cfg(feature = "step-stats")]
+    step_stats: StepStats,
+    /// Statistics of the last executed step
+    #[cfg(feature = "step-stats")]
+    pub(crate) last_step_stats: Option<StepStats>,
 }
 
 impl<C: CoordinateSystem> InternalGenerator<C> {
@@ -74,43 +305,72 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         grid: GridDefinition<C>,
         node_selection_heuristic: NodeSelectionHeuristic,
         model_selection_heuristic: ModelSelectionHeuristic,
+        heuristic_schedule: Vec<HeuristicPhase>,
         rng_mode: RngMode,
-        observers: Vec<crossbeam_channel::Sender<GenerationUpdate>>,
+        observers: Vec<ObserverSender>,
+        initial_bans: InitialBans,
+        candidate_storage: CandidateStorage,
+        selection_noise: f32,
+        active_nodes: Option<Arc<BitVec<usize>>>,
+        anchors: Vec<NodeIndex>,
+        node_weights: Arc<NodeWeights>,
     ) -> Self {
-        let models_count = rules.models_count();
-        let nodes_count = grid.total_size();
-        let direction_count = grid.directions().len();
-
-        let seed = match rng_mode {
-            RngMode::Seeded(seed) => seed,
-            RngMode::RandomSeed => rand::thread_rng().gen::<u64>(),
+        let active_nodes_count = match &active_nodes {
+            Some(active_nodes) => active_nodes.count_ones(),
+            None => grid.total_size(),
         };
+        let candidates = Candidates::new(&grid, &rules, candidate_storage, active_nodes);
+        let possible_models_counts = initial_possible_models_counts(&candidates, &rules, &grid);
+        let generated_counts = GeneratedModelCounts::new(&rules, &grid);
+        let anchor_distances = Arc::new(anchor_distances(&candidates, &grid, &anchors));
+
+        let seed = rng_mode.seed();
 
+        let base_heuristics = (node_selection_heuristic, model_selection_heuristic);
         let node_selection_heuristic = InternalNodeSelectionHeuristic::from_external(
             node_selection_heuristic,
             &rules,
-            grid.total_size(),
+            &grid,
+            &anchor_distances,
         );
 
         Self {
+            candidates,
             grid,
             rules,
+            active_nodes_count,
+            anchors,
+            anchor_distances,
+            node_weights,
 
             node_selection_heuristic,
             model_selection_heuristic,
+            base_heuristics,
+            heuristic_schedule,
+            current_phase: None,
+            contradictions_count: 0,
+            selection_noise,
 
-            rng: StdRng::seed_from_u64(seed),
+            rng: GeneratorRng::new(&rng_mode, seed),
             seed,
 
             status: InternalGeneratorStatus::Ongoing,
-            nodes: bitvec![1; nodes_count * models_count],
-            nodes_left_to_generate: nodes_count,
-            possible_models_counts: vec![models_count; nodes_count],
+            nodes_left_to_generate: active_nodes_count,
+            possible_models_counts,
+            generated_counts,
 
             observers,
+            callbacks: Callbacks::default(),
+            initial_bans,
 
             propagation_stack: Vec::new(),
-            supports_count: Array::zeros((nodes_count, models_count, direction_count)),
+
+            journal: Journal::default(),
+
+            #[cfg(feature = "step-stats")]
+            step_stats: StepStats::default(),
+            #[cfg(feature = "step-stats")]
+            last_step_stats: None,
         }
     }
 }
@@ -118,15 +378,12 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
 impl<C: CoordinateSystem> InternalGenerator<C> {
     #[inline]
     fn is_model_possible(&self, node: NodeIndex, model: ModelVariantIndex) -> bool {
-        self.nodes[node * self.rules.models_count() + model] == true
+        self.candidates.is_possible(node, model)
     }
 
     #[inline]
     fn get_model_index(&self, node_index: NodeIndex) -> ModelVariantIndex {
-        self.nodes[node_index * self.rules.models_count()
-            ..node_index * self.rules.models_count() + self.rules.models_count()]
-            .first_one()
-            .unwrap_or(0)
+        self.candidates.first_possible(node_index)
     }
 
     #[inline]
@@ -134,18 +391,85 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         node_index < self.possible_models_counts.len()
     }
 
+    #[inline]
+    pub(crate) fn is_active_node(&self, node_index: NodeIndex) -> bool {
+        self.candidates.is_active(node_index)
+    }
+
     pub(crate) fn possible_model_indexes(
         &self,
         node_index: NodeIndex,
-    ) -> IterOnes<'_, ModelVariantIndex, LocalBits> {
-        self.nodes[node_index * self.rules.models_count()
-            ..node_index * self.rules.models_count() + self.rules.models_count()]
-            .iter_ones()
+    ) -> impl Iterator<Item = ModelVariantIndex> + '_ {
+        self.candidates.possible_models(node_index)
+    }
+
+    /// Returns the number of materialized candidates pages and the total number of pages
+    pub(crate) fn candidates_pages_usage(&self) -> (usize, usize) {
+        self.candidates.pages_usage()
+    }
+
+    pub(crate) fn node_selection_heuristic(&self) -> NodeSelectionHeuristic {
+        self.node_selection_heuristic.to_external()
+    }
+
+    pub(crate) fn set_node_selection_heuristic(&mut self, heuristic: NodeSelectionHeuristic) {
+        self.node_selection_heuristic = InternalNodeSelectionHeuristic::from_external(
+            heuristic,
+            &self.rules,
+            &self.grid,
+            &self.anchor_distances,
+        );
+        // Journaled heuristic data would not match the new heuristic
+        self.journal.clear();
+        // The heuristic may track some data about the bans. Replay the bans that already occurred in the current generation.
+        for node_index in 0..self.grid.total_size() {
+            for model_index in 0..self.rules.models_count() {
+                if !self.is_model_possible(node_index, model_index) {
+                    self.node_selection_heuristic.handle_ban(
+                        node_index,
+                        model_index,
+                        self.rules.weight_unchecked(model_index),
+                    );
+                }
+            }
+        }
+    }
+
+    pub(crate) fn set_base_node_selection_heuristic(&mut self, heuristic: NodeSelectionHeuristic) {
+        self.base_heuristics.0 = heuristic;
+        self.set_node_selection_heuristic(heuristic);
+    }
+
+    /// Switches to the heuristics of the last phase of the heuristic schedule whose condition is met (or to the base heuristics if there is none), if it is not the current phase.
+    fn update_heuristic_phase(&mut self) {
+        let generated_ratio = match self.active_nodes_count {
+            0 => 1.,
+            total => (total - self.nodes_left_to_generate) as f32 / total as f32,
+        };
+        let phase = self
+            .heuristic_schedule
+            .iter()
+            .rposition(|phase| phase.is_met(generated_ratio, self.contradictions_count));
+        if phase == self.current_phase {
+            return;
+        }
+        self.current_phase = phase;
+        let (node_heuristic, model_heuristic) = match phase {
+            Some(index) => {
+                let phase = &self.heuristic_schedule[index];
+                (phase.node_heuristic, phase.model_heuristic)
+            }
+            None => self.base_heuristics,
+        };
+        self.model_selection_heuristic = model_heuristic;
+        if node_heuristic != self.node_selection_heuristic() {
+            self.set_node_selection_heuristic(node_heuristic);
+        }
     }
 
     fn check_if_done(&mut self) -> GenerationStatus {
         if self.nodes_left_to_generate == 0 {
-            self.status = InternalGeneratorStatus::Done;
+            self.set_done();
             GenerationStatus::Done
         } else {
             self.status = InternalGeneratorStatus::Ongoing;
@@ -153,18 +477,28 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         }
     }
 
+    fn set_done(&mut self) {
+        if !matches!(self.status, InternalGeneratorStatus::Done) {
+            self.status = InternalGeneratorStatus::Done;
+            self.contradictions_count = 0;
+            self.callbacks.done(self.seed);
+        }
+    }
+
     fn reset_with_seed(&mut self, seed: u64) {
         self.seed = seed;
-        self.rng = StdRng::seed_from_u64(seed);
+        self.rng.reseed(seed);
 
         self.status = InternalGeneratorStatus::Ongoing;
 
-        let nodes_count = self.grid.total_size();
-        self.nodes = bitvec![1;self.rules.models_count() * nodes_count ];
-        self.nodes_left_to_generate = nodes_count;
-        self.possible_models_counts = vec![self.rules.models_count(); nodes_count];
+        self.candidates.reset();
+        self.nodes_left_to_generate = self.active_nodes_count;
+        self.possible_models_counts =
+            initial_possible_models_counts(&self.candidates, &self.rules, &self.grid);
+        self.generated_counts.reset();
         self.propagation_stack = Vec::new();
         self.node_selection_heuristic.reinitialize();
+        self.journal.clear();
     }
 
     /// Advances the seed
@@ -175,7 +509,156 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
     ) -> GenerationStatus {
         // Gen next seed from current rng
         let next_seed = self.rng.gen::<u64>();
-        self.reset_with_seed(next_seed);
+        self.reinitialize_with_seed(next_seed, collector, initial_nodes)
+    }
+
+    /// Resets the generator with a seed and an RNG given by `rng_mode`
+    pub(crate) fn reinitialize_with_rng(
+        &mut self,
+        rng_mode: &RngMode,
+        collector: &mut Collector,
+        initial_nodes: &Vec<(NodeIndex, ModelVariantIndex)>,
+    ) -> GenerationStatus {
+        let seed = rng_mode.seed();
+        self.rng = GeneratorRng::new(rng_mode, seed);
+        self.reinitialize_with_seed(seed, collector, initial_nodes)
+    }
+
+    /// Creates a new generator with the same configuration (rules, grid, heuristics, initial bans, candidates storage and kind of RNG), without any observer nor callback. The new generator still needs to be reinitialized with a seed.
+    pub(crate) fn fork(&self) -> Self {
+        let rng_mode = match &self.rng {
+            GeneratorRng::Std(_) => RngMode::Seeded(self.seed),
+            GeneratorRng::External(external_rng, _) => RngMode::External(external_rng.clone()),
+        };
+        Self::new(
+            Arc::clone(&self.rules),
+            self.grid.clone(),
+            self.base_heuristics.0,
+            self.base_heuristics.1,
+            self.heuristic_schedule.clone(),
+            rng_mode,
+            Vec::new(),
+            self.initial_bans.clone(),
+            self.candidates.storage(),
+            self.selection_noise,
+            self.candidates.active_nodes(),
+            self.anchors.clone(),
+            Arc::clone(&self.node_weights),
+        )
+    }
+
+    /// Creates a new generator with the same rules, heuristics, candidates storage and kind of RNG, on a sub-grid `grid` of the grid of this generator, without any observer nor callback. `grid_indexes[sub_index]` is the index in the grid of this generator of the node `sub_index` of `grid`.
+    ///
+    /// The initial bans, active nodes, anchors and node weights of this generator are restricted to the nodes of the sub-grid. The new generator still needs to be reinitialized with a seed.
+    pub(crate) fn fork_on_sub_grid(
+        &self,
+        grid: GridDefinition<C>,
+        grid_indexes: &[NodeIndex],
+    ) -> Self {
+        let rng_mode = match &self.rng {
+            GeneratorRng::Std(_) => RngMode::Seeded(self.seed),
+            GeneratorRng::External(external_rng, _) => RngMode::External(external_rng.clone()),
+        };
+        let sub_indexes: HashMap<NodeIndex, NodeIndex> = grid_indexes
+            .iter()
+            .enumerate()
+            .map(|(sub_index, &node_index)| (node_index, sub_index))
+            .collect();
+        let mut initial_bans = InitialBans {
+            nodes: self
+                .initial_bans
+                .nodes
+                .iter()
+                .filter_map(|&(node_index, model_variant_index)| {
+                    sub_indexes
+                        .get(&node_index)
+                        .map(|&sub_index| (sub_index, model_variant_index))
+                })
+                .collect(),
+            layers: Vec::new(),
+        };
+        // The layers of the sub-grid do not match the layers of the grid: layer bans are given per node
+        if !self.initial_bans.layers.is_empty() {
+            for (sub_index, &node_index) in grid_indexes.iter().enumerate() {
+                let y = self.grid.pos_from_index(node_index).y;
+                initial_bans.nodes.extend(
+                    self.initial_bans
+                        .layers
+                        .iter()
+                        .filter(|&&(layer, _)| layer == y)
+                        .map(|&(_, model_variant_index)| (sub_index, model_variant_index)),
+                );
+            }
+        }
+        let active_nodes = self.candidates.active_nodes().map(|_| {
+            Arc::new(
+                grid_indexes
+                    .iter()
+                    .map(|&node_index| self.is_active_node(node_index))
+                    .collect::<BitVec<usize>>(),
+            )
+        });
+        let anchors = self
+            .anchors
+            .iter()
+            .filter_map(|node_index| sub_indexes.get(node_index).copied())
+            .collect();
+        Self::new(
+            Arc::clone(&self.rules),
+            grid,
+            self.base_heuristics.0,
+            self.base_heuristics.1,
+            self.heuristic_schedule.clone(),
+            rng_mode,
+            Vec::new(),
+            initial_bans,
+            self.candidates.storage(),
+            self.selection_noise,
+            active_nodes,
+            anchors,
+            Arc::new(self.node_weights.restricted(grid_indexes)),
+        )
+    }
+
+    /// Returns `count` new seeds, drawn from the current RNG
+    pub(crate) fn next_seeds(&mut self, count: usize) -> Vec<u64> {
+        (0..count).map(|_| self.rng.gen::<u64>()).collect()
+    }
+
+    /// Reinitializes the generator with `seed` then generates the whole grid, with up to `retry_count` retries
+    pub(crate) fn generate_with_seed(
+        &mut self,
+        seed: u64,
+        retry_count: u32,
+        initial_nodes: &Vec<(NodeIndex, ModelVariantIndex)>,
+    ) -> Result<GenInfo, GeneratorError> {
+        match self.reinitialize_with_seed(seed, &mut None, initial_nodes) {
+            GenerationStatus::Done => Ok(GenInfo { try_count: 1 }),
+            GenerationStatus::Ongoing => self.generate(&mut None, retry_count, initial_nodes),
+        }
+    }
+
+    /// Reinitializes the generator with `seed` and the checked `initial_nodes` (see [`InternalGenerator::reinitialize_with_checked_nodes`]), then generates the whole grid, with up to `retry_count` retries
+    pub(crate) fn generate_with_checked_nodes(
+        &mut self,
+        seed: u64,
+        retry_count: u32,
+        initial_nodes: &Vec<(NodeIndex, ModelVariantIndex)>,
+    ) -> Result<GenInfo, GeneratorError> {
+        match self.reinitialize_with_checked_nodes(seed, initial_nodes)? {
+            GenerationStatus::Done => Ok(GenInfo { try_count: 1 }),
+            GenerationStatus::Ongoing => self.generate(&mut None, retry_count, initial_nodes),
+        }
+    }
+
+    /// Resets the generator with the given seed
+    pub(crate) fn reinitialize_with_seed(
+        &mut self,
+        seed: u64,
+        collector: &mut Collector,
+        initial_nodes: &Vec<(NodeIndex, ModelVariantIndex)>,
+    ) -> GenerationStatus {
+        self.reset_with_seed(seed);
 
         #[cfg(feature = "debug-traces")]
         info!(
@@ -183,8 +666,8 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
             self.seed, self.status
         );
 
-        for obs in &mut self.observers {
-            let _ = obs.send(GenerationUpdate::Reinitializing(self.seed));
+        for obs in &self.observers {
+            obs.send(GenerationUpdate::Reinitializing(self.seed));
         }
 
         // Since Pre-gen succeeded. The following calls will always succeed.
@@ -193,6 +676,34 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
             .unwrap()
     }
 
+    /// Resets the generator with `seed`, then sets and propagates `initial_nodes`. Unlike [`InternalGenerator::reinitialize_with_seed`], the initial nodes are checked: nodes incompatible with the previous ones fail the generation instead of panicking.
+    pub(crate) fn reinitialize_with_checked_nodes(
+        &mut self,
+        seed: u64,
+        initial_nodes: &Vec<(NodeIndex, ModelVariantIndex)>,
+    ) -> Result<GenerationStatus, GeneratorError> {
+        self.reset_with_seed(seed);
+        for obs in &self.observers {
+            obs.send(GenerationUpdate::Reinitializing(self.seed));
+        }
+        self.initialize_supports_count(&mut None)?;
+        for &(node_index, model_variant_index) in initial_nodes.iter() {
+            match self.check_set_and_propagate_parameters(node_index, model_variant_index) {
+                Ok(NodeSetStatus::AlreadySet) => continue,
+                Ok(NodeSetStatus::CanBeSet) => (),
+                Err(_) => {
+                    self.signal_contradiction(node_index);
+                    return Err(GeneratorError { node_index });
+                }
+            }
+            match self.unchecked_set_and_propagate(node_index, model_variant_index, &mut None)? {
+                GenerationStatus::Ongoing => (),
+                GenerationStatus::Done => return Ok(GenerationStatus::Done),
+            }
+        }
+        Ok(self.check_if_done())
+    }
+
     /// Initialize the supports counts array. This may already start to generate/ban/... some nodes according to the given constraints.
     ///
     /// Returns `Ok` if the initialization went well and sets the internal status to [`InternalGeneratorStatus::Ongoing`] or [`InternalGeneratorStatus::Done`]. Else, sets the internal status to [`InternalGeneratorStatus::Failed`] and returns [`GeneratorError`]
@@ -203,37 +714,32 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         #[cfg(feature = "debug-traces")]
         debug!("Initializing support counts");
 
-        let mut neighbours = vec![None; self.grid.directions().len()];
+        // During initialization, the support count for a model "from" a direction is simply the count of allowed adjacent models when looking in the opposite direction, or 0 for a non-looping border (no neighbour from this direction). Those initial counts are set by the candidates storage on reset.
         for node in 0..self.grid.total_size() {
-            // For a given `node`, `neighbours[direction]` will hold the optionnal index of the neighbour node in `direction`
-            for direction in self.grid.directions() {
-                let grid_pos = self.grid.pos_from_index(node);
-                neighbours[*direction as usize] =
-                    self.grid.get_next_index_in_direction(&grid_pos, *direction);
+            if !self.is_active_node(node) {
+                continue;
+            }
+            for model in self.candidates.unsupported_models(node) {
+                if self.is_model_possible(node, model) {
+                    // Ban model for node since it would 100% lead to a contradiction at some point during the generation.
+                    if let Err(err) = self.ban_model_from_node(node, model, collector) {
+                        self.signal_contradiction(node);
+                        return Err(err);
+                    }
+                }
             }
+        }
 
-            for model in 0..self.rules.models_count() {
-                for direction in self.grid.directions() {
-                    let opposite_dir = direction.opposite();
-                    // During initialization, the support count for a model "from" a direction is simply the count of allowed adjacent models when looking in the opposite direction, or 0 for a non-looping border (no neighbour from this direction).
-                    match neighbours[opposite_dir as usize] {
-                        Some(_) => {
-                            let allowed_models_count =
-                                self.rules.allowed_models(model, opposite_dir).len();
-                            self.supports_count[(node, model, *direction as usize)] =
-                                allowed_models_count;
-                            if allowed_models_count == 0 && self.is_model_possible(node, model) {
-                                // Ban model for node since it would 100% lead to a contradiction at some point during the generation.
-                                if let Err(err) = self.ban_model_from_node(node, model, collector) {
-                                    self.signal_contradiction(node);
-                                    return Err(err);
-                                }
-                                // We don't need to process the remaining directions, iterate on the next model.
-                                break;
-                            }
-                        }
-                        None => self.supports_count[(node, model, *direction as usize)] = 0,
-                    };
+        for i in 0..self.initial_bans.nodes.len() {
+            let (node, model) = self.initial_bans.nodes[i];
+            self.apply_initial_ban(node, model, collector)?;
+        }
+        for i in 0..self.initial_bans.layers.len() {
+            let (y, model) = self.initial_bans.layers[i];
+            for z in 0..self.grid.size_z() {
+                for x in 0..self.grid.size_x() {
+                    let node = self.grid.index_from_coords(x, y, z);
+                    self.apply_initial_ban(node, model, collector)?;
                 }
             }
         }
@@ -250,6 +756,22 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         Ok(self.check_if_done())
     }
 
+    /// Bans `model` from `node` if it is still possible there, during the initialization
+    fn apply_initial_ban(
+        &mut self,
+        node: NodeIndex,
+        model: ModelVariantIndex,
+        collector: &mut Collector,
+    ) -> Result<(), GeneratorError> {
+        if self.is_active_node(node) && self.is_model_possible(node, model) {
+            if let Err(err) = self.ban_model_from_node(node, model, collector) {
+                self.signal_contradiction(node);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
     /// Cannot fail since pre-gen was successful
     fn generate_initial_nodes(
         &mut self,
@@ -317,6 +839,9 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         if !self.is_valid_node_index(node_index) {
             return Err(NodeSetError::InvalidNodeIndex(node_index));
         }
+        if !self.is_active_node(node_index) {
+            return Err(NodeSetError::InactiveNodeIndex(node_index));
+        }
         if !self.is_model_possible(node_index, model_variant_index) {
             return Err(NodeSetError::IllegalModel(model_variant_index, node_index));
         }
@@ -332,10 +857,18 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         retry_count: u32,
         initial_nodes: &Vec<(NodeIndex, ModelVariantIndex)>,
     ) -> Result<GenInfo, GeneratorError> {
+        // A whole generation is not journaled: the previous actions cannot be undone over it
+        self.journal.clear();
         let mut last_error = None;
         for try_index in 0..=retry_count {
+            // One span per try, the seed is recorded once known
             #[cfg(feature = "debug-traces")]
-            info!("Try n°{}", try_index + 1);
+            let try_span = info_span!(
+                "generation_try",
+                try_number = try_index + 1,
+                seed = field::Empty
+            )
+            .entered();
 
             if let Some(collector) = collector {
                 collector.clear();
@@ -343,7 +876,11 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
             match self.status {
                 InternalGeneratorStatus::Ongoing => (),
                 InternalGeneratorStatus::Done | InternalGeneratorStatus::Failed(_) => {
-                    match self.reinitialize(collector, initial_nodes) {
+                    let status = self.reinitialize(collector, initial_nodes);
+                    if try_index > 0 {
+                        self.callbacks.retry(try_index + 1, self.seed);
+                    }
+                    match status {
                         GenerationStatus::Ongoing => (),
                         GenerationStatus::Done => {
                             return Ok(GenInfo {
@@ -353,6 +890,8 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
                     }
                 }
             }
+            #[cfg(feature = "debug-traces")]
+            try_span.record("seed", self.seed);
             match self.generate_remaining_nodes(collector) {
                 Ok(_) => {
                     return Ok(GenInfo {
@@ -384,27 +923,187 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
     }
 
     /// Top-level handler of public API calls.
-    pub(crate) fn set_and_propagate(
+    /// Same as [`InternalGenerator::set_and_propagate`] but the parameters are always checked, even if the generation is done.
+    pub(crate) fn collapse_node(
         &mut self,
         node_index: NodeIndex,
         model_variant_index: ModelVariantIndex,
         collector: &mut Collector,
     ) -> Result<GenerationStatus, NodeSetError> {
-        match self.status {
-            InternalGeneratorStatus::Ongoing => (),
-            InternalGeneratorStatus::Done => return Ok(GenerationStatus::Done),
-            InternalGeneratorStatus::Failed(err) => return Err(err.into()),
+        if let InternalGeneratorStatus::Failed(err) = self.status {
+            return Err(err.into());
         }
+        self.checked_set_and_propagate(node_index, model_variant_index, collector)
+    }
 
+    /// Checks the parameters then sets and propagates `model_variant_index` on `node_index` as one journaled action. Returns the current status if the node is already set to this model.
+    fn checked_set_and_propagate(
+        &mut self,
+        node_index: NodeIndex,
+        model_variant_index: ModelVariantIndex,
+        collector: &mut Collector,
+    ) -> Result<GenerationStatus, NodeSetError> {
         match self.check_set_and_propagate_parameters(node_index, model_variant_index)? {
-            NodeSetStatus::AlreadySet => {
-                // Nothing to do. We can't be done here
-                return Ok(GenerationStatus::Ongoing);
+            NodeSetStatus::AlreadySet => Ok(match self.status {
+                InternalGeneratorStatus::Done => GenerationStatus::Done,
+                _ => GenerationStatus::Ongoing,
+            }),
+            NodeSetStatus::CanBeSet => Ok(self.journaled(|generator| {
+                generator.unchecked_set_and_propagate(node_index, model_variant_index, collector)
+            })?),
+        }
+    }
+
+    /// Runs `action` as one journaled action if the journal is enabled, so that all the nodes it modifies can be restored by [`InternalGenerator::undo`]
+    fn journaled<T>(&mut self, action: impl FnOnce(&mut Self) -> T) -> T {
+        if self.journal.is_enabled() {
+            let snapshot = self.generator_snapshot();
+            self.journal.begin(snapshot, self.grid.total_size());
+        }
+        let res = action(self);
+        self.journal.commit();
+        res
+    }
+
+    pub(crate) fn undo_limit(&self) -> usize {
+        self.journal.limit()
+    }
+
+    pub(crate) fn set_undo_limit(&mut self, limit: usize) {
+        self.journal.set_limit(limit);
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        self.journal.can_undo()
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        self.journal.can_redo()
+    }
+
+    /// Reverts the last journaled action, if any. Returns the current status.
+    pub(crate) fn undo(&mut self) -> Result<GenerationStatus, GeneratorError> {
+        if let Some(entry) = self.journal.pop_undo() {
+            let redo_entry = self.apply_journal_entry(entry);
+            self.journal.push_redo(redo_entry);
+        }
+        self.current_status()
+    }
+
+    /// Re-applies the last undone action, if any. Returns the current status.
+    pub(crate) fn redo(&mut self) -> Result<GenerationStatus, GeneratorError> {
+        if let Some(entry) = self.journal.pop_redo() {
+            let undo_entry = self.apply_journal_entry(entry);
+            self.journal.push_undo(undo_entry);
+        }
+        self.current_status()
+    }
+
+    fn current_status(&self) -> Result<GenerationStatus, GeneratorError> {
+        match self.status {
+            InternalGeneratorStatus::Ongoing => Ok(GenerationStatus::Ongoing),
+            InternalGeneratorStatus::Done => Ok(GenerationStatus::Done),
+            InternalGeneratorStatus::Failed(err) => Err(err),
+        }
+    }
+
+    fn generator_snapshot(&self) -> GeneratorSnapshot {
+        GeneratorSnapshot {
+            status: self.status,
+            nodes_left_to_generate: self.nodes_left_to_generate,
+            rng: self.rng.clone(),
+            current_phase: self.current_phase,
+            contradictions_count: self.contradictions_count,
+            model_selection_heuristic: self.model_selection_heuristic,
+        }
+    }
+
+    fn node_snapshot(&self, node_index: NodeIndex) -> NodeSnapshot {
+        let (models, supports) = self.candidates.node_state(node_index);
+        NodeSnapshot {
+            node_index,
+            models,
+            possible_models_count: self.possible_models_counts[node_index],
+            supports,
+            entropy_data: self.node_selection_heuristic.node_entropy_data(node_index),
+        }
+    }
+
+    /// Records the state of `node_index` in the journal if an action is being recorded and if it was not recorded yet.
+    #[inline]
+    fn journal_node(&mut self, node_index: NodeIndex) {
+        if self.journal.needs_record(node_index) {
+            let snapshot = self.node_snapshot(node_index);
+            self.journal.record(snapshot);
+        }
+    }
+
+    /// Restores the state saved in `entry` and returns an entry with the state that was replaced, to be able to go back to it.
+    ///
+    /// Observers are signaled with the nodes which are not generated anymore, and with the nodes that are now generated.
+    fn apply_journal_entry(&mut self, entry: JournalEntry) -> JournalEntry {
+        let mut replaced_entry = JournalEntry {
+            generator: self.generator_snapshot(),
+            nodes: Vec::with_capacity(entry.nodes.len()),
+        };
+        // Any pending propagation (after a contradiction) is obsolete
+        self.propagation_stack.clear();
+        // The node selection heuristic cannot differ: changing it clears the journal
+        let generator = entry.generator;
+        self.status = generator.status;
+        self.nodes_left_to_generate = generator.nodes_left_to_generate;
+        self.rng = generator.rng;
+        self.current_phase = generator.current_phase;
+        self.contradictions_count = generator.contradictions_count;
+        self.model_selection_heuristic = generator.model_selection_heuristic;
+
+        for snapshot in entry.nodes {
+            let node_index = snapshot.node_index;
+            let replaced = self.node_snapshot(node_index);
+            let was_generated = replaced.possible_models_count == 1;
+
+            self.candidates
+                .set_node_state(node_index, &snapshot.models, &snapshot.supports);
+            self.possible_models_counts[node_index] = snapshot.possible_models_count;
+            if let Some(entropy_data) = snapshot.entropy_data {
+                self.node_selection_heuristic
+                    .set_node_entropy_data(node_index, entropy_data);
             }
-            NodeSetStatus::CanBeSet => (),
+
+            let is_generated = snapshot.possible_models_count == 1;
+            self.update_generated_count(node_index);
+            let update = match (was_generated, is_generated) {
+                (true, false) => Some(GenerationUpdate::Reverted(node_index)),
+                (_, true) if replaced.models != snapshot.models => {
+                    Some(GenerationUpdate::Generated(GeneratedNode {
+                        node_index,
+                        model_instance: *self.rules.model(self.get_model_index(node_index)),
+                    }))
+                }
+                _ => None,
+            };
+            if let Some(update) = update {
+                for obs in &self.observers {
+                    obs.send_node_update(update, &self.grid, node_index);
+                }
+            }
+            replaced_entry.nodes.push(replaced);
         }
+        replaced_entry
+    }
 
-        Ok(self.unchecked_set_and_propagate(node_index, model_variant_index, collector)?)
+    pub(crate) fn set_and_propagate(
+        &mut self,
+        node_index: NodeIndex,
+        model_variant_index: ModelVariantIndex,
+        collector: &mut Collector,
+    ) -> Result<GenerationStatus, NodeSetError> {
+        match self.status {
+            InternalGeneratorStatus::Ongoing => (),
+            InternalGeneratorStatus::Done => return Ok(GenerationStatus::Done),
+            InternalGeneratorStatus::Failed(err) => return Err(err.into()),
+        }
+        self.checked_set_and_propagate(node_index, model_variant_index, collector)
     }
 
     /// Top-level handler of public API calls.
@@ -418,7 +1117,7 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
             InternalGeneratorStatus::Failed(err) => return Err(err),
         }
 
-        self.unchecked_select_and_propagate(collector)
+        self.journaled(|generator| generator.unchecked_select_and_propagate(collector))
     }
 
     /// - node_index and model_variant_index must be valid
@@ -431,6 +1130,15 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         model_variant_index: ModelVariantIndex,
         collector: &mut Collector,
     ) -> Result<GenerationStatus, GeneratorError> {
+        #[cfg(feature = "debug-traces")]
+        let _step_span = debug_span!(
+            "generation_step",
+            node_index,
+            model_index = model_variant_index,
+            model_name = self.rules.name_unchecked_str(model_variant_index),
+            forced = true
+        )
+        .entered();
         #[cfg(feature = "debug-traces")]
         debug!(
             "Set model {:?} named '{}' for node {} at position {:?}",
@@ -440,7 +1148,7 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
             self.grid.pos_from_index(node_index)
         );
 
-        if !self.observers.is_empty() {
+        if !self.observers.is_empty() || collector.is_some() {
             self.signal_selection(collector, node_index, model_variant_index);
         }
 
@@ -458,19 +1166,66 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         &mut self,
         collector: &mut Collector,
     ) -> Result<GenerationStatus, GeneratorError> {
-        let node_index = match self
-            .node_selection_heuristic
-            .select_node(&self.possible_models_counts, &mut self.rng)
+        #[cfg(feature = "step-stats")]
+        let (start, nodes_left) = {
+            self.step_stats = StepStats::default();
+            (Instant::now(), self.nodes_left_to_generate)
+        };
+
+        let result = self.unchecked_select_and_propagate_step(collector);
+
+        #[cfg(feature = "step-stats")]
         {
+            self.step_stats.elapsed = start.elapsed();
+            self.step_stats.generated_nodes =
+                nodes_left.saturating_sub(self.nodes_left_to_generate);
+            self.last_step_stats = Some(self.step_stats);
+            self.callbacks.step(&self.step_stats, self.seed);
+        }
+        result
+    }
+
+    fn unchecked_select_and_propagate_step(
+        &mut self,
+        collector: &mut Collector,
+    ) -> Result<GenerationStatus, GeneratorError> {
+        profile_scope!("select_and_propagate");
+        if !self.heuristic_schedule.is_empty() {
+            self.update_heuristic_phase();
+        }
+        let node_index = match self.node_selection_heuristic.select_node(
+            &self.candidates,
+            &self.possible_models_counts,
+            self.selection_noise,
+            &mut self.rng,
+        ) {
             Some(index) => index,
             None => {
                 // TODO Here, should not be able to find None anymore.
-                self.status = InternalGeneratorStatus::Done;
+                self.set_done();
                 return Ok(GenerationStatus::Done);
             }
         };
+        #[cfg(feature = "step-stats")]
+        {
+            self.step_stats.node_index = Some(node_index);
+        }
         // We found a node not yet generated. "Observe/collapse" the node: select a model for the node
-        let selected_model_index = self.select_model(node_index);
+        let Some(selected_model_index) = self.select_model(node_index) else {
+            // Only zero-weight models, or models which reached their count limits, are left on this node: they can't be selected
+            self.signal_contradiction(node_index);
+            return Err(GeneratorError { node_index });
+        };
+
+        #[cfg(feature = "debug-traces")]
+        let _step_span = debug_span!(
+            "generation_step",
+            node_index,
+            model_index = selected_model_index,
+            model_name = self.rules.name_unchecked_str(selected_model_index),
+            forced = false
+        )
+        .entered();
 
         #[cfg(feature = "debug-traces")]
         debug!(
@@ -495,26 +1250,101 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
     }
 
     /// There should at least be one possible model for this node index. May panic otherwise.
-    fn select_model(&mut self, node_index: NodeIndex) -> usize {
+    ///
+    /// Returns `None` if all the possible models of this node have a weight of `0` or reached one of their count limits.
+    fn select_model(&mut self, node_index: NodeIndex) -> Option<usize> {
+        profile_scope!("select_model");
         match self.model_selection_heuristic {
             ModelSelectionHeuristic::WeightedProbability => {
                 let possible_models: Vec<ModelVariantIndex> = (0..self.rules.models_count())
                     .filter(|&model_index| self.is_model_possible(node_index, model_index))
                     .collect();
 
+                // Original models of the already generated adjacent nodes, with their direction
+                let mut generated_neighbours = Vec::new();
+                if self.rules.has_pair_weights() {
+                    let position = self.grid.pos_from_index(node_index);
+                    for &direction in self.grid.directions() {
+                        if let Some(neighbour_index) =
+                            self.grid.get_next_index_in_direction(&position, direction)
+                        {
+                            if self.possible_models_counts[neighbour_index] == 1 {
+                                let neighbour_model =
+                                    self.rules.model(self.get_model_index(neighbour_index));
+                                generated_neighbours.push((direction, neighbour_model.model_index));
+                            }
+                        }
+                    }
+                }
+
+                let has_count_limits = self.rules.has_count_limits();
                 // TODO May cache the current sum of weights at each node.
-                let weighted_distribution = WeightedIndex::new(
-                    possible_models
-                        .iter()
-                        .map(|&model_index| self.rules.weight_unchecked(model_index)),
-                )
-                .unwrap();
-                possible_models[weighted_distribution.sample(&mut self.rng)]
+                // Weights are accumulated as `f64`: with thousands of candidates with tiny weights, `f32` cumulative weights would bias the selection.
+                let weighted_distribution =
+                    WeightedIndex::new(possible_models.iter().map(|&model_index| {
+                        let original_model = self.rules.model(model_index).model_index;
+                        // Models which reached one of their count limits can't be selected anymore
+                        if has_count_limits && self.count_limit_reached(node_index, model_index) {
+                            return 0.;
+                        }
+                        let weight = self.rules.weight_unchecked(model_index)
+                            * self.node_weights.multiplier(original_model, node_index) as f64;
+                        generated_neighbours.iter().fold(
+                            weight,
+                            |weight, &(direction, neighbour_model)| {
+                                weight
+                                    * self.rules.pair_weight(
+                                        original_model,
+                                        direction,
+                                        neighbour_model,
+                                    ) as f64
+                            },
+                        )
+                    }))
+                    // Only fails if all the weights are 0
+                    .ok()?;
+                Some(possible_models[weighted_distribution.sample(&mut self.rng)])
             }
         }
     }
 
+    /// Returns `true` if the original model of the model variant `model_index` reached one of its [`CountLimits`] in the column or the layer of `node_index`.
+    ///
+    /// The limits are only enforced here, when a model is selected: models forced by the propagation (or set as initial nodes, or collapsed manually) are counted but may exceed the limits.
+    fn count_limit_reached(&self, node_index: NodeIndex, model_index: ModelVariantIndex) -> bool {
+        let limits = self.rules.variant_count_limits_unchecked(model_index);
+        if limits.is_unlimited() {
+            return false;
+        }
+        self.generated_counts.limit_reached(
+            &self.grid,
+            node_index,
+            self.rules.model(model_index).model_index,
+            limits,
+        )
+    }
+
+    /// Updates the count limits counters of `node_index` after a change of its possible models count: the node is counted with its model if a single model is left, else uncounted.
+    #[inline]
+    fn update_generated_count(&mut self, node_index: NodeIndex) {
+        if !self.generated_counts.is_enabled() {
+            return;
+        }
+        match self.possible_models_counts[node_index] {
+            1 => {
+                let original_model = self
+                    .rules
+                    .model(self.get_model_index(node_index))
+                    .model_index;
+                self.generated_counts
+                    .count(&self.grid, node_index, original_model);
+            }
+            _ => self.generated_counts.uncount(&self.grid, node_index),
+        }
+    }
+
     fn handle_selected(&mut self, node_index: usize, selected_model_index: ModelVariantIndex) {
+        self.journal_node(node_index);
         // Iterate all the possible models because we don't have an easy way to iterate only the models possible at node_index. But we'll filter impossible models right away. TODO: benchmark iter_ones
         for model_index in 0..self.rules.models_count() {
             if model_index == selected_model_index {
@@ -526,24 +1356,11 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
 
             // Enqueue removal for propagation
             self.enqueue_removal_to_propagate(node_index, model_index);
-
-            // None of these model are possible on this node now, set their support to 0
-            for dir in self.grid.directions() {
-                self.supports_count[(node_index, model_index, *dir as usize)] = 0;
-            }
-        }
-        // Remove eliminated possibilities (after enqueuing the propagation entries because we currently filter on the possible models)
-        // TODO Remove alias ?
-        let models_count = self.rules.models_count();
-        for mut bit in self.nodes
-            [node_index * models_count..node_index * models_count + models_count]
-            .iter_mut()
-        {
-            *bit = false;
         }
-        self.nodes
-            .set(node_index * models_count + selected_model_index, true);
+        // Remove eliminated possibilities and set their supports to 0 (after enqueuing the propagation entries because we currently filter on the possible models)
+        self.candidates.select(node_index, selected_model_index);
         self.possible_models_counts[node_index] = 1;
+        self.update_generated_count(node_index);
     }
 
     /// Returns [`GeneratorError`] if the node has no possible models left. Else, returns `Ok`.
@@ -557,17 +1374,14 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         model: usize,
         collector: &mut Collector,
     ) -> Result<(), GeneratorError> {
-        // Update the supports
-        for dir in self.grid.directions() {
-            let supports_count = &mut self.supports_count[(node_index, model, *dir as usize)];
-            *supports_count = 0;
-        }
-        // Update the state
-        self.nodes
-            .set(node_index * self.rules.models_count() + model, false);
+        self.journal_node(node_index);
+        // Update the supports and the state
+        self.candidates.ban(node_index, model);
 
         let number_of_models_left = &mut self.possible_models_counts[node_index];
         *number_of_models_left = number_of_models_left.saturating_sub(1);
+        let number_of_models_left = *number_of_models_left;
+        self.update_generated_count(node_index);
 
         self.node_selection_heuristic.handle_ban(
             node_index,
@@ -585,7 +1399,7 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
             number_of_models_left
         );
 
-        match *number_of_models_left {
+        match number_of_models_left {
             0 => return Err(GeneratorError { node_index }),
             1 => {
                 #[cfg(feature = "debug-traces")]
@@ -632,10 +1446,20 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
     ///
     /// Does not modify the generator internal status.
     fn propagate(&mut self, collector: &mut Collector) -> Result<(), GeneratorError> {
+        profile_scope!("propagate");
         // Clone the ref to allow for mutability of other members in the interior loops
         let rules = Arc::clone(&self.rules);
 
+        // One span per propagation wave, with the number of bans queued when the wave starts
+        #[cfg(feature = "debug-traces")]
+        let _propagation_span =
+            debug_span!("propagation", queued_bans = self.propagation_stack.len()).entered();
+
         while let Some(from) = self.propagation_stack.pop() {
+            #[cfg(feature = "step-stats")]
+            {
+                self.step_stats.propagated_bans += 1;
+            }
             let from_position = self.grid.pos_from_index(from.node_index);
 
             #[cfg(feature = "debug-traces")]
@@ -646,21 +1470,46 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
                 from.node_index
             );
 
-            // We want to update all the adjacent nodes (= in all directions)
-            for dir in self.grid.directions() {
-                // Get the adjacent node in this direction, it may not exist.
-                if let Some(to_node_index) =
-                    self.grid.get_next_index_in_direction(&from_position, *dir)
+            // We want to update all the adjacent nodes (= in all directions, and the offsets of the direction set if the rules have one)
+            for adjacency in 0..rules.adjacencies_count() {
+                // Get the adjacent node through this adjacency, it may not exist.
+                if let Some(to_node_index) = self
+                    .candidates
+                    .adjacent_active_node(&from_position, adjacency)
                 {
+                    self.journal_node(to_node_index);
                     // Decrease the support count of all models previously supported by "from"
-                    for &model in rules.allowed_models(from.model_index, *dir) {
-                        let supports_count =
-                            &mut self.supports_count[(to_node_index, model, *dir as usize)];
-                        if *supports_count > 0 {
-                            *supports_count -= 1;
-                            // When we find a model which is now unsupported, we queue a ban
-                            // We check > 0  and for == because we only want to queue the event once.
-                            if *supports_count == 0 {
+                    #[cfg(not(feature = "simd-propagation"))]
+                    for &model in rules.allowed_models(from.model_index, adjacency) {
+                        // When we find a model which is now unsupported, we queue a ban
+                        // We only decrement non-zero counts because we only want to queue the event once.
+                        if let Some(0) =
+                            self.candidates
+                                .decrement_supports(to_node_index, model, adjacency)
+                        {
+                            self.ban_model_from_node(to_node_index, model, collector)?;
+                        }
+                    }
+                    // Only visit the models both supported by "from" and still possible on the adjacent node, a word of models at a time. Impossible models have no supports left.
+                    #[cfg(feature = "simd-propagation")]
+                    for (word_index, &mask_word) in rules
+                        .allowed_mask_words(from.model_index, adjacency)
+                        .iter()
+                        .enumerate()
+                    {
+                        let mut word = mask_word
+                            & self
+                                .candidates
+                                .possible_models_word(to_node_index, word_index);
+                        while word != 0 {
+                            let model =
+                                word_index * usize::BITS as usize + word.trailing_zeros() as usize;
+                            // Clear the lowest set bit
+                            word &= word - 1;
+                            if let Some(0) =
+                                self.candidates
+                                    .decrement_supports(to_node_index, model, adjacency)
+                            {
                                 self.ban_model_from_node(to_node_index, model, collector)?;
                             }
                         }
@@ -677,13 +1526,14 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         node_index: NodeIndex,
         model_index: ModelVariantIndex,
     ) {
+        profile_scope!("signal_selection");
         let grid_node = GeneratedNode {
             node_index,
             model_instance: self.rules.model(model_index).clone(),
         };
         let update = GenerationUpdate::Generated(grid_node);
-        for obs in &mut self.observers {
-            let _ = obs.send(update);
+        for obs in &self.observers {
+            obs.send_node_update(update, &self.grid, node_index);
         }
         if let Some(collector) = collector {
             collector.push(grid_node);
@@ -692,18 +1542,32 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
     }
 
     fn signal_contradiction(&mut self, node_index: NodeIndex) {
+        profile_scope!("signal_contradiction");
         #[cfg(feature = "debug-traces")]
-        debug!("Generation failed due to a contradiction");
+        debug!(
+            node_index,
+            seed = self.seed,
+            "Generation failed due to a contradiction"
+        );
 
-        self.status = InternalGeneratorStatus::Failed(GeneratorError { node_index });
-        for obs in &mut self.observers {
-            let _ = obs.send(GenerationUpdate::Failed(node_index));
+        let error = GeneratorError { node_index };
+        self.status = InternalGeneratorStatus::Failed(error);
+        self.contradictions_count = self.contradictions_count.saturating_add(1);
+        for obs in &self.observers {
+            obs.send(GenerationUpdate::Failed(node_index));
         }
+        self.callbacks.contradiction(&error, self.seed);
     }
 
     /// Should only be called when the nodes are fully generated
+    /// Returns the model generated on `node_index`, if the node is active and generated
+    pub(crate) fn generated_model_index(&self, node_index: NodeIndex) -> Option<ModelVariantIndex> {
+        (self.is_active_node(node_index) && self.possible_models_counts[node_index] == 1)
+            .then(|| self.get_model_index(node_index))
+    }
+
     pub(crate) fn to_grid_data(&self) -> GridData<C, ModelInstance> {
-        let mut generated_nodes = Vec::with_capacity(self.nodes.len());
+        let mut generated_nodes = Vec::with_capacity(self.grid.total_size());
         for node_index in 0..self.grid.total_size() {
             let model_index = self.get_model_index(node_index);
             generated_nodes.push(self.rules.model(model_index).clone())
@@ -712,3 +1576,48 @@ impl<C: CoordinateSystem> InternalGenerator<C> {
         GridData::new(self.grid.clone(), generated_nodes)
     }
 }
+
+/// Initial count of possible models of each node: all the models for the active nodes, `0` for the inactive nodes (which are never selected nor generated).
+fn initial_possible_models_counts<C: CoordinateSystem>(
+    candidates: &Candidates<C>,
+    rules: &Rules<C>,
+    grid: &GridDefinition<C>,
+) -> Vec<usize> {
+    (0..grid.total_size())
+        .map(|node_index| match candidates.is_active(node_index) {
+            true => rules.models_count(),
+            false => 0,
+        })
+        .collect()
+}
+
+/// Distance (in grid steps, through the active nodes) from each node to the closest anchor. `u32::MAX` for the nodes which cannot be reached from an anchor, `0` for all the nodes if there is no anchor.
+fn anchor_distances<C: CoordinateSystem>(
+    candidates: &Candidates<C>,
+    grid: &GridDefinition<C>,
+    anchors: &Vec<NodeIndex>,
+) -> Vec<u32> {
+    if anchors.is_empty() {
+        return vec![0; grid.total_size()];
+    }
+    let mut distances = vec![u32::MAX; grid.total_size()];
+    let mut queue = VecDeque::new();
+    for &anchor in anchors.iter() {
+        if distances[anchor] != 0 {
+            distances[anchor] = 0;
+            queue.push_back(anchor);
+        }
+    }
+    while let Some(node_index) = queue.pop_front() {
+        let position = grid.pos_from_index(node_index);
+        for adjacency in 0..grid.directions().len() {
+            if let Some(neighbour) = candidates.adjacent_active_node(&position, adjacency) {
+                if distances[neighbour] == u32::MAX {
+                    distances[neighbour] = distances[node_index] + 1;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+    distances
+}
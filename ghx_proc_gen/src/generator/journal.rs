@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+
+use bitvec::{bitvec, vec::BitVec};
+use ndarray::{Array, Ix2};
+
+use crate::NodeIndex;
+
+use super::{
+    internal_generator::{GeneratorRng, InternalGeneratorStatus},
+    node_heuristic::NodeEntropyData,
+    ModelSelectionHeuristic,
+};
+
+/// State of a node before (or after) a journaled action
+pub(crate) struct NodeSnapshot {
+    pub(crate) node_index: NodeIndex,
+    /// Possible models on the node
+    pub(crate) models: BitVec<usize>,
+    pub(crate) possible_models_count: usize,
+    /// Supports count of the node, indexed by `[model_index][direction]`
+    pub(crate) supports: Array<usize, Ix2>,
+    pub(crate) entropy_data: Option<NodeEntropyData>,
+}
+
+/// State of the generator itself before (or after) a journaled action
+pub(crate) struct GeneratorSnapshot {
+    pub(crate) status: InternalGeneratorStatus,
+    pub(crate) nodes_left_to_generate: usize,
+    /// Random source, so that the actions following an undo draw the same values as the undone action
+    pub(crate) rng: GeneratorRng,
+    pub(crate) current_phase: Option<usize>,
+    pub(crate) contradictions_count: u32,
+    pub(crate) model_selection_heuristic: ModelSelectionHeuristic,
+}
+
+/// State deltas of a journaled action: the state of the generator and of all the nodes modified by the action.
+pub(crate) struct JournalEntry {
+    pub(crate) generator: GeneratorSnapshot,
+    pub(crate) nodes: Vec<NodeSnapshot>,
+}
+
+/// Undo/redo journal of a generator
+#[derive(Default)]
+pub(crate) struct Journal {
+    /// Maximum number of actions that can be undone. `0` disables the journal.
+    limit: usize,
+    undo_stack: VecDeque<JournalEntry>,
+    redo_stack: Vec<JournalEntry>,
+    /// Entry of the action currently being recorded
+    recording: Option<JournalEntry>,
+    /// `recorded_nodes[node_index]` is true if the node was already recorded in the current entry
+    recorded_nodes: BitVec<usize>,
+}
+
+impl Journal {
+    pub(crate) fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.undo_stack.len() > limit {
+            self.undo_stack.pop_front();
+        }
+        if limit == 0 {
+            self.redo_stack.clear();
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.recording = None;
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub(crate) fn begin(&mut self, generator: GeneratorSnapshot, nodes_count: usize) {
+        if self.recorded_nodes.len() != nodes_count {
+            self.recorded_nodes = bitvec![0; nodes_count];
+        }
+        self.recording = Some(JournalEntry {
+            generator,
+            nodes: Vec::new(),
+        });
+    }
+
+    /// Returns `true` if an action is being recorded and `node_index` was not recorded yet for this action.
+    #[inline]
+    pub(crate) fn needs_record(&self, node_index: NodeIndex) -> bool {
+        self.recording.is_some() && !self.recorded_nodes[node_index]
+    }
+
+    pub(crate) fn record(&mut self, snapshot: NodeSnapshot) {
+        if let Some(entry) = &mut self.recording {
+            self.recorded_nodes.set(snapshot.node_index, true);
+            entry.nodes.push(snapshot);
+        }
+    }
+
+    /// Ends the recording of the current action. A new action invalidates the redo history.
+    pub(crate) fn commit(&mut self) {
+        let Some(entry) = self.recording.take() else {
+            return;
+        };
+        for node in entry.nodes.iter() {
+            self.recorded_nodes.set(node.node_index, false);
+        }
+        self.redo_stack.clear();
+        self.undo_stack.push_back(entry);
+        if self.undo_stack.len() > self.limit {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<JournalEntry> {
+        self.undo_stack.pop_back()
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<JournalEntry> {
+        self.redo_stack.pop()
+    }
+
+    pub(crate) fn push_undo(&mut self, entry: JournalEntry) {
+        self.undo_stack.push_back(entry);
+    }
+
+    pub(crate) fn push_redo(&mut self, entry: JournalEntry) {
+        self.redo_stack.push(entry);
+    }
+}
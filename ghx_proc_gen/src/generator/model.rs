@@ -1,20 +1,22 @@
-use std::{borrow::Cow, collections::HashSet, fmt, marker::PhantomData};
+use std::{borrow::Cow, collections::BTreeSet, fmt, marker::PhantomData};
 
 use ghx_grid::{
     coordinate_system::{Cartesian2D, Cartesian3D, CoordinateSystem},
     direction::Direction,
 };
-#[cfg(feature = "debug-traces")]
-use tracing::warn;
 
 #[cfg(feature = "bevy")]
 use bevy::ecs::component::Component;
 #[cfg(feature = "reflect")]
 use bevy::{ecs::reflect::ReflectComponent, reflect::Reflect};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use super::{
+    diagonal::DiagonalSockets,
+    direction_set::GridOffset,
     rules::CARTESIAN_2D_ROTATION_AXIS,
-    socket::{Socket, SocketId, SocketsCartesian2D, SocketsCartesian3D},
+    socket::{Socket, SocketId, SocketsCartesian2D, SocketsCartesian3D, SocketsRemapping},
 };
 
 /// Index of an original model
@@ -26,6 +28,25 @@ pub type ModelVariantIndex = usize;
 /// Default weight of [`Model`] and [`ModelTemplate`]
 pub const DEFAULT_MODEL_WEIGHT: f32 = 1.0;
 
+/// Default priority of [`Model`] and [`ModelTemplate`]
+pub const DEFAULT_MODEL_PRIORITY: u32 = 0;
+
+/// Maximum counts of a model in the generated output, see [`ModelTemplate::with_max_per_column`] and [`ModelTemplate::with_max_per_layer`]. All the variations (rotations) of a model are counted together.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CountLimits {
+    /// Maximum number of nodes with this model in a column: the nodes with the same `x` and `z` coordinates. `None` if unlimited.
+    pub per_column: Option<u32>,
+    /// Maximum number of nodes with this model in a layer: the nodes with the same `y` coordinate. `None` if unlimited.
+    pub per_layer: Option<u32>,
+}
+
+impl CountLimits {
+    /// Returns `true` if none of the limits is set
+    pub fn is_unlimited(&self) -> bool {
+        self.per_column.is_none() && self.per_layer.is_none()
+    }
+}
+
 #[derive(Clone, Debug)]
 /// Most of the information about a [`Model`] (but notably without any [`ModelIndex`]).
 ///
@@ -37,6 +58,22 @@ pub struct ModelTemplate<C> {
     ///
     ///  Defaults to [`DEFAULT_MODEL_WEIGHT`]
     weight: f32,
+    /// Collapse priority of this [`ModelTemplate`], used by [`super::node_heuristic::NodeSelectionHeuristic::PriorityFirst`].
+    ///
+    /// Defaults to [`DEFAULT_MODEL_PRIORITY`]
+    priority: u32,
+    /// Maximum counts of this [`ModelTemplate`] in the columns and layers of the output.
+    ///
+    /// Unlimited by default
+    count_limits: CountLimits,
+    /// `true` if this [`ModelTemplate`] represents empty space, see [`ModelTemplate::with_void`].
+    ///
+    /// Defaults to `false`
+    void: bool,
+    /// Probability for a node generated with this [`ModelTemplate`] to be spawned/reported as empty, see [`ModelTemplate::with_emptiness_chance`].
+    ///
+    /// Defaults to `0.`
+    emptiness_chance: f32,
     /// Allowed rotations of this [`ModelTemplate`] in the output, around the rotation axis specified in the rules.
     ///
     /// Defaults to only [`ModelRotation::Rot0`].
@@ -44,7 +81,15 @@ pub struct ModelTemplate<C> {
     /// Notes:
     /// - In 3d, sockets of a model that are on the rotation axis are rotated into new sockets when the model itself is rotated. See [`crate::generator::socket::SocketCollection`] for how to define and/or constrain sockets connections on the rotation axis.
     /// - In 2d, the rotation axis cannot be modified and is set to [`Direction::ZForward`].
-    allowed_rotations: HashSet<ModelRotation>,
+    ///
+    /// Stored in a [`BTreeSet`] to keep a deterministic iteration order.
+    allowed_rotations: BTreeSet<ModelRotation>,
+    /// Tags of this [`ModelTemplate`], stored in the [`super::rules::Rules`] to be queried on the generated output.
+    tags: Vec<Cow<'static, str>>,
+    /// Allowed connections of this [`ModelTemplate`] towards the neighbours at some offsets. Only used by rules built with a [`super::direction_set::DirectionSet`] containing the offsets, see [`super::rules::RulesBuilder::with_direction_set`].
+    ///
+    /// The model accepts any neighbour at an offset without sockets.
+    offset_sockets: Vec<(GridOffset, Vec<Socket>)>,
     typestate: PhantomData<C>,
 }
 
@@ -52,8 +97,14 @@ impl ModelTemplate<Cartesian3D> {
     pub(crate) fn new(sockets: SocketsCartesian3D) -> ModelTemplate<Cartesian3D> {
         Self {
             sockets: sockets.into(),
-            allowed_rotations: HashSet::from([ModelRotation::Rot0]),
+            allowed_rotations: BTreeSet::from([ModelRotation::Rot0]),
             weight: DEFAULT_MODEL_WEIGHT,
+            priority: DEFAULT_MODEL_PRIORITY,
+            count_limits: CountLimits::default(),
+            void: false,
+            emptiness_chance: 0.,
+            tags: Vec::new(),
+            offset_sockets: Vec::new(),
             typestate: PhantomData,
         }
     }
@@ -63,7 +114,13 @@ impl ModelTemplate<Cartesian3D> {
         Self {
             sockets: self.rotated_sockets(rotation, axis),
             weight: self.weight,
+            priority: self.priority,
+            count_limits: self.count_limits,
+            void: self.void,
+            emptiness_chance: self.emptiness_chance,
             allowed_rotations: self.allowed_rotations.clone(),
+            tags: self.tags.clone(),
+            offset_sockets: self.rotated_offset_sockets(rotation, axis),
             typestate: PhantomData,
         }
     }
@@ -73,8 +130,14 @@ impl ModelTemplate<Cartesian2D> {
     pub(crate) fn new(sockets: SocketsCartesian2D) -> ModelTemplate<Cartesian2D> {
         Self {
             sockets: sockets.into(),
-            allowed_rotations: HashSet::from([ModelRotation::Rot0]),
+            allowed_rotations: BTreeSet::from([ModelRotation::Rot0]),
             weight: DEFAULT_MODEL_WEIGHT,
+            priority: DEFAULT_MODEL_PRIORITY,
+            count_limits: CountLimits::default(),
+            void: false,
+            emptiness_chance: 0.,
+            tags: Vec::new(),
+            offset_sockets: Vec::new(),
             typestate: PhantomData,
         }
     }
@@ -84,7 +147,45 @@ impl ModelTemplate<Cartesian2D> {
         Self {
             sockets: self.rotated_sockets(rotation, CARTESIAN_2D_ROTATION_AXIS),
             weight: self.weight,
+            priority: self.priority,
+            count_limits: self.count_limits,
+            void: self.void,
+            emptiness_chance: self.emptiness_chance,
             allowed_rotations: self.allowed_rotations.clone(),
+            tags: self.tags.clone(),
+            offset_sockets: self.rotated_offset_sockets(rotation, CARTESIAN_2D_ROTATION_AXIS),
+            typestate: PhantomData,
+        }
+    }
+
+    /// Specify the sockets of this [`ModelTemplate`] on its diagonals. They are only used by rules built with [`super::rules::RulesBuilder::with_diagonals`], and are rotated with the model.
+    ///
+    /// Two models can be diagonal neighbours if their sockets on the facing corners are compatible. A model without diagonal sockets accepts any diagonal neighbour. Shorthand for [`ModelTemplate::with_offset_sockets`] on each [`super::diagonal::Diagonal`] offset.
+    pub fn with_diagonal_sockets(mut self, sockets: DiagonalSockets) -> Self {
+        let offset_sockets: Vec<(GridOffset, Vec<Socket>)> = sockets.into();
+        for (offset, sockets) in offset_sockets {
+            set_offset_sockets(&mut self.offset_sockets, offset, sockets);
+        }
+        self
+    }
+
+    /// Returns a [`Cartesian3D`] copy of this [`ModelTemplate`]: its 2d sockets are kept on the X and Y axes, and `up` and `down` are used as its sockets on [`Direction::ZForward`] and [`Direction::ZBackward`], the rotation axis of 2d models.
+    ///
+    /// All the other members are kept, the offset sockets (diagonals included) stay in the XY plane.
+    pub fn to_cartesian_3d(&self, up: Socket, down: Socket) -> ModelTemplate<Cartesian3D> {
+        let mut sockets = self.sockets.clone();
+        sockets.push(vec![up]);
+        sockets.push(vec![down]);
+        ModelTemplate {
+            sockets,
+            weight: self.weight,
+            priority: self.priority,
+            count_limits: self.count_limits,
+            void: self.void,
+            emptiness_chance: self.emptiness_chance,
+            allowed_rotations: self.allowed_rotations.clone(),
+            tags: self.tags.clone(),
+            offset_sockets: self.offset_sockets.clone(),
             typestate: PhantomData,
         }
     }
@@ -95,7 +196,7 @@ impl<C> ModelTemplate<C> {
     ///
     /// Rotations are specified as counter-clockwise
     pub fn with_rotation(mut self, rotation: ModelRotation) -> Self {
-        self.allowed_rotations = HashSet::from([rotation]);
+        self.allowed_rotations = BTreeSet::from([rotation]);
         self
     }
     /// Specify that this [`ModelTemplate`] can be rotated by `rotation`, in addition to its currently allowed rotations.
@@ -108,8 +209,8 @@ impl<C> ModelTemplate<C> {
     /// Specify that this [`ModelTemplate`] can be rotated in every way specified in `rotations`.
     ///
     /// Rotations are specified as counter-clockwise
-    pub fn with_rotations<R: Into<HashSet<ModelRotation>>>(mut self, rotations: R) -> Self {
-        self.allowed_rotations = rotations.into();
+    pub fn with_rotations<R: IntoIterator<Item = ModelRotation>>(mut self, rotations: R) -> Self {
+        self.allowed_rotations = rotations.into_iter().collect();
         self
     }
     /// Specify that this [`ModelTemplate`] can be rotated in every way specified in `rotations` in addition to its currently allowed rotations.
@@ -130,26 +231,115 @@ impl<C> ModelTemplate<C> {
         self
     }
 
-    /// Specify this [`ModelTemplate`] weight. The `weight` value should be strictly superior to `0`. If it is not the case, the value will be overriden by `f32::MIN_POSITIVE`.
+    /// Specify this [`ModelTemplate`] weight. The `weight` value should be finite and positive. If it is not the case, building the [`super::rules::Rules`] will return a [`crate::RulesBuilderError::InvalidWeight`].
     ///
     /// Used by a [`super::Generator`] when using [`super::ModelSelectionHeuristic::WeightedProbability`] and [`super::node_heuristic::NodeSelectionHeuristic::MinimumEntropy`].
     ///
+    /// A weight of `0` makes a constraint-only model: it is never selected by the generator, but can still be set as an initial node, collapsed manually or forced by the propagation. A node where only zero-weight models are left when it is selected fails the generation with a contradiction.
+    ///
     /// All the variations (rotations) of this [`ModelTemplate`] will use the same weight.
     pub fn with_weight<W: Into<f32>>(mut self, weight: W) -> Self {
-        let mut checked_weight = weight.into();
-        if checked_weight <= 0. {
-            #[cfg(feature = "debug-traces")]
-            warn!(
-                "Template had an invalid weight {} <= 0., weight overriden to f32::MIN: {}",
-                checked_weight,
-                f32::MIN_POSITIVE
-            );
-            checked_weight = f32::MIN_POSITIVE
-        };
-        self.weight = checked_weight;
+        self.weight = weight.into();
+        self
+    }
+
+    /// Specify this [`ModelTemplate`] collapse priority. Defaults to [`DEFAULT_MODEL_PRIORITY`].
+    ///
+    /// Used by a [`super::Generator`] when using [`super::node_heuristic::NodeSelectionHeuristic::PriorityFirst`]: nodes which can still be a model with a higher priority are generated first. Give a high priority to the structural models (ground, walls) to resolve them before the props.
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Limits the number of nodes of a column (the nodes with the same `x` and `z` coordinates) which can be generated with this [`ModelTemplate`] to `max`. For example: at most one windmill per column, to avoid stacked props.
+    ///
+    /// The limit is enforced when the generator selects a model for a node: once a column holds `max` nodes with this model, the model is never selected again in this column. A model forced by the propagation, set as an initial node or collapsed manually is not limited, but is still counted. A node where only limited models are left when it is selected fails the generation with a contradiction.
+    ///
+    /// All the variations (rotations) of this [`ModelTemplate`] are counted together.
+    pub fn with_max_per_column(mut self, max: u32) -> Self {
+        self.count_limits.per_column = Some(max);
+        self
+    }
+
+    /// Limits the number of nodes of a layer (the nodes with the same `y` coordinate) which can be generated with this [`ModelTemplate`] to `max`. For example: at most 3 bridges per layer. See [`ModelTemplate::with_max_per_column`] for how the limit is enforced.
+    pub fn with_max_per_layer(mut self, max: u32) -> Self {
+        self.count_limits.per_layer = Some(max);
+        self
+    }
+
+    /// Marks this [`ModelTemplate`] as a void model: a model representing empty space, with nothing to display or export.
+    ///
+    /// The flag does not change the generation. It is stored in the [`super::rules::Rules`] (see [`super::rules::Rules::is_void`]) so that all the consumers of the output (viewers, exporters, stats, Bevy plugins) can skip the void nodes consistently.
+    pub fn with_void(mut self) -> Self {
+        self.void = true;
+        self
+    }
+
+    /// Sets the probability (clamped between `0.` and `1.`) for a node generated with this [`ModelTemplate`] to be spawned/reported as empty. For example: a sparse decoration model which only spawns a prop on some of its nodes.
+    ///
+    /// The generation is not affected: the model is selected and constrains its neighbours as usual. The outcome is resolved deterministically from the generator's seed and the node index by [`super::rules::Rules::is_spawned_empty`], so that the density of the output can be tuned without adding more models.
+    pub fn with_emptiness_chance(mut self, chance: f32) -> Self {
+        self.emptiness_chance = chance.clamp(0., 1.);
+        self
+    }
+
+    /// Adds `tag` to the tags of this [`ModelTemplate`]. Tags are stored in the [`super::rules::Rules`] and can be used to query the generated output, see [`crate::grid_data::ModelTagQueries`].
+    pub fn with_tag(mut self, tag: impl Into<Cow<'static, str>>) -> Self {
+        add_tag(&mut self.tags, tag.into());
+        self
+    }
+    /// Adds all the `tags` to the tags of this [`ModelTemplate`], see [`ModelTemplate::with_tag`].
+    pub fn with_tags<I, T>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Cow<'static, str>>,
+    {
+        for tag in tags.into_iter() {
+            add_tag(&mut self.tags, tag.into());
+        }
         self
     }
 
+    /// Specify the sockets of this [`ModelTemplate`] towards its neighbour at `offset`, replacing the previous ones if any. They are only used by rules whose [`super::direction_set::DirectionSet`] contains `offset` (see [`super::rules::RulesBuilder::with_direction_set`]), and are rotated with the model.
+    ///
+    /// Two models can be neighbours at `offset` if the sockets of the first one towards `offset` are compatible with the sockets of the second one towards the opposite offset. A model without sockets towards an offset accepts any neighbour there.
+    pub fn with_offset_sockets(mut self, offset: GridOffset, sockets: Vec<Socket>) -> Self {
+        set_offset_sockets(&mut self.offset_sockets, offset, sockets);
+        self
+    }
+
+    fn remap_sockets(&mut self, sockets_remapping: &SocketsRemapping) {
+        for socket in self
+            .sockets
+            .iter_mut()
+            .chain(self.offset_sockets.iter_mut().map(|(_, sockets)| sockets))
+            .flatten()
+        {
+            *socket = sockets_remapping.socket(*socket);
+        }
+    }
+
+    fn rotated_offset_sockets(
+        &self,
+        rotation: ModelRotation,
+        rot_axis: Direction,
+    ) -> Vec<(GridOffset, Vec<Socket>)> {
+        self.offset_sockets
+            .iter()
+            .map(|(offset, sockets)| {
+                let rotated_offset = offset.rotated(rotation, rot_axis);
+                let mut sockets = sockets.clone();
+                // Like the sockets on the rotation axis, sockets on an offset left in place by the rotation are marked as rotated
+                if rotated_offset == *offset {
+                    for socket in &mut sockets {
+                        socket.rotate(rotation);
+                    }
+                }
+                (rotated_offset, sockets)
+            })
+            .collect()
+    }
+
     fn rotated_sockets(&self, rotation: ModelRotation, rot_axis: Direction) -> Vec<Vec<Socket>> {
         let mut rotated_sockets = vec![Vec::new(); self.sockets.len()];
 
@@ -177,6 +367,23 @@ impl<C> ModelTemplate<C> {
     }
 }
 
+fn set_offset_sockets(
+    offset_sockets: &mut Vec<(GridOffset, Vec<Socket>)>,
+    offset: GridOffset,
+    sockets: Vec<Socket>,
+) {
+    match offset_sockets.iter_mut().find(|(o, _)| *o == offset) {
+        Some((_, existing)) => *existing = sockets,
+        None => offset_sockets.push((offset, sockets)),
+    }
+}
+
+fn add_tag(tags: &mut Vec<Cow<'static, str>>, tag: Cow<'static, str>) {
+    if !tags.contains(&tag) {
+        tags.push(tag);
+    }
+}
+
 /// Used to create one or more [`Model`]. Created models can then be used in a [`super::rules::RulesBuilder`]
 #[derive(Clone)]
 pub struct ModelCollection<C: CoordinateSystem> {
@@ -213,6 +420,51 @@ impl<C: CoordinateSystem> ModelCollection<C> {
         self.models.iter_mut()
     }
 
+    /// Moves all the models of `other` at the end of this collection, and returns their new indexes.
+    ///
+    /// `sockets_remapping` is the remapping returned when merging the [`super::socket::SocketCollection`] of `other` into the sockets of this collection, see [`super::socket::SocketCollection::merge`]. Use [`SocketsRemapping::default`] if both collections already use the same sockets.
+    ///
+    /// ### Example
+    /// ```
+    /// use ghx_proc_gen::generator::{model::ModelCollection, rules::RulesBuilder, socket::{SocketCollection, SocketsCartesian2D}};
+    ///
+    /// let mut terrain_sockets = SocketCollection::new();
+    /// let grass = terrain_sockets.create();
+    /// terrain_sockets.add_connection(grass, vec![grass]);
+    /// let mut terrain_models = ModelCollection::new();
+    /// terrain_models.create(SocketsCartesian2D::Mono(grass));
+    ///
+    /// let mut props_sockets = SocketCollection::new();
+    /// let fence = props_sockets.create();
+    /// props_sockets.add_connection(fence, vec![fence]);
+    /// let mut props_models = ModelCollection::new();
+    /// let fence_model = props_models.create(SocketsCartesian2D::Mono(fence)).index();
+    ///
+    /// let sockets_remapping = terrain_sockets.merge(props_sockets);
+    /// let models_remapping = terrain_models.merge(props_models, &sockets_remapping);
+    /// assert_eq!(models_remapping.model_index(fence_model), 1);
+    ///
+    /// terrain_sockets.add_connection(grass, vec![sockets_remapping.socket(fence)]);
+    /// let rules = RulesBuilder::new_cartesian_2d(terrain_models, terrain_sockets).build().unwrap();
+    /// assert_eq!(rules.original_models_count(), 2);
+    /// ```
+    pub fn merge(
+        &mut self,
+        other: ModelCollection<C>,
+        sockets_remapping: &SocketsRemapping,
+    ) -> ModelsRemapping {
+        let remapping = ModelsRemapping {
+            offset: self.models.len(),
+            count: other.models.len(),
+        };
+        for mut model in other.models.into_iter() {
+            model.index = remapping.model_index(model.index);
+            model.template.remap_sockets(sockets_remapping);
+            self.models.push(model);
+        }
+        remapping
+    }
+
     /// Returns the last model in the colleciton if any
     pub fn last(&self) -> Option<&Model<C>> {
         self.models.last()
@@ -224,27 +476,57 @@ impl<C: CoordinateSystem> ModelCollection<C> {
     }
 
     pub(crate) fn create_variations(&self, rotation_axis: Direction) -> Vec<ModelVariation> {
-        let mut model_variations = Vec::new();
-        for model in self.models.iter() {
-            // Iterate on a vec of all possible node rotations and filter with the set to have a deterministic insertion order of model variations.
-            for rotation in ALL_MODEL_ROTATIONS {
-                if model.template.allowed_rotations.contains(&rotation) {
-                    let rotated_sockets = model.template.rotated_sockets(*rotation, rotation_axis);
-                    model_variations.push(ModelVariation {
-                        sockets: rotated_sockets
-                            .iter()
-                            .map(|dir| dir.iter().map(|s| s.id()).collect())
-                            .collect(),
-                        weight: model.template.weight,
-                        original_index: model.index,
-                        rotation: *rotation,
-                        #[cfg(feature = "models-names")]
-                        name: model.name.clone(),
-                    });
-                }
-            }
+        self.models
+            .iter()
+            .flat_map(|model| model.create_variations(rotation_axis))
+            .collect()
+    }
+}
+
+impl ModelCollection<Cartesian2D> {
+    /// Returns a [`Cartesian3D`] copy of this collection, with all its models lifted to 3d with the same `up` and `down` sockets, see [`ModelTemplate::to_cartesian_3d`]. The models keep their indexes and names.
+    pub fn to_cartesian_3d(&self, up: Socket, down: Socket) -> ModelCollection<Cartesian3D> {
+        ModelCollection {
+            models: self
+                .models
+                .iter()
+                .map(|model| Model {
+                    index: model.index,
+                    template: model.template.to_cartesian_3d(up, down),
+                    #[cfg(feature = "models-names")]
+                    name: model.name.clone(),
+                })
+                .collect(),
         }
-        model_variations
+    }
+}
+
+/// New indexes of the models of a [`ModelCollection`] merged into another one, returned by [`ModelCollection::merge`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModelsRemapping {
+    offset: usize,
+    count: usize,
+}
+
+impl ModelsRemapping {
+    /// Returns the offset added to the indexes of the merged models
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns how many models were merged
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Converts the index of a model of the merged collection to its index in the resulting collection
+    pub fn model_index(&self, model_index: ModelIndex) -> ModelIndex {
+        model_index + self.offset
+    }
+
+    /// Returns the range of the indexes of the merged models in the resulting collection
+    pub fn model_indexes(&self) -> std::ops::Range<ModelIndex> {
+        self.offset..self.offset + self.count
     }
 }
 
@@ -274,11 +556,42 @@ impl<C: CoordinateSystem> Model<C> {
         self.index
     }
 
+    pub(crate) fn create_variations(&self, rotation_axis: Direction) -> Vec<ModelVariation> {
+        let mut model_variations = Vec::new();
+        // Iterate on a vec of all possible node rotations and filter with the set to have a deterministic insertion order of model variations.
+        for rotation in ALL_MODEL_ROTATIONS {
+            if self.template.allowed_rotations.contains(&rotation) {
+                let rotated_sockets = self.template.rotated_sockets(*rotation, rotation_axis);
+                let rotated_offset_sockets = self
+                    .template
+                    .rotated_offset_sockets(*rotation, rotation_axis);
+                model_variations.push(ModelVariation {
+                    sockets: rotated_sockets
+                        .iter()
+                        .map(|dir| dir.iter().map(|s| s.id()).collect())
+                        .collect(),
+                    offset_sockets: rotated_offset_sockets
+                        .iter()
+                        .map(|(offset, sockets)| {
+                            (*offset, sockets.iter().map(|s| s.id()).collect())
+                        })
+                        .collect(),
+                    weight: self.template.weight,
+                    original_index: self.index,
+                    rotation: *rotation,
+                    #[cfg(feature = "models-names")]
+                    name: self.name.clone(),
+                });
+            }
+        }
+        model_variations
+    }
+
     /// Specify that this [`Model`] can be rotated in exactly one way: `rotation`
     ///
     /// Rotations are specified as counter-clockwise
     pub fn with_rotation(&mut self, rotation: ModelRotation) -> &mut Self {
-        self.template.allowed_rotations = HashSet::from([rotation]);
+        self.template.allowed_rotations = BTreeSet::from([rotation]);
         self
     }
     /// Specify that this [`Model`] can be rotated by `rotation`, in addition to its currently allowed rotations.
@@ -291,8 +604,11 @@ impl<C: CoordinateSystem> Model<C> {
     /// Specify that this [`Model`] can be rotated in every way specified in `rotations`.
     ///
     /// Rotations are specified as counter-clockwise
-    pub fn with_rotations<R: Into<HashSet<ModelRotation>>>(&mut self, rotations: R) -> &mut Self {
-        self.template.allowed_rotations = rotations.into();
+    pub fn with_rotations<R: IntoIterator<Item = ModelRotation>>(
+        &mut self,
+        rotations: R,
+    ) -> &mut Self {
+        self.template.allowed_rotations = rotations.into_iter().collect();
         self
     }
     /// Specify that this [`Model`] can be rotated in every way specified in `rotations` in addition to its currently allowed rotations.
@@ -315,25 +631,110 @@ impl<C: CoordinateSystem> Model<C> {
         self
     }
 
-    /// Specify this [`Model`] weight. The `weight` value should be strictly superior to `0`. If it is not the case, the value will be overriden by `f32::MIN_POSITIVE`.
+    /// Specify this [`Model`] weight. The `weight` value should be finite and positive, see [`ModelTemplate::with_weight`]. If it is not the case, building the [`super::rules::Rules`] will return a [`crate::RulesBuilderError::InvalidWeight`].
     ///
     /// Used by a [`super::Generator`] when using [`super::ModelSelectionHeuristic::WeightedProbability`] and [`super::node_heuristic::NodeSelectionHeuristic::MinimumEntropy`].
     ///
     /// All the variations (rotations) of this [`Model`] will use the same weight.
     pub fn with_weight<W: Into<f32>>(&mut self, weight: W) -> &mut Self {
-        let mut checked_weight = weight.into();
-        if checked_weight <= 0. {
-            #[cfg(feature = "debug-traces")]
-            warn!(
-                "Model with index {}, name {:?}, had an invalid weight {} <= 0., weight overriden to f32::MIN: {}",
-                self.index, self.name, checked_weight, f32::MIN_POSITIVE
-            );
-            checked_weight = f32::MIN_POSITIVE
-        };
-        self.template.weight = checked_weight;
+        self.template.weight = weight.into();
+        self
+    }
+
+    /// Specify this [`Model`] collapse priority, see [`ModelTemplate::with_priority`].
+    pub fn with_priority(&mut self, priority: u32) -> &mut Self {
+        self.template.priority = priority;
+        self
+    }
+
+    /// Returns the collapse priority of this [`Model`]
+    pub fn priority(&self) -> u32 {
+        self.template.priority
+    }
+
+    /// Limits the number of nodes of a column with this [`Model`], see [`ModelTemplate::with_max_per_column`].
+    pub fn with_max_per_column(&mut self, max: u32) -> &mut Self {
+        self.template.count_limits.per_column = Some(max);
+        self
+    }
+
+    /// Limits the number of nodes of a layer with this [`Model`], see [`ModelTemplate::with_max_per_layer`].
+    pub fn with_max_per_layer(&mut self, max: u32) -> &mut Self {
+        self.template.count_limits.per_layer = Some(max);
         self
     }
 
+    /// Returns the [`CountLimits`] of this [`Model`]
+    pub fn count_limits(&self) -> CountLimits {
+        self.template.count_limits
+    }
+
+    /// Marks this [`Model`] as a void model, see [`ModelTemplate::with_void`].
+    pub fn with_void(&mut self) -> &mut Self {
+        self.template.void = true;
+        self
+    }
+
+    /// Returns `true` if this [`Model`] is a void model
+    pub fn is_void(&self) -> bool {
+        self.template.void
+    }
+
+    /// Sets the emptiness chance of this [`Model`], see [`ModelTemplate::with_emptiness_chance`].
+    pub fn with_emptiness_chance(&mut self, chance: f32) -> &mut Self {
+        self.template.emptiness_chance = chance.clamp(0., 1.);
+        self
+    }
+
+    /// Returns the emptiness chance of this [`Model`]
+    pub fn emptiness_chance(&self) -> f32 {
+        self.template.emptiness_chance
+    }
+
+    /// Adds `tag` to the tags of this [`Model`]. Tags are stored in the [`super::rules::Rules`] and can be used to query the generated output, see [`crate::grid_data::ModelTagQueries`].
+    ///
+    /// All the variations (rotations) of this [`Model`] share the same tags.
+    pub fn with_tag(&mut self, tag: impl Into<Cow<'static, str>>) -> &mut Self {
+        add_tag(&mut self.template.tags, tag.into());
+        self
+    }
+
+    /// Adds all the `tags` to the tags of this [`Model`], see [`Model::with_tag`].
+    ///
+    /// ### Example
+    /// ```
+    /// use ghx_proc_gen::generator::{socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::coordinate_system::Cartesian2D;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let ground = sockets.create();
+    /// let mut models = ModelCollection::<Cartesian2D>::new();
+    /// models
+    ///     .create(SocketsCartesian2D::Mono(ground))
+    ///     .with_tags(["grass", "walkable"]);
+    /// ```
+    pub fn with_tags<I, T>(&mut self, tags: I) -> &mut Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Cow<'static, str>>,
+    {
+        for tag in tags.into_iter() {
+            add_tag(&mut self.template.tags, tag.into());
+        }
+        self
+    }
+
+    /// Specify the sockets of this [`Model`] towards its neighbour at `offset`, see [`ModelTemplate::with_offset_sockets`].
+    pub fn with_offset_sockets(&mut self, offset: GridOffset, sockets: Vec<Socket>) -> &mut Self {
+        set_offset_sockets(&mut self.template.offset_sockets, offset, sockets);
+        self
+    }
+
+    /// Returns the tags of this [`Model`]
+    pub fn tags(&self) -> &[Cow<'static, str>] {
+        &self.template.tags
+    }
+
     #[allow(unused_mut)]
     /// Register the given name for this model.
     ///
@@ -364,17 +765,33 @@ impl<C: CoordinateSystem> Model<C> {
         }
     }
 }
+impl Model<Cartesian2D> {
+    /// Specify the sockets of this [`Model`] on its diagonals, see [`ModelTemplate::with_diagonal_sockets`].
+    pub fn with_diagonal_sockets(&mut self, sockets: DiagonalSockets) -> &mut Self {
+        let offset_sockets: Vec<(GridOffset, Vec<Socket>)> = sockets.into();
+        for (offset, sockets) in offset_sockets {
+            set_offset_sockets(&mut self.template.offset_sockets, offset, sockets);
+        }
+        self
+    }
+}
+
 impl<C: CoordinateSystem> Into<ModelTemplate<C>> for Model<C> {
     fn into(self) -> ModelTemplate<C> {
         self.template.clone()
     }
 }
 
+/// A model expanded into one of its rotations, see [`ModelVariation`]. [`ExpandedNodeModel::sockets_in`] returns the sockets of the expanded model in a direction.
+pub type ExpandedNodeModel = ModelVariation;
+
 /// This is a variation of a user [`Model`] generated by the [`crate::generator::Rules`]. One [`Model`] may be transformed into one ore more [`ModelVariation`] depending on the number of allowed rotations of the model.
 #[derive(Debug)]
 pub struct ModelVariation {
     /// Allowed connections for this [`Model`] in the output
     sockets: Vec<Vec<SocketId>>,
+    /// Allowed connections for this [`Model`] towards the neighbours at some offsets, any neighbour is allowed at the other offsets
+    offset_sockets: Vec<(GridOffset, Vec<SocketId>)>,
     /// Weight factor influencing the density of this [`Model`] in the generated output. Defaults to 1
     weight: f32,
     /// Index of the [`Model`] this was expanded from
@@ -392,6 +809,36 @@ impl ModelVariation {
     pub fn sockets(&self) -> &Vec<Vec<SocketId>> {
         &self.sockets
     }
+    /// Returns all the sockets of the model: in each direction, then towards each offset
+    pub(crate) fn all_sockets(&self) -> impl Iterator<Item = SocketId> + '_ {
+        self.sockets
+            .iter()
+            .flatten()
+            .chain(self.offset_sockets.iter().flat_map(|(_, sockets)| sockets))
+            .copied()
+    }
+
+    /// Returns the sockets of the model in `direction`. Returns an empty list if `direction` is not a direction of the coordinate system of the model.
+    pub fn sockets_in(&self, direction: Direction) -> Vec<Socket> {
+        match self.sockets.get(direction as usize) {
+            Some(sockets) => sockets
+                .iter()
+                .map(|&socket_id| Socket::from_id(socket_id))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+    /// Returns the sockets of the model towards its neighbours at some offsets (see [`ModelTemplate::with_offset_sockets`]), already rotated. Any neighbour is allowed at the other offsets.
+    pub fn offset_sockets(&self) -> &[(GridOffset, Vec<SocketId>)] {
+        &self.offset_sockets
+    }
+    /// Returns the sockets of the model towards its neighbour at `offset`, `None` if any neighbour is allowed there
+    pub fn sockets_towards(&self, offset: GridOffset) -> Option<&Vec<SocketId>> {
+        self.offset_sockets
+            .iter()
+            .find(|(o, _)| *o == offset)
+            .map(|(_, sockets)| sockets)
+    }
     /// Returns the weight of the model
     pub fn weight(&self) -> f32 {
         self.weight
@@ -416,6 +863,7 @@ impl ModelVariation {
 /// Used to identify a specific variation of an input model.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(Component, Default))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
 pub struct ModelInstance {
     /// Index of the original [`Model`]
@@ -431,8 +879,9 @@ impl fmt::Display for ModelInstance {
 }
 
 /// Represents a rotation around an Axis, in the trigonometric(counterclockwise) direction
-#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "bevy", derive(Component))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
 pub enum ModelRotation {
     /// Rotation of 0°
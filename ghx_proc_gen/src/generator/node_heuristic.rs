@@ -1,18 +1,20 @@
-use ghx_grid::coordinate_system::CoordinateSystem;
-use rand::{rngs::StdRng, Rng};
+use std::sync::Arc;
+
+use ghx_grid::{coordinate_system::CoordinateSystem, grid::GridDefinition};
+use rand::Rng;
 
 use crate::NodeIndex;
 
-use super::rules::Rules;
+use super::{candidates::Candidates, rules::Rules, ModelSelectionHeuristic};
 
 /// Defines a heuristic for the choice of a node to generate. For some given Rules, each heuristic will lead to different visual results and different failure rates.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum NodeSelectionHeuristic {
-    /// The node with with the minimum count of possible models remaining will be chosen at each selection iteration. If multiple nodes have the same value, a random one is picked.
+    /// The node with with the minimum count of possible models remaining will be chosen at each selection iteration. If multiple nodes have the same value, a random one is picked (drawn from the generator RNG, see [`crate::generator::builder::GeneratorBuilder::with_selection_noise`]).
     ///s
     /// Similar to `MinimumEntropy` when the models have all more or less the same weight.
     MinimumRemainingValue,
-    /// The node with the minimum Shannon entropy (computed from the models weights) will be chosen at each selection iteration. If multiple nodes have the same value, a random one is picked.
+    /// The node with the minimum Shannon entropy (computed from the models weights) will be chosen at each selection iteration. If multiple nodes have the same value, a random one is picked (drawn from the generator RNG, see [`crate::generator::builder::GeneratorBuilder::with_selection_noise`]).
     ///
     ///  Similar to `MinimumRemainingValue` when the models have all more or less the same weight.
     MinimumEntropy,
@@ -20,9 +22,109 @@ pub enum NodeSelectionHeuristic {
     ///
     /// Often causes a **very high generation failure rate**, except for very simple rules.
     Random,
+    /// The generation grows outward from the anchor nodes given to [`crate::generator::builder::GeneratorBuilder::with_anchors`]: the chosen node is always one of the closest nodes (in grid steps, BFS order) to an anchor. Among them, the node with the minimum count of possible models remaining is chosen, like [`NodeSelectionHeuristic::MinimumRemainingValue`].
+    ///
+    /// Useful to make a generation visually and logically expand from chosen points (a settlement growing from its town center for example), and usually lowers the contradiction rate near the anchors. Without anchors, behaves like [`NodeSelectionHeuristic::MinimumRemainingValue`].
+    GrowFromAnchors,
+    /// Completes each Y layer of the grid before starting the next one, bottom-up (from `y = 0`). Inside a layer, the node with the minimum count of possible models remaining is chosen, like [`NodeSelectionHeuristic::MinimumRemainingValue`].
+    ///
+    /// Matches rules structured in vertical layers (terrain, then props, then canopy for example) and greatly reduces the contradictions between vertically-dependent layers.
+    LayerByLayer,
+    /// Generates first the nodes which can still be a model with a higher collapse priority (see [`crate::generator::model::ModelTemplate::with_priority`]), so that structural models (ground, walls) are resolved before the nodes whose candidates are only low-priority props. Among the nodes with the same highest remaining priority, the node with the minimum count of possible models remaining is chosen, like [`NodeSelectionHeuristic::MinimumRemainingValue`].
+    ///
+    /// Slower than the other heuristics since the candidates of each node are visited at each selection. Without priorities, behaves like [`NodeSelectionHeuristic::MinimumRemainingValue`].
+    PriorityFirst,
+}
+
+/// Condition starting a [`HeuristicPhase`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PhaseCondition {
+    /// The phase starts once this ratio (in `[0, 1]`) of the nodes to generate are generated
+    GeneratedRatio(f32),
+    /// The phase starts after this number of contradictions. Contradictions are counted since the last successful generation: the retries of a same generation share the count.
+    Contradictions(u32),
+}
+
+/// A phase of a heuristic schedule: the heuristics used by a [`crate::generator::Generator`] once its condition is met. See [`crate::generator::builder::GeneratorBuilder::with_heuristic_schedule`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HeuristicPhase {
+    /// Condition starting the phase
+    pub condition: PhaseCondition,
+    /// Node selection heuristic used during the phase
+    pub node_heuristic: NodeSelectionHeuristic,
+    /// Model selection heuristic used during the phase
+    pub model_heuristic: ModelSelectionHeuristic,
 }
 
-const MAX_NOISE_VALUE: f32 = 1E-2;
+impl HeuristicPhase {
+    /// Creates a phase using `node_heuristic` once `condition` is met, with the default [`ModelSelectionHeuristic`]
+    pub fn new(condition: PhaseCondition, node_heuristic: NodeSelectionHeuristic) -> Self {
+        Self {
+            condition,
+            node_heuristic,
+            model_heuristic: ModelSelectionHeuristic::default(),
+        }
+    }
+
+    /// Specifies the [`ModelSelectionHeuristic`] used during the phase
+    pub fn with_model_heuristic(mut self, model_heuristic: ModelSelectionHeuristic) -> Self {
+        self.model_heuristic = model_heuristic;
+        self
+    }
+
+    /// Returns `true` if the condition of the phase is met
+    pub(crate) fn is_met(&self, generated_ratio: f32, contradictions_count: u32) -> bool {
+        match self.condition {
+            PhaseCondition::GeneratedRatio(ratio) => generated_ratio >= ratio,
+            PhaseCondition::Contradictions(count) => contradictions_count >= count,
+        }
+    }
+}
+
+/// Default magnitude of the noise used to break the ties between nodes in the node selection, see [`crate::generator::builder::GeneratorBuilder::with_selection_noise`]
+pub const DEFAULT_SELECTION_NOISE: f32 = 1E-2;
+
+/// Draws a noise value in `[0, noise)` from `rng`, without drawing if `noise` is `0`
+fn draw_noise<R: Rng>(noise: f32, rng: &mut R) -> f32 {
+    match noise > 0. {
+        true => noise * rng.gen::<f32>(),
+        false => 0.,
+    }
+}
+
+/// Picks a node not generated yet with the lowest rank. Among the nodes of this rank, picks the node with the minimum count of possible models remaining (with some noise to break the ties).
+fn select_by_rank<F: Fn(NodeIndex) -> u32, R: Rng>(
+    node_rank: F,
+    possible_models_counts: &Vec<usize>,
+    noise: f32,
+    rng: &mut R,
+) -> Option<NodeIndex> {
+    let (mut min_rank, mut min) = (u32::MAX, f32::MAX);
+    let mut picked_node = None;
+    for (index, &possibilities_count) in possible_models_counts.iter().enumerate() {
+        if possibilities_count <= 1 {
+            continue;
+        }
+        let rank = node_rank(index);
+        // Only the nodes with the current lowest rank are considered
+        if rank <= min_rank {
+            let value = possibilities_count as f32 + draw_noise(noise, rng);
+            if rank < min_rank || value < min {
+                (min_rank, min) = (rank, value);
+                picked_node = Some(index);
+            }
+        }
+    }
+    picked_node
+}
+
+/// Replaces negative or non-finite noise values by `0`
+pub(crate) fn sanitize_selection_noise(noise: f32) -> f32 {
+    match noise.is_finite() && noise > 0. {
+        true => noise,
+        false => 0.,
+    }
+}
 
 /// Defines a heuristic for the choice of a node to generate.
 pub(crate) enum InternalNodeSelectionHeuristic {
@@ -33,23 +135,36 @@ pub(crate) enum InternalNodeSelectionHeuristic {
         /// Current entropy data for a given node
         node_entropies: Vec<NodeEntropyData>,
         /// Value of `weight * log(weight)` for a given model
-        models_weight_log_weights: Vec<f32>,
+        models_weight_log_weights: Vec<f64>,
     },
     Random,
+    GrowFromAnchors {
+        /// Distance (in grid steps) from each node to the closest anchor
+        anchor_distances: Arc<Vec<u32>>,
+    },
+    LayerByLayer {
+        /// Y layer of each node
+        node_layers: Vec<u32>,
+    },
+    PriorityFirst {
+        /// Rank of the priority of each model (`0` for the highest priority of the rules)
+        models_ranks: Vec<u32>,
+    },
 }
 
+/// The sums are kept as `f64` since they are decremented on each ban: with `f32`, the accumulated rounding errors become significant with many models of small weights.
 #[derive(Clone, Copy)]
 pub(crate) struct NodeEntropyData {
     /// Shannon entropy of the node
     entropy: f32,
     /// Sum of the weights of the models still possible on the node
-    weight_sum: f32,
+    weight_sum: f64,
     /// Sum of `weight * log(weight)` of the models still possible on the node
-    weight_log_weight_sum: f32,
+    weight_log_weight_sum: f64,
 }
 
 impl NodeEntropyData {
-    fn new(weight_sum: f32, weight_log_weight_sum: f32) -> Self {
+    fn new(weight_sum: f64, weight_log_weight_sum: f64) -> Self {
         Self {
             entropy: entropy(weight_sum, weight_log_weight_sum),
             weight_sum,
@@ -62,15 +177,20 @@ impl NodeEntropyData {
     }
 }
 
-fn entropy(weight_sum: f32, weight_log_weight_sum: f32) -> f32 {
-    f32::ln(weight_sum) - weight_log_weight_sum / weight_sum
+fn entropy(weight_sum: f64, weight_log_weight_sum: f64) -> f32 {
+    // Nodes with only zero-weight models left have no choice to make
+    if !(weight_sum > 0.) {
+        return 0.;
+    }
+    (f64::ln(weight_sum) - weight_log_weight_sum / weight_sum) as f32
 }
 
 impl InternalNodeSelectionHeuristic {
     pub(crate) fn from_external<T: CoordinateSystem + Clone>(
         heuristic: NodeSelectionHeuristic,
         rules: &Rules<T>,
-        node_count: usize,
+        grid: &GridDefinition<T>,
+        anchor_distances: &Arc<Vec<u32>>,
     ) -> Self {
         match heuristic {
             NodeSelectionHeuristic::MinimumRemainingValue => {
@@ -78,8 +198,63 @@ impl InternalNodeSelectionHeuristic {
             }
             NodeSelectionHeuristic::Random => InternalNodeSelectionHeuristic::Random,
             NodeSelectionHeuristic::MinimumEntropy => {
-                InternalNodeSelectionHeuristic::new_minimum_entropy(rules, node_count)
+                InternalNodeSelectionHeuristic::new_minimum_entropy(rules, grid.total_size())
             }
+            NodeSelectionHeuristic::GrowFromAnchors => {
+                InternalNodeSelectionHeuristic::GrowFromAnchors {
+                    anchor_distances: Arc::clone(anchor_distances),
+                }
+            }
+            NodeSelectionHeuristic::PriorityFirst => {
+                InternalNodeSelectionHeuristic::new_priority_first(rules)
+            }
+            NodeSelectionHeuristic::LayerByLayer => InternalNodeSelectionHeuristic::LayerByLayer {
+                node_layers: (0..grid.total_size())
+                    .map(|node_index| grid.pos_from_index(node_index).y)
+                    .collect(),
+            },
+        }
+    }
+
+    pub(crate) fn to_external(&self) -> NodeSelectionHeuristic {
+        match self {
+            InternalNodeSelectionHeuristic::MinimumRemainingValue => {
+                NodeSelectionHeuristic::MinimumRemainingValue
+            }
+            InternalNodeSelectionHeuristic::MinimumEntropy { .. } => {
+                NodeSelectionHeuristic::MinimumEntropy
+            }
+            InternalNodeSelectionHeuristic::Random => NodeSelectionHeuristic::Random,
+            InternalNodeSelectionHeuristic::GrowFromAnchors { .. } => {
+                NodeSelectionHeuristic::GrowFromAnchors
+            }
+            InternalNodeSelectionHeuristic::LayerByLayer { .. } => {
+                NodeSelectionHeuristic::LayerByLayer
+            }
+            InternalNodeSelectionHeuristic::PriorityFirst { .. } => {
+                NodeSelectionHeuristic::PriorityFirst
+            }
+        }
+    }
+
+    fn new_priority_first<T: CoordinateSystem>(rules: &Rules<T>) -> InternalNodeSelectionHeuristic {
+        let priorities: Vec<u32> = (0..rules.models_count())
+            .map(|model_index| rules.variant_priority_unchecked(model_index))
+            .collect();
+        // Distinct priorities, from the highest to the lowest
+        let mut distinct_priorities = priorities.clone();
+        distinct_priorities.sort_unstable_by(|a, b| b.cmp(a));
+        distinct_priorities.dedup();
+        InternalNodeSelectionHeuristic::PriorityFirst {
+            models_ranks: priorities
+                .iter()
+                .map(|priority| {
+                    distinct_priorities
+                        .iter()
+                        .position(|p| p == priority)
+                        .unwrap() as u32
+                })
+                .collect(),
         }
     }
 
@@ -92,7 +267,11 @@ impl InternalNodeSelectionHeuristic {
         let mut all_models_weight_log_weight_sum = 0.;
         for model_index in 0..rules.models_count() {
             let weight = rules.weight_unchecked(model_index);
-            let weight_log_weight = weight * f32::ln(weight);
+            // `weight * ln(weight)` tends to 0 when `weight` tends to 0
+            let weight_log_weight = match weight > 0. {
+                true => weight * f64::ln(weight),
+                false => 0.,
+            };
             models_weight_log_weights.push(weight_log_weight);
             all_models_weight_sum += weight;
             all_models_weight_log_weight_sum += weight_log_weight;
@@ -123,7 +302,7 @@ impl InternalNodeSelectionHeuristic {
         }
     }
 
-    pub(crate) fn handle_ban(&mut self, node_index: NodeIndex, model_index: usize, weight: f32) {
+    pub(crate) fn handle_ban(&mut self, node_index: NodeIndex, model_index: usize, weight: f64) {
         match self {
             InternalNodeSelectionHeuristic::MinimumEntropy {
                 initial_node_entropy_data: _,
@@ -140,12 +319,39 @@ impl InternalNodeSelectionHeuristic {
         }
     }
 
-    /// Picks a node according to the heuristic
-    pub(crate) fn select_node(
+    /// Returns the entropy data of a node, if tracked by the heuristic
+    pub(crate) fn node_entropy_data(&self, node_index: NodeIndex) -> Option<NodeEntropyData> {
+        match self {
+            InternalNodeSelectionHeuristic::MinimumEntropy { node_entropies, .. } => {
+                Some(node_entropies[node_index])
+            }
+            _ => None,
+        }
+    }
+
+    /// Overwrites the entropy data of a node, if tracked by the heuristic
+    pub(crate) fn set_node_entropy_data(
+        &mut self,
+        node_index: NodeIndex,
+        entropy_data: NodeEntropyData,
+    ) {
+        match self {
+            InternalNodeSelectionHeuristic::MinimumEntropy { node_entropies, .. } => {
+                node_entropies[node_index] = entropy_data;
+            }
+            _ => (),
+        }
+    }
+
+    /// Picks a node according to the heuristic. `noise` is the magnitude of the random noise used to break the ties, drawn from `rng` (no draw is made if `noise` is `0`).
+    pub(crate) fn select_node<C: CoordinateSystem, R: Rng>(
         &self,
+        candidates: &Candidates<C>,
         possible_models_counts: &Vec<usize>,
-        rng: &mut StdRng,
+        noise: f32,
+        rng: &mut R,
     ) -> Option<NodeIndex> {
+        profile_scope!("select_node");
         match self {
             InternalNodeSelectionHeuristic::MinimumRemainingValue => {
                 let mut min = f32::MAX;
@@ -154,7 +360,7 @@ impl InternalNodeSelectionHeuristic {
                     // If the node is not generated yet (multiple possibilities)
                     if possibilities_count > 1 {
                         // Noise added to models count so that when evaluating multiples candidates with the same value, we pick a random one, not in the evaluation order.
-                        let noise = MAX_NOISE_VALUE * rng.gen::<f32>();
+                        let noise = draw_noise(noise, rng);
                         if (possibilities_count as f32 + noise) < min {
                             min = possibilities_count as f32 + noise;
                             picked_node = Some(index);
@@ -173,7 +379,7 @@ impl InternalNodeSelectionHeuristic {
                 for (index, &possibilities_count) in possible_models_counts.iter().enumerate() {
                     let entropy = node_entropies[index].entropy();
                     if possibilities_count > 1 && entropy < min {
-                        let noise = MAX_NOISE_VALUE * rng.gen::<f32>();
+                        let noise = draw_noise(noise, rng);
                         if (entropy + noise) < min {
                             min = entropy + noise;
                             picked_node = Some(index);
@@ -195,6 +401,31 @@ impl InternalNodeSelectionHeuristic {
                 }
                 picked_node
             }
+            InternalNodeSelectionHeuristic::GrowFromAnchors { anchor_distances } => select_by_rank(
+                |node_index| anchor_distances[node_index],
+                possible_models_counts,
+                noise,
+                rng,
+            ),
+            InternalNodeSelectionHeuristic::LayerByLayer { node_layers } => select_by_rank(
+                |node_index| node_layers[node_index],
+                possible_models_counts,
+                noise,
+                rng,
+            ),
+            InternalNodeSelectionHeuristic::PriorityFirst { models_ranks } => select_by_rank(
+                // Rank of the highest priority among the models still possible on the node
+                |node_index| {
+                    candidates
+                        .possible_models(node_index)
+                        .map(|model_index| models_ranks[model_index])
+                        .min()
+                        .unwrap_or(u32::MAX)
+                },
+                possible_models_counts,
+                noise,
+                rng,
+            ),
         }
     }
 }
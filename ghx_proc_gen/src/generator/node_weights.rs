@@ -0,0 +1,112 @@
+use ghx_grid::{coordinate_system::CoordinateSystem, direction::Direction, grid::GridDefinition};
+
+use crate::NodeIndex;
+
+use super::model::ModelIndex;
+
+/// Per-node multipliers of the weights of some original models, used by [`super::ModelSelectionHeuristic::WeightedProbability`] on top of the models weights and pair weights. See [`super::builder::GeneratorBuilder::with_node_weights`] and [`super::builder::GeneratorBuilder::with_weight_gradient`].
+///
+/// Models without multipliers keep their weight on all the nodes.
+#[derive(Clone, Debug, Default)]
+pub struct NodeWeights {
+    /// Indexed by [`ModelIndex`]: multipliers indexed by [`NodeIndex`] for each modified original model, `None` for the others. Empty if no model has multipliers.
+    multipliers: Vec<Option<Vec<f32>>>,
+}
+
+impl NodeWeights {
+    /// Creates [`NodeWeights`] without any multiplier
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no model has multipliers
+    pub fn is_empty(&self) -> bool {
+        self.multipliers.is_empty()
+    }
+
+    /// Multiplies the weight of the original model `model_index` on each of the `nodes_count` nodes by the value returned by `multiplier` for this node. Multipliers add up multiplicatively with the existing ones.
+    ///
+    /// Negative or non-finite multipliers are replaced by `0`.
+    pub fn multiply(
+        &mut self,
+        model_index: ModelIndex,
+        nodes_count: usize,
+        multiplier: impl Fn(NodeIndex) -> f32,
+    ) {
+        if self.multipliers.len() <= model_index {
+            self.multipliers.resize(model_index + 1, None);
+        }
+        let multipliers =
+            self.multipliers[model_index].get_or_insert_with(|| vec![1.; nodes_count]);
+        for (node_index, value) in multipliers.iter_mut().enumerate() {
+            *value *= sanitize_multiplier(multiplier(node_index));
+        }
+    }
+
+    /// Returns the multipliers of the nodes `node_indexes` only, indexed by the position of the nodes in `node_indexes`
+    pub(crate) fn restricted(&self, node_indexes: &[NodeIndex]) -> NodeWeights {
+        NodeWeights {
+            multipliers: self
+                .multipliers
+                .iter()
+                .map(|multipliers| {
+                    let multipliers = multipliers.as_ref()?;
+                    Some(
+                        node_indexes
+                            .iter()
+                            .map(|&node_index| multipliers.get(node_index).copied().unwrap_or(1.))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the multiplier of the weight of the original model `model_index` on the node `node_index`
+    #[inline]
+    pub fn multiplier(&self, model_index: ModelIndex, node_index: NodeIndex) -> f32 {
+        match self.multipliers.get(model_index) {
+            Some(Some(multipliers)) => multipliers.get(node_index).copied().unwrap_or(1.),
+            _ => 1.,
+        }
+    }
+}
+
+/// Returns a function giving, for each node of `grid`, the value of a linear gradient going from `from` on the first layer of nodes along `direction` to `to` on the last one.
+///
+/// On a grid with a single layer along `direction`, the gradient is `from` on all the nodes.
+pub fn gradient<C: CoordinateSystem>(
+    grid: &GridDefinition<C>,
+    direction: Direction,
+    from: f32,
+    to: f32,
+) -> impl Fn(NodeIndex) -> f32 + '_ {
+    let (axis, forward) = match direction {
+        Direction::XForward => (0, true),
+        Direction::XBackward => (0, false),
+        Direction::YForward => (1, true),
+        Direction::YBackward => (1, false),
+        Direction::ZForward => (2, true),
+        Direction::ZBackward => (2, false),
+    };
+    let size = [grid.size_x(), grid.size_y(), grid.size_z()][axis];
+    move |node_index| {
+        if size <= 1 {
+            return from;
+        }
+        let position = grid.pos_from_index(node_index);
+        let coord = [position.x, position.y, position.z][axis];
+        let layer = match forward {
+            true => coord,
+            false => size - 1 - coord,
+        };
+        from + (to - from) * layer as f32 / (size - 1) as f32
+    }
+}
+
+fn sanitize_multiplier(multiplier: f32) -> f32 {
+    match multiplier.is_finite() && multiplier > 0. {
+        true => multiplier,
+        false => 0.,
+    }
+}
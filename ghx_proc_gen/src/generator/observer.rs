@@ -1,10 +1,22 @@
-use super::{model::ModelInstance, GeneratedNode, Generator};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use super::{model::ModelInstance, rules::Rules, GeneratedNode, Generator};
 
 #[cfg(feature = "bevy")]
 use bevy::ecs::component::Component;
 use ghx_grid::{
     coordinate_system::CoordinateSystem,
-    grid::{GridData, GridDefinition},
+    grid::{GridData, GridDefinition, GridPosition},
+};
+
+use crate::{
+    serialization::{stream_header, write_stream_update},
+    NodeIndex,
 };
 
 /// Update sent by a [`crate::generator::Generator`]
@@ -16,6 +28,159 @@ pub enum GenerationUpdate {
     Reinitializing(u64),
     /// The generation failed due to a contradiction at the specified node_index
     Failed(usize),
+    /// A previously generated node, at the specified node_index, is not generated anymore. Sent when undoing an action with [`crate::generator::Generator::undo`].
+    Reverted(usize),
+}
+
+/// A box-shaped region of a grid, between two positions (both inclusive)
+#[derive(Clone, Debug)]
+pub struct NodeRegion {
+    /// Position with the lowest coordinates of the region
+    pub min: GridPosition,
+    /// Position with the highest coordinates of the region
+    pub max: GridPosition,
+}
+
+impl NodeRegion {
+    /// Creates a new [`NodeRegion`] between `min` and `max` (both inclusive)
+    pub fn new(min: GridPosition, max: GridPosition) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns `true` if `position` is inside the region
+    pub fn contains(&self, position: &GridPosition) -> bool {
+        (self.min.x..=self.max.x).contains(&position.x)
+            && (self.min.y..=self.max.y).contains(&position.y)
+            && (self.min.z..=self.max.z).contains(&position.z)
+    }
+}
+
+/// All the [`GenerationUpdate`] received since the last poll of an observer, coalesced into their net effect.
+///
+/// - A reinitialization discards the updates received before it.
+/// - A node generated multiple times only appears once, with its latest model.
+/// - A node generated then reverted inside the batch does not appear in the generated nodes.
+///
+/// To apply a batch on a view of the generation: clear everything if [`GenerationBatch::reinitialized`], then clear the [`GenerationBatch::reverted`] nodes, then add the [`GenerationBatch::generated`] nodes.
+#[derive(Clone, Debug, Default)]
+pub struct GenerationBatch {
+    reinitialization_seed: Option<u64>,
+    /// Generated nodes in generation order. `None` for the nodes reverted or generated again since.
+    generated: Vec<Option<GeneratedNode>>,
+    /// Index in `generated` of the latest generated node of each node index
+    generated_indexes: HashMap<NodeIndex, usize>,
+    reverted: BTreeSet<NodeIndex>,
+    failed: Vec<NodeIndex>,
+}
+
+impl GenerationBatch {
+    /// Creates a batch from a sequence of updates
+    pub fn from_updates<I: IntoIterator<Item = GenerationUpdate>>(updates: I) -> Self {
+        let mut batch = Self::default();
+        for update in updates {
+            batch.push(update);
+        }
+        batch
+    }
+
+    /// Coalesces `update` into the batch
+    pub fn push(&mut self, update: GenerationUpdate) {
+        match update {
+            GenerationUpdate::Generated(grid_node) => {
+                self.remove_generated(grid_node.node_index);
+                self.generated_indexes
+                    .insert(grid_node.node_index, self.generated.len());
+                self.generated.push(Some(grid_node));
+            }
+            GenerationUpdate::Reinitializing(seed) => {
+                *self = Self::default();
+                self.reinitialization_seed = Some(seed);
+            }
+            GenerationUpdate::Failed(node_index) => self.failed.push(node_index),
+            GenerationUpdate::Reverted(node_index) => {
+                self.remove_generated(node_index);
+                self.reverted.insert(node_index);
+            }
+        }
+    }
+
+    fn remove_generated(&mut self, node_index: NodeIndex) {
+        if let Some(index) = self.generated_indexes.remove(&node_index) {
+            self.generated[index] = None;
+        }
+    }
+
+    /// Returns `true` if the batch contains no update
+    pub fn is_empty(&self) -> bool {
+        self.reinitialization_seed.is_none()
+            && self.generated_indexes.is_empty()
+            && self.reverted.is_empty()
+            && self.failed.is_empty()
+    }
+
+    /// Returns `true` if the generator was reinitialized during the batch: everything generated before the batch should be cleared.
+    pub fn reinitialized(&self) -> bool {
+        self.reinitialization_seed.is_some()
+    }
+
+    /// Returns the seed of the last reinitialization of the batch, if any
+    pub fn reinitialization_seed(&self) -> Option<u64> {
+        self.reinitialization_seed
+    }
+
+    /// Returns the nodes generated during the batch (after the last reinitialization), in generation order, each node at most once.
+    pub fn generated(&self) -> impl Iterator<Item = &GeneratedNode> {
+        self.generated.iter().flatten()
+    }
+
+    /// Returns the number of nodes in [`GenerationBatch::generated`]
+    pub fn generated_count(&self) -> usize {
+        self.generated_indexes.len()
+    }
+
+    /// Returns the indexes (sorted, each at most once) of the nodes reverted during the batch (after the last reinitialization). They may have been generated in a previous batch and should be cleared before adding the [`GenerationBatch::generated`] nodes.
+    pub fn reverted(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.reverted.iter().copied()
+    }
+
+    /// Returns the indexes of the nodes where the generation failed during the batch (after the last reinitialization)
+    pub fn failed(&self) -> &[NodeIndex] {
+        &self.failed
+    }
+}
+
+/// Sending side of an observer queue, optionally filtered to only send the updates of the nodes in a [`NodeRegion`]
+#[derive(Clone)]
+pub(crate) struct ObserverSender {
+    sender: crossbeam_channel::Sender<GenerationUpdate>,
+    region: Option<NodeRegion>,
+}
+
+impl ObserverSender {
+    pub(crate) fn new(
+        sender: crossbeam_channel::Sender<GenerationUpdate>,
+        region: Option<NodeRegion>,
+    ) -> Self {
+        Self { sender, region }
+    }
+
+    /// Sends an update concerning the whole generation
+    pub(crate) fn send(&self, update: GenerationUpdate) {
+        let _ = self.sender.send(update);
+    }
+
+    /// Sends an update concerning the node `node_index`, if it is in the observed region
+    pub(crate) fn send_node_update<C: CoordinateSystem>(
+        &self,
+        update: GenerationUpdate,
+        grid: &GridDefinition<C>,
+        node_index: NodeIndex,
+    ) {
+        match &self.region {
+            Some(region) if !region.contains(&grid.pos_from_index(node_index)) => (),
+            _ => self.send(update),
+        }
+    }
 }
 
 /// Observer with a queue of the [`GenerationUpdate`] sent by the [`crate::generator::Generator`] which also maintains a coherent state of the current generation in a [`GridData`]
@@ -25,26 +190,48 @@ pub enum GenerationUpdate {
 pub struct QueuedStatefulObserver<T: CoordinateSystem> {
     grid_data: GridData<T, Option<ModelInstance>>,
     receiver: crossbeam_channel::Receiver<GenerationUpdate>,
+    /// `true` for the void models, indexed by [`super::model::ModelIndex`]
+    void_models: Vec<bool>,
 }
 
 impl<T: CoordinateSystem> QueuedStatefulObserver<T> {
     /// Creates a new [`QueuedStatefulObserver`] for a given [`crate::generator::Generator`]
     pub fn new(generator: &mut Generator<T>) -> Self {
         let receiver = generator.create_observer_queue();
-        QueuedStatefulObserver::create(receiver, generator.grid())
+        QueuedStatefulObserver::create(receiver, generator.grid(), Some(generator.rules()))
     }
 
     pub(crate) fn create(
         receiver: crossbeam_channel::Receiver<GenerationUpdate>,
         grid: &GridDefinition<T>,
+        rules: Option<&Rules<T>>,
     ) -> Self {
+        let void_models = match rules {
+            Some(rules) => (0..rules.original_models_count())
+                .map(|model_index| rules.is_void(model_index))
+                .collect(),
+            None => Vec::new(),
+        };
         QueuedStatefulObserver {
             grid_data: GridData::new(grid.clone(), vec![None; grid.total_size()]),
             receiver,
+            void_models,
+        }
+    }
+
+    /// Returns `true` if the node `node_index` is generated with a void model, see [`super::model::ModelTemplate::with_void`].
+    pub fn is_void(&self, node_index: NodeIndex) -> bool {
+        match *self.grid_data.get(node_index) {
+            Some(model_instance) => self
+                .void_models
+                .get(model_instance.model_index)
+                .copied()
+                .unwrap_or(false),
+            None => false,
         }
     }
 
-    /// Returns a ref to the observer's [`GridData`]
+    /// Returns a ref to the observer's [`GridData`]. Once the generation is done, it can be converted into a complete output with [`crate::grid_data::IntoComplete::into_complete`].
     pub fn grid_data(&self) -> &GridData<T, Option<ModelInstance>> {
         &self.grid_data
     }
@@ -58,6 +245,7 @@ impl<T: CoordinateSystem> QueuedStatefulObserver<T> {
                     .set(grid_node.node_index, Some(grid_node.model_instance)),
                 GenerationUpdate::Reinitializing(_) => self.grid_data.reset(None),
                 GenerationUpdate::Failed(_) => self.grid_data.reset(None),
+                GenerationUpdate::Reverted(node_index) => self.grid_data.set(node_index, None),
             }
         }
     }
@@ -74,6 +262,7 @@ impl<T: CoordinateSystem> QueuedStatefulObserver<T> {
                         .set(grid_node.node_index, Some(grid_node.model_instance)),
                     GenerationUpdate::Reinitializing(_) => self.grid_data.reset(None),
                     GenerationUpdate::Failed(_) => self.grid_data.reset(None),
+                    GenerationUpdate::Reverted(node_index) => self.grid_data.set(node_index, None),
                 }
                 Some(update)
             }
@@ -97,6 +286,17 @@ impl QueuedObserver {
         QueuedObserver { receiver }
     }
 
+    /// Creates a new [`QueuedObserver`] for a given [`crate::generator::Generator`], which only receives the [`GenerationUpdate::Generated`] and [`GenerationUpdate::Reverted`] updates of the nodes inside `region`.
+    ///
+    /// [`GenerationUpdate::Reinitializing`] and [`GenerationUpdate::Failed`] concern the whole generation and are always received.
+    pub fn for_region<T: CoordinateSystem>(
+        generator: &mut Generator<T>,
+        region: NodeRegion,
+    ) -> Self {
+        let receiver = generator.create_region_observer_queue(region);
+        QueuedObserver { receiver }
+    }
+
     pub(crate) fn create(receiver: crossbeam_channel::Receiver<GenerationUpdate>) -> Self {
         Self { receiver }
     }
@@ -122,4 +322,161 @@ impl QueuedObserver {
             Err(_) => None,
         }
     }
+
+    /// Dequeues all queued updates and coalesces them into a [`GenerationBatch`].
+    pub fn dequeue_batch(&mut self) -> GenerationBatch {
+        GenerationBatch::from_updates(self.receiver.try_iter())
+    }
+}
+
+/// Observer which coalesces all the [`GenerationUpdate`] sent by the [`crate::generator::Generator`] since its last poll into a single [`GenerationBatch`]. Typically polled once per frame.
+///
+/// Can be used in a different thread than the generator's thread.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::{generator::{builder::GeneratorBuilder, model::ModelCollection, observer::BatchedObserver, rules::RulesBuilder, socket::{SocketCollection, SocketsCartesian2D}}};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let a = sockets.create();
+/// sockets.add_connection(a, vec![a]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(a));
+/// models.create(SocketsCartesian2D::Mono(a));
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+/// let mut generator = GeneratorBuilder::new()
+///    .with_rules(rules)
+///    .with_grid(GridDefinition::new_cartesian_2d(4, 4, false, false))
+///    .build()
+///    .unwrap();
+/// let mut observer = BatchedObserver::new(&mut generator);
+///
+/// generator.generate().unwrap();
+/// let batch = observer.poll();
+/// assert_eq!(batch.generated_count(), 16);
+/// assert!(observer.poll().is_empty());
+///
+/// // Generating again reinitializes the generator: the previous nodes are discarded
+/// generator.generate().unwrap();
+/// let batch = observer.poll();
+/// assert!(batch.reinitialized());
+/// assert_eq!(batch.generated_count(), 16);
+/// ```
+#[cfg_attr(feature = "bevy", derive(Component))]
+pub struct BatchedObserver {
+    receiver: crossbeam_channel::Receiver<GenerationUpdate>,
+}
+
+impl BatchedObserver {
+    /// Creates a new [`BatchedObserver`] for a given [`crate::generator::Generator`]
+    pub fn new<T: CoordinateSystem>(generator: &mut Generator<T>) -> Self {
+        let receiver = generator.create_observer_queue();
+        BatchedObserver { receiver }
+    }
+
+    /// Creates a new [`BatchedObserver`] for a given [`crate::generator::Generator`], which only receives the updates of the nodes inside `region`. See [`QueuedObserver::for_region`].
+    pub fn for_region<T: CoordinateSystem>(
+        generator: &mut Generator<T>,
+        region: NodeRegion,
+    ) -> Self {
+        let receiver = generator.create_region_observer_queue(region);
+        BatchedObserver { receiver }
+    }
+
+    pub(crate) fn create(receiver: crossbeam_channel::Receiver<GenerationUpdate>) -> Self {
+        Self { receiver }
+    }
+
+    /// Dequeues all the updates received since the last poll and coalesces them into a [`GenerationBatch`]. The batch is empty if no update was received.
+    pub fn poll(&mut self) -> GenerationBatch {
+        GenerationBatch::from_updates(self.receiver.try_iter())
+    }
+}
+
+/// Observer streaming the [`GenerationUpdate`] sent by the [`crate::generator::Generator`] to a writer (typically an append-only file), in a simple framed binary format.
+///
+/// For very large generations: a crash in the middle of the generation does not lose the progress already written, and other processes can follow the generation by tailing the file. The stream is read back with [`crate::serialization::read_stream`] or [`crate::serialization::replay_stream`].
+///
+/// The updates are written (and the writer flushed) on each call to [`StreamObserver::write_pending`], typically after each generation step or batch of steps.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::{generator::{builder::GeneratorBuilder, model::ModelCollection, observer::StreamObserver, rules::RulesBuilder, socket::{SocketCollection, SocketsCartesian2D}, GenerationStatus}, serialization::replay_stream};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let a = sockets.create();
+/// sockets.add_connection(a, vec![a]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(a));
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+/// let grid = GridDefinition::new_cartesian_2d(4, 4, false, false);
+/// let mut generator = GeneratorBuilder::new()
+///    .with_rules(rules)
+///    .with_grid(grid.clone())
+///    .build()
+///    .unwrap();
+/// let mut observer = StreamObserver::new(&mut generator, Vec::new()).unwrap();
+///
+/// while let Ok(GenerationStatus::Ongoing) = generator.select_and_propagate() {
+///     observer.write_pending().unwrap();
+/// }
+/// observer.write_pending().unwrap();
+///
+/// let recovered = replay_stream(&observer.into_inner(), &grid).unwrap();
+/// assert!(recovered.nodes().iter().all(|node| node.is_some()));
+/// ```
+pub struct StreamObserver<W: Write> {
+    receiver: crossbeam_channel::Receiver<GenerationUpdate>,
+    writer: W,
+    buffer: Vec<u8>,
+}
+
+impl StreamObserver<BufWriter<File>> {
+    /// Creates a [`StreamObserver`] for a given [`crate::generator::Generator`], streaming to a new file at `path` (truncated if it already exists)
+    pub fn create_file<T: CoordinateSystem, P: AsRef<Path>>(
+        generator: &mut Generator<T>,
+        path: P,
+    ) -> io::Result<Self> {
+        Self::new(generator, BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> StreamObserver<W> {
+    /// Creates a [`StreamObserver`] for a given [`crate::generator::Generator`], streaming to `writer`. The header of the stream is written immediately.
+    pub fn new<T: CoordinateSystem>(
+        generator: &mut Generator<T>,
+        mut writer: W,
+    ) -> io::Result<Self> {
+        writer.write_all(&stream_header(generator.grid().size()))?;
+        writer.flush()?;
+        Ok(Self {
+            receiver: generator.create_observer_queue(),
+            writer,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Writes all the updates received since the last call and flushes the writer.
+    ///
+    /// Returns how many updates were written.
+    pub fn write_pending(&mut self) -> io::Result<usize> {
+        self.buffer.clear();
+        let mut count = 0;
+        for update in self.receiver.try_iter() {
+            write_stream_update(&mut self.buffer, &update);
+            count += 1;
+        }
+        self.writer.write_all(&self.buffer)?;
+        self.writer.flush()?;
+        Ok(count)
+    }
+
+    /// Returns the underlying writer. The updates not written yet are lost.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
 }
@@ -0,0 +1,185 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use ghx_grid::coordinate_system::CoordinateSystem;
+
+use crate::RegistryError;
+
+use super::{
+    model::{Model, ModelCollection, ModelIndex, ModelTemplate},
+    socket::{Socket, SocketCollection},
+};
+
+/// Separator between the namespace and the name of a key, as in `"base:grass"`
+pub const NAMESPACE_SEPARATOR: char = ':';
+
+/// Sockets and models referenced by string keys in namespaces, such as `"base:grass"` or `"mod:lava"`. Allows tiles provided by different sources (a base game and its mods for example) to be connected by name.
+///
+/// Namespaces must be registered (at any time) before creating sockets or models in them.
+///
+/// Sockets and models get their ids in creation order: registering the same sockets and models in the same order always gives the same ids, and creating new ones never changes the ids of the existing ones.
+///
+/// ### Example
+/// ```
+/// use ghx_proc_gen::generator::{registry::Registry, socket::SocketsCartesian2D};
+/// use ghx_grid::coordinate_system::Cartesian2D;
+///
+/// let mut registry = Registry::<Cartesian2D>::new();
+/// registry.register_namespace("base").unwrap();
+/// let grass = registry.create_socket("base:grass").unwrap();
+/// registry.add_connection("base:grass", &["base:grass"]).unwrap();
+/// registry.create_model("base:grass", SocketsCartesian2D::Mono(grass)).unwrap();
+///
+/// // Later, a mod adds its own namespace and connects to the base sockets by name
+/// registry.register_namespace("mod").unwrap();
+/// let lava = registry.create_socket("mod:lava").unwrap();
+/// registry.add_connection("mod:lava", &["mod:lava", "base:grass"]).unwrap();
+/// registry.create_model("mod:lava", SocketsCartesian2D::Mono(lava)).unwrap();
+///
+/// assert_eq!(registry.model_index("mod:lava"), Ok(1));
+/// assert!(registry.create_socket("other:stone").is_err());
+/// let (models, sockets) = registry.into_collections();
+/// ```
+pub struct Registry<C: CoordinateSystem> {
+    namespaces: BTreeSet<String>,
+    sockets: SocketCollection,
+    sockets_keys: BTreeMap<String, Socket>,
+    models: ModelCollection<C>,
+    models_keys: BTreeMap<String, ModelIndex>,
+    /// Key of each model, indexed by [`ModelIndex`]
+    models_by_index: Vec<String>,
+}
+
+impl<C: CoordinateSystem> Registry<C> {
+    /// Creates an empty [`Registry`], without any namespace
+    pub fn new() -> Self {
+        Self {
+            namespaces: BTreeSet::new(),
+            sockets: SocketCollection::new(),
+            sockets_keys: BTreeMap::new(),
+            models: ModelCollection::new(),
+            models_keys: BTreeMap::new(),
+            models_by_index: Vec::new(),
+        }
+    }
+
+    /// Registers a new namespace. The namespace must not be empty, must not contain a [`NAMESPACE_SEPARATOR`] and must not be already registered.
+    pub fn register_namespace(&mut self, namespace: &str) -> Result<(), RegistryError> {
+        if namespace.is_empty() || namespace.contains(NAMESPACE_SEPARATOR) {
+            return Err(RegistryError::InvalidNamespace(namespace.to_string()));
+        }
+        if !self.namespaces.insert(namespace.to_string()) {
+            return Err(RegistryError::DuplicateNamespace(namespace.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if `namespace` is registered
+    pub fn has_namespace(&self, namespace: &str) -> bool {
+        self.namespaces.contains(namespace)
+    }
+
+    /// Returns an iterator over the registered namespaces, in alphabetical order
+    pub fn namespaces(&self) -> impl Iterator<Item = &str> {
+        self.namespaces.iter().map(|namespace| namespace.as_str())
+    }
+
+    /// Checks that `key` is a `namespace:name` key in a registered namespace
+    fn check_key(&self, key: &str) -> Result<(), RegistryError> {
+        match key.split_once(NAMESPACE_SEPARATOR) {
+            Some((namespace, name)) if !name.is_empty() && !name.contains(NAMESPACE_SEPARATOR) => {
+                match self.namespaces.contains(namespace) {
+                    true => Ok(()),
+                    false => Err(RegistryError::UnknownNamespace(key.to_string())),
+                }
+            }
+            _ => Err(RegistryError::InvalidKey(key.to_string())),
+        }
+    }
+
+    /// Creates a new [`Socket`] with the key `key` (`namespace:name`) and returns it.
+    pub fn create_socket(&mut self, key: &str) -> Result<Socket, RegistryError> {
+        self.check_key(key)?;
+        if self.sockets_keys.contains_key(key) {
+            return Err(RegistryError::DuplicateKey(key.to_string()));
+        }
+        let socket = self.sockets.create();
+        self.sockets_keys.insert(key.to_string(), socket);
+        Ok(socket)
+    }
+
+    /// Returns the [`Socket`] with the key `key`
+    pub fn socket(&self, key: &str) -> Result<Socket, RegistryError> {
+        self.sockets_keys
+            .get(key)
+            .copied()
+            .ok_or_else(|| RegistryError::UnknownSocket(key.to_string()))
+    }
+
+    fn sockets(&self, keys: &[&str]) -> Result<Vec<Socket>, RegistryError> {
+        keys.iter().map(|key| self.socket(key)).collect()
+    }
+
+    /// Adds a connection between the sockets referenced by their keys, see [`SocketCollection::add_connection`]
+    pub fn add_connection(&mut self, from: &str, to: &[&str]) -> Result<&mut Self, RegistryError> {
+        let (from, to) = (self.socket(from)?, self.sockets(to)?);
+        self.sockets.add_connection(from, to);
+        Ok(self)
+    }
+
+    /// Adds a connection between all the rotations of the sockets referenced by their keys, see [`SocketCollection::add_rotated_connection`]
+    pub fn add_rotated_connection(
+        &mut self,
+        from: &str,
+        to: &[&str],
+    ) -> Result<&mut Self, RegistryError> {
+        let (from, to) = (self.socket(from)?, self.sockets(to)?);
+        self.sockets.add_rotated_connection(from, to);
+        Ok(self)
+    }
+
+    /// Returns the [`SocketCollection`] of the registry, to add connections not covered by the registry methods
+    pub fn sockets_mut(&mut self) -> &mut SocketCollection {
+        &mut self.sockets
+    }
+
+    /// Creates a new [`Model`] with the key `key` (`namespace:name`) and returns a reference to it. The key is also registered as the name of the model.
+    pub fn create_model<T: Into<ModelTemplate<C>>>(
+        &mut self,
+        key: &str,
+        template: T,
+    ) -> Result<&mut Model<C>, RegistryError> {
+        self.check_key(key)?;
+        if self.models_keys.contains_key(key) {
+            return Err(RegistryError::DuplicateKey(key.to_string()));
+        }
+        let model = self.models.create(template);
+        self.models_keys.insert(key.to_string(), model.index());
+        self.models_by_index.push(key.to_string());
+        Ok(model.with_name(key.to_string()))
+    }
+
+    /// Returns the [`ModelIndex`] of the model with the key `key`
+    pub fn model_index(&self, key: &str) -> Result<ModelIndex, RegistryError> {
+        self.models_keys
+            .get(key)
+            .copied()
+            .ok_or_else(|| RegistryError::UnknownModel(key.to_string()))
+    }
+
+    /// Returns the key of the model at `model_index`, if any
+    pub fn model_key(&self, model_index: ModelIndex) -> Option<&str> {
+        self.models_by_index
+            .get(model_index)
+            .map(|key| key.as_str())
+    }
+
+    /// Returns the models of the registry
+    pub fn models(&self) -> &ModelCollection<C> {
+        &self.models
+    }
+
+    /// Consumes the registry and returns its models and sockets, to build [`super::rules::Rules`]
+    pub fn into_collections(self) -> (ModelCollection<C>, SocketCollection) {
+        (self.models, self.sockets)
+    }
+}
@@ -0,0 +1,236 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use ghx_grid::{coordinate_system::CoordinateSystem, grid::GridData};
+
+use crate::{NodeIndex, RepairError};
+
+use super::{
+    builder::GeneratorBuilder,
+    model::ModelInstance,
+    rules::{Rules, Violation},
+    GenInfo, RngMode,
+};
+
+/// Parameters of a [`repair`]
+#[derive(Clone)]
+pub struct RepairOptions {
+    /// Distance (in steps along the grid directions) around the invalid nodes regenerated by the first repair attempt
+    pub radius: u32,
+    /// Maximum distance around the invalid nodes. When a repair attempt fails, it is retried with a radius increased by one, until this maximum is reached.
+    pub max_radius: u32,
+    /// Max retry count of the generator used by each repair attempt
+    pub max_retry_count: u32,
+    /// Random source of the generator used by each repair attempt
+    pub rng_mode: RngMode,
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        Self {
+            radius: 1,
+            max_radius: 4,
+            max_retry_count: 10,
+            rng_mode: RngMode::RandomSeed,
+        }
+    }
+}
+
+/// Information about a successful [`repair`]
+#[derive(Clone, Debug)]
+pub struct RepairInfo {
+    /// Nodes which did not respect the rules before the repair
+    pub invalid_nodes: Vec<NodeIndex>,
+    /// Nodes which were regenerated. Empty if the grid was already valid.
+    pub regenerated_nodes: Vec<NodeIndex>,
+    /// Radius used by the successful repair attempt
+    pub radius: u32,
+    /// Information about the generation of the successful repair attempt. `None` if the grid was already valid.
+    pub gen_info: Option<GenInfo>,
+}
+
+/// Returns the indexes of the nodes of `grid_data` involved in a [`Violation`] of `rules` (see [`Rules::validate_output`]), in increasing order.
+pub fn invalid_nodes<C: CoordinateSystem>(
+    rules: &Rules<C>,
+    grid_data: &GridData<C, ModelInstance>,
+) -> Vec<NodeIndex> {
+    let Err(violations) = rules.validate_output(grid_data) else {
+        return Vec::new();
+    };
+    let mut nodes = Vec::new();
+    for violation in violations {
+        let (node_index, neighbour_index) = violation_nodes(&violation);
+        nodes.push(node_index);
+        nodes.extend(neighbour_index);
+    }
+    nodes.sort_unstable();
+    nodes.dedup();
+    nodes
+}
+
+/// Repairs a hand-edited `grid_data` so that it respects `rules`.
+///
+/// The nodes in `edited_nodes` are the user edits: they are kept as is. The other nodes involved in a [`Violation`], as well as all their (non-edited) neighbours up to [`RepairOptions::radius`], are regenerated while all the remaining nodes are frozen. If this local generation fails, it is retried with a bigger radius, up to [`RepairOptions::max_radius`].
+///
+/// Returns the repaired grid, which is the same as `grid_data` if it was already valid.
+///
+/// ### Example
+/// ```
+/// use std::sync::Arc;
+/// use ghx_proc_gen::generator::{builder::GeneratorBuilder, repair::{repair, RepairOptions}, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::{ModelCollection, ModelInstance, ModelRotation}};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (sand, beach, water) = (sockets.create(), sockets.create(), sockets.create());
+/// sockets.add_connection(sand, vec![sand, beach]).add_connection(water, vec![water, beach]).add_connection(beach, vec![beach]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(sand));
+/// models.create(SocketsCartesian2D::Mono(beach));
+/// models.create(SocketsCartesian2D::Mono(water));
+/// let rules = Arc::new(RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap());
+///
+/// let mut generator = GeneratorBuilder::new()
+///     .with_shared_rules(rules.clone())
+///     .with_grid(GridDefinition::new_cartesian_2d(10, 10, false, false))
+///     .build()
+///     .unwrap();
+/// let (_gen_info, mut grid_data) = generator.generate_grid().unwrap();
+///
+/// // The user paints some water in the middle of the map
+/// grid_data.set(55, ModelInstance { model_index: 2, rotation: ModelRotation::Rot0 });
+/// let (repaired, _repair_info) = repair(rules.clone(), &grid_data, &[55], &RepairOptions::default()).unwrap();
+/// assert!(rules.validate_output(&repaired).is_ok());
+/// assert_eq!(repaired.get(55).model_index, 2);
+/// ```
+pub fn repair<C: CoordinateSystem>(
+    rules: Arc<Rules<C>>,
+    grid_data: &GridData<C, ModelInstance>,
+    edited_nodes: &[NodeIndex],
+    options: &RepairOptions,
+) -> Result<(GridData<C, ModelInstance>, RepairInfo), RepairError> {
+    let grid = grid_data.grid();
+    let mut edited = vec![false; grid.total_size()];
+    for &node_index in edited_nodes {
+        if node_index >= grid.total_size() {
+            return Err(RepairError::InvalidNodeIndex(node_index));
+        }
+        edited[node_index] = true;
+    }
+
+    let violations = match rules.validate_output(grid_data) {
+        Ok(()) => {
+            return Ok((
+                grid_data.clone(),
+                RepairInfo {
+                    invalid_nodes: Vec::new(),
+                    regenerated_nodes: Vec::new(),
+                    radius: 0,
+                    gen_info: None,
+                },
+            ))
+        }
+        Err(violations) => violations,
+    };
+    let mut invalid_nodes = Vec::new();
+    for violation in violations.iter() {
+        // Violations only involving edited nodes cannot be repaired
+        match violation_nodes(violation) {
+            (node_index, None) if edited[node_index] => {
+                return Err(RepairError::UnknownModel(node_index))
+            }
+            (node_index, Some(neighbour_index))
+                if edited[node_index] && edited[neighbour_index] =>
+            {
+                return Err(RepairError::ConflictingEdits(node_index, neighbour_index))
+            }
+            (node_index, neighbour_index) => {
+                invalid_nodes.push(node_index);
+                invalid_nodes.extend(neighbour_index);
+            }
+        }
+    }
+    invalid_nodes.sort_unstable();
+    invalid_nodes.dedup();
+
+    for radius in options.radius..=options.max_radius.max(options.radius) {
+        let regenerated_nodes = neighbourhood(grid_data, &invalid_nodes, &edited, radius);
+        let mut frozen = GridData::new(
+            grid.clone(),
+            grid_data.nodes().iter().copied().map(Some).collect(),
+        );
+        for &node_index in regenerated_nodes.iter() {
+            frozen.set(node_index, None);
+        }
+        let Ok(builder) = GeneratorBuilder::new()
+            .with_shared_rules(Arc::clone(&rules))
+            .with_grid(grid.clone())
+            .with_max_retry_count(options.max_retry_count)
+            .with_rng(options.rng_mode.clone())
+            .with_initial_grid(frozen)
+        else {
+            continue;
+        };
+        // Building fails if the frozen nodes already contradict each other through the regenerated region
+        let Ok(mut generator) = builder.build() else {
+            continue;
+        };
+        if let Ok((gen_info, output)) = generator.generate_grid() {
+            return Ok((
+                output,
+                RepairInfo {
+                    invalid_nodes,
+                    regenerated_nodes,
+                    radius,
+                    gen_info: Some(gen_info),
+                },
+            ));
+        }
+    }
+    Err(RepairError::Failed(options.max_radius.max(options.radius)))
+}
+
+/// Returns the node of a violation, and its neighbour for an adjacency violation
+fn violation_nodes(violation: &Violation) -> (NodeIndex, Option<NodeIndex>) {
+    match *violation {
+        Violation::UnknownModel(node_index, _) => (node_index, None),
+        Violation::Adjacency(adjacency) => (adjacency.node_index, Some(adjacency.neighbour_index)),
+        Violation::OffsetAdjacency {
+            node_index,
+            neighbour_index,
+            ..
+        } => (node_index, Some(neighbour_index)),
+    }
+}
+
+/// Returns the non-edited nodes at a distance of at most `radius` from the `invalid_nodes`, in increasing order.
+fn neighbourhood<C: CoordinateSystem>(
+    grid_data: &GridData<C, ModelInstance>,
+    invalid_nodes: &[NodeIndex],
+    edited: &[bool],
+    radius: u32,
+) -> Vec<NodeIndex> {
+    let grid = grid_data.grid();
+    let mut distances = vec![u32::MAX; grid.total_size()];
+    let mut queue = VecDeque::new();
+    for &node_index in invalid_nodes {
+        distances[node_index] = 0;
+        queue.push_back(node_index);
+    }
+    while let Some(node_index) = queue.pop_front() {
+        let distance = distances[node_index];
+        if distance >= radius {
+            continue;
+        }
+        let position = grid.pos_from_index(node_index);
+        for &direction in grid.directions() {
+            if let Some(neighbour_index) = grid.get_next_index_in_direction(&position, direction) {
+                if distances[neighbour_index] == u32::MAX {
+                    distances[neighbour_index] = distance + 1;
+                    queue.push_back(neighbour_index);
+                }
+            }
+        }
+    }
+    (0..grid.total_size())
+        .filter(|&node_index| distances[node_index] != u32::MAX && !edited[node_index])
+        .collect()
+}
@@ -1,16 +1,17 @@
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
-    marker::PhantomData,
 };
 
 use ghx_grid::{
     coordinate_system::{Cartesian2D, Cartesian3D, CoordinateSystem},
     direction::Direction,
+    grid::GridData,
 };
-use ndarray::{Array, Ix1, Ix2};
+use ndarray::{aview1, Array, Ix1, Ix2};
+
+use bitvec::{bitvec, slice::BitSlice, vec::BitVec};
 
-#[cfg(feature = "models-names")]
 use std::borrow::Cow;
 
 #[cfg(feature = "debug-traces")]
@@ -22,13 +23,18 @@ use bevy::ecs::component::Component;
 use bevy::{ecs::reflect::ReflectComponent, reflect::Reflect};
 
 use super::{
+    diagonal::Diagonal,
+    direction_set::{adjacent_node_index, DirectionSet, GridOffset},
     model::{
-        Model, ModelCollection, ModelIndex, ModelInstance, ModelRotation, ModelVariantIndex,
-        ALL_MODEL_ROTATIONS,
+        CountLimits, Model, ModelCollection, ModelIndex, ModelInstance, ModelRotation,
+        ModelTemplate, ModelVariantIndex, ModelVariation, ALL_MODEL_ROTATIONS,
     },
-    socket::SocketCollection,
+    socket::{Socket, SocketCollection, SocketId},
 };
-use crate::{NodeSetError, RulesBuilderError};
+use crate::{NodeIndex, NodeSetError, RulesBuilderError};
+
+/// `sockets_to_models.get(socket)[direction]` holds all the models that have `socket` from `direction`
+type SocketsToModels = BTreeMap<SocketId, Array<BTreeSet<ModelVariantIndex>, Ix1>>;
 
 /// Rotation axis in a 2D cartesian coordinate system
 pub const CARTESIAN_2D_ROTATION_AXIS: Direction = Direction::ZForward;
@@ -39,6 +45,111 @@ pub struct RulesBuilder<C: CoordinateSystem> {
     socket_collection: SocketCollection,
     rotation_axis: Direction,
     coord_system: C,
+    pair_weights: Vec<PairWeight>,
+    direction_set: Option<DirectionSet>,
+    normalize_rotation_weights: bool,
+    direction_aliases: DirectionAliases,
+}
+
+/// Named aliases for the directions of some [`Rules`], such as `"up"` for [`Direction::ZForward`] when a [`Cartesian3D`] grid is used as stacked 2d layers. The aliases are used in the [`RulesBuilderError`] and [`RulesLint`] messages and by the socket labels of the `bevy_ghx_proc_gen` debug plugin, and can be queried on the [`Rules`], see [`RulesBuilder::with_direction_alias`]. The socket enums ([`super::socket::SocketsCartesian3D`], ...) keep their axis-based fields.
+///
+/// ### Example
+/// ```
+/// use ghx_proc_gen::generator::rules::DirectionAliases;
+/// use ghx_grid::direction::Direction;
+///
+/// let aliases = DirectionAliases::new()
+///     .with_alias(Direction::ZForward, "up")
+///     .with_alias(Direction::ZBackward, "down");
+/// assert_eq!(aliases.direction("up"), Some(Direction::ZForward));
+/// assert_eq!(aliases.named(Direction::ZBackward).to_string(), "down (ZBackward)");
+/// assert_eq!(aliases.named(Direction::XForward).to_string(), "XForward");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DirectionAliases {
+    aliases: Vec<(Direction, &'static str)>,
+}
+
+impl DirectionAliases {
+    /// Creates an empty set of aliases
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the alias of `direction`, replacing its previous alias if any
+    pub fn with_alias(mut self, direction: Direction, alias: &'static str) -> Self {
+        self.set_alias(direction, alias);
+        self
+    }
+
+    /// Sets the alias of `direction`, replacing its previous alias if any
+    pub fn set_alias(&mut self, direction: Direction, alias: &'static str) {
+        match self.aliases.iter_mut().find(|(d, _)| *d == direction) {
+            Some((_, previous_alias)) => *previous_alias = alias,
+            None => self.aliases.push((direction, alias)),
+        }
+    }
+
+    /// Returns the alias of `direction`, if any
+    pub fn alias(&self, direction: Direction) -> Option<&'static str> {
+        self.aliases
+            .iter()
+            .find(|(d, _)| *d == direction)
+            .map(|(_, alias)| *alias)
+    }
+
+    /// Returns the direction with the alias `alias`, if any
+    pub fn direction(&self, alias: &str) -> Option<Direction> {
+        self.aliases
+            .iter()
+            .find(|(_, a)| *a == alias)
+            .map(|(direction, _)| *direction)
+    }
+
+    /// Returns `direction` with its alias, to be displayed
+    pub fn named(&self, direction: Direction) -> NamedDirection {
+        NamedDirection {
+            direction,
+            alias: self.alias(direction),
+        }
+    }
+}
+
+/// A [`Direction`] with its alias if it has one (see [`DirectionAliases`]). Displayed as `alias (Direction)`, or as the [`Direction`] only without alias.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NamedDirection {
+    /// The named direction
+    pub direction: Direction,
+    /// Alias of the direction, if any
+    pub alias: Option<&'static str>,
+}
+
+impl fmt::Display for NamedDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.alias {
+            Some(alias) => write!(f, "{} ({:?})", alias, self.direction),
+            None => write!(f, "{:?}", self.direction),
+        }
+    }
+}
+
+impl From<Direction> for NamedDirection {
+    fn from(direction: Direction) -> Self {
+        Self {
+            direction,
+            alias: None,
+        }
+    }
+}
+
+/// Weight multiplier applied to a model when a neighbour node is generated with another model, see [`RulesBuilder::with_pair_weight`]
+#[derive(Clone, Copy, Debug)]
+struct PairWeight {
+    model: ModelIndex,
+    /// `None` for all the directions
+    direction: Option<Direction>,
+    neighbour: ModelIndex,
+    multiplier: f32,
 }
 
 impl RulesBuilder<Cartesian2D> {
@@ -70,9 +181,83 @@ impl RulesBuilder<Cartesian2D> {
             socket_collection,
             rotation_axis: CARTESIAN_2D_ROTATION_AXIS,
             coord_system: Cartesian2D,
+            pair_weights: Vec::new(),
+            direction_set: None,
+            normalize_rotation_weights: false,
+            direction_aliases: DirectionAliases::new(),
+        }
+    }
+}
+impl RulesBuilder<Cartesian2D> {
+    /// Adds the 4 [`Diagonal`] adjacencies to the rules: the generation will also constrain the models of diagonal neighbours, using the sockets given by [`ModelTemplate::with_diagonal_sockets`]. This prevents corner artifacts that the 4 direct adjacencies cannot see.
+    ///
+    /// A model without diagonal sockets accepts any diagonal neighbour. Diagonals of a node follow the looping of the grid. Shorthand for [`RulesBuilder::with_direction_set`] with [`DirectionSet::diagonals_2d`].
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{diagonal::DiagonalSockets, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::coordinate_system::Cartesian2D;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (side, corner) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(side, vec![side]);
+    /// sockets.add_connection(corner, vec![corner]);
+    ///
+    /// let mut models = ModelCollection::<Cartesian2D>::new();
+    /// models
+    ///     .create(SocketsCartesian2D::Mono(side))
+    ///     .with_diagonal_sockets(DiagonalSockets::Mono(corner));
+    ///
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets)
+    ///     .with_diagonals()
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(rules.has_diagonals());
+    /// ```
+    pub fn with_diagonals(self) -> Self {
+        self.with_direction_set(DirectionSet::diagonals_2d())
+    }
+
+    /// Lifts these 2d rules into [`Cartesian3D`] rules: the models keep their sockets on the X and Y axes, and use `up` and `down` as their sockets on [`Direction::ZForward`] and [`Direction::ZBackward`] (see [`ModelCollection::to_cartesian_3d`]). [`Direction::ZForward`] stays the rotation axis.
+    ///
+    /// Allows to reuse 2d rules in layered 3d setups (tile layers for example) without re-authoring the models. The connections of `up` and `down` must be added to the [`SocketCollection`] given to this builder. The pair weights, direction aliases and direction set (diagonals included) are kept.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (white, black) = (sockets.create(), sockets.create());
+    /// let (up, down) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(white, vec![black]);
+    /// sockets.add_connection(up, vec![down]);
+    ///
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(white));
+    /// models.create(SocketsCartesian2D::Mono(black));
+    ///
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets)
+    ///     .into_cartesian_3d(up, down)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(rules.original_models_count(), 2);
+    /// ```
+    pub fn into_cartesian_3d(self, up: Socket, down: Socket) -> RulesBuilder<Cartesian3D> {
+        RulesBuilder {
+            models: self.models.to_cartesian_3d(up, down),
+            socket_collection: self.socket_collection,
+            rotation_axis: CARTESIAN_2D_ROTATION_AXIS,
+            coord_system: Cartesian3D,
+            pair_weights: self.pair_weights,
+            direction_set: self.direction_set,
+            normalize_rotation_weights: self.normalize_rotation_weights,
+            direction_aliases: self.direction_aliases,
         }
     }
 }
+
 impl RulesBuilder<Cartesian3D> {
     /// Used to create Rules for a 3d cartesian grid.
     ///
@@ -130,6 +315,10 @@ impl RulesBuilder<Cartesian3D> {
             socket_collection,
             rotation_axis: Direction::YForward,
             coord_system: Cartesian3D,
+            pair_weights: Vec::new(),
+            direction_set: None,
+            normalize_rotation_weights: false,
+            direction_aliases: DirectionAliases::new(),
         }
     }
 }
@@ -143,19 +332,353 @@ impl RulesBuilder<Cartesian3D> {
 }
 
 impl<C: CoordinateSystem> RulesBuilder<C> {
+    /// Adds a pair weight: when selecting a model for a node, the weight of `model` (all its rotations) is multiplied by `multiplier` for each adjacent node (in any direction) already generated with `neighbour` (any rotation).
+    ///
+    /// Used by [`super::ModelSelectionHeuristic::WeightedProbability`] to make some models more (`multiplier > 1.`) or less (`multiplier < 1.`) likely to be next to each other, e.g. forest next to forest x3. Multipliers for the same pair are multiplied together.
+    ///
+    /// The `multiplier` value should be finite and strictly superior to `0`. If it is not the case, building the [`Rules`] will return a [`RulesBuilderError::InvalidPairWeight`].
+    pub fn with_pair_weight(
+        mut self,
+        model: ModelIndex,
+        neighbour: ModelIndex,
+        multiplier: f32,
+    ) -> Self {
+        self.pair_weights.push(PairWeight {
+            model,
+            direction: None,
+            neighbour,
+            multiplier,
+        });
+        self
+    }
+
+    /// Same as [`RulesBuilder::with_pair_weight`] but the multiplier is only applied when `neighbour` is on the adjacent node in `direction`.
+    pub fn with_directional_pair_weight(
+        mut self,
+        model: ModelIndex,
+        direction: Direction,
+        neighbour: ModelIndex,
+        multiplier: f32,
+    ) -> Self {
+        self.pair_weights.push(PairWeight {
+            model,
+            direction: Some(direction),
+            neighbour,
+            multiplier,
+        });
+        self
+    }
+
+    /// Normalizes the weights of the models across their rotations: the weight of each variation of a model is its weight divided by its number of allowed rotations.
+    ///
+    /// By default, each rotation of a model has the full weight of the model, so a model with 4 allowed rotations is selected 4 times as often as a model with the same weight and a single rotation. With this option, the weight of a model reflects its total frequency of appearance, whatever its rotations.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (a, b) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(a, vec![a, b]).add_connection(b, vec![b]);
+    ///
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(a));
+    /// models
+    ///     .create(SocketsCartesian2D::Simple { x_pos: a, x_neg: b, y_pos: b, y_neg: b })
+    ///     .with_all_rotations();
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets)
+    ///     .with_normalized_rotation_weights()
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(rules.weight(0), Some(1.));
+    /// assert_eq!(rules.weight(1), Some(0.25));
+    /// ```
+    pub fn with_normalized_rotation_weights(mut self) -> Self {
+        self.normalize_rotation_weights = true;
+        self
+    }
+
+    /// Registers `alias` as the name of `direction` in the [`Rules`]. The alias is used in the [`RulesBuilderError`] and [`RulesLint`] messages and by the socket labels of the `bevy_ghx_proc_gen` debug plugin, and can be queried with [`Rules::named_direction`] and [`Rules::direction_from_alias`].
+    ///
+    /// Useful when the directions of the coordinate system do not match their meaning, such as [`Direction::ZForward`] being "up" when a [`Cartesian3D`] grid is used as stacked 2d layers.
+    ///
+    /// ### Example
+    /// ```
+    /// use ghx_proc_gen::generator::{rules::RulesBuilder, socket::{SocketsCartesian3D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::{coordinate_system::Cartesian3D, direction::Direction};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (layer, void) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(layer, vec![layer]);
+    /// let mut models = ModelCollection::<Cartesian3D>::new();
+    /// models.create(SocketsCartesian3D::Multiple {
+    ///     x_pos: vec![layer], x_neg: vec![layer], y_pos: vec![layer], y_neg: vec![layer],
+    ///     z_pos: vec![], z_neg: vec![void],
+    /// });
+    ///
+    /// let error = RulesBuilder::new_cartesian_3d(models, sockets)
+    ///     .with_rotation_axis(Direction::ZForward)
+    ///     .with_direction_alias(Direction::ZForward, "up")
+    ///     .build()
+    ///     .err()
+    ///     .unwrap();
+    /// assert_eq!(error.to_string(), "Model with index `0` has no socket in direction up (ZForward)");
+    /// ```
+    pub fn with_direction_alias(mut self, direction: Direction, alias: &'static str) -> Self {
+        self.direction_aliases.set_alias(direction, alias);
+        self
+    }
+
+    /// Replaces all the direction aliases of the [`Rules`] by `aliases`, see [`RulesBuilder::with_direction_alias`]
+    pub fn with_direction_aliases(mut self, aliases: DirectionAliases) -> Self {
+        self.direction_aliases = aliases;
+        self
+    }
+
+    /// Adds the offsets of `direction_set` as adjacencies of the rules, after the directions of the coordinate system: the generation will also constrain the models of the neighbours at those offsets, using the sockets given by [`ModelTemplate::with_offset_sockets`]. Replaces the previous direction set if any.
+    ///
+    /// This allows 8-connected 2d grids ([`DirectionSet::diagonals_2d`]), 26-connected 3d grids ([`DirectionSet::diagonals_3d`]) or any other neighbourhood. The offsets sockets of a model rotate with it, so the set must be closed under the allowed rotations of the models (checked by [`RulesBuilder::build`]). A model without sockets towards an offset accepts any neighbour there, and the neighbours at an offset follow the looping of the grid.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{direction_set::{DirectionSet, GridOffset}, rules::RulesBuilder, socket::{SocketsCartesian3D, SocketCollection}, model::ModelCollection};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (side, edge) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(side, vec![side]);
+    /// sockets.add_connection(edge, vec![edge]);
+    ///
+    /// let mut models = ModelCollection::new();
+    /// models
+    ///     .create(SocketsCartesian3D::Mono(side))
+    ///     .with_offset_sockets(GridOffset::new(1, 1, 0), vec![edge])
+    ///     .with_all_rotations();
+    ///
+    /// let rules = RulesBuilder::new_cartesian_3d(models, sockets)
+    ///     .with_direction_set(DirectionSet::diagonals_3d())
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(rules.direction_set().unwrap().len(), 20);
+    /// ```
+    pub fn with_direction_set(mut self, direction_set: DirectionSet) -> Self {
+        self.direction_set = Some(direction_set);
+        self
+    }
+
     /// Builds the [`Rules`] from the current configuration of the [`RulesBuilder`]
     ///
-    /// May return [`crate::RulesBuilderError::NoModelsOrSockets`] if `models` or `socket_collection` are empty.
+    /// Returns a [`RulesBuilderError`] if:
+    /// - `models` or `socket_collection` are empty
+    /// - a model has no socket in a direction
+    /// - a model weight is not finite and positive, or a pair weight multiplier is not finite and strictly superior to `0`
+    /// - the rotation axis is not valid for the coordinate system
+    /// - an offset of the direction set is not valid for the coordinate system, or is rotated out of the set by an allowed rotation of a model
     pub fn build(self) -> Result<Rules<C>, RulesBuilderError> {
         Rules::new(
             self.models,
             self.socket_collection,
             self.rotation_axis,
             self.coord_system,
+            self.pair_weights,
+            self.direction_set,
+            self.normalize_rotation_weights,
+            self.direction_aliases,
         )
     }
 }
 
+/// In 2d, the models can only be rotated around the Z axis. In 3d, around any axis.
+fn is_valid_rotation_axis<C: CoordinateSystem>(coord_system: &C, rotation_axis: Direction) -> bool {
+    match coord_system.directions().len() {
+        4 => matches!(rotation_axis, Direction::ZForward | Direction::ZBackward),
+        _ => coord_system.directions().contains(&rotation_axis),
+    }
+}
+
+fn check_direction_set(
+    direction_set: &Option<DirectionSet>,
+    directions: &[Direction],
+    model_variations: &[ModelVariation],
+    rotation_axis: Direction,
+) -> Result<(), RulesBuilderError> {
+    let Some(direction_set) = direction_set else {
+        return Ok(());
+    };
+    if let Some(offset) = direction_set.offset_outside(directions) {
+        return Err(RulesBuilderError::InvalidDirectionSetOffset(offset));
+    }
+    let rotations: BTreeSet<ModelRotation> = model_variations
+        .iter()
+        .map(|model_variation| model_variation.rotation())
+        .collect();
+    for rotation in rotations {
+        if let Some(offset) = direction_set.offset_not_rotating(rotation, rotation_axis) {
+            return Err(RulesBuilderError::DirectionSetNotRotationInvariant(offset));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the sockets of `model_variation` towards each offset of `direction_set`, `None` for an offset without sockets
+fn variant_offset_sockets(
+    model_variation: &ModelVariation,
+    direction_set: &Option<DirectionSet>,
+) -> Vec<Option<Vec<SocketId>>> {
+    direction_set
+        .iter()
+        .flat_map(|direction_set| direction_set.offsets())
+        .map(|offset| model_variation.sockets_towards(*offset).cloned())
+        .collect()
+}
+
+fn check_model_variation(
+    model_variation: &ModelVariation,
+    directions: &[Direction],
+    direction_aliases: &DirectionAliases,
+) -> Result<(), RulesBuilderError> {
+    let weight = model_variation.weight();
+    if !(weight >= 0.) || weight.is_infinite() {
+        return Err(RulesBuilderError::InvalidWeight(
+            model_variation.original_index(),
+            weight,
+        ));
+    }
+    for &direction in directions {
+        if model_variation.sockets()[direction as usize].is_empty() {
+            return Err(RulesBuilderError::EmptySocketList(
+                model_variation.original_index(),
+                direction_aliases.named(direction),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn register_model_sockets(
+    sockets_to_models: &mut SocketsToModels,
+    model_index: ModelVariantIndex,
+    model_sockets: &Vec<Vec<SocketId>>,
+    directions: &[Direction],
+) {
+    for &direction in directions {
+        let opposite_dir = direction.opposite() as usize;
+        for socket in &model_sockets[direction as usize] {
+            let compatible_models = sockets_to_models
+                .entry(*socket)
+                .or_insert_with(|| Array::from_elem(directions.len(), BTreeSet::new()));
+            compatible_models[opposite_dir].insert(model_index);
+        }
+    }
+}
+
+/// Returns all the models allowed next to a model with `sockets` in `direction`
+fn compute_allowed_neighbours(
+    sockets: &Vec<SocketId>,
+    direction: Direction,
+    socket_collection: &SocketCollection,
+    sockets_to_models: &SocketsToModels,
+) -> Vec<ModelVariantIndex> {
+    let mut allowed_neighbours = Vec::new();
+    // We filter unique models with a Set, but what we want in the Rules is a Vec for access speed, caching, and iteration determinism. The Vec keeps the insertion order.
+    let mut unique_models = BTreeSet::new();
+    // For each socket of the model in this direction: get all the sockets that are compatible for connection
+    for socket in sockets {
+        if let Some(compatible_sockets) = socket_collection.get_compatibles(*socket) {
+            for compatible_socket in compatible_sockets {
+                // For each of those: get all the models that have this socket from direction
+                // `sockets_to_models` may not have an entry for `compatible_socket` depending on user input data (socket present in sockets_connections but not in a model)
+                if let Some(allowed_models) = sockets_to_models.get(compatible_socket) {
+                    for allowed_model in &allowed_models[direction as usize] {
+                        if unique_models.insert(*allowed_model) {
+                            allowed_neighbours.push(*allowed_model);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    allowed_neighbours
+}
+
+/// Returns all the models allowed next to a model with `sockets` towards an offset of the direction set, whose opposite is at `opposite_index`. A model without sockets towards an offset (`None`) allows and is allowed by any model.
+fn compute_offset_allowed_neighbours(
+    sockets: &Option<Vec<SocketId>>,
+    opposite_index: usize,
+    socket_collection: &SocketCollection,
+    variants_offset_sockets: &Vec<Vec<Option<Vec<SocketId>>>>,
+) -> Vec<ModelVariantIndex> {
+    let Some(sockets) = sockets else {
+        return (0..variants_offset_sockets.len()).collect();
+    };
+    variants_offset_sockets
+        .iter()
+        .enumerate()
+        .filter(|(_, other_sockets)| match &other_sockets[opposite_index] {
+            None => true,
+            Some(other_sockets) => sockets.iter().any(|socket| {
+                socket_collection
+                    .get_compatibles(*socket)
+                    .is_some_and(|compatibles| {
+                        other_sockets
+                            .iter()
+                            .any(|other| compatibles.contains(other))
+                    })
+            }),
+        })
+        .map(|(model, _)| model)
+        .collect()
+}
+
+fn compute_allowed_mask(
+    allowed_models: &Vec<ModelVariantIndex>,
+    models_count: usize,
+) -> BitVec<usize> {
+    let mut mask = bitvec![0; models_count];
+    for &allowed_model in allowed_models {
+        mask.set(allowed_model, true);
+    }
+    mask
+}
+
+/// Set of model variants, returned by [`Rules::allowed_mask`]: a bitmask where the bit at index `i` is set if the model variant `i` is in the set.
+#[derive(Clone, Copy, Debug)]
+pub struct ModelsMask<'a>(&'a BitSlice<usize>);
+
+impl<'a> ModelsMask<'a> {
+    /// Returns `true` if the model variant `model` is in the set. Returns `false` if `model` is not a valid model variant index.
+    #[inline]
+    pub fn contains(&self, model: ModelVariantIndex) -> bool {
+        self.0.get(model).is_some_and(|bit| *bit)
+    }
+
+    /// Returns the number of model variants in the set
+    pub fn count(&self) -> usize {
+        self.0.count_ones()
+    }
+
+    /// Returns the model variants in the set, in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = ModelVariantIndex> + 'a {
+        self.0.iter_ones()
+    }
+
+    /// Returns the size of the mask, which is [`Rules::models_count`]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the mask has no bits
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the mask as words of `usize::BITS` models, the model variant `i` being the bit `i % usize::BITS` of the word `i / usize::BITS`. Bits after [`ModelsMask::len`] are always 0.
+    pub fn as_words(&self) -> &'a [usize] {
+        self.0.as_raw_slice()
+    }
+}
+
 /// Information about a Model
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "bevy", derive(Component, Default))]
@@ -177,6 +700,256 @@ impl fmt::Display for ModelInfo {
     }
 }
 
+/// An adjacency between two nodes which is not allowed by some [`Rules`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdjacencyViolation {
+    /// Index of the node
+    pub node_index: NodeIndex,
+    /// Index of the adjacent node
+    pub neighbour_index: NodeIndex,
+    /// Direction from the node to the adjacent node
+    pub direction: Direction,
+}
+
+/// Two sockets facing each other between two model variants without any connection between them, see [`AdjacencyDiagnostic`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MissingConnection {
+    /// Rotation of the first model
+    pub rotation_a: ModelRotation,
+    /// Rotation of the second model
+    pub rotation_b: ModelRotation,
+    /// Socket of the first model, facing the second model
+    pub socket_a: Socket,
+    /// Socket of the second model, facing the first model
+    pub socket_b: Socket,
+}
+
+/// Explains which rotations of two original models can be adjacent in a direction, and which socket connections are missing for the other rotations. Returned by [`Rules::explain_adjacency`].
+///
+/// Displayed as a human-readable report listing, for each rotations pair that cannot be adjacent, the socket pairs that could be connected (with [`SocketCollection::add_connection`]) to allow it.
+#[derive(Clone, Debug)]
+pub struct AdjacencyDiagnostic {
+    /// Index of the first original model
+    pub model_a: ModelIndex,
+    /// Direction from the first model to the second model
+    pub direction: NamedDirection,
+    /// Index of the second original model
+    pub model_b: ModelIndex,
+    /// Pairs of rotations `(rotation_a, rotation_b)` of the models which are allowed to be adjacent
+    pub allowed: Vec<(ModelRotation, ModelRotation)>,
+    /// Facing sockets of the pairs of rotations which are not allowed to be adjacent. A single connection between the sockets of a pair of rotations is enough to allow it.
+    pub missing: Vec<MissingConnection>,
+}
+
+impl AdjacencyDiagnostic {
+    /// Returns `true` if at least one rotation of the first model can be adjacent to one rotation of the second model
+    pub fn is_allowed(&self) -> bool {
+        !self.allowed.is_empty()
+    }
+}
+
+impl fmt::Display for AdjacencyDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.is_allowed() {
+            true => {
+                write!(
+                    f,
+                    "Models {} and {} can be adjacent in direction {} with rotations",
+                    self.model_a, self.model_b, self.direction
+                )?;
+                for (rotation_a, rotation_b) in self.allowed.iter() {
+                    write!(f, " ({}, {})", rotation_a, rotation_b)?;
+                }
+            }
+            false => write!(
+                f,
+                "Models {} and {} can never be adjacent in direction {}",
+                self.model_a, self.model_b, self.direction
+            )?,
+        }
+        let mut rotations = None;
+        for missing in self.missing.iter() {
+            if rotations != Some((missing.rotation_a, missing.rotation_b)) {
+                rotations = Some((missing.rotation_a, missing.rotation_b));
+                write!(
+                    f,
+                    "\n- rotations ({}, {}) are missing a connection between one of:",
+                    missing.rotation_a, missing.rotation_b
+                )?;
+            }
+            write!(f, " [{} <-> {}]", missing.socket_a, missing.socket_b)?;
+        }
+        Ok(())
+    }
+}
+
+/// A node of a [`GridData`] which does not respect some [`Rules`], see [`Rules::validate_output`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The model instance of the node does not exist in the rules
+    UnknownModel(NodeIndex, ModelInstance),
+    /// Two adjacent nodes are not allowed to be adjacent
+    Adjacency(AdjacencyViolation),
+    /// Two nodes adjacent through an offset of the direction set are not allowed to be adjacent, see [`RulesBuilder::with_direction_set`]
+    OffsetAdjacency {
+        /// Index of the node
+        node_index: NodeIndex,
+        /// Index of the adjacent node
+        neighbour_index: NodeIndex,
+        /// Offset from the node to the adjacent node
+        offset: GridOffset,
+    },
+}
+
+/// Ratio to the median weight of the models above which (or below the inverse of which) a model weight is reported by [`Rules::lint`]
+pub const WEIGHT_ANOMALY_RATIO: f32 = 100.;
+
+/// A likely mistake in some [`Rules`], see [`Rules::lint`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum RulesLint {
+    /// A model variant has no allowed neighbour in some directions: its sockets in those directions cannot be connected to the sockets of any model variant. It can only be generated on the borders of a non-looping grid.
+    UnmatchedVariant {
+        /// Index of the original model
+        model_index: ModelIndex,
+        /// Rotation of the model variant
+        rotation: ModelRotation,
+        /// Name of the model, if any (with the `models-names` feature)
+        name: Option<Cow<'static, str>>,
+        /// Directions without any allowed neighbour
+        directions: Vec<NamedDirection>,
+    },
+    /// The weight of an original model is more than [`WEIGHT_ANOMALY_RATIO`] times higher, or lower, than the median weight of the models (likely a typo)
+    WeightAnomaly {
+        /// Index of the original model
+        model_index: ModelIndex,
+        /// Name of the model, if any (with the `models-names` feature)
+        name: Option<Cow<'static, str>>,
+        /// Weight of the model
+        weight: f32,
+        /// Median weight of the original models
+        median: f32,
+    },
+    /// A socket is only connected to itself. Often intended (a grass socket only connected to grass), but can also be a forgotten connection.
+    SelfConnectedSocket {
+        /// The socket
+        socket: Socket,
+        /// Name of the socket, if any (see [`SocketCollection::set_name`])
+        name: Option<Cow<'static, str>>,
+    },
+}
+
+impl fmt::Display for RulesLint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RulesLint::UnmatchedVariant {
+                model_index,
+                rotation,
+                name,
+                directions,
+            } => {
+                write!(f, "Model {}", model_index)?;
+                if let Some(name) = name {
+                    write!(f, " ({})", name)?;
+                }
+                write!(
+                    f,
+                    " with rotation {} can never have a neighbour in direction",
+                    rotation
+                )?;
+                for direction in directions.iter() {
+                    write!(f, " {}", direction)?;
+                }
+                Ok(())
+            }
+            RulesLint::WeightAnomaly {
+                model_index,
+                name,
+                weight,
+                median,
+            } => {
+                write!(f, "Model {}", model_index)?;
+                if let Some(name) = name {
+                    write!(f, " ({})", name)?;
+                }
+                write!(
+                    f,
+                    " has a weight of {} for a median weight of {}",
+                    weight, median
+                )
+            }
+            RulesLint::SelfConnectedSocket { socket, name } => match name {
+                Some(name) => write!(
+                    f,
+                    "Socket {} ({}) is only connected to itself",
+                    name, socket
+                ),
+                None => write!(f, "{} is only connected to itself", socket),
+            },
+        }
+    }
+}
+
+/// FNV-1a hasher used by [`Rules::fingerprint`] and [`Rules::is_spawned_empty`]. Unlike the std hashers, its output is guaranteed to be the same on all platforms and Rust versions.
+struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, value: u64) {
+        for byte in value.to_le_bytes() {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Statistics about some [`Rules`], see [`Rules::stats`]
+#[derive(Clone, Debug)]
+pub struct RulesStats {
+    /// Number of original input models
+    pub original_models_count: usize,
+    /// Number of models variations, expanded from the original models with their rotations
+    pub models_count: usize,
+    /// Average number of models allowed next to a model variation, for each adjacency: the directions of the coordinate system, followed by the offsets of the direction set if the rules have one
+    pub average_allowed_neighbours: Vec<f32>,
+    /// Estimated memory used by a [`super::Generator`] for each node of its grid, in bytes. See [`RulesStats::estimated_grid_memory`]
+    pub node_memory: usize,
+    /// Estimated memory used by the adjacency data of the rules, in bytes. Shared by all the generators using the rules.
+    pub rules_memory: usize,
+}
+
+impl RulesStats {
+    /// Returns the estimated memory used by a [`super::Generator`] for a grid of `nodes_count` nodes, in bytes.
+    ///
+    /// This is the memory needed by the candidates of all the nodes with [`super::CandidateStorage::Dense`], which is the upper bound with [`super::CandidateStorage::Paged`]. Temporary buffers used by the propagation and memory used by observers are not included.
+    pub fn estimated_grid_memory(&self, nodes_count: usize) -> usize {
+        nodes_count.saturating_mul(self.node_memory)
+    }
+}
+
+impl fmt::Display for RulesStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} models ({} variations), {} bytes per node, {} bytes of rules, average allowed neighbours: {:?}",
+            self.original_models_count,
+            self.models_count,
+            self.node_memory,
+            self.rules_memory,
+            self.average_allowed_neighbours
+        )
+    }
+}
+
 /// Defines the rules of a generation: the coordinate system, the models, the way they can be rotated, the sockets and their connections.
 ///
 /// A same set of [`Rules`] can be shared by multiple generators.
@@ -197,12 +970,43 @@ pub struct Rules<C: CoordinateSystem> {
 
     /// The vector `allowed_neighbours[model_index][direction]` holds all the allowed adjacent models (indexes) to `model_index` in `direction`.
     ///
+    /// When the rules have a [`DirectionSet`], its offsets follow the directions: `allowed_neighbours[model_index][directions_count + offset_index]`.
+    ///
     /// Calculated from models variations.
     ///
     /// Note: this cannot be a simple 3d array since the third dimension is different for each element.
     allowed_neighbours: Array<Vec<usize>, Ix2>,
+    /// `allowed_masks[model_index][direction]` has a bit set for each model in `allowed_neighbours[model_index][direction]`
+    allowed_masks: Array<BitVec<usize>, Ix2>,
+
+    /// Tags of the original models, indexed by [`ModelIndex`]
+    tags: Vec<Vec<Cow<'static, str>>>,
+    /// Collapse priorities of the original models, indexed by [`ModelIndex`]
+    priorities: Vec<u32>,
+    /// Count limits of the original models, indexed by [`ModelIndex`]
+    count_limits: Vec<CountLimits>,
+    /// `true` for the void original models, indexed by [`ModelIndex`]
+    void_models: Vec<bool>,
+    /// Emptiness chances of the original models, indexed by [`ModelIndex`]
+    emptiness_chances: Vec<f32>,
 
-    typestate: PhantomData<C>,
+    /// Weight multipliers, indexed by `(model, direction, neighbour)` original models indexes. See [`RulesBuilder::with_pair_weight`]
+    pair_weights: HashMap<(ModelIndex, usize, ModelIndex), f32>,
+
+    // === Sockets information, kept to add models incrementally ===
+    socket_collection: SocketCollection,
+    rotation_axis: Direction,
+    coord_system: C,
+    /// `variants_sockets[model_index][direction]` holds the sockets of the model variant `model_index` in `direction`
+    variants_sockets: Vec<Vec<Vec<SocketId>>>,
+    sockets_to_models: SocketsToModels,
+    /// Additional adjacencies of the rules, see [`RulesBuilder::with_direction_set`]
+    direction_set: Option<DirectionSet>,
+    /// `variants_offset_sockets[model_index][offset_index]` holds the sockets of the model variant `model_index` towards the offset at `offset_index` of the direction set
+    variants_offset_sockets: Vec<Vec<Option<Vec<SocketId>>>>,
+    /// `true` if the weights of the variations of a model are divided by its number of variations, see [`RulesBuilder::with_normalized_rotation_weights`]
+    normalize_rotation_weights: bool,
+    direction_aliases: DirectionAliases,
 }
 
 impl<C: CoordinateSystem> Rules<C> {
@@ -211,59 +1015,97 @@ impl<C: CoordinateSystem> Rules<C> {
         socket_collection: SocketCollection,
         rotation_axis: Direction,
         coord_system: C,
+        pair_weights: Vec<PairWeight>,
+        direction_set: Option<DirectionSet>,
+        normalize_rotation_weights: bool,
+        direction_aliases: DirectionAliases,
     ) -> Result<Rules<C>, RulesBuilderError> {
         let original_models_count = models.models_count();
+        let tags = models.models().map(|model| model.tags().to_vec()).collect();
+        let priorities = models.models().map(|model| model.priority()).collect();
+        let count_limits = models.models().map(|model| model.count_limits()).collect();
+        let void_models = models.models().map(|model| model.is_void()).collect();
+        let emptiness_chances = models
+            .models()
+            .map(|model| model.emptiness_chance())
+            .collect();
+        if socket_collection.is_empty() {
+            return Err(RulesBuilderError::NoSockets);
+        }
+        if let Some((from, to)) = socket_collection.find_invalid_flow_connection() {
+            return Err(RulesBuilderError::InvalidFlowConnection(from, to));
+        }
+        if !is_valid_rotation_axis(&coord_system, rotation_axis) {
+            return Err(RulesBuilderError::InvalidRotationAxis(
+                direction_aliases.named(rotation_axis),
+            ));
+        }
         let mut model_variations = models.create_variations(rotation_axis);
         // We test the expanded models because a model may have no rotations allowed.
-        if model_variations.len() == 0 || socket_collection.is_empty() {
-            return Err(RulesBuilderError::NoModelsOrSockets);
+        if model_variations.len() == 0 {
+            return Err(RulesBuilderError::NoModels);
+        }
+        for model_variation in model_variations.iter() {
+            check_model_variation(
+                model_variation,
+                coord_system.directions(),
+                &direction_aliases,
+            )?;
         }
+        check_direction_set(
+            &direction_set,
+            coord_system.directions(),
+            &model_variations,
+            rotation_axis,
+        )?;
 
-        // Temporary collection to reverse the relation: sockets_to_models.get(socket)[direction] will hold all the models that have 'socket' from 'direction'
-        let mut sockets_to_models = HashMap::new();
-        // Using a BTreeSet because HashSet order is not deterministic. Performance impact is non-existant since `sockets_to_models` is discarded after building the Rules.
-        let empty_in_all_directions: Array<BTreeSet<ModelVariantIndex>, Ix1> =
-            Array::from_elem(coord_system.directions().len(), BTreeSet::new());
+        // Collection to reverse the relation: sockets_to_models.get(socket)[direction] will hold all the models that have 'socket' from 'direction'
+        // Using a BTreeMap and BTreeSets because HashMap and HashSet orders are not deterministic. `sockets_to_models` is kept in the Rules to be able to add models incrementally.
+        let mut sockets_to_models = BTreeMap::new();
+        for (model_index, model) in model_variations.iter().enumerate() {
+            register_model_sockets(
+                &mut sockets_to_models,
+                model_index,
+                model.sockets(),
+                coord_system.directions(),
+            );
+        }
+
+        let directions_count = coord_system.directions().len();
+        let adjacencies_count =
+            directions_count + direction_set.as_ref().map_or(0, |set| set.len());
+        let mut allowed_neighbours =
+            Array::from_elem((model_variations.len(), adjacencies_count), Vec::new());
         for (model_index, model) in model_variations.iter().enumerate() {
             for &direction in coord_system.directions() {
-                let opposite_dir = direction.opposite() as usize;
-                for socket in &model.sockets()[direction as usize] {
-                    let compatible_models = sockets_to_models
-                        .entry(socket)
-                        .or_insert(empty_in_all_directions.clone());
-                    compatible_models[opposite_dir].insert(model_index);
+                allowed_neighbours[(model_index, direction as usize)] = compute_allowed_neighbours(
+                    &model.sockets()[direction as usize],
+                    direction,
+                    &socket_collection,
+                    &sockets_to_models,
+                );
+            }
+        }
+        let variants_offset_sockets: Vec<Vec<Option<Vec<SocketId>>>> = model_variations
+            .iter()
+            .map(|model_variation| variant_offset_sockets(model_variation, &direction_set))
+            .collect();
+        if let Some(direction_set) = &direction_set {
+            for (model_index, sockets) in variants_offset_sockets.iter().enumerate() {
+                for offset_index in 0..direction_set.len() {
+                    allowed_neighbours[(model_index, directions_count + offset_index)] =
+                        compute_offset_allowed_neighbours(
+                            &sockets[offset_index],
+                            direction_set.opposite(offset_index),
+                            &socket_collection,
+                            &variants_offset_sockets,
+                        );
                 }
             }
         }
 
-        let mut allowed_neighbours = Array::from_elem(
-            (model_variations.len(), coord_system.directions().len()),
-            Vec::new(),
-        );
-        for (model_index, model) in model_variations.iter().enumerate() {
-            for &direction in coord_system.directions() {
-                // We filter unique models with a Set, but waht we want in the Rules is a Vec for access speed, caching, and iteration determinism.
-                let mut unique_models = HashSet::new();
-                // For each socket of the model in this direction: get all the sockets that are compatible for connection
-                for socket in &model.sockets()[direction as usize] {
-                    if let Some(compatible_sockets) = socket_collection.get_compatibles(*socket) {
-                        for compatible_socket in compatible_sockets {
-                            // For each of those: get all the models that have this socket from direction
-                            // `sockets_to_models` may not have an entry for `compatible_socket` depending on user input data (socket present in sockets_connections but not in a model)
-                            if let Some(allowed_models) = sockets_to_models.get(&compatible_socket)
-                            {
-                                for allowed_model in &allowed_models[direction as usize] {
-                                    if unique_models.insert(*allowed_model) {
-                                        allowed_neighbours[(model_index, direction as usize)]
-                                            .push(*allowed_model);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        let allowed_masks = allowed_neighbours
+            .map(|allowed_models| compute_allowed_mask(allowed_models, model_variations.len()));
 
         // Discard socket information, build linear buffers containing the info needed during the generation
         let mut weights = Vec::with_capacity(model_variations.len());
@@ -271,10 +1113,24 @@ impl<C: CoordinateSystem> Rules<C> {
         #[cfg(feature = "models-names")]
         let mut names = Vec::with_capacity(model_variations.len());
 
+        let variants_sockets = model_variations
+            .iter()
+            .map(|model_variation| model_variation.sockets().clone())
+            .collect();
         let mut models_mapping =
             Array::from_elem((original_models_count, ALL_MODEL_ROTATIONS.len()), None);
+        let mut variations_counts = vec![0usize; original_models_count];
+        for model_variation in model_variations.iter() {
+            variations_counts[model_variation.original_index()] += 1;
+        }
         for (index, model_variation) in model_variations.iter_mut().enumerate() {
-            weights.push(model_variation.weight());
+            weights.push(match normalize_rotation_weights {
+                true => {
+                    model_variation.weight()
+                        / variations_counts[model_variation.original_index()] as f32
+                }
+                false => model_variation.weight(),
+            });
             model_instances.push(model_variation.to_instance());
             #[cfg(feature = "models-names")]
             names.push(model_variation.name.take());
@@ -285,6 +1141,26 @@ impl<C: CoordinateSystem> Rules<C> {
             )] = Some(index);
         }
 
+        let mut pair_weights_map = HashMap::new();
+        for pair_weight in pair_weights {
+            if !(pair_weight.multiplier > 0.) || pair_weight.multiplier.is_infinite() {
+                return Err(RulesBuilderError::InvalidPairWeight(
+                    pair_weight.model,
+                    pair_weight.neighbour,
+                    pair_weight.multiplier,
+                ));
+            }
+            let directions = match pair_weight.direction {
+                Some(direction) => vec![direction],
+                None => coord_system.directions().to_vec(),
+            };
+            for direction in directions {
+                *pair_weights_map
+                    .entry((pair_weight.model, direction as usize, pair_weight.neighbour))
+                    .or_insert(1.) *= pair_weight.multiplier;
+            }
+        }
+
         #[cfg(feature = "debug-traces")]
         {
             trace!(
@@ -301,17 +1177,776 @@ impl<C: CoordinateSystem> Rules<C> {
             #[cfg(feature = "models-names")]
             names,
             allowed_neighbours,
-            typestate: PhantomData,
+            allowed_masks,
+            tags,
+            priorities,
+            count_limits,
+            void_models,
+            emptiness_chances,
+            pair_weights: pair_weights_map,
+            socket_collection,
+            rotation_axis,
+            coord_system,
+            variants_sockets,
+            sockets_to_models,
+            direction_set,
+            variants_offset_sockets,
+            normalize_rotation_weights,
+            direction_aliases,
+        })
+    }
+
+    /// Adds a new model to the rules and returns its [`ModelIndex`].
+    ///
+    /// The model variations and their adjacency are computed incrementally: only the models which can be adjacent to the new model are updated, which is much faster than building new [`Rules`] for big rule sets. The resulting rules are identical to rules built with the new model added last in the [`ModelCollection`].
+    ///
+    /// If the rules have a direction set (see [`RulesBuilder::with_direction_set`]), the offset adjacencies of all the models are recomputed.
+    ///
+    /// The sockets of the model must come from the [`SocketCollection`] used to build these rules. Pair weights (see [`RulesBuilder::with_pair_weight`]) are not defined for the new model. Generators created with the previous rules should be recreated to use the new model.
+    ///
+    /// Returns a [`RulesBuilderError`] (and leaves the rules unchanged) if the model has no socket in a direction, if it uses a socket unknown to the [`SocketCollection`] of the rules, if its weight is invalid, or if one of its rotations moves an offset out of the direction set.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{
+    ///     model::ModelCollection,
+    ///     rules::RulesBuilder,
+    ///     socket::{SocketCollection, SocketsCartesian3D},
+    /// };
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (side, top, bottom) = (sockets.create(), sockets.create(), sockets.create());
+    /// sockets
+    ///     .add_connection(side, vec![side])
+    ///     .add_rotated_connection(top, vec![bottom]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian3D::Mono(side));
+    /// models
+    ///     .create(SocketsCartesian3D::Simple {
+    ///         x_pos: side,
+    ///         x_neg: side,
+    ///         z_pos: side,
+    ///         z_neg: side,
+    ///         y_pos: top,
+    ///         y_neg: bottom,
+    ///     })
+    ///     .with_all_rotations();
+    /// let mut rules = RulesBuilder::new_cartesian_3d(models.clone(), sockets.clone())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let new_model = SocketsCartesian3D::Simple {
+    ///     x_pos: side,
+    ///     x_neg: bottom,
+    ///     z_pos: side,
+    ///     z_neg: top,
+    ///     y_pos: top,
+    ///     y_neg: side,
+    /// }
+    /// .to_template()
+    /// .with_all_rotations();
+    /// assert_eq!(rules.add_model(new_model.clone()).unwrap(), 2);
+    ///
+    /// // Same rules as rules rebuilt from scratch with the new model
+    /// models.create(new_model);
+    /// let rebuilt_rules = RulesBuilder::new_cartesian_3d(models, sockets).build().unwrap();
+    /// assert_eq!(rules.fingerprint(), rebuilt_rules.fingerprint());
+    ///
+    /// // Sockets from another collection are rejected
+    /// let mut other_sockets = SocketCollection::new();
+    /// let unknown = (0..4).map(|_| other_sockets.create()).last().unwrap();
+    /// assert!(rules.add_model(SocketsCartesian3D::Mono(unknown)).is_err());
+    /// assert_eq!(rules.original_models_count(), 3);
+    /// ```
+    pub fn add_model<T: Into<ModelTemplate<C>>>(
+        &mut self,
+        template: T,
+    ) -> Result<ModelIndex, RulesBuilderError> {
+        let model_index = self.original_models_count;
+        let model = Model::from_template(template.into(), model_index);
+        let first_variant = self.models.len();
+        let new_variations = model.create_variations(self.rotation_axis);
+        for model_variation in new_variations.iter() {
+            check_model_variation(
+                model_variation,
+                self.coord_system.directions(),
+                &self.direction_aliases,
+            )?;
+            if let Some(socket) = model_variation
+                .all_sockets()
+                .find(|&socket| !self.socket_collection.contains(socket))
+            {
+                return Err(RulesBuilderError::UnknownSocket(
+                    model_index,
+                    Socket::from_id(socket),
+                ));
+            }
+        }
+        check_direction_set(
+            &self.direction_set,
+            self.coord_system.directions(),
+            &new_variations,
+            self.rotation_axis,
+        )?;
+        let variations_count = new_variations.len();
+        let models_count = first_variant + new_variations.len();
+        // Copied to be able to mutate the rules while iterating the directions
+        let directions = self.coord_system.directions().to_vec();
+
+        // Register the new variations
+        self.tags.push(model.tags().to_vec());
+        self.priorities.push(model.priority());
+        self.count_limits.push(model.count_limits());
+        self.void_models.push(model.is_void());
+        self.emptiness_chances.push(model.emptiness_chance());
+        let mut mapping_row = vec![None; ALL_MODEL_ROTATIONS.len()];
+        for (offset, mut model_variation) in new_variations.into_iter().enumerate() {
+            let variant_index = first_variant + offset;
+            register_model_sockets(
+                &mut self.sockets_to_models,
+                variant_index,
+                model_variation.sockets(),
+                &directions,
+            );
+            mapping_row[model_variation.rotation().index() as usize] = Some(variant_index);
+            self.weights.push(match self.normalize_rotation_weights {
+                true => model_variation.weight() / variations_count as f32,
+                false => model_variation.weight(),
+            });
+            self.models.push(model_variation.to_instance());
+            #[cfg(feature = "models-names")]
+            self.names.push(model_variation.name.take());
+            self.variants_sockets
+                .push(model_variation.sockets().clone());
+            self.variants_offset_sockets.push(variant_offset_sockets(
+                &model_variation,
+                &self.direction_set,
+            ));
+        }
+        // Unwrap is safe, the row has the same length as the other rows
+        self.models_mapping.push_row(aview1(&mapping_row)).unwrap();
+        self.original_models_count += 1;
+
+        // Existing models which can be adjacent to a new variation. Since socket connections are symmetric, those are the models with a socket compatible with one of the new variation sockets.
+        let mut updated_neighbours = BTreeSet::new();
+        for variant_index in first_variant..models_count {
+            for &direction in directions.iter() {
+                for socket in &self.variants_sockets[variant_index][direction as usize] {
+                    let Some(compatible_sockets) = self.socket_collection.get_compatibles(*socket)
+                    else {
+                        continue;
+                    };
+                    for compatible_socket in compatible_sockets {
+                        if let Some(models) = self.sockets_to_models.get(compatible_socket) {
+                            for &model in models[direction as usize].iter() {
+                                if model < first_variant {
+                                    updated_neighbours.insert((model, direction.opposite()));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Extend the adjacency arrays with the new variations
+        for mask in self.allowed_masks.iter_mut() {
+            mask.resize(models_count, false);
+        }
+        let adjacencies_count = self.adjacencies_count();
+        let empty_neighbours_row = vec![Vec::new(); adjacencies_count];
+        let empty_masks_row = vec![bitvec![0; models_count]; adjacencies_count];
+        for variant_index in first_variant..models_count {
+            // Unwraps are safe, the rows have the same length as the other rows
+            self.allowed_neighbours
+                .push_row(aview1(&empty_neighbours_row))
+                .unwrap();
+            self.allowed_masks
+                .push_row(aview1(&empty_masks_row))
+                .unwrap();
+            for &direction in directions.iter() {
+                updated_neighbours.insert((variant_index, direction));
+            }
+        }
+        for (model, direction) in updated_neighbours {
+            let allowed_neighbours = compute_allowed_neighbours(
+                &self.variants_sockets[model][direction as usize],
+                direction,
+                &self.socket_collection,
+                &self.sockets_to_models,
+            );
+            self.allowed_masks[(model, direction as usize)] =
+                compute_allowed_mask(&allowed_neighbours, models_count);
+            self.allowed_neighbours[(model, direction as usize)] = allowed_neighbours;
+        }
+        if let Some(direction_set) = &self.direction_set {
+            for model in 0..models_count {
+                for offset_index in 0..direction_set.len() {
+                    let adjacency = directions.len() + offset_index;
+                    let allowed_neighbours = compute_offset_allowed_neighbours(
+                        &self.variants_offset_sockets[model][offset_index],
+                        direction_set.opposite(offset_index),
+                        &self.socket_collection,
+                        &self.variants_offset_sockets,
+                    );
+                    self.allowed_masks[(model, adjacency)] =
+                        compute_allowed_mask(&allowed_neighbours, models_count);
+                    self.allowed_neighbours[(model, adjacency)] = allowed_neighbours;
+                }
+            }
+        }
+
+        Ok(model_index)
+    }
+
+    /// Returns `true` if the rules constrain the [`Diagonal`] adjacencies, see [`RulesBuilder::with_diagonals`]
+    #[inline]
+    pub fn has_diagonals(&self) -> bool {
+        self.direction_set.as_ref().is_some_and(|direction_set| {
+            Diagonal::ALL
+                .iter()
+                .all(|diagonal| direction_set.index_of(diagonal.offset()).is_some())
         })
     }
 
+    /// Returns the additional adjacencies of the rules, if any. See [`RulesBuilder::with_direction_set`]
+    #[inline]
+    pub fn direction_set(&self) -> Option<&DirectionSet> {
+        self.direction_set.as_ref()
+    }
+
+    /// Number of adjacencies of a node: the directions of the coordinate system, followed by the offsets of the direction set if the rules have one.
+    #[inline]
+    pub(crate) fn adjacencies_count(&self) -> usize {
+        self.allowed_neighbours.ncols()
+    }
+
+    /// Returns the models allowed next to `model` through `adjacency` (see [`Rules::adjacencies_count`])
     #[inline]
     pub(crate) fn allowed_models(
         &self,
         model: ModelVariantIndex,
-        direction: Direction,
+        adjacency: usize,
     ) -> &Vec<ModelVariantIndex> {
-        &self.allowed_neighbours[(model, direction as usize)]
+        &self.allowed_neighbours[(model, adjacency)]
+    }
+
+    /// Returns a bitmask of the models allowed as neighbours of `model` in `direction`: the bit at index `i` is set if the model variant `i` is allowed.
+    ///
+    /// The mask has [`Rules::models_count`] bits. Masks are precomputed when building the [`Rules`].
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::{ghx_grid::direction::Direction, generator::{rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection}};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (white, black) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(white, vec![black]);
+    ///
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(white));
+    /// models.create(SocketsCartesian2D::Mono(black));
+    ///
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    /// let mask = rules.allowed_mask(0, Direction::XForward);
+    /// assert!(!mask.contains(0) && mask.contains(1));
+    /// assert_eq!(mask.iter().collect::<Vec<_>>(), vec![1]);
+    /// ```
+    #[inline]
+    pub fn allowed_mask(&self, model: ModelVariantIndex, direction: Direction) -> ModelsMask<'_> {
+        ModelsMask(&self.allowed_masks[(model, direction as usize)])
+    }
+
+    /// Returns `true` if the model variant `model_b` is allowed as a neighbour of the model variant `model_a` in `direction`. Returns `false` if a model variant index is not valid, or if `direction` is not a direction of the coordinate system of the rules.
+    ///
+    /// Adjacency is symmetric: `compatible(a, direction, b)` is the same as `compatible(b, direction.opposite(), a)`.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::{ghx_grid::direction::Direction, generator::{rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection}};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (sand, water_side, water) = (sockets.create(), sockets.create(), sockets.create());
+    /// sockets.add_connection(sand, vec![sand, water_side]);
+    /// sockets.add_connection(water, vec![water, water_side]);
+    ///
+    /// let mut models = ModelCollection::new();
+    /// let sand_model = models.create(SocketsCartesian2D::Mono(sand)).index();
+    /// let water_model = models.create(SocketsCartesian2D::Mono(water)).index();
+    ///
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    /// assert!(!rules.models_compatible(sand_model, Direction::XForward, water_model));
+    /// assert!(rules.compatible(0, Direction::YForward, 0));
+    /// // There is no z axis in a 2d coordinate system
+    /// assert!(!rules.compatible(0, Direction::ZForward, 0));
+    /// assert!(rules.sockets_in(0, Direction::ZForward).is_empty());
+    /// ```
+    pub fn compatible(
+        &self,
+        model_a: ModelVariantIndex,
+        direction: Direction,
+        model_b: ModelVariantIndex,
+    ) -> bool {
+        self.is_valid_model_variant_index(model_a)
+            && self.is_valid_model_variant_index(model_b)
+            && self.coord_system.directions().contains(&direction)
+            && self.allowed_masks[(model_a, direction as usize)][model_b]
+    }
+
+    /// Returns `true` if any variation (rotation) of the original model `model_b` is allowed as a neighbour of any variation of the original model `model_a` in `direction`. Returns `false` if a model index is not valid, or if `direction` is not a direction of the coordinate system of the rules.
+    pub fn models_compatible(
+        &self,
+        model_a: ModelIndex,
+        direction: Direction,
+        model_b: ModelIndex,
+    ) -> bool {
+        let variants = |model_index: ModelIndex| {
+            ALL_MODEL_ROTATIONS
+                .iter()
+                .filter_map(move |&rot| self.variant_index(model_index, rot))
+        };
+        variants(model_a).any(|variant_a| {
+            variants(model_b).any(|variant_b| self.compatible(variant_a, direction, variant_b))
+        })
+    }
+
+    /// Explains why the original models `model_a` and `model_b` can or cannot be adjacent in `direction` (`model_b` being the neighbour of `model_a` in `direction`): lists the pairs of rotations allowed to be adjacent, and for the other pairs, the facing sockets which are not connected. Useful to find a forgotten [`SocketCollection::add_connection`], for a single rotation for example.
+    ///
+    /// Returns `None` if a model index is not valid or if `direction` is not a direction of these rules.
+    ///
+    /// ### Example
+    /// ```
+    /// use ghx_proc_gen::generator::{rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    /// use ghx_grid::direction::Direction;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (road, grass) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(road, vec![road]).add_connection(grass, vec![grass]);
+    ///
+    /// let mut models = ModelCollection::new();
+    /// let road_end = models
+    ///     .create(SocketsCartesian2D::Simple { x_pos: road, x_neg: grass, y_pos: grass, y_neg: grass })
+    ///     .with_all_rotations()
+    ///     .index();
+    /// let plain = models.create(SocketsCartesian2D::Mono(grass)).index();
+    ///
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    /// let diagnostic = rules.explain_adjacency(road_end, Direction::XForward, plain).unwrap();
+    /// assert!(diagnostic.is_allowed());
+    /// // The rotation 0 of the road end faces the grass with its road socket
+    /// assert!(diagnostic.missing.iter().all(|missing| missing.rotation_a == ModelRotation::Rot0));
+    /// println!("{}", diagnostic);
+    /// ```
+    pub fn explain_adjacency(
+        &self,
+        model_a: ModelIndex,
+        direction: Direction,
+        model_b: ModelIndex,
+    ) -> Option<AdjacencyDiagnostic> {
+        if model_a >= self.original_models_count
+            || model_b >= self.original_models_count
+            || !self.coord_system.directions().contains(&direction)
+        {
+            return None;
+        }
+        let mut diagnostic = AdjacencyDiagnostic {
+            model_a,
+            direction: self.named_direction(direction),
+            model_b,
+            allowed: Vec::new(),
+            missing: Vec::new(),
+        };
+        for &rotation_a in ALL_MODEL_ROTATIONS {
+            let Some(variant_a) = self.variant_index(model_a, rotation_a) else {
+                continue;
+            };
+            for &rotation_b in ALL_MODEL_ROTATIONS {
+                let Some(variant_b) = self.variant_index(model_b, rotation_b) else {
+                    continue;
+                };
+                if self.compatible(variant_a, direction, variant_b) {
+                    diagnostic.allowed.push((rotation_a, rotation_b));
+                    continue;
+                }
+                let sockets_b = self.sockets_in(variant_b, direction.opposite());
+                for socket_a in self.sockets_in(variant_a, direction) {
+                    for &socket_b in sockets_b.iter() {
+                        diagnostic.missing.push(MissingConnection {
+                            rotation_a,
+                            rotation_b,
+                            socket_a,
+                            socket_b,
+                        });
+                    }
+                }
+            }
+        }
+        Some(diagnostic)
+    }
+
+    /// Returns the sockets of the model variant `model` in `direction`, after its rotation. Returns an empty list if the model variant index is not valid or if `direction` is not a direction of the coordinate system of the rules.
+    pub fn sockets_in(&self, model: ModelVariantIndex, direction: Direction) -> Vec<Socket> {
+        if !self.coord_system.directions().contains(&direction) {
+            return Vec::new();
+        }
+        match self.variants_sockets.get(model) {
+            Some(sockets) => sockets[direction as usize]
+                .iter()
+                .map(|&socket_id| Socket::from_id(socket_id))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the name of `socket` in the [`SocketCollection`] of these rules, if it was named (see [`SocketCollection::set_name`])
+    pub fn socket_name(&self, socket: &Socket) -> Option<&str> {
+        self.socket_collection.name(socket)
+    }
+
+    /// Returns a label for `socket` to be displayed by debug tools: its name (and rotation if rotated) if it was named, else its index (see the [`std::fmt::Display`] implementation of [`Socket`])
+    pub fn socket_label(&self, socket: &Socket) -> String {
+        match (self.socket_name(socket), socket.rotation()) {
+            (Some(name), ModelRotation::Rot0) => name.to_string(),
+            (Some(name), rot) => format!("{} (rot: {})", name, rot),
+            (None, _) => socket.to_string(),
+        }
+    }
+
+    /// Checks every pair of adjacent nodes of `grid_data` against these rules, including the offsets of the [`DirectionSet`] if the rules have one.
+    ///
+    /// Returns all the [`Violation`] found, in nodes order. Each pair of adjacent nodes is checked once, from the node with the lowest index. Useful to validate hand-edited grids, or grids loaded from a file.
+    ///
+    /// ### Example
+    /// ```
+    /// use ghx_proc_gen::generator::{rules::{RulesBuilder, Violation}, socket::{SocketsCartesian2D, SocketCollection}, model::{ModelCollection, ModelInstance, ModelRotation}};
+    /// use ghx_grid::grid::{GridData, GridDefinition};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (sand, water) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(sand, vec![sand]).add_connection(water, vec![water]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(sand));
+    /// models.create(SocketsCartesian2D::Mono(water));
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    ///
+    /// let sand_instance = ModelInstance { model_index: 0, rotation: ModelRotation::Rot0 };
+    /// let mut grid_data = GridData::new(GridDefinition::new_cartesian_2d(3, 1, false, false), vec![sand_instance; 3]);
+    /// assert!(rules.validate_output(&grid_data).is_ok());
+    ///
+    /// grid_data.set(1, ModelInstance { model_index: 1, rotation: ModelRotation::Rot0 });
+    /// assert_eq!(rules.validate_output(&grid_data).unwrap_err().len(), 2);
+    /// ```
+    pub fn validate_output(
+        &self,
+        grid_data: &GridData<C, ModelInstance>,
+    ) -> Result<(), Vec<Violation>> {
+        let grid = grid_data.grid();
+        let directions_count = grid.directions().len();
+        let variant = |node_index: NodeIndex| {
+            let model_instance = grid_data.get(node_index);
+            self.variant_index(model_instance.model_index, model_instance.rotation)
+        };
+        let mut violations = Vec::new();
+        for (node_index, model_instance) in grid_data.nodes().iter().enumerate() {
+            let Some(node_variant) = variant(node_index) else {
+                violations.push(Violation::UnknownModel(node_index, *model_instance));
+                continue;
+            };
+            let position = grid.pos_from_index(node_index);
+            for adjacency in 0..self.adjacencies_count() {
+                let Some(neighbour_index) =
+                    adjacent_node_index(grid, self.direction_set.as_ref(), &position, adjacency)
+                else {
+                    continue;
+                };
+                if neighbour_index < node_index {
+                    continue;
+                }
+                // Unknown neighbours are reported on their own
+                let Some(neighbour_variant) = variant(neighbour_index) else {
+                    continue;
+                };
+                if self.allowed_masks[(node_variant, adjacency)][neighbour_variant] {
+                    continue;
+                }
+                violations.push(match adjacency < directions_count {
+                    true => Violation::Adjacency(AdjacencyViolation {
+                        node_index,
+                        neighbour_index,
+                        direction: grid.directions()[adjacency],
+                    }),
+                    false => Violation::OffsetAdjacency {
+                        node_index,
+                        neighbour_index,
+                        // Unwrap is safe, adjacencies after the directions are offsets of the direction set
+                        offset: self.direction_set.as_ref().unwrap().offsets()
+                            [adjacency - directions_count],
+                    },
+                });
+            }
+        }
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(violations),
+        }
+    }
+
+    /// Same as [`Rules::allowed_mask`] for a [`Diagonal`] adjacency. Returns `None` if the diagonal is not in the direction set of the rules.
+    #[inline]
+    pub fn allowed_diagonal_mask(
+        &self,
+        model: ModelVariantIndex,
+        diagonal: Diagonal,
+    ) -> Option<ModelsMask<'_>> {
+        self.allowed_offset_mask(model, diagonal.offset())
+    }
+
+    /// Same as [`Rules::allowed_mask`] for an `offset` of the direction set (see [`RulesBuilder::with_direction_set`]). Returns `None` if `offset` is not in the direction set of the rules.
+    #[inline]
+    pub fn allowed_offset_mask(
+        &self,
+        model: ModelVariantIndex,
+        offset: GridOffset,
+    ) -> Option<ModelsMask<'_>> {
+        let offset_index = self.direction_set.as_ref()?.index_of(offset)?;
+        Some(ModelsMask(
+            &self.allowed_masks[(model, self.coord_system.directions().len() + offset_index)],
+        ))
+    }
+
+    /// Same as [`Rules::allowed_mask`] for an `adjacency` (see [`Rules::adjacencies_count`]), stored as words of `usize::BITS` models. Bits after [`Rules::models_count`] are always 0.
+    #[cfg(feature = "simd-propagation")]
+    #[inline]
+    pub(crate) fn allowed_mask_words(
+        &self,
+        model: ModelVariantIndex,
+        adjacency: usize,
+    ) -> &[usize] {
+        self.allowed_masks[(model, adjacency)].as_raw_slice()
+    }
+
+    /// Returns [`RulesStats`] about these rules, to predict the memory needed by a generation before running it.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{rules::RulesBuilder, socket::{SocketsCartesian3D, SocketCollection}, model::ModelCollection};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (white, black) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(white, vec![black]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian3D::Mono(white));
+    /// models.create(SocketsCartesian3D::Mono(black));
+    /// let rules = RulesBuilder::new_cartesian_3d(models, sockets).build().unwrap();
+    ///
+    /// let stats = rules.stats();
+    /// assert_eq!(stats.models_count, 2);
+    /// assert_eq!(stats.average_allowed_neighbours, vec![1.; 6]);
+    /// // Will a 200x200x10 generation fit in 1 GB ?
+    /// assert!(stats.estimated_grid_memory(200 * 200 * 10) < 1 << 30);
+    /// ```
+    pub fn stats(&self) -> RulesStats {
+        let models_count = self.models_count();
+        let adjacencies_count = self.adjacencies_count();
+        let average_allowed_neighbours = (0..adjacencies_count)
+            .map(|adjacency| {
+                let total: usize = (0..models_count)
+                    .map(|model| self.allowed_neighbours[(model, adjacency)].len())
+                    .sum();
+                total as f32 / models_count as f32
+            })
+            .collect();
+        let usize_size = std::mem::size_of::<usize>();
+        // Possible models bits, supports counts for each model and adjacency, possible models count
+        let node_memory =
+            models_count.div_ceil(8) + (models_count * adjacencies_count + 1) * usize_size;
+        let neighbours_count: usize = self.allowed_neighbours.iter().map(Vec::len).sum();
+        let rules_memory = neighbours_count * usize_size
+            + models_count
+                * adjacencies_count
+                * models_count.div_ceil(usize::BITS as usize)
+                * usize_size;
+        RulesStats {
+            original_models_count: self.original_models_count,
+            models_count,
+            average_allowed_neighbours,
+            node_memory,
+            rules_memory,
+        }
+    }
+
+    /// Looks for likely mistakes in these rules, and returns them in this order:
+    /// - the model variants which can never have a neighbour in some directions (see [`RulesLint::UnmatchedVariant`])
+    /// - the original models with a weight more than [`WEIGHT_ANOMALY_RATIO`] times higher, or lower, than the median weight (see [`RulesLint::WeightAnomaly`])
+    /// - the sockets only connected to themselves (see [`RulesLint::SelfConnectedSocket`])
+    ///
+    /// The lints do not prevent the rules from being used, some of them may be intended.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{rules::{RulesBuilder, RulesLint}, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (grass, path) = (sockets.create_named("grass"), sockets.create_named("path"));
+    /// sockets.add_connection(grass, vec![grass]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(grass));
+    /// models.create(SocketsCartesian2D::Mono(grass)).with_weight(2.);
+    /// // Typo, the weight should have been 0.5
+    /// models.create(SocketsCartesian2D::Mono(grass)).with_weight(500.);
+    /// // The path socket is not connected to anything
+    /// models.create(SocketsCartesian2D::Simple { x_pos: path, x_neg: grass, y_pos: grass, y_neg: grass });
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    ///
+    /// let lints = rules.lint();
+    /// assert!(lints.iter().any(|lint| matches!(lint, RulesLint::UnmatchedVariant { model_index: 3, .. })));
+    /// assert!(lints.iter().any(|lint| matches!(lint, RulesLint::WeightAnomaly { model_index: 2, .. })));
+    /// assert!(lints.iter().any(|lint| matches!(lint, RulesLint::SelfConnectedSocket { .. })));
+    /// for lint in lints {
+    ///     println!("{}", lint);
+    /// }
+    /// ```
+    pub fn lint(&self) -> Vec<RulesLint> {
+        let mut lints = Vec::new();
+        for (variant, instance) in self.models.iter().enumerate() {
+            let directions: Vec<NamedDirection> = self
+                .coord_system
+                .directions()
+                .iter()
+                .filter(|&&direction| {
+                    self.allowed_neighbours[(variant, direction as usize)].is_empty()
+                })
+                .map(|&direction| self.named_direction(direction))
+                .collect();
+            if !directions.is_empty() {
+                lints.push(RulesLint::UnmatchedVariant {
+                    model_index: instance.model_index,
+                    rotation: instance.rotation,
+                    name: self.variant_name(variant),
+                    directions,
+                });
+            }
+        }
+
+        // Weight and first variant of each original model
+        let original_weights: Vec<(f32, ModelVariantIndex)> = (0..self.original_models_count)
+            .filter_map(|model_index| {
+                let variants: Vec<ModelVariantIndex> = ALL_MODEL_ROTATIONS
+                    .iter()
+                    .filter_map(|&rotation| self.variant_index(model_index, rotation))
+                    .collect();
+                let first_variant = *variants.first()?;
+                let weight = match self.normalize_rotation_weights {
+                    true => variants.iter().map(|&variant| self.weights[variant]).sum(),
+                    false => self.weights[first_variant],
+                };
+                Some((weight, first_variant))
+            })
+            .collect();
+        let mut sorted_weights: Vec<f32> =
+            original_weights.iter().map(|(weight, _)| *weight).collect();
+        sorted_weights.sort_by(|a, b| a.total_cmp(b));
+        let median = match sorted_weights.len() {
+            0 => 0.,
+            len if len % 2 == 0 => (sorted_weights[len / 2 - 1] + sorted_weights[len / 2]) / 2.,
+            len => sorted_weights[len / 2],
+        };
+        if median > 0. {
+            for &(weight, variant) in original_weights.iter() {
+                if weight > median * WEIGHT_ANOMALY_RATIO || weight < median / WEIGHT_ANOMALY_RATIO
+                {
+                    lints.push(RulesLint::WeightAnomaly {
+                        model_index: self.models[variant].model_index,
+                        name: self.variant_name(variant),
+                        weight,
+                        median,
+                    });
+                }
+            }
+        }
+
+        for socket in self.socket_collection.self_connected_sockets() {
+            lints.push(RulesLint::SelfConnectedSocket {
+                socket,
+                name: self
+                    .socket_collection
+                    .name(&socket)
+                    .map(|name| Cow::Owned(name.to_string())),
+            });
+        }
+        lints
+    }
+
+    /// Returns a stable hash of the content of these rules which affects the generation: the adjacencies, the model variations with their weights, the allowed neighbours of each variation, the models priorities and count limits and the pair weights.
+    ///
+    /// The names, tags and direction aliases are not included. The fingerprint only depends on the rules content, not on the platform or Rust version, so it can be stored next to a serialized output (see [`crate::serialization::to_bytes_with_fingerprint`]) to detect that it was generated with different rules.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::generator::{rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (white, black) = (sockets.create(), sockets.create());
+    /// sockets.add_connection(white, vec![black]);
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(white));
+    /// models.create(SocketsCartesian2D::Mono(black));
+    /// let rules = RulesBuilder::new_cartesian_2d(models.clone(), sockets.clone()).build().unwrap();
+    /// let same_rules = RulesBuilder::new_cartesian_2d(models.clone(), sockets.clone()).build().unwrap();
+    /// assert_eq!(rules.fingerprint(), same_rules.fingerprint());
+    ///
+    /// models.create(SocketsCartesian2D::Mono(white));
+    /// let other_rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    /// assert_ne!(rules.fingerprint(), other_rules.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        hasher.write(self.adjacencies_count() as u64);
+        for offset in self.direction_set.iter().flat_map(|set| set.offsets()) {
+            hasher.write(offset.dx as u64);
+            hasher.write(offset.dy as u64);
+            hasher.write(offset.dz as u64);
+        }
+        hasher.write(self.rotation_axis as u64);
+        hasher.write(self.original_models_count as u64);
+        for (model_index, (priority, count_limits)) in
+            self.priorities.iter().zip(&self.count_limits).enumerate()
+        {
+            hasher.write(model_index as u64);
+            hasher.write(*priority as u64);
+            for limit in [count_limits.per_column, count_limits.per_layer] {
+                hasher.write(limit.map_or(u64::MAX, |limit| limit as u64));
+            }
+        }
+        hasher.write(self.models.len() as u64);
+        for (model, weight) in self.models.iter().zip(&self.weights) {
+            hasher.write(model.model_index as u64);
+            hasher.write(model.rotation.index() as u64);
+            hasher.write(weight.to_bits() as u64);
+        }
+        for allowed_neighbours in self.allowed_neighbours.iter() {
+            hasher.write(allowed_neighbours.len() as u64);
+            for neighbour in allowed_neighbours {
+                hasher.write(*neighbour as u64);
+            }
+        }
+        // HashMap iteration order is not stable
+        let mut pair_weights: Vec<_> = self.pair_weights.iter().collect();
+        pair_weights.sort_by_key(|(key, _)| **key);
+        hasher.write(pair_weights.len() as u64);
+        for ((model, direction, neighbour), multiplier) in pair_weights {
+            hasher.write(*model as u64);
+            hasher.write(*direction as u64);
+            hasher.write(*neighbour as u64);
+            hasher.write(multiplier.to_bits() as u64);
+        }
+        hasher.finish()
     }
 
     /// Returns the number of models (expanded from the input models) present in the rules
@@ -340,9 +1975,31 @@ impl<C: CoordinateSystem> Rules<C> {
         }
     }
 
+    /// Returns the weight of a model variant as a `f64`, used by the generator to accumulate weights without losing precision
     #[inline]
-    pub(crate) fn weight_unchecked(&self, model_index: ModelVariantIndex) -> f32 {
-        self.weights[model_index]
+    pub(crate) fn weight_unchecked(&self, model_index: ModelVariantIndex) -> f64 {
+        self.weights[model_index] as f64
+    }
+
+    #[inline]
+    pub(crate) fn has_pair_weights(&self) -> bool {
+        !self.pair_weights.is_empty()
+    }
+
+    /// Returns the weight multiplier applied to the original model `model` when `neighbour` is generated on the adjacent node in `direction`. See [`RulesBuilder::with_pair_weight`]
+    pub fn pair_weight(
+        &self,
+        model: ModelIndex,
+        direction: Direction,
+        neighbour: ModelIndex,
+    ) -> f32 {
+        match self
+            .pair_weights
+            .get(&(model, direction as usize, neighbour))
+        {
+            Some(multiplier) => *multiplier,
+            None => 1.,
+        }
     }
 
     /// Returns the weight of a model variant as an [`Option`]. Returns [`None`] if this model variant index is not valid.
@@ -371,6 +2028,117 @@ impl<C: CoordinateSystem> Rules<C> {
         }
     }
 
+    /// Returns the tags of the original model with index `model_index`. Returns an empty slice if this model index is not valid.
+    pub fn tags(&self, model_index: ModelIndex) -> &[Cow<'static, str>] {
+        match self.tags.get(model_index) {
+            Some(tags) => tags,
+            None => &[],
+        }
+    }
+
+    pub(crate) fn coord_system(&self) -> &C {
+        &self.coord_system
+    }
+
+    /// Returns the direction aliases of the rules, see [`RulesBuilder::with_direction_alias`]
+    pub fn direction_aliases(&self) -> &DirectionAliases {
+        &self.direction_aliases
+    }
+
+    /// Returns `direction` with its alias in these rules, to be displayed
+    pub fn named_direction(&self, direction: Direction) -> NamedDirection {
+        self.direction_aliases.named(direction)
+    }
+
+    /// Returns the direction with the alias `alias` in these rules, if any
+    pub fn direction_from_alias(&self, alias: &str) -> Option<Direction> {
+        self.direction_aliases.direction(alias)
+    }
+
+    /// Returns the collapse priority of the original model with index `model_index`, see [`super::model::ModelTemplate::with_priority`]. Returns `None` if this model index is not valid.
+    pub fn priority(&self, model_index: ModelIndex) -> Option<u32> {
+        self.priorities.get(model_index).copied()
+    }
+
+    /// Returns the collapse priority of the model variant `model_index`
+    pub(crate) fn variant_priority_unchecked(&self, model_index: ModelVariantIndex) -> u32 {
+        self.priorities[self.models[model_index].model_index]
+    }
+
+    /// Returns the [`CountLimits`] of the original model with index `model_index`, see [`super::model::ModelTemplate::with_max_per_column`]. Returns `None` if this model index is not valid.
+    pub fn count_limits(&self, model_index: ModelIndex) -> Option<CountLimits> {
+        self.count_limits.get(model_index).copied()
+    }
+
+    /// Returns `true` if at least one model has [`CountLimits`]
+    pub(crate) fn has_count_limits(&self) -> bool {
+        self.count_limits
+            .iter()
+            .any(|limits| !limits.is_unlimited())
+    }
+
+    /// Returns the [`CountLimits`] of the model variant `model_index`
+    pub(crate) fn variant_count_limits_unchecked(
+        &self,
+        model_index: ModelVariantIndex,
+    ) -> CountLimits {
+        self.count_limits[self.models[model_index].model_index]
+    }
+
+    /// Returns `true` if the original model with index `model_index` is a void model, see [`super::model::ModelTemplate::with_void`]. Returns `false` if this model index is not valid.
+    pub fn is_void(&self, model_index: ModelIndex) -> bool {
+        self.void_models.get(model_index).copied().unwrap_or(false)
+    }
+
+    /// Returns the indexes of all the void original models
+    pub fn void_models(&self) -> Vec<ModelIndex> {
+        (0..self.original_models_count)
+            .filter(|&model_index| self.is_void(model_index))
+            .collect()
+    }
+
+    /// Returns the emptiness chance of the original model with index `model_index`, see [`super::model::ModelTemplate::with_emptiness_chance`]. Returns `0.` if this model index is not valid.
+    pub fn emptiness_chance(&self, model_index: ModelIndex) -> f32 {
+        self.emptiness_chances
+            .get(model_index)
+            .copied()
+            .unwrap_or(0.)
+    }
+
+    /// Returns `true` if the node `node_index`, generated with the original model `model_index` by a generator seeded with `seed`, should be spawned/reported as empty, see [`super::model::ModelTemplate::with_emptiness_chance`].
+    ///
+    /// The result only depends on its parameters: the same generation always gives the same empty nodes, on all platforms.
+    pub fn is_spawned_empty(
+        &self,
+        model_index: ModelIndex,
+        seed: u64,
+        node_index: NodeIndex,
+    ) -> bool {
+        let chance = self.emptiness_chance(model_index);
+        if chance <= 0. {
+            return false;
+        }
+        let mut hasher = StableHasher::new();
+        hasher.write(seed);
+        hasher.write(node_index as u64);
+        hasher.write(model_index as u64);
+        // Uses the 53 high bits of the hash to get a uniform value in [0, 1)
+        let roll = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+        roll < chance as f64
+    }
+
+    /// Returns `true` if the original model with index `model_index` has the tag `tag`
+    pub fn has_tag(&self, model_index: ModelIndex, tag: &str) -> bool {
+        self.tags(model_index).iter().any(|t| t == tag)
+    }
+
+    /// Returns the indexes of all the original models with the tag `tag`
+    pub fn models_with_tag(&self, tag: &str) -> Vec<ModelIndex> {
+        (0..self.original_models_count)
+            .filter(|&model_index| self.has_tag(model_index, tag))
+            .collect()
+    }
+
     #[cfg(feature = "models-names")]
     #[inline]
     pub(crate) fn name_unchecked(&self, model_index: ModelVariantIndex) -> Cow<'static, str> {
@@ -389,6 +2157,18 @@ impl<C: CoordinateSystem> Rules<C> {
         }
     }
 
+    /// Returns the name of a model variant, if it has one
+    #[cfg(feature = "models-names")]
+    fn variant_name(&self, model_index: ModelVariantIndex) -> Option<Cow<'static, str>> {
+        self.names[model_index].clone()
+    }
+
+    /// Returns the name of a model variant, always `None` without the `models-names` feature
+    #[cfg(not(feature = "models-names"))]
+    fn variant_name(&self, _model_index: ModelVariantIndex) -> Option<Cow<'static, str>> {
+        None
+    }
+
     /// Returns the name of a model variant as an [`Option`].
     ///
     /// Returns [`None`]  if this model variant index is not valid or if it does not have a name.
@@ -0,0 +1,115 @@
+use ghx_grid::{coordinate_system::CoordinateSystem, grid::GridData};
+
+use crate::SkeletonGenerationError;
+
+use super::{
+    builder::{GeneratorBuilder, Set},
+    model::{ModelIndex, ModelInstance},
+    rules::Rules,
+    Generator,
+};
+
+/// Maps the models of some "skeleton" [`Rules`] (roads, rivers, ...) to the models of some full [`Rules`], see [`generate_with_skeleton`].
+///
+/// Skeleton models without a mapped full model are not frozen: the full generation is free to generate anything on their nodes.
+#[derive(Clone, Debug)]
+pub struct SkeletonMapping {
+    /// `mapping[skeleton_model_index]` is the optional full model index mapped to this skeleton model.
+    mapping: Vec<Option<ModelIndex>>,
+}
+
+impl SkeletonMapping {
+    /// Creates a mapping from a closure returning the full model mapped to each skeleton model
+    pub fn from_fn<C: CoordinateSystem, F: Fn(ModelIndex) -> Option<ModelIndex>>(
+        skeleton_rules: &Rules<C>,
+        f: F,
+    ) -> Self {
+        Self {
+            mapping: (0..skeleton_rules.original_models_count()).map(f).collect(),
+        }
+    }
+
+    /// Maps each skeleton model to the full model with the same name, if any.
+    #[cfg(feature = "models-names")]
+    pub fn by_name<C: CoordinateSystem>(skeleton_rules: &Rules<C>, full_rules: &Rules<C>) -> Self {
+        Self::from_fn(skeleton_rules, |skeleton_model| {
+            let name = original_model_name(skeleton_rules, skeleton_model)?;
+            (0..full_rules.original_models_count())
+                .find(|&full_model| original_model_name(full_rules, full_model) == Some(name))
+        })
+    }
+
+    /// Maps each skeleton model to the first full model sharing one of the given `tags` with it (tags are tried in order).
+    pub fn by_tags<C: CoordinateSystem>(
+        skeleton_rules: &Rules<C>,
+        full_rules: &Rules<C>,
+        tags: &[&str],
+    ) -> Self {
+        Self::from_fn(skeleton_rules, |skeleton_model| {
+            tags.iter()
+                .filter(|tag| skeleton_rules.has_tag(skeleton_model, tag))
+                .find_map(|tag| full_rules.models_with_tag(tag).first().copied())
+        })
+    }
+
+    /// Returns the full model index mapped to `skeleton_model`, if any
+    pub fn get(&self, skeleton_model: ModelIndex) -> Option<ModelIndex> {
+        self.mapping.get(skeleton_model).copied().flatten()
+    }
+
+    /// Converts a skeleton generation output into constraints for the full generation: each node with a mapped skeleton model is frozen to the mapped full model, with the same rotation.
+    ///
+    /// The result can be given to [`GeneratorBuilder::with_initial_grid`].
+    pub fn freeze<C: CoordinateSystem>(
+        &self,
+        skeleton_output: &GridData<C, ModelInstance>,
+    ) -> GridData<C, Option<ModelInstance>> {
+        GridData::new(
+            skeleton_output.grid().clone(),
+            skeleton_output
+                .nodes()
+                .iter()
+                .map(|skeleton_instance| {
+                    self.get(skeleton_instance.model_index)
+                        .map(|model_index| ModelInstance {
+                            model_index,
+                            rotation: skeleton_instance.rotation,
+                        })
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(feature = "models-names")]
+fn original_model_name<C: CoordinateSystem>(
+    rules: &Rules<C>,
+    model_index: ModelIndex,
+) -> Option<&str> {
+    super::model::ALL_MODEL_ROTATIONS
+        .iter()
+        .find_map(|rot| rules.variant_index(model_index, *rot))
+        .and_then(|variant_index| rules.name_str(variant_index))
+}
+
+/// Two-pass generation: runs `skeleton_generator` with reduced "skeleton" rules (roads, rivers, ...), freezes the generated skeleton nodes as constraints (using `mapping`), then builds a generator from `full_builder` with those constraints and runs it to generate the remaining nodes with the full rules.
+///
+/// The frozen nodes are registered as initial nodes of the full generator: they are kept when it reinitializes.
+///
+/// Returns the full [`Generator`] and its output if successful.
+pub fn generate_with_skeleton<C: CoordinateSystem>(
+    skeleton_generator: &mut Generator<C>,
+    full_builder: GeneratorBuilder<Set, Set, C>,
+    mapping: &SkeletonMapping,
+) -> Result<(Generator<C>, GridData<C, ModelInstance>), SkeletonGenerationError> {
+    let (_gen_info, skeleton_output) = skeleton_generator
+        .generate_grid()
+        .map_err(SkeletonGenerationError::SkeletonFailed)?;
+    let mut full_generator = full_builder
+        .with_initial_grid(mapping.freeze(&skeleton_output))?
+        .build()?;
+    let (_gen_info, full_output) = full_generator
+        .generate_grid()
+        .map_err(SkeletonGenerationError::FullFailed)?;
+    Ok((full_generator, full_output))
+}
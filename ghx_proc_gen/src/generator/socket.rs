@@ -1,4 +1,8 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
 
 use ghx_grid::coordinate_system::{Cartesian2D, Cartesian3D};
 
@@ -13,10 +17,14 @@ pub(crate) type SocketId = u64;
 pub struct SocketCollection {
     incremental_socket_index: u32,
 
-    /// For uniqueness
-    uniques: HashMap<SocketId, HashSet<SocketId>>,
+    /// For uniqueness. Ordered maps and sets are used so that iterating the collection is deterministic.
+    uniques: BTreeMap<SocketId, BTreeSet<SocketId>>,
     /// For determinism and sequential access
-    compatibles: HashMap<SocketId, Vec<SocketId>>,
+    compatibles: BTreeMap<SocketId, Vec<SocketId>>,
+    /// [`Flow`] of the directed sockets, by socket index
+    flows: BTreeMap<u32, Flow>,
+    /// Names of the named sockets, by socket index
+    names: BTreeMap<u32, Cow<'static, str>>,
 }
 
 impl SocketCollection {
@@ -24,8 +32,10 @@ impl SocketCollection {
     pub fn new() -> Self {
         Self {
             incremental_socket_index: 0,
-            uniques: HashMap::new(),
-            compatibles: HashMap::new(),
+            uniques: BTreeMap::new(),
+            compatibles: BTreeMap::new(),
+            flows: BTreeMap::new(),
+            names: BTreeMap::new(),
         }
     }
 
@@ -36,6 +46,64 @@ impl SocketCollection {
         socket
     }
 
+    /// Creates a new [`Socket`] named `name` in the collection and returns it. Socket names are only used to debug the rules, see [`SocketCollection::set_name`].
+    pub fn create_named(&mut self, name: impl Into<Cow<'static, str>>) -> Socket {
+        let socket = self.create();
+        self.set_name(socket, name);
+        socket
+    }
+
+    /// Names `socket`, replacing its previous name if any. Socket names are displayed by the debug tools (such as the socket directions of the selected node in the `bevy_ghx_proc_gen` debug plugin) instead of the socket indexes, to make it easier to see why two models can or cannot be neighbours.
+    ///
+    /// All the rotations of a socket share its name.
+    pub fn set_name(&mut self, socket: Socket, name: impl Into<Cow<'static, str>>) -> &mut Self {
+        self.names.insert(socket.socket_index, name.into());
+        self
+    }
+
+    /// Returns the name of `socket`, if it was named
+    pub fn name(&self, socket: &Socket) -> Option<&str> {
+        self.names
+            .get(&socket.socket_index)
+            .map(|name| name.as_ref())
+    }
+
+    /// Creates a new pair of directed sockets in the collection, connected from the outflow to the inflow, and returns it.
+    ///
+    /// Directed sockets model flows with a consistent direction, such as rivers or one-way roads: a model with the outflow on one side can only be followed by a model with the inflow on the facing side. Since the [`Flow`] of a socket does not depend on its side or rotation, rotated models keep their flow direction.
+    ///
+    /// The rules can only be built if every connection involving a directed socket goes from an outflow to an inflow, for all the rotations of the sockets (see [`crate::RulesBuilderError::InvalidFlowConnection`]). More connections can be added between the outflows and inflows of different pairs (a river flowing into a lake for example).
+    ///
+    /// ### Example
+    /// ```
+    /// use ghx_proc_gen::generator::{rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection};
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let land = sockets.create();
+    /// let river = sockets.create_directed();
+    /// sockets.add_connection(land, vec![land]);
+    ///
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(land));
+    /// // The river flows from x- to x+ before rotation
+    /// models
+    ///     .create(SocketsCartesian2D::Simple { x_pos: river.outflow, x_neg: river.inflow, y_pos: land, y_neg: land })
+    ///     .with_all_rotations();
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    /// ```
+    pub fn create_directed(&mut self) -> DirectedSocket {
+        let (outflow, inflow) = (self.create(), self.create());
+        self.flows.insert(outflow.socket_index, Flow::Outflow);
+        self.flows.insert(inflow.socket_index, Flow::Inflow);
+        self.register_connection(&outflow, &inflow);
+        DirectedSocket { outflow, inflow }
+    }
+
+    /// Returns the [`Flow`] of `socket` if it was created by [`SocketCollection::create_directed`], `None` otherwise
+    pub fn flow(&self, socket: Socket) -> Option<Flow> {
+        self.flows.get(&socket.socket_index).copied()
+    }
+
     /// Adds a connection between two sockets. [`super::model::Model`] with sockets `from` can connect to model with
     /// sockets `to` and vice versa.
     ///
@@ -183,15 +251,80 @@ impl SocketCollection {
         self
     }
 
+    /// Moves all the sockets and connections of `other` into this collection.
+    ///
+    /// The sockets of `other` get new indexes in this collection: the returned [`SocketsRemapping`] converts them (and is needed to merge the [`super::model::ModelCollection`] using them, see [`super::model::ModelCollection::merge`]). The sockets of this collection are unchanged.
+    ///
+    /// ### Example
+    /// ```
+    /// use ghx_proc_gen::generator::socket::SocketCollection;
+    ///
+    /// let mut terrain_sockets = SocketCollection::new();
+    /// let grass = terrain_sockets.create();
+    /// terrain_sockets.add_connection(grass, vec![grass]);
+    ///
+    /// let mut props_sockets = SocketCollection::new();
+    /// let fence = props_sockets.create();
+    /// props_sockets.add_connection(fence, vec![fence]);
+    ///
+    /// let remapping = terrain_sockets.merge(props_sockets);
+    /// let fence = remapping.socket(fence);
+    /// // Sockets of both packs can now be connected
+    /// terrain_sockets.add_connection(grass, vec![fence]);
+    /// ```
+    pub fn merge(&mut self, other: SocketCollection) -> SocketsRemapping {
+        let remapping = SocketsRemapping {
+            offset: self.incremental_socket_index,
+            count: other.incremental_socket_index,
+        };
+        self.incremental_socket_index += other.incremental_socket_index;
+        for (socket_index, flow) in other.flows.iter() {
+            self.flows.insert(socket_index + remapping.offset, *flow);
+        }
+        for (socket_index, name) in other.names.iter() {
+            self.names
+                .insert(socket_index + remapping.offset, name.clone());
+        }
+        for (from, to_sockets) in other.compatibles.iter() {
+            for to in to_sockets.iter() {
+                self.register_connection_half_id(
+                    remapping.socket_id(*from),
+                    remapping.socket_id(*to),
+                );
+            }
+        }
+        remapping
+    }
+
+    /// Returns the sockets, in index order, which are connected to some sockets but only to themselves (in any rotation)
+    pub(crate) fn self_connected_sockets(&self) -> Vec<Socket> {
+        let mut connected_to_others = BTreeMap::new();
+        for (from, to_sockets) in self.compatibles.iter() {
+            let from = Socket::from_id(*from);
+            let others = to_sockets
+                .iter()
+                .any(|to| Socket::from_id(*to).socket_index != from.socket_index);
+            *connected_to_others
+                .entry(from.socket_index)
+                .or_insert(false) |= others;
+        }
+        connected_to_others
+            .into_iter()
+            .filter(|(_, others)| !others)
+            .map(|(socket_index, _)| Socket::new(socket_index))
+            .collect()
+    }
+
     fn register_connection_half(&mut self, from: &Socket, to: &Socket) {
+        self.register_connection_half_id(from.id(), to.id());
+    }
+
+    fn register_connection_half_id(&mut self, from: SocketId, to: SocketId) {
         // TODO Decide if we check for existence
-        let connectable_sockets = self.uniques.entry(from.id()).or_insert(HashSet::new());
+        let connectable_sockets = self.uniques.entry(from).or_insert(BTreeSet::new());
 
-        if connectable_sockets.insert(to.id()) {
-            self.compatibles
-                .entry(from.id())
-                .or_insert(Vec::new())
-                .push(to.id());
+        if connectable_sockets.insert(to) {
+            self.compatibles.entry(from).or_insert(Vec::new()).push(to);
         }
     }
 
@@ -200,6 +333,38 @@ impl SocketCollection {
         self.register_connection_half(to, from);
     }
 
+    /// Returns all the sockets that can be connected to `socket`, in the order their connections were added. Rotated sockets (on the rotation axis of the rules) are only compatible with the rotations they were connected to.
+    ///
+    /// ### Example
+    /// ```
+    /// use ghx_proc_gen::generator::socket::SocketCollection;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let (a, b, c) = (sockets.create(), sockets.create(), sockets.create());
+    /// sockets.add_connection(a, vec![a, b]);
+    /// assert_eq!(sockets.compatible_sockets(a), vec![a, b]);
+    /// assert_eq!(sockets.compatible_sockets(b), vec![a]);
+    /// assert!(sockets.compatible_sockets(c).is_empty());
+    /// ```
+    pub fn compatible_sockets(&self, socket: Socket) -> Vec<Socket> {
+        match self.get_compatibles(socket.id()) {
+            Some(compatibles) => compatibles.iter().map(|&id| Socket::from_id(id)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns `true` if a connection was added between `from` and `to`
+    pub fn are_compatible(&self, from: Socket, to: Socket) -> bool {
+        self.uniques
+            .get(&from.id())
+            .is_some_and(|compatibles| compatibles.contains(&to.id()))
+    }
+
+    /// Returns `true` if the socket `socket` (in any rotation) was created by this collection
+    pub(crate) fn contains(&self, socket: SocketId) -> bool {
+        Socket::from_id(socket).socket_index < self.incremental_socket_index
+    }
+
     pub(crate) fn get_compatibles(&self, socket: SocketId) -> Option<&Vec<SocketId>> {
         self.compatibles.get(&socket)
     }
@@ -207,6 +372,74 @@ impl SocketCollection {
     pub(crate) fn is_empty(&self) -> bool {
         self.incremental_socket_index == 0
     }
+
+    /// Returns the first connection involving a directed socket which does not go from an outflow to an inflow, if any. All the rotations of the sockets are checked.
+    pub(crate) fn find_invalid_flow_connection(&self) -> Option<(Socket, Socket)> {
+        self.compatibles.iter().find_map(|(&from, to_sockets)| {
+            let from = Socket::from_id(from);
+            to_sockets.iter().find_map(|&to| {
+                let to = Socket::from_id(to);
+                match (self.flow(from), self.flow(to)) {
+                    (None, None)
+                    | (Some(Flow::Outflow), Some(Flow::Inflow))
+                    | (Some(Flow::Inflow), Some(Flow::Outflow)) => None,
+                    _ => Some((from, to)),
+                }
+            })
+        })
+    }
+}
+
+/// Role of a directed socket in a flow, see [`SocketCollection::create_directed`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    /// The flow leaves the model through this socket
+    Outflow,
+    /// The flow enters the model through this socket
+    Inflow,
+}
+
+/// A pair of directed sockets, created by [`SocketCollection::create_directed`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DirectedSocket {
+    /// Socket through which the flow leaves a model
+    pub outflow: Socket,
+    /// Socket through which the flow enters a model
+    pub inflow: Socket,
+}
+
+/// New indexes of the sockets of a [`SocketCollection`] merged into another one, returned by [`SocketCollection::merge`]
+///
+/// The default value does not remap anything, it can be used to merge [`super::model::ModelCollection`] already sharing the same [`SocketCollection`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SocketsRemapping {
+    offset: u32,
+    count: u32,
+}
+
+impl SocketsRemapping {
+    /// Returns the offset added to the indexes of the merged sockets
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns how many sockets were merged
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Converts a socket of the merged collection to its socket in the resulting collection
+    pub fn socket(&self, socket: Socket) -> Socket {
+        Socket {
+            socket_index: socket.socket_index + self.offset,
+            rot: socket.rot,
+        }
+    }
+
+    /// The socket index is stored in the low 32 bits of a [`SocketId`]
+    fn socket_id(&self, socket_id: SocketId) -> SocketId {
+        socket_id + self.offset as u64
+    }
 }
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
@@ -223,7 +456,26 @@ pub struct Socket {
     rot: ModelRotation,
 }
 
+impl fmt::Display for Socket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.rot {
+            ModelRotation::Rot0 => write!(f, "socket {}", self.socket_index),
+            rot => write!(f, "socket {} (rot: {})", self.socket_index, rot),
+        }
+    }
+}
+
 impl Socket {
+    /// Returns the index of the socket in its [`SocketCollection`]
+    pub fn index(&self) -> u32 {
+        self.socket_index
+    }
+
+    /// Returns the rotation of the socket. Always [`ModelRotation::Rot0`] for sockets that are not on the rotation axis of the rules.
+    pub fn rotation(&self) -> ModelRotation {
+        self.rot
+    }
+
     pub(crate) fn new(socket_index: u32) -> Self {
         Self {
             socket_index,
@@ -235,6 +487,14 @@ impl Socket {
         self.socket_index as u64 + ((self.rot.index() as u64) << 32)
     }
 
+    /// Inverse of [`Socket::id`]
+    pub(crate) fn from_id(id: SocketId) -> Self {
+        Self {
+            socket_index: id as u32,
+            rot: ALL_MODEL_ROTATIONS[(id >> 32) as usize % ALL_MODEL_ROTATIONS.len()],
+        }
+    }
+
     pub(crate) fn rotated(&self, rotation: ModelRotation) -> Socket {
         let mut rotated_socket = self.clone();
         rotated_socket.rot = rotated_socket.rot.rotated(rotation);
@@ -393,3 +653,51 @@ impl SocketsCartesian3D {
         ModelTemplate::<Cartesian3D>::new(self)
     }
 }
+
+/// Declares sockets and their connections in a [`SocketCollection`], one rule per line.
+///
+/// The first argument is the [`SocketCollection`] variable, followed by rules terminated by `;`:
+/// - `let a, b, c;` creates the sockets `a`, `b` and `c` in the collection and binds them to local variables
+/// - `a <-> b, c;` connects `a` to `b` and `c`, see [`SocketCollection::add_connection`]
+/// - `a ^ b, c;` connects all the rotations of `a` to all the rotations of `b` and `c`, see [`SocketCollection::add_rotated_connection`]
+///
+/// Sockets are referred to by their variable names.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::{sockets, generator::socket::SocketCollection};
+///
+/// let mut sockets = SocketCollection::new();
+/// let void = sockets.create();
+/// sockets! { sockets;
+///     let grass, void_and_grass, grass_and_void, layer_0_up, layer_1_down;
+///     void <-> void;
+///     grass <-> grass;
+///     void_and_grass <-> grass_and_void;
+///     layer_0_up ^ layer_1_down;
+/// }
+/// // Same as:
+/// // let (grass, void_and_grass, ...) = (sockets.create(), sockets.create(), ...);
+/// // sockets
+/// //     .add_connection(void, vec![void])
+/// //     .add_connection(grass, vec![grass])
+/// //     .add_connection(void_and_grass, vec![grass_and_void])
+/// //     .add_rotated_connection(layer_0_up, vec![layer_1_down]);
+/// ```
+#[macro_export]
+macro_rules! sockets {
+    ($collection:ident; let $($socket:ident),+ ; $($rest:tt)*) => {
+        $(let $socket = $collection.create();)+
+        $crate::sockets!($collection; $($rest)*);
+    };
+    ($collection:ident; $from:ident <-> $($to:ident),+ ; $($rest:tt)*) => {
+        $collection.add_connection($from, vec![$($to),+]);
+        $crate::sockets!($collection; $($rest)*);
+    };
+    ($collection:ident; $from:ident ^ $($to:ident),+ ; $($rest:tt)*) => {
+        $collection.add_rotated_connection($from, vec![$($to),+]);
+        $crate::sockets!($collection; $($rest)*);
+    };
+    ($collection:ident;) => {};
+}
@@ -0,0 +1,147 @@
+use std::{env, fmt::Write, fs, path::Path};
+
+use ghx_grid::{
+    coordinate_system::CoordinateSystem,
+    grid::{GridData, GridDefinition},
+};
+
+use crate::{
+    generator::model::{ModelInstance, ALL_MODEL_ROTATIONS},
+    grid_data::grid_diff_report,
+    GoldenError,
+};
+
+/// When this environment variable is set (to any value), [`check_golden`] (re)writes the golden files instead of comparing against them.
+pub const UPDATE_GOLDENS_ENV_VAR: &str = "GHX_PROC_GEN_UPDATE_GOLDENS";
+
+/// Header of the golden format, bumped if the format changes.
+const GOLDEN_HEADER: &str = "# ghx_proc_gen golden v1";
+
+/// Serializes a generation output to a stable text format, suitable to be stored as a golden file.
+///
+/// The format is: a header line, a line with the grid size, then one line per node (in node index order) with the model index and the model rotation in degrees.
+pub fn to_golden_string<C: CoordinateSystem>(grid_data: &GridData<C, ModelInstance>) -> String {
+    let (size_x, size_y, size_z) = grid_data.grid().size();
+    let mut golden = format!("{}\nsize {} {} {}\n", GOLDEN_HEADER, size_x, size_y, size_z);
+    for model_instance in grid_data.nodes() {
+        let _ = writeln!(
+            golden,
+            "{} {}",
+            model_instance.model_index,
+            model_instance.rotation.value()
+        );
+    }
+    golden
+}
+
+/// Parses a golden string (as written by [`to_golden_string`]) into a [`GridData`] using the given `grid`.
+///
+/// Returns a [`GoldenError::InvalidFormat`] if the string is malformed or if its size does not match the size of `grid`.
+pub fn from_golden_string<C: CoordinateSystem>(
+    golden: &str,
+    grid: &GridDefinition<C>,
+) -> Result<GridData<C, ModelInstance>, GoldenError> {
+    let mut lines = golden.lines().enumerate();
+    match lines.next() {
+        Some((_, GOLDEN_HEADER)) => (),
+        _ => return Err(GoldenError::InvalidFormat(1, "missing header".to_string())),
+    }
+    let expected_size = grid.size();
+    let size_line = format!(
+        "size {} {} {}",
+        expected_size.0, expected_size.1, expected_size.2
+    );
+    match lines.next() {
+        Some((_, line)) if line == size_line => (),
+        Some((_, line)) => {
+            return Err(GoldenError::InvalidFormat(
+                2,
+                format!("expected `{}`, found `{}`", size_line, line),
+            ))
+        }
+        None => return Err(GoldenError::InvalidFormat(2, "missing size".to_string())),
+    }
+
+    let mut nodes = Vec::with_capacity(grid.total_size());
+    for (line_index, line) in lines {
+        let invalid_line = || GoldenError::InvalidFormat(line_index + 1, line.to_string());
+        let mut values = line.split_whitespace();
+        let (Some(model_index), Some(rotation), None) =
+            (values.next(), values.next(), values.next())
+        else {
+            return Err(invalid_line());
+        };
+        let model_index = model_index.parse::<usize>().map_err(|_| invalid_line())?;
+        let rotation = rotation.parse::<u32>().map_err(|_| invalid_line())?;
+        let rotation = ALL_MODEL_ROTATIONS
+            .iter()
+            .find(|rot| rot.value() == rotation)
+            .ok_or_else(invalid_line)?;
+        nodes.push(ModelInstance {
+            model_index,
+            rotation: *rotation,
+        });
+    }
+    if nodes.len() != grid.total_size() {
+        return Err(GoldenError::InvalidFormat(
+            nodes.len() + 2,
+            format!(
+                "expected {} nodes, found {}",
+                grid.total_size(),
+                nodes.len()
+            ),
+        ));
+    }
+    Ok(GridData::new(grid.clone(), nodes))
+}
+
+/// Compares a generation output to the golden file at `path`.
+///
+/// - Returns `Ok` if they match.
+/// - Returns a [`GoldenError::Mismatch`] with a report of the differing nodes if they don't.
+/// - If the [`UPDATE_GOLDENS_ENV_VAR`] environment variable is set, writes `grid_data` to `path` instead (creating the parent directories if needed).
+///
+/// ### Example
+///
+/// A regression test for some rules, checking that a given seed always gives the same output.
+/// ```no_run
+/// use ghx_proc_gen::{golden::check_golden, generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection, RngMode}};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (white, black) = (sockets.create(), sockets.create());
+/// sockets.add_connection(white, vec![black]);
+///
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(white));
+/// models.create(SocketsCartesian2D::Mono(black));
+///
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+/// let mut generator = GeneratorBuilder::new()
+///    .with_rules(rules)
+///    .with_grid(GridDefinition::new_cartesian_2d(8, 8, false, false))
+///    .with_rng(RngMode::Seeded(42))
+///    .build()
+///    .unwrap();
+/// let (_gen_info, grid_data) = generator.generate_grid().unwrap();
+/// check_golden(&grid_data, "tests/goldens/chessboard_42.txt").unwrap();
+/// ```
+pub fn check_golden<C: CoordinateSystem, P: AsRef<Path>>(
+    grid_data: &GridData<C, ModelInstance>,
+    path: P,
+) -> Result<(), GoldenError> {
+    let path = path.as_ref();
+    if env::var_os(UPDATE_GOLDENS_ENV_VAR).is_some() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, to_golden_string(grid_data))?;
+        return Ok(());
+    }
+
+    let golden = from_golden_string(&fs::read_to_string(path)?, grid_data.grid())?;
+    match grid_diff_report(&golden, grid_data) {
+        None => Ok(()),
+        Some(report) => Err(GoldenError::Mismatch(report)),
+    }
+}
@@ -0,0 +1,721 @@
+use std::{
+    fmt::{Debug, Write},
+    mem,
+};
+
+use ghx_grid::{
+    coordinate_system::{Cartesian2D, Cartesian3D, CoordinateSystem},
+    direction::Direction,
+    grid::{GridData, GridDefinition, GridPosition},
+};
+
+use crate::{
+    generator::{
+        model::{ModelIndex, ModelInstance, ModelRotation, ALL_MODEL_ROTATIONS},
+        observer::NodeRegion,
+        rules::Rules,
+    },
+    NodeIndex,
+};
+
+/// Condition on the model generated on a neighbour of a node, such as the ones of a [`crate::decoration::DecorationRule`] (or of the model assets of `bevy_ghx_proc_gen`).
+///
+/// Directions are grid directions: they are not rotated with the model of the node.
+#[derive(Clone, Debug)]
+pub enum NeighbourCondition {
+    /// Met if the neighbour in this direction is generated and its model is one of the given models
+    Is(Direction, Vec<ModelIndex>),
+    /// Met if the neighbour in this direction does not exist, is not generated yet, or if its model is not one of the given models
+    IsNot(Direction, Vec<ModelIndex>),
+}
+
+impl NeighbourCondition {
+    /// Returns `true` if the condition is met. `neighbour` returns the model generated on the neighbour of the node in a direction, if any.
+    pub fn is_met<F: Fn(Direction) -> Option<ModelInstance>>(&self, neighbour: &F) -> bool {
+        match self {
+            NeighbourCondition::Is(direction, models) => {
+                neighbour(*direction).is_some_and(|instance| models.contains(&instance.model_index))
+            }
+            NeighbourCondition::IsNot(direction, models) => !neighbour(*direction)
+                .is_some_and(|instance| models.contains(&instance.model_index)),
+        }
+    }
+
+    /// Returns `true` if the condition is met by the node `node_index` of a generation output
+    pub fn is_met_in<C: CoordinateSystem>(
+        &self,
+        grid_data: &GridData<C, ModelInstance>,
+        node_index: NodeIndex,
+    ) -> bool {
+        let grid = grid_data.grid();
+        let position = grid.pos_from_index(node_index);
+        self.is_met(&|direction| {
+            grid.get_next_index_in_direction(&position, direction)
+                .map(|neighbour| *grid_data.get(neighbour))
+        })
+    }
+}
+
+/// Maximum number of differing nodes listed by [`grid_diff_report`]
+const MAX_REPORTED_DIFFS: usize = 10;
+
+/// Structural comparison of two [`GridData`], such as the outputs of two generations.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::{ghx_grid::grid::{GridData, GridDefinition}, grid_data::GridDataComparison};
+///
+/// let grid = GridDefinition::new_cartesian_2d(2, 2, false, false);
+/// let a = GridData::new(grid.clone(), vec![0, 1, 2, 3]);
+/// let b = GridData::new(grid, vec![0, 1, 5, 3]);
+///
+/// assert_eq!(a.diff(&b), vec![(2, &2, &5)]);
+/// assert_eq!(a.similarity(&b), 0.75);
+/// ```
+pub trait GridDataComparison<C: CoordinateSystem, D: PartialEq> {
+    /// Returns all the nodes whose data differ between `self` and `other`, as `(node_index, self_data, other_data)` tuples ordered by node index.
+    ///
+    /// Only the nodes indexes existing in both grids are compared.
+    fn diff<'a>(&'a self, other: &'a GridData<C, D>) -> Vec<(NodeIndex, &'a D, &'a D)>;
+
+    /// Returns a similarity score between `0.` (all nodes differ) and `1.` (identical grids): the ratio of equal nodes over the node count of the biggest grid.
+    fn similarity(&self, other: &GridData<C, D>) -> f32;
+}
+
+impl<C: CoordinateSystem, D: PartialEq> GridDataComparison<C, D> for GridData<C, D> {
+    fn diff<'a>(&'a self, other: &'a GridData<C, D>) -> Vec<(NodeIndex, &'a D, &'a D)> {
+        self.nodes()
+            .iter()
+            .zip(other.nodes().iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(node_index, (a, b))| (node_index, a, b))
+            .collect()
+    }
+
+    fn similarity(&self, other: &GridData<C, D>) -> f32 {
+        let nodes_count = self.nodes().len().max(other.nodes().len());
+        if nodes_count == 0 {
+            return 1.;
+        }
+        let equal_nodes_count = self
+            .nodes()
+            .iter()
+            .zip(other.nodes().iter())
+            .filter(|(a, b)| a == b)
+            .count();
+        equal_nodes_count as f32 / nodes_count as f32
+    }
+}
+
+/// Conversion of a partially known [`GridData`], such as the grid of a [`crate::generator::observer::QueuedStatefulObserver`], into a complete output.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::{ghx_grid::grid::{GridData, GridDefinition}, grid_data::IntoComplete};
+///
+/// let grid = GridDefinition::new_cartesian_2d(2, 2, false, false);
+/// let partial = GridData::new(grid.clone(), vec![Some(0), None, Some(2), None]);
+/// assert_eq!(partial.into_complete().unwrap_err(), vec![1, 3]);
+///
+/// let full = GridData::new(grid, vec![Some(0), Some(1), Some(2), Some(3)]);
+/// assert_eq!(full.into_complete().unwrap().nodes(), &vec![0, 1, 2, 3]);
+/// ```
+pub trait IntoComplete<C: CoordinateSystem, D> {
+    /// Returns a [`GridData`] with the value of every node, or the indexes of all the nodes without a value, in nodes order.
+    fn into_complete(self) -> Result<GridData<C, D>, Vec<NodeIndex>>;
+}
+
+impl<C: CoordinateSystem, D> IntoComplete<C, D> for GridData<C, Option<D>> {
+    fn into_complete(mut self) -> Result<GridData<C, D>, Vec<NodeIndex>> {
+        let missing_nodes: Vec<NodeIndex> = self
+            .nodes()
+            .iter()
+            .enumerate()
+            .filter_map(|(node_index, value)| value.is_none().then_some(node_index))
+            .collect();
+        if !missing_nodes.is_empty() {
+            return Err(missing_nodes);
+        }
+        let values = mem::take(self.nodes_mut()).into_iter().flatten().collect();
+        Ok(GridData::new(self.grid().clone(), values))
+    }
+}
+
+/// Queries over a generated output, based on the tags of the models (see [`crate::generator::model::Model::with_tags`]) stored in the [`Rules`].
+pub trait ModelTagQueries<C: CoordinateSystem> {
+    /// Returns the indexes of all the nodes whose model has the tag `tag`, ordered by node index.
+    fn nodes_with_tag(&self, rules: &Rules<C>, tag: &str) -> Vec<NodeIndex>;
+
+    /// Returns the positions of all the nodes whose model has the tag `tag`, ordered by node index.
+    fn positions_with_tag(&self, rules: &Rules<C>, tag: &str) -> Vec<GridPosition>;
+}
+
+impl<C: CoordinateSystem> ModelTagQueries<C> for GridData<C, ModelInstance> {
+    fn nodes_with_tag(&self, rules: &Rules<C>, tag: &str) -> Vec<NodeIndex> {
+        self.nodes()
+            .iter()
+            .enumerate()
+            .filter(|(_, model_instance)| rules.has_tag(model_instance.model_index, tag))
+            .map(|(node_index, _)| node_index)
+            .collect()
+    }
+
+    fn positions_with_tag(&self, rules: &Rules<C>, tag: &str) -> Vec<GridPosition> {
+        self.nodes_with_tag(rules, tag)
+            .into_iter()
+            .map(|node_index| self.grid().pos_from_index(node_index))
+            .collect()
+    }
+}
+
+/// Counts of the models of a generated output, per original model and per rotation, see [`ModelHistogramQuery::model_histogram`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelHistogram {
+    /// `counts[model_index][rotation_index]` is the number of nodes with this original model and rotation
+    counts: Vec<[usize; 4]>,
+    /// Share of the nodes expected for each original model from the weights of its variations in the rules
+    expected_frequencies: Vec<f32>,
+    total: usize,
+}
+
+impl ModelHistogram {
+    /// Returns the number of nodes of the output
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the number of nodes with the original model `model_index`, all rotations included. Returns 0 for an unknown model.
+    pub fn count(&self, model_index: ModelIndex) -> usize {
+        self.counts
+            .get(model_index)
+            .map_or(0, |counts| counts.iter().sum())
+    }
+
+    /// Returns the number of nodes with the original model `model_index` rotated by `rotation`. Returns 0 for an unknown model.
+    pub fn count_rotated(&self, model_index: ModelIndex, rotation: ModelRotation) -> usize {
+        self.counts
+            .get(model_index)
+            .map_or(0, |counts| counts[rotation.index() as usize])
+    }
+
+    /// Returns the share (between `0.` and `1.`) of the nodes with the original model `model_index`
+    pub fn frequency(&self, model_index: ModelIndex) -> f32 {
+        match self.total {
+            0 => 0.,
+            total => self.count(model_index) as f32 / total as f32,
+        }
+    }
+
+    /// Returns the share (between `0.` and `1.`) of the nodes that the original model `model_index` would have if the models were only distributed by their weights: the sum of the weights of its variations over the sum of all the weights.
+    ///
+    /// The constraints of the rules usually make the real frequencies deviate from those, see [`ModelHistogram::chi_square`].
+    pub fn expected_frequency(&self, model_index: ModelIndex) -> f32 {
+        self.expected_frequencies
+            .get(model_index)
+            .copied()
+            .unwrap_or(0.)
+    }
+
+    /// Returns, for each original model, the ratio of its frequency over its expected frequency: above `1.` the model appears more than its weight suggests, below `1.` less.
+    pub fn weight_ratios(&self) -> Vec<f32> {
+        (0..self.counts.len())
+            .map(|model_index| match self.expected_frequency(model_index) {
+                expected if expected > 0. => self.frequency(model_index) / expected,
+                _ => 0.,
+            })
+            .collect()
+    }
+
+    /// Returns the chi-square statistic of the counts against the counts expected from the weights (see [`ModelHistogram::expected_frequency`]): `sum((observed - expected)² / expected)` over the original models.
+    ///
+    /// `0.` means that the output follows the weights exactly. Useful to compare the effect of weights changes on a same rule set and grid, over a few seeds.
+    pub fn chi_square(&self) -> f32 {
+        (0..self.counts.len())
+            .filter_map(|model_index| {
+                let expected = self.expected_frequency(model_index) * self.total as f32;
+                match expected > 0. {
+                    true => {
+                        let deviation = self.count(model_index) as f32 - expected;
+                        Some(deviation * deviation / expected)
+                    }
+                    false => None,
+                }
+            })
+            .sum()
+    }
+}
+
+/// Statistics over the models of a generated output, to tune the weights of the [`Rules`].
+pub trait ModelHistogramQuery<C: CoordinateSystem> {
+    /// Returns the [`ModelHistogram`] of the output: counts per original model and per rotation, and their deviation from the weights of the `rules`.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use ghx_proc_gen::{grid_data::ModelHistogramQuery, generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection, RngMode}};
+    /// use ghx_grid::grid::GridDefinition;
+    ///
+    /// let mut sockets = SocketCollection::new();
+    /// let ground = sockets.create();
+    /// sockets.add_connection(ground, vec![ground]);
+    ///
+    /// let mut models = ModelCollection::new();
+    /// models.create(SocketsCartesian2D::Mono(ground)).with_weight(3.);
+    /// models.create(SocketsCartesian2D::Mono(ground));
+    ///
+    /// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+    /// let mut generator = GeneratorBuilder::new()
+    ///    .with_rules(rules)
+    ///    .with_grid(GridDefinition::new_cartesian_2d(16, 16, false, false))
+    ///    .with_rng(RngMode::Seeded(42))
+    ///    .build()
+    ///    .unwrap();
+    /// let (_gen_info, output) = generator.generate_grid().unwrap();
+    ///
+    /// let histogram = output.model_histogram(generator.rules());
+    /// assert_eq!(histogram.count(0) + histogram.count(1), 256);
+    /// assert_eq!(histogram.expected_frequency(0), 0.75);
+    /// ```
+    fn model_histogram(&self, rules: &Rules<C>) -> ModelHistogram;
+}
+
+impl<C: CoordinateSystem> ModelHistogramQuery<C> for GridData<C, ModelInstance> {
+    fn model_histogram(&self, rules: &Rules<C>) -> ModelHistogram {
+        let models_count = rules.original_models_count();
+        let mut counts = vec![[0; 4]; models_count];
+        for model_instance in self.nodes() {
+            if let Some(counts) = counts.get_mut(model_instance.model_index) {
+                counts[model_instance.rotation.index() as usize] += 1;
+            }
+        }
+
+        let models_weights: Vec<f32> = (0..models_count)
+            .map(|model_index| {
+                ALL_MODEL_ROTATIONS
+                    .iter()
+                    .filter_map(|&rotation| rules.variant_index(model_index, rotation))
+                    .filter_map(|variant| rules.weight(variant))
+                    .sum()
+            })
+            .collect();
+        let weights_sum: f32 = models_weights.iter().sum();
+        let expected_frequencies = models_weights
+            .iter()
+            .map(|weight| match weights_sum > 0. {
+                true => weight / weights_sum,
+                false => 0.,
+            })
+            .collect();
+
+        ModelHistogram {
+            counts,
+            expected_frequencies,
+            total: self.nodes().len(),
+        }
+    }
+}
+
+/// Iterator over the nodes of a box-shaped region of a grid, see [`GridSlices`].
+///
+/// Yields `(node_index, position)` pairs, x varying first, then y, then z.
+pub struct RegionNodes<'a, C: CoordinateSystem> {
+    grid: &'a GridDefinition<C>,
+    min: GridPosition,
+    max: GridPosition,
+    /// Next position to yield, `None` once the iteration is done
+    next: Option<GridPosition>,
+}
+
+impl<'a, C: CoordinateSystem> RegionNodes<'a, C> {
+    /// Creates an iterator over the nodes of `region` clamped to the bounds of `grid`
+    fn new(grid: &'a GridDefinition<C>, region: &NodeRegion) -> Self {
+        let max = GridPosition::new(
+            region.max.x.min(grid.size_x().saturating_sub(1)),
+            region.max.y.min(grid.size_y().saturating_sub(1)),
+            region.max.z.min(grid.size_z().saturating_sub(1)),
+        );
+        let min = region.min.clone();
+        let empty = grid.total_size() == 0 || min.x > max.x || min.y > max.y || min.z > max.z;
+        Self {
+            grid,
+            next: (!empty).then(|| min.clone()),
+            min,
+            max,
+        }
+    }
+}
+
+impl<'a, C: CoordinateSystem> Iterator for RegionNodes<'a, C> {
+    type Item = (NodeIndex, GridPosition);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.next.take()?;
+        let (x, y, z) = (position.x, position.y, position.z);
+        self.next = if x < self.max.x {
+            Some(GridPosition::new(x + 1, y, z))
+        } else if y < self.max.y {
+            Some(GridPosition::new(self.min.x, y + 1, z))
+        } else if z < self.max.z {
+            Some(GridPosition::new(self.min.x, self.min.y, z + 1))
+        } else {
+            None
+        };
+        Some((self.grid.index_from_coords(x, y, z), position))
+    }
+}
+
+/// Structured iteration over the columns, layers and box-shaped regions of a grid, implemented for [`GridDefinition`] and [`GridData`].
+///
+/// Coordinates outside of the grid are clamped, an iteration fully outside of the grid yields nothing.
+///
+/// ### Example
+///
+/// Find the surface height of each column of a generated output:
+/// ```
+/// use ghx_proc_gen::{grid_data::GridSlices, ghx_grid::grid::{GridData, GridDefinition}};
+///
+/// let grid = GridDefinition::new_cartesian_3d(2, 3, 1, false, false, false);
+/// // `true` for the solid nodes
+/// let output = GridData::new(grid, vec![true, true, true, false, false, false]);
+///
+/// let surface_height = |x, z| {
+///     output
+///         .iter_column(x, z)
+///         .filter(|(node_index, _)| *output.get(*node_index))
+///         .map(|(_, position)| position.y)
+///         .max()
+/// };
+/// assert_eq!(surface_height(0, 0), Some(1));
+/// assert_eq!(surface_height(1, 0), Some(0));
+/// assert_eq!(output.iter_layer(2).count(), 2);
+/// ```
+pub trait GridSlices<C: CoordinateSystem> {
+    /// Returns the grid definition the iterations are done on
+    fn grid_definition(&self) -> &GridDefinition<C>;
+
+    /// Iterates over the nodes of the column at (`x`, `z`), from the lowest y to the highest
+    fn iter_column(&self, x: u32, z: u32) -> RegionNodes<'_, C> {
+        let grid = self.grid_definition();
+        RegionNodes::new(
+            grid,
+            &NodeRegion::new(
+                GridPosition::new(x, 0, z),
+                GridPosition::new(x, grid.size_y(), z),
+            ),
+        )
+    }
+
+    /// Iterates over the nodes of the horizontal layer at height `y`
+    fn iter_layer(&self, y: u32) -> RegionNodes<'_, C> {
+        let grid = self.grid_definition();
+        RegionNodes::new(
+            grid,
+            &NodeRegion::new(
+                GridPosition::new(0, y, 0),
+                GridPosition::new(grid.size_x(), y, grid.size_z()),
+            ),
+        )
+    }
+
+    /// Iterates over the nodes of a box-shaped `region`
+    fn iter_region(&self, region: &NodeRegion) -> RegionNodes<'_, C> {
+        RegionNodes::new(self.grid_definition(), region)
+    }
+}
+
+impl<C: CoordinateSystem> GridSlices<C> for GridDefinition<C> {
+    fn grid_definition(&self) -> &GridDefinition<C> {
+        self
+    }
+}
+
+impl<C: CoordinateSystem, D> GridSlices<C> for GridData<C, D> {
+    fn grid_definition(&self) -> &GridDefinition<C> {
+        self.grid()
+    }
+}
+
+/// Surface extraction over a 3d output, with Y as the vertical axis
+pub trait SurfaceMap<T> {
+    /// Returns a 2d [`GridData`] where the node `(x, y)` holds the surface height of the `(x, z = y)` column of this 3d grid: the Y of its topmost node which is not void (according to `is_void`) plus one, or `0` if the whole column is void.
+    ///
+    /// The result can be given to [`crate::generator::heightmap::Heightmap::from_grid_data`]. Useful to place characters, navigation seeds or decals on a generated terrain.
+    ///
+    /// ### Example
+    /// ```
+    /// use ghx_proc_gen::{grid_data::SurfaceMap, ghx_grid::grid::{GridData, GridDefinition}};
+    ///
+    /// let grid = GridDefinition::new_cartesian_3d(2, 3, 1, false, false, false);
+    /// // `0` for the void nodes
+    /// let output = GridData::new(grid, vec![1, 0, 2, 0, 0, 0]);
+    ///
+    /// let surface = output.surface_map(|&model| model == 0);
+    /// assert_eq!(surface.nodes(), &vec![2, 0]);
+    /// ```
+    fn surface_map<F: Fn(&T) -> bool>(&self, is_void: F) -> GridData<Cartesian2D, u32>;
+}
+
+impl<T> SurfaceMap<T> for GridData<Cartesian3D, T> {
+    fn surface_map<F: Fn(&T) -> bool>(&self, is_void: F) -> GridData<Cartesian2D, u32> {
+        let (size_x, _, size_z) = self.grid().size();
+        let mut heights = Vec::with_capacity((size_x * size_z) as usize);
+        for z in 0..size_z {
+            for x in 0..size_x {
+                heights.push(
+                    self.iter_column(x, z)
+                        .filter(|(node_index, _)| !is_void(self.get(*node_index)))
+                        .last()
+                        .map_or(0, |(_, position)| position.y + 1),
+                );
+            }
+        }
+        GridData::new(
+            GridDefinition::new_cartesian_2d(size_x, size_z, false, false),
+            heights,
+        )
+    }
+}
+
+/// Boolean operations and shape constructors for masks of nodes, to compose the active regions given to [`crate::generator::builder::GeneratorBuilder::with_active_nodes`] for non-rectangular generations.
+///
+/// The boolean operations return a mask on the grid of `self`: nodes indexes missing in `other` are considered `false`.
+///
+/// ### Example
+///
+/// An island with a lagoon, crossed by a channel:
+/// ```
+/// use ghx_proc_gen::{grid_data::GridMask, ghx_grid::grid::{GridData, GridDefinition, GridPosition}};
+///
+/// let grid = GridDefinition::new_cartesian_2d(21, 21, false, false);
+/// let center = GridPosition::new_xy(10, 10);
+/// let island = GridData::disc(&grid, center, 10.)
+///     .difference(&GridData::disc(&grid, center, 4.))
+///     .difference(&GridData::corridor(&grid, GridPosition::new_xy(0, 10), center, 1.));
+/// assert!(*island.get_2d(10, 18));
+/// assert!(!*island.get_2d(10, 10));
+/// assert!(!*island.get_2d(2, 10));
+/// ```
+pub trait GridMask<C: CoordinateSystem>: Sized {
+    /// Returns a mask of `grid` with all its nodes set to `value`
+    fn filled(grid: &GridDefinition<C>, value: bool) -> Self;
+
+    /// Returns a mask of `grid` with the nodes at a distance of at most `radius` from `center` set to `true`: a disc on a 2d grid (or on a single layer), a ball on a 3d grid.
+    fn disc(grid: &GridDefinition<C>, center: GridPosition, radius: f32) -> Self;
+
+    /// Returns a mask of `grid` with the nodes of the border of `region` set to `true`: the nodes of `region` which are at less than `thickness` nodes from one of its sides. Only the axes on which `region` spans more than one node have sides: on a 2d grid (or on a single layer), this is a rectangle ring.
+    fn rectangle_ring(grid: &GridDefinition<C>, region: &NodeRegion, thickness: u32) -> Self;
+
+    /// Returns a mask of `grid` with the nodes at a distance of at most `radius` from the segment between `from` and `to` set to `true`, to carve corridors, roads or rivers.
+    fn corridor(
+        grid: &GridDefinition<C>,
+        from: GridPosition,
+        to: GridPosition,
+        radius: f32,
+    ) -> Self;
+
+    /// Returns a mask with the nodes set to `true` in `self` or in `other`
+    fn union(&self, other: &Self) -> Self;
+
+    /// Returns a mask with the nodes set to `true` in both `self` and `other`
+    fn intersection(&self, other: &Self) -> Self;
+
+    /// Returns a mask with the nodes set to `true` in `self` but not in `other`
+    fn difference(&self, other: &Self) -> Self;
+
+    /// Returns a mask with all the nodes of `self` inverted
+    fn complement(&self) -> Self;
+}
+
+impl<C: CoordinateSystem> GridMask<C> for GridData<C, bool> {
+    fn filled(grid: &GridDefinition<C>, value: bool) -> Self {
+        GridData::new(grid.clone(), vec![value; grid.total_size()])
+    }
+
+    fn disc(grid: &GridDefinition<C>, center: GridPosition, radius: f32) -> Self {
+        let center = position_to_vec(&center);
+        mask_from_fn(grid, |position| {
+            squared_distance(position_to_vec(position), center) <= radius * radius
+        })
+    }
+
+    fn rectangle_ring(grid: &GridDefinition<C>, region: &NodeRegion, thickness: u32) -> Self {
+        let min = [region.min.x, region.min.y, region.min.z];
+        let max = [region.max.x, region.max.y, region.max.z];
+        mask_from_fn(grid, |position| {
+            if !region.contains(position) {
+                return false;
+            }
+            let coords = [position.x, position.y, position.z];
+            (0..3).any(|axis| {
+                max[axis] > min[axis]
+                    && (coords[axis] - min[axis] < thickness
+                        || max[axis] - coords[axis] < thickness)
+            })
+        })
+    }
+
+    fn corridor(
+        grid: &GridDefinition<C>,
+        from: GridPosition,
+        to: GridPosition,
+        radius: f32,
+    ) -> Self {
+        let (from, to) = (position_to_vec(&from), position_to_vec(&to));
+        let segment = [to[0] - from[0], to[1] - from[1], to[2] - from[2]];
+        let segment_length_squared = squared_distance(to, from);
+        mask_from_fn(grid, |position| {
+            let position = position_to_vec(position);
+            // Projection of the position on the segment, clamped to its ends
+            let t = match segment_length_squared > 0. {
+                true => ((0..3)
+                    .map(|axis| (position[axis] - from[axis]) * segment[axis])
+                    .sum::<f32>()
+                    / segment_length_squared)
+                    .clamp(0., 1.),
+                false => 0.,
+            };
+            let closest = [
+                from[0] + t * segment[0],
+                from[1] + t * segment[1],
+                from[2] + t * segment[2],
+            ];
+            squared_distance(position, closest) <= radius * radius
+        })
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        combine_masks(self, other, |a, b| a || b)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        combine_masks(self, other, |a, b| a && b)
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        combine_masks(self, other, |a, b| a && !b)
+    }
+
+    fn complement(&self) -> Self {
+        GridData::new(
+            self.grid().clone(),
+            self.nodes().iter().map(|value| !value).collect(),
+        )
+    }
+}
+
+fn mask_from_fn<C: CoordinateSystem, F: Fn(&GridPosition) -> bool>(
+    grid: &GridDefinition<C>,
+    f: F,
+) -> GridData<C, bool> {
+    GridData::new(
+        grid.clone(),
+        (0..grid.total_size())
+            .map(|node_index| f(&grid.pos_from_index(node_index)))
+            .collect(),
+    )
+}
+
+fn combine_masks<C: CoordinateSystem, F: Fn(bool, bool) -> bool>(
+    mask: &GridData<C, bool>,
+    other: &GridData<C, bool>,
+    f: F,
+) -> GridData<C, bool> {
+    GridData::new(
+        mask.grid().clone(),
+        mask.nodes()
+            .iter()
+            .enumerate()
+            .map(|(node_index, &value)| {
+                f(
+                    value,
+                    other.nodes().get(node_index).copied().unwrap_or(false),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn position_to_vec(position: &GridPosition) -> [f32; 3] {
+    [position.x as f32, position.y as f32, position.z as f32]
+}
+
+fn squared_distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|axis| (a[axis] - b[axis]).powi(2)).sum()
+}
+
+/// Returns `None` if both grids have the same size and the same data, else returns a human readable report of their differences.
+///
+/// Used by [`crate::assert_grid_eq`]
+pub fn grid_diff_report<C: CoordinateSystem, D: PartialEq + Debug>(
+    left: &GridData<C, D>,
+    right: &GridData<C, D>,
+) -> Option<String> {
+    if left.grid().size() != right.grid().size() {
+        return Some(format!(
+            "grid sizes differ: left {:?}, right {:?}",
+            left.grid().size(),
+            right.grid().size()
+        ));
+    }
+    let diffs = left.diff(right);
+    if diffs.is_empty() {
+        return None;
+    }
+
+    let mut report = format!(
+        "{} nodes differ (similarity: {})",
+        diffs.len(),
+        left.similarity(right)
+    );
+    for (node_index, a, b) in diffs.iter().take(MAX_REPORTED_DIFFS) {
+        let _ = write!(
+            report,
+            "\n  node {} at {{{}}}: left {:?}, right {:?}",
+            node_index,
+            left.grid().pos_from_index(*node_index),
+            a,
+            b
+        );
+    }
+    if diffs.len() > MAX_REPORTED_DIFFS {
+        let _ = write!(report, "\n  ...");
+    }
+    Some(report)
+}
+
+/// Asserts that two [`GridData`] are equal: same size and same data on every node.
+///
+/// On failure, the panic message lists the differing nodes (see [`crate::grid_data::grid_diff_report`]).
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::{assert_grid_eq, ghx_grid::grid::{GridData, GridDefinition}};
+///
+/// let grid = GridDefinition::new_cartesian_2d(2, 2, false, false);
+/// let a = GridData::new(grid.clone(), vec![0, 1, 2, 3]);
+/// let b = GridData::new(grid, vec![0, 1, 2, 3]);
+///
+/// assert_grid_eq!(a, b);
+/// assert_grid_eq!(a, b, "generation with seed {} changed", 42);
+/// ```
+#[macro_export]
+macro_rules! assert_grid_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        if let Some(report) = $crate::grid_data::grid_diff_report(&$left, &$right) {
+            panic!("assertion failed: grids are not equal, {}", report);
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        if let Some(report) = $crate::grid_data::grid_diff_report(&$left, &$right) {
+            panic!(
+                "assertion failed: grids are not equal, {}: {}",
+                format_args!($($arg)+),
+                report
+            );
+        }
+    };
+}
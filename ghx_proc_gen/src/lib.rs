@@ -3,13 +3,101 @@
 //! A library for 2D & 3D procedural generation with Model synthesis/Wave function Collapse.
 //! Also provide grid utilities to manipulate 2d & 3d grid data.
 
-use generator::model::{ModelIndex, ModelRotation, ModelVariantIndex};
+use generator::{
+    dungeon::DungeonStats,
+    model::{ModelIndex, ModelRotation, ModelVariantIndex},
+    rules::NamedDirection,
+    socket::Socket,
+};
 use ghx_grid::grid::GridIndex;
 
 pub use ghx_grid;
 
+/// Opens a profiling scope named `$name` until the end of the current block, when a profiling feature is enabled (`profile-puffin` and/or `profile-tracing`). Does nothing otherwise.
+macro_rules! profile_scope {
+    ($name:literal) => {
+        #[cfg(feature = "profile-puffin")]
+        puffin::profile_scope!($name);
+        #[cfg(feature = "profile-tracing")]
+        let _profile_span = tracing::trace_span!($name).entered();
+    };
+}
+
+/// Post-generation decoration pass, to spawn extra details on a generation output without involving the generator
+pub mod decoration;
+/// Records the steps of a 2d generation as image frames, saved as PNG files or as an animated GIF
+#[cfg(feature = "image")]
+pub mod frame_recorder;
 /// Model synthesis/Wave function Collapse generator
 pub mod generator;
+/// Utilities to store generation outputs as golden files and check that generations stay deterministic
+#[cfg(feature = "test-utils")]
+pub mod golden;
+/// Utilities to inspect & compare [`ghx_grid::grid::GridData`], such as the outputs of a [`generator::Generator`]
+pub mod grid_data;
+/// Export of generated 3d outputs as OBJ or glTF meshes, to inspect them in any 3d software
+#[cfg(feature = "mesh-export")]
+pub mod mesh_export;
+/// Re-exports the most used types (builder, rules, models, sockets, heuristics, observers and grid types), to be glob-imported with `use ghx_proc_gen::prelude::*;`.
+///
+/// The prelude keeps the imports of rules files and examples stable when the internal modules move.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::prelude::*;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (white, black) = (sockets.create(), sockets.create());
+/// sockets.add_connection(white, vec![black]);
+/// let mut models = ModelCollection::<Cartesian2D>::new();
+/// models.create(SocketsCartesian2D::Mono(white));
+/// models.create(SocketsCartesian2D::Mono(black));
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+///
+/// let mut generator = GeneratorBuilder::new()
+///     .with_rules(rules)
+///     .with_grid(GridDefinition::new_cartesian_2d(4, 4, false, false))
+///     .with_node_heuristic(NodeSelectionHeuristic::MinimumRemainingValue)
+///     .build()
+///     .unwrap();
+/// let (_gen_info, _output) = generator.generate_grid().unwrap();
+/// ```
+pub mod prelude;
+/// Versioned formats (serde and compact binary) to save generation outputs and reload them without re-generating, to stream the updates of a generation, and to save generation timelines
+pub mod serialization;
+/// Ready-made rule sets (pipes, platformer tiles, dungeon walls/floors/doors) to generate something meaningful before authoring custom models and sockets
+#[cfg(feature = "templates")]
+pub mod templates;
+/// Terminal viewer of 2d generations, with keyboard controls to pause, step or run the generation
+#[cfg(feature = "term")]
+pub mod terminal_viewer;
+/// Seeded random rules & grids, and solver invariants checks, with optional `proptest` and `quickcheck` strategies
+#[cfg(feature = "test-utils")]
+pub mod testing;
+/// Image-in/image-out texture synthesis, with overlapping WFC on the pixels patterns of an image
+#[cfg(feature = "image")]
+pub mod texture_synthesis;
+/// Interactive terminal debugger of 2d generations, built on ratatui
+#[cfg(feature = "tui")]
+pub mod tui_debugger;
+
+/// Returns a distinct default color (linear RGB, between `0.` and `1.`) for each model index, by stepping the hue with the golden ratio
+#[cfg(any(feature = "image", feature = "mesh-export"))]
+pub(crate) fn palette_color(model_index: ModelIndex) -> [f32; 3] {
+    let hue = (model_index as f32 * 0.618_034).fract() * 6.;
+    let x = 1. - (hue % 2. - 1.).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1., x, 0.),
+        1 => (x, 1., 0.),
+        2 => (0., 1., x),
+        3 => (0., x, 1.),
+        4 => (x, 0., 1.),
+        _ => (1., 0., x),
+    };
+    // Softened saturation, closer to a material color than a pure hue
+    [0.25 + 0.7 * r, 0.25 + 0.7 * g, 0.25 + 0.7 * b]
+}
 
 /// Our grid elements are called Nodes
 pub type NodeIndex = GridIndex;
@@ -25,9 +113,53 @@ pub struct GeneratorError {
 /// Error returned by a [`generator::rules::RulesBuilder`] when correct [`generator::rules::Rules`] cannot be built
 #[derive(thiserror::Error, Debug, Clone, Copy)]
 pub enum RulesBuilderError {
-    /// Rules cannot be built without models or sockets
-    #[error("Empty models or sockets collection")]
-    NoModelsOrSockets,
+    /// Rules cannot be built without models (or if no model has an allowed rotation)
+    #[error("Empty models collection, or no model has an allowed rotation")]
+    NoModels,
+    /// Rules cannot be built without sockets
+    #[error("Empty socket collection")]
+    NoSockets,
+    /// A model has no socket in a direction, it could never have a neighbour in this direction
+    #[error("Model with index `{0}` has no socket in direction {1}")]
+    EmptySocketList(ModelIndex, NamedDirection),
+    /// A model weight must be finite and positive (`0` included, see [`generator::model::ModelTemplate::with_weight`])
+    #[error("Model with index `{0}` has an invalid weight `{1}`, weights must be finite and >= 0")]
+    InvalidWeight(ModelIndex, f32),
+    /// A pair weight multiplier must be finite and strictly superior to `0`
+    #[error("Pair weight between models `{0}` and `{1}` has an invalid multiplier `{2}`, multipliers must be finite and > 0")]
+    InvalidPairWeight(ModelIndex, ModelIndex, f32),
+    /// The rotation axis is not valid for the coordinate system: in 2d, the models can only be rotated around the Z axis
+    #[error("Invalid rotation axis {0} for this coordinate system")]
+    InvalidRotationAxis(NamedDirection),
+    /// A model uses a socket which was not created by the socket collection of the rules, see [`generator::rules::Rules::add_model`]
+    #[error("Model with index `{0}` uses {1}, which was not created by the socket collection of the rules")]
+    UnknownSocket(ModelIndex, Socket),
+    /// A connection involving a directed socket does not go from an outflow to an inflow, see [`generator::socket::SocketCollection::create_directed`]
+    #[error("Connection between {0} and {1} does not go from an outflow to an inflow")]
+    InvalidFlowConnection(Socket, Socket),
+    /// An offset of the direction set moves along an axis which is not in the coordinate system: in 2d, the offsets must stay in the XY plane
+    #[error("Offset {0} of the direction set is not valid for this coordinate system")]
+    InvalidDirectionSetOffset(generator::direction_set::GridOffset),
+    /// A model rotation moves an offset of the direction set to an offset which is not in the set, the rotated sockets of the model could not be used
+    #[error("Offset {0} of the direction set is rotated out of the set by the allowed rotations of the models")]
+    DirectionSetNotRotationInvariant(generator::direction_set::GridOffset),
+}
+
+/// Error returned by [`generator::direction_set::DirectionSet::new`] when the offsets do not form a valid set
+#[derive(thiserror::Error, Debug, Clone, Copy)]
+pub enum DirectionSetError {
+    /// An offset is `(0, 0, 0)`
+    #[error("Direction set offsets cannot be zero")]
+    ZeroOffset,
+    /// An offset is the offset of a direction, which is already an adjacency of the rules
+    #[error("Offset {0} is the offset of a direction")]
+    DirectionOffset(generator::direction_set::GridOffset),
+    /// An offset is present more than once
+    #[error("Offset {0} is present more than once")]
+    DuplicateOffset(generator::direction_set::GridOffset),
+    /// An offset does not have its opposite in the set
+    #[error("Offset {0} does not have its opposite in the set")]
+    MissingOpposite(generator::direction_set::GridOffset),
 }
 
 /// Error returned by a [`generator::Generator`] when a node set operation fails
@@ -42,6 +174,9 @@ pub enum NodeSetError {
     /// An invalid node index was given
     #[error("Invalid node index `{0}`, does not exist in the grid")]
     InvalidNodeIndex(NodeIndex),
+    /// An operation requested a node masked out of the generation (to set a model on it, or as an anchor), see [`generator::builder::GeneratorBuilder::with_active_nodes`]
+    #[error("Node `{0}` is not an active node of the generation")]
+    InactiveNodeIndex(NodeIndex),
     /// An operation requested to set a model on a node that does not allow it
     #[error("Model variant `{0}` not allowed by the Rules on node {1}")]
     IllegalModel(ModelVariantIndex, NodeIndex),
@@ -59,4 +194,184 @@ pub enum GeneratorBuilderError {
     /// Error returned by a [`generator::builder::GeneratorBuilder`] when a given grid does not match the size of the builder's grid.
     #[error("Given grid size {0:?} does not match the expected size {1:?}")]
     InvalidGridSize((u32, u32, u32), (u32, u32, u32)),
+    /// Error returned by a [`generator::builder::GeneratorBuilder`] when its grid has no nodes (a size of 0 on an axis).
+    #[error("Grid of size {0:?} has no nodes")]
+    EmptyGrid((u32, u32, u32)),
+    /// Error returned by [`generator::builder::GeneratorBuilder::with_node_weights`] and [`generator::builder::GeneratorBuilder::with_weight_gradient`] when the given original model index does not exist in the rules.
+    #[error("Invalid model index `{0}`, does not exist in the rules")]
+    InvalidModelIndex(ModelIndex),
+    /// Error returned by [`generator::builder::GeneratorBuilder::with_ground_level`] when the given layer is not in the grid.
+    #[error("Layer y = {0} does not exist in a grid of {1} layers")]
+    InvalidLayer(u32, u32),
+}
+
+/// Errors returned by [`generator::hierarchical::HierarchicalGenerator::generate`]
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum HierarchicalGenerationError {
+    /// The coarse generation failed
+    #[error("Coarse generation failed: {0}")]
+    CoarseFailed(GeneratorError),
+    /// The fine generator could not be built with the upscaled constraints
+    #[error("Failed to build the fine generator: {0}")]
+    BuilderError(#[from] GeneratorBuilderError),
+    /// The fine generation failed, the upscaled constraints may be too restrictive for the fine rules
+    #[error("Fine generation failed: {0}")]
+    FineFailed(GeneratorError),
+}
+
+/// Errors returned by [`generator::dungeon::DungeonConstraints::generate`]
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum DungeonGenerationError {
+    /// A generation failed after all its retries
+    #[error("Dungeon generation failed: {0}")]
+    GenerationFailed(GeneratorError),
+    /// None of the generated outputs satisfied the dungeon constraints
+    #[error("No output satisfied the dungeon constraints after {0} attempts, last output: {1:?}")]
+    Unsatisfied(u32, DungeonStats),
+}
+
+/// Errors returned by [`generator::skeleton::generate_with_skeleton`]
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum SkeletonGenerationError {
+    /// The skeleton generation failed
+    #[error("Skeleton generation failed: {0}")]
+    SkeletonFailed(GeneratorError),
+    /// The full generator could not be built with the skeleton constraints
+    #[error("Failed to build the full generator: {0}")]
+    BuilderError(#[from] GeneratorBuilderError),
+    /// The full generation failed, the skeleton constraints may be too restrictive for the full rules
+    #[error("Full generation failed: {0}")]
+    FullFailed(GeneratorError),
+}
+
+/// Errors returned by [`generator::repair::repair`]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum RepairError {
+    /// An invalid node index was given as an edited node
+    #[error("Invalid node index `{0}`, does not exist in the grid")]
+    InvalidNodeIndex(NodeIndex),
+    /// An edited node has a model instance which does not exist in the rules
+    #[error("Edited node `{0}` has a model which does not exist in the rules")]
+    UnknownModel(NodeIndex),
+    /// Two adjacent edited nodes are not allowed to be adjacent by the rules, the edits themselves need to be changed
+    #[error("Edited nodes `{0}` and `{1}` are not allowed to be adjacent")]
+    ConflictingEdits(NodeIndex, NodeIndex),
+    /// No repair attempt succeeded, up to this radius
+    #[error("Repair failed with a radius of up to {0}")]
+    Failed(u32),
+}
+
+/// Errors returned when reading or comparing golden outputs, see [`golden`]
+#[cfg(feature = "test-utils")]
+#[derive(thiserror::Error, Debug)]
+pub enum GoldenError {
+    /// The golden file could not be read or written
+    #[error("Golden file io error: {0} (set the `GHX_PROC_GEN_UPDATE_GOLDENS` env variable to write the golden files)")]
+    Io(#[from] std::io::Error),
+    /// The golden data is malformed, or does not match the grid size
+    #[error("Invalid golden data at line {0}: {1}")]
+    InvalidFormat(usize, String),
+    /// The output differs from the golden
+    #[error("Output does not match the golden: {0}")]
+    Mismatch(String),
+}
+
+/// Errors returned when reading a serialized generation output, see [`serialization`]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SerializationError {
+    /// The data does not start with the expected header
+    #[error("Invalid header, not a ghx_proc_gen serialized grid")]
+    InvalidHeader,
+    /// The data was written with a format version not supported by this version of the crate
+    #[error("Unsupported format version `{0}`, expected at most `{1}`")]
+    UnsupportedVersion(u32, u32),
+    /// The size of the serialized grid does not match the size of the grid it is loaded into
+    #[error("Serialized grid size {0:?} does not match the expected size {1:?}")]
+    SizeMismatch((u32, u32, u32), (u32, u32, u32)),
+    /// The serialized grid does not have as many nodes as its size
+    #[error("Serialized grid has {0} nodes, expected {1}")]
+    NodesCountMismatch(usize, usize),
+    /// The data ended before all the nodes were read
+    #[error("Unexpected end of data")]
+    UnexpectedEnd,
+    /// A node has an invalid rotation index
+    #[error("Invalid rotation index `{0}` at node {1}")]
+    InvalidRotation(u8, NodeIndex),
+    /// A varint does not fit in the expected integer type
+    #[error("Invalid varint at byte {0}")]
+    InvalidVarint(usize),
+    /// The data has bytes left after the last node
+    #[error("{0} unexpected trailing bytes")]
+    TrailingBytes(usize),
+    /// An updates stream record has an unknown tag
+    #[error("Invalid record tag `{0}` at byte {1}")]
+    InvalidRecordTag(u8, usize),
+    /// The output was generated with different rules, see [`generator::rules::Rules::fingerprint`]
+    #[error("Output generated with rules of fingerprint `{0:#x}`, expected `{1:#x}`")]
+    RulesMismatch(u64, u64),
+}
+
+/// Errors returned by a [`generator::registry::Registry`]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// A namespace is empty or contains the namespace separator
+    #[error("Invalid namespace `{0}`")]
+    InvalidNamespace(String),
+    /// A namespace is registered twice
+    #[error("Namespace `{0}` is already registered")]
+    DuplicateNamespace(String),
+    /// A key is not of the form `namespace:name`
+    #[error("Invalid key `{0}`, keys are of the form `namespace:name`")]
+    InvalidKey(String),
+    /// The namespace of a key is not registered
+    #[error("Unknown namespace in key `{0}`")]
+    UnknownNamespace(String),
+    /// A socket or a model key is registered twice
+    #[error("Key `{0}` is already registered")]
+    DuplicateKey(String),
+    /// No socket is registered with this key
+    #[error("Unknown socket `{0}`")]
+    UnknownSocket(String),
+    /// No model is registered with this key
+    #[error("Unknown model `{0}`")]
+    UnknownModel(String),
+}
+
+/// Errors returned when a solver invariant does not hold, see [`testing::check_solver_invariants`]
+#[cfg(feature = "test-utils")]
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum InvariantError {
+    /// The output of the generation with this seed has adjacencies not allowed by the rules
+    #[error("Output of seed `{0}` has {} adjacency violations: {1:?}", .1.len())]
+    AdjacencyViolations(u64, Vec<testing::AdjacencyViolation>),
+    /// The generation took more tries than allowed by its max retry count
+    #[error("Generation took {0} tries with a max retry count of {1}")]
+    TooManyTries(u32, u32),
+    /// Two generations with the same seed did not give the same output
+    #[error("Two generations with seed `{0}` gave different outputs")]
+    NonDeterministic(u64),
+    /// The generator of the rules and grid could not be built
+    #[error("Invalid generator: {0}")]
+    Builder(#[from] GeneratorBuilderError),
+}
+
+/// Errors returned by the [`texture_synthesis`] of an image
+#[cfg(feature = "image")]
+#[derive(thiserror::Error, Debug)]
+pub enum TextureSynthesisError {
+    /// The pattern size is 0 or bigger than the input image
+    #[error("Invalid pattern size `{0}` for an input image of {1}x{2} pixels")]
+    InvalidPatternSize(u32, u32, u32),
+    /// The rules built from the patterns are invalid
+    #[error("Invalid patterns rules: {0}")]
+    Rules(#[from] RulesBuilderError),
+    /// The generator could not be built
+    #[error("Failed to build the generator: {0}")]
+    GeneratorBuilder(#[from] GeneratorBuilderError),
+    /// The generation failed
+    #[error("Generation failed: {0}")]
+    Generation(#[from] GeneratorError),
+    /// The input image could not be read, or the output image could not be written
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
 }
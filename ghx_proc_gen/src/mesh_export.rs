@@ -0,0 +1,324 @@
+use std::{fmt::Write as _, fs, io, path::Path};
+
+use ghx_grid::{coordinate_system::Cartesian3D, grid::GridData};
+
+use crate::generator::{
+    model::{ModelIndex, ModelInstance, ModelRotation},
+    rules::Rules,
+};
+
+/// A triangle mesh, in node units: a mesh filling exactly one node goes from `-0.5` to `0.5` on each axis.
+#[derive(Clone, Debug, Default)]
+pub struct ModelMesh {
+    /// Positions of the vertices
+    pub positions: Vec<[f32; 3]>,
+    /// Vertices indexes of the triangles, 3 per triangle, counter-clockwise
+    pub indices: Vec<u32>,
+}
+
+impl ModelMesh {
+    /// Creates a cube centered on the node, of size `size` in node units
+    pub fn cube(size: f32) -> Self {
+        let h = size / 2.;
+        let positions = vec![
+            [-h, -h, -h],
+            [h, -h, -h],
+            [h, h, -h],
+            [-h, h, -h],
+            [-h, -h, h],
+            [h, -h, h],
+            [h, h, h],
+            [-h, h, h],
+        ];
+        #[rustfmt::skip]
+        let indices = vec![
+            0, 2, 1, 0, 3, 2, // z-
+            4, 5, 6, 4, 6, 7, // z+
+            0, 4, 7, 0, 7, 3, // x-
+            1, 2, 6, 1, 6, 5, // x+
+            0, 1, 5, 0, 5, 4, // y-
+            3, 7, 6, 3, 6, 2, // y+
+        ];
+        Self { positions, indices }
+    }
+}
+
+/// Exports a generated 3d output as a single combined mesh, in the OBJ or glTF format, to inspect it in any 3d software.
+///
+/// Each node is rendered with the [`ModelMesh`] registered for its model (a cube by default), rotated by its model rotation around the Y axis, and colored per model.
+///
+/// ### Example
+/// ```
+/// use ghx_proc_gen::{mesh_export::{MeshExporter, ModelMesh}, generator::model::{ModelInstance, ModelRotation}};
+/// use ghx_grid::grid::{GridData, GridDefinition};
+///
+/// let instance = |model_index| ModelInstance { model_index, rotation: ModelRotation::Rot0 };
+/// let output = GridData::new(
+///     GridDefinition::new_cartesian_3d(2, 1, 1, false, false, false),
+///     vec![instance(0), instance(1)],
+/// );
+/// let exporter = MeshExporter::new()
+///     .with_hidden_model(0)
+///     .with_model_mesh(1, ModelMesh::cube(0.5))
+///     .with_model_color(1, [0.2, 0.6, 0.2]);
+///
+/// let obj = exporter.to_obj(&output);
+/// assert_eq!(obj.lines().filter(|line| line.starts_with("f ")).count(), 12);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MeshExporter {
+    node_size: [f32; 3],
+    default_mesh: ModelMesh,
+    /// Meshes overrides, indexed by [`ModelIndex`]
+    meshes: Vec<Option<ModelMesh>>,
+    /// Colors overrides, indexed by [`ModelIndex`]
+    colors: Vec<Option<[f32; 3]>>,
+    /// Models not exported, indexed by [`ModelIndex`]
+    hidden: Vec<bool>,
+}
+
+impl Default for MeshExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MeshExporter {
+    /// Creates an exporter rendering every model as a unit cube, with a color derived from its model index
+    pub fn new() -> Self {
+        Self {
+            node_size: [1., 1., 1.],
+            default_mesh: ModelMesh::cube(1.),
+            meshes: Vec::new(),
+            colors: Vec::new(),
+            hidden: Vec::new(),
+        }
+    }
+
+    /// Sets the size of a node in the exported mesh. Defaults to `1.` on each axis.
+    pub fn with_node_size(mut self, node_size: [f32; 3]) -> Self {
+        self.node_size = node_size;
+        self
+    }
+
+    /// Sets the mesh used by the models without a specific mesh. Defaults to [`ModelMesh::cube`] of size `1.`.
+    pub fn with_default_mesh(mut self, mesh: ModelMesh) -> Self {
+        self.default_mesh = mesh;
+        self
+    }
+
+    /// Sets the mesh used for the model `model_index`
+    pub fn with_model_mesh(mut self, model_index: ModelIndex, mesh: ModelMesh) -> Self {
+        set_indexed(&mut self.meshes, model_index, Some(mesh));
+        self
+    }
+
+    /// Sets the color (linear RGB, between `0.` and `1.`) used for the model `model_index`
+    pub fn with_model_color(mut self, model_index: ModelIndex, color: [f32; 3]) -> Self {
+        set_indexed(&mut self.colors, model_index, Some(color));
+        self
+    }
+
+    /// The nodes with the model `model_index` will not be exported. Typically used for void models.
+    pub fn with_hidden_model(mut self, model_index: ModelIndex) -> Self {
+        set_indexed(&mut self.hidden, model_index, true);
+        self
+    }
+
+    /// The nodes with one of the void models of `rules` (see [`crate::generator::model::ModelTemplate::with_void`]) will not be exported.
+    pub fn with_hidden_void_models(mut self, rules: &Rules<Cartesian3D>) -> Self {
+        for model_index in rules.void_models() {
+            set_indexed(&mut self.hidden, model_index, true);
+        }
+        self
+    }
+
+    /// Returns the color used for the model `model_index`
+    pub fn model_color(&self, model_index: ModelIndex) -> [f32; 3] {
+        match self.colors.get(model_index).copied().flatten() {
+            Some(color) => color,
+            None => crate::palette_color(model_index),
+        }
+    }
+
+    /// Returns the combined mesh of `grid_data` in the OBJ format, with vertex colors (`v x y z r g b`)
+    pub fn to_obj(&self, grid_data: &GridData<Cartesian3D, ModelInstance>) -> String {
+        let mesh = self.combine(grid_data);
+        let mut obj = String::from("# Exported by ghx_proc_gen\no generation\n");
+        for (position, color) in mesh.positions.iter().zip(mesh.colors.iter()) {
+            let _ = writeln!(
+                obj,
+                "v {} {} {} {} {} {}",
+                position[0], position[1], position[2], color[0], color[1], color[2]
+            );
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let _ = writeln!(
+                obj,
+                "f {} {} {}",
+                triangle[0] + 1,
+                triangle[1] + 1,
+                triangle[2] + 1
+            );
+        }
+        obj
+    }
+
+    /// Writes the combined mesh of `grid_data` to an OBJ file, see [`MeshExporter::to_obj`]
+    pub fn write_obj<P: AsRef<Path>>(
+        &self,
+        grid_data: &GridData<Cartesian3D, ModelInstance>,
+        path: P,
+    ) -> io::Result<()> {
+        fs::write(path, self.to_obj(grid_data))
+    }
+
+    /// Returns the combined mesh of `grid_data` as a self-contained glTF 2.0 json document, with its buffer embedded as a base64 data uri and the colors stored as vertex colors
+    pub fn to_gltf(&self, grid_data: &GridData<Cartesian3D, ModelInstance>) -> String {
+        let mesh = self.combine(grid_data);
+        let vertices_count = mesh.positions.len();
+        let mut buffer = Vec::with_capacity(vertices_count * 24 + mesh.indices.len() * 4);
+        for value in mesh.positions.iter().flatten() {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in mesh.colors.iter().flatten() {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        for index in mesh.indices.iter() {
+            buffer.extend_from_slice(&index.to_le_bytes());
+        }
+
+        let (mut min, mut max) = ([0.; 3], [0.; 3]);
+        if let Some(first) = mesh.positions.first() {
+            (min, max) = (*first, *first);
+        }
+        for position in mesh.positions.iter() {
+            for (axis, &value) in position.iter().enumerate() {
+                min[axis] = min[axis].min(value);
+                max[axis] = max[axis].max(value);
+            }
+        }
+
+        let positions_length = vertices_count * 12;
+        let indices_length = mesh.indices.len() * 4;
+        format!(
+            concat!(
+                r#"{{"asset":{{"version":"2.0","generator":"ghx_proc_gen"}},"scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0,"name":"generation"}}],"#,
+                r#""meshes":[{{"primitives":[{{"attributes":{{"POSITION":0,"COLOR_0":1}},"indices":2}}]}}],"#,
+                r#""accessors":["#,
+                r#"{{"bufferView":0,"componentType":5126,"count":{vertices_count},"type":"VEC3","min":[{min0},{min1},{min2}],"max":[{max0},{max1},{max2}]}},"#,
+                r#"{{"bufferView":1,"componentType":5126,"count":{vertices_count},"type":"VEC3"}},"#,
+                r#"{{"bufferView":2,"componentType":5125,"count":{indices_count},"type":"SCALAR"}}],"#,
+                r#""bufferViews":["#,
+                r#"{{"buffer":0,"byteOffset":0,"byteLength":{positions_length},"target":34962}},"#,
+                r#"{{"buffer":0,"byteOffset":{positions_length},"byteLength":{positions_length},"target":34962}},"#,
+                r#"{{"buffer":0,"byteOffset":{indices_offset},"byteLength":{indices_length},"target":34963}}],"#,
+                r#""buffers":[{{"byteLength":{buffer_length},"uri":"data:application/octet-stream;base64,{data}"}}]}}"#
+            ),
+            vertices_count = vertices_count,
+            indices_count = mesh.indices.len(),
+            min0 = min[0],
+            min1 = min[1],
+            min2 = min[2],
+            max0 = max[0],
+            max1 = max[1],
+            max2 = max[2],
+            positions_length = positions_length,
+            indices_offset = 2 * positions_length,
+            indices_length = indices_length,
+            buffer_length = buffer.len(),
+            data = base64(&buffer),
+        )
+    }
+
+    /// Writes the combined mesh of `grid_data` to a `.gltf` file, see [`MeshExporter::to_gltf`]
+    pub fn write_gltf<P: AsRef<Path>>(
+        &self,
+        grid_data: &GridData<Cartesian3D, ModelInstance>,
+        path: P,
+    ) -> io::Result<()> {
+        fs::write(path, self.to_gltf(grid_data))
+    }
+
+    fn combine(&self, grid_data: &GridData<Cartesian3D, ModelInstance>) -> CombinedMesh {
+        let mut combined = CombinedMesh::default();
+        let grid = grid_data.grid();
+        for (node_index, model_instance) in grid_data.nodes().iter().enumerate() {
+            let model_index = model_instance.model_index;
+            if self.hidden.get(model_index).copied().unwrap_or(false) {
+                continue;
+            }
+            let mesh = match self.meshes.get(model_index) {
+                Some(Some(mesh)) => mesh,
+                _ => &self.default_mesh,
+            };
+            let color = self.model_color(model_index);
+            let position = grid.pos_from_index(node_index);
+            let center = [
+                (position.x as f32 + 0.5) * self.node_size[0],
+                (position.y as f32 + 0.5) * self.node_size[1],
+                (position.z as f32 + 0.5) * self.node_size[2],
+            ];
+            let first_vertex = combined.positions.len() as u32;
+            for vertex in mesh.positions.iter() {
+                let [x, y, z] = rotate_y(*vertex, model_instance.rotation);
+                combined.positions.push([
+                    center[0] + x * self.node_size[0],
+                    center[1] + y * self.node_size[1],
+                    center[2] + z * self.node_size[2],
+                ]);
+                combined.colors.push(color);
+            }
+            combined
+                .indices
+                .extend(mesh.indices.iter().map(|index| first_vertex + index));
+        }
+        combined
+    }
+}
+
+#[derive(Default)]
+struct CombinedMesh {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+}
+
+fn set_indexed<T: Clone + Default>(values: &mut Vec<T>, index: usize, value: T) {
+    if values.len() <= index {
+        values.resize(index + 1, T::default());
+    }
+    values[index] = value;
+}
+
+/// Rotates `vertex` counter-clockwise around the Y axis
+fn rotate_y(vertex: [f32; 3], rotation: ModelRotation) -> [f32; 3] {
+    let (sin, cos) = match rotation {
+        ModelRotation::Rot0 => return vertex,
+        ModelRotation::Rot90 => (1., 0.),
+        ModelRotation::Rot180 => (0., -1.),
+        ModelRotation::Rot270 => (-1., 0.),
+    };
+    let [x, y, z] = vertex;
+    [x * cos + z * sin, y, -x * sin + z * cos]
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,18 @@
+pub use ghx_grid::{
+    coordinate_system::{Cartesian2D, Cartesian3D, CoordinateSystem},
+    direction::Direction,
+    grid::{GridData, GridDefinition, GridPosition},
+};
+
+pub use crate::{
+    generator::{
+        builder::GeneratorBuilder,
+        model::{Model, ModelCollection, ModelIndex, ModelInstance, ModelRotation, ModelTemplate},
+        node_heuristic::{HeuristicPhase, NodeSelectionHeuristic, PhaseCondition},
+        observer::{GenerationUpdate, QueuedObserver, QueuedStatefulObserver},
+        rules::{Rules, RulesBuilder},
+        socket::{Socket, SocketCollection, SocketsCartesian2D, SocketsCartesian3D},
+        GenerationStatus, Generator, ModelSelectionHeuristic, RngMode,
+    },
+    sockets, GeneratorBuilderError, GeneratorError, NodeIndex, NodeSetError, RulesBuilderError,
+};
@@ -0,0 +1,534 @@
+use ghx_grid::{
+    coordinate_system::CoordinateSystem,
+    grid::{GridData, GridDefinition},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    generator::{
+        model::{ModelInstance, ALL_MODEL_ROTATIONS},
+        observer::GenerationUpdate,
+        rules::Rules,
+        GeneratedNode,
+    },
+    SerializationError,
+};
+
+/// Version of the serialization formats, bumped if one of the formats changes.
+///
+/// Version 2 added the optional [`Rules::fingerprint`] to the binary format. Data written with an older version can still be read.
+pub const FORMAT_VERSION: u32 = 2;
+/// Oldest format version which can still be read
+const MIN_FORMAT_VERSION: u32 = 1;
+
+/// Magic bytes at the start of the binary format
+const BINARY_MAGIC: &[u8; 4] = b"GHXG";
+/// Magic bytes at the start of the updates stream format
+const STREAM_MAGIC: &[u8; 4] = b"GHXS";
+/// Magic bytes at the start of the timeline format
+const TIMELINE_MAGIC: &[u8; 4] = b"GHXT";
+
+const STREAM_TAG_GENERATED: u8 = 0;
+const STREAM_TAG_REINITIALIZING: u8 = 1;
+const STREAM_TAG_FAILED: u8 = 2;
+const STREAM_TAG_REVERTED: u8 = 3;
+
+/// Serializes a generation output to a compact binary format.
+///
+/// The format is: the magic bytes `GHXG`, the format version and the grid size (as varints), a rules fingerprint flag (on 1 byte) followed by the fingerprint (on 8 bytes) if the flag is set, then one entry per node (in node index order) with the model index (as a varint) and the rotation index (on 1 byte).
+///
+/// Only the size of the grid is stored, the output is reloaded into a [`GridDefinition`] with [`from_bytes`]. See [`to_bytes_with_fingerprint`] to also store the fingerprint of the rules used to generate the output.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::{serialization::{from_bytes, to_bytes}, generator::model::{ModelInstance, ModelRotation}};
+/// use ghx_grid::grid::{GridData, GridDefinition};
+///
+/// let grid = GridDefinition::new_cartesian_2d(2, 1, false, false);
+/// let output = GridData::new(
+///     grid.clone(),
+///     vec![
+///         ModelInstance { model_index: 0, rotation: ModelRotation::Rot0 },
+///         ModelInstance { model_index: 300, rotation: ModelRotation::Rot90 },
+///     ],
+/// );
+/// let bytes = to_bytes(&output);
+/// let reloaded = from_bytes(&bytes, &grid).unwrap();
+/// assert_eq!(reloaded.nodes(), output.nodes());
+/// ```
+pub fn to_bytes<C: CoordinateSystem>(grid_data: &GridData<C, ModelInstance>) -> Vec<u8> {
+    write_bytes(grid_data, None)
+}
+
+/// Same as [`to_bytes`] but also stores `fingerprint`, the [`Rules::fingerprint`] of the rules used to generate the output, so that loading the output with changed rules can be detected by [`from_bytes_checked`].
+pub fn to_bytes_with_fingerprint<C: CoordinateSystem>(
+    grid_data: &GridData<C, ModelInstance>,
+    fingerprint: u64,
+) -> Vec<u8> {
+    write_bytes(grid_data, Some(fingerprint))
+}
+
+fn write_bytes<C: CoordinateSystem>(
+    grid_data: &GridData<C, ModelInstance>,
+    fingerprint: Option<u64>,
+) -> Vec<u8> {
+    let (size_x, size_y, size_z) = grid_data.grid().size();
+    let mut bytes = Vec::with_capacity(BINARY_MAGIC.len() + 25 + 2 * grid_data.nodes().len());
+    bytes.extend_from_slice(BINARY_MAGIC);
+    for value in [FORMAT_VERSION, size_x, size_y, size_z] {
+        write_varint(&mut bytes, value as u64);
+    }
+    match fingerprint {
+        Some(fingerprint) => {
+            bytes.push(1);
+            bytes.extend_from_slice(&fingerprint.to_le_bytes());
+        }
+        None => bytes.push(0),
+    }
+    for model_instance in grid_data.nodes() {
+        write_varint(&mut bytes, model_instance.model_index as u64);
+        bytes.push(model_instance.rotation.index());
+    }
+    bytes
+}
+
+/// Parses bytes written by [`to_bytes`] (or [`to_bytes_with_fingerprint`]) into a [`GridData`] using the given `grid`. The rules fingerprint, if any, is ignored.
+///
+/// Returns a [`SerializationError`] if the bytes are malformed, were written with an unsupported format version, or if their size does not match the size of `grid`.
+pub fn from_bytes<C: CoordinateSystem>(
+    bytes: &[u8],
+    grid: &GridDefinition<C>,
+) -> Result<GridData<C, ModelInstance>, SerializationError> {
+    let (mut reader, size, _fingerprint) = read_header(bytes)?;
+    read_nodes(&mut reader, size, grid)
+}
+
+/// Same as [`from_bytes`] but also checks that the output was generated with `rules`: returns a [`SerializationError::RulesMismatch`] if the stored fingerprint differs from the [`Rules::fingerprint`] of `rules`.
+///
+/// Outputs serialized without a fingerprint (with [`to_bytes`], or with an older format version) are not checked. Use [`read_fingerprint`] to handle those cases, or to migrate the outputs generated with older rules.
+///
+/// ### Example
+///
+/// ```
+/// use ghx_proc_gen::{serialization::{from_bytes_checked, to_bytes_with_fingerprint}, generator::{model::{ModelCollection, ModelInstance, ModelRotation}, rules::RulesBuilder, socket::{SocketCollection, SocketsCartesian2D}}};
+/// use ghx_grid::grid::{GridData, GridDefinition};
+///
+/// let mut sockets = SocketCollection::new();
+/// let socket = sockets.create();
+/// sockets.add_connection(socket, vec![socket]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(socket));
+/// let rules = RulesBuilder::new_cartesian_2d(models.clone(), sockets.clone()).build().unwrap();
+///
+/// let grid = GridDefinition::new_cartesian_2d(2, 1, false, false);
+/// let output = GridData::new(grid.clone(), vec![ModelInstance { model_index: 0, rotation: ModelRotation::Rot0 }; 2]);
+/// let bytes = to_bytes_with_fingerprint(&output, rules.fingerprint());
+/// assert!(from_bytes_checked(&bytes, &grid, &rules).is_ok());
+///
+/// models.create(SocketsCartesian2D::Mono(socket)).with_weight(2.);
+/// let changed_rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+/// assert!(from_bytes_checked(&bytes, &grid, &changed_rules).is_err());
+/// ```
+pub fn from_bytes_checked<C: CoordinateSystem>(
+    bytes: &[u8],
+    grid: &GridDefinition<C>,
+    rules: &Rules<C>,
+) -> Result<GridData<C, ModelInstance>, SerializationError> {
+    let (mut reader, size, fingerprint) = read_header(bytes)?;
+    if let Some(fingerprint) = fingerprint {
+        let expected = rules.fingerprint();
+        if fingerprint != expected {
+            return Err(SerializationError::RulesMismatch(fingerprint, expected));
+        }
+    }
+    read_nodes(&mut reader, size, grid)
+}
+
+/// Returns the rules fingerprint stored in bytes written by [`to_bytes_with_fingerprint`], or `None` if the output was serialized without a fingerprint.
+pub fn read_fingerprint(bytes: &[u8]) -> Result<Option<u64>, SerializationError> {
+    let (_reader, _size, fingerprint) = read_header(bytes)?;
+    Ok(fingerprint)
+}
+
+/// Reads the header of the binary format. Returns a reader positioned on the first node, the grid size and the rules fingerprint.
+fn read_header(
+    bytes: &[u8],
+) -> Result<(BytesReader<'_>, (u32, u32, u32), Option<u64>), SerializationError> {
+    if !bytes.starts_with(BINARY_MAGIC) {
+        return Err(SerializationError::InvalidHeader);
+    }
+    let mut reader = BytesReader {
+        bytes,
+        offset: BINARY_MAGIC.len(),
+    };
+    let version = reader.read_version()?;
+    let size = (reader.read_u32()?, reader.read_u32()?, reader.read_u32()?);
+    // Fingerprints were added in version 2
+    let fingerprint = match version >= 2 && reader.read_byte()? != 0 {
+        true => {
+            let mut fingerprint = [0; 8];
+            for byte in fingerprint.iter_mut() {
+                *byte = reader.read_byte()?;
+            }
+            Some(u64::from_le_bytes(fingerprint))
+        }
+        false => None,
+    };
+    Ok((reader, size, fingerprint))
+}
+
+fn read_nodes<C: CoordinateSystem>(
+    reader: &mut BytesReader,
+    size: (u32, u32, u32),
+    grid: &GridDefinition<C>,
+) -> Result<GridData<C, ModelInstance>, SerializationError> {
+    let bytes = reader.bytes;
+    if size != grid.size() {
+        return Err(SerializationError::SizeMismatch(size, grid.size()));
+    }
+
+    let mut nodes = Vec::with_capacity(grid.total_size());
+    for node_index in 0..grid.total_size() {
+        let model_index = reader.read_varint()? as usize;
+        let rotation_index = reader.read_byte()?;
+        let rotation = ALL_MODEL_ROTATIONS.get(rotation_index as usize).ok_or(
+            SerializationError::InvalidRotation(rotation_index, node_index),
+        )?;
+        nodes.push(ModelInstance {
+            model_index,
+            rotation: *rotation,
+        });
+    }
+    if reader.offset < bytes.len() {
+        return Err(SerializationError::TrailingBytes(
+            bytes.len() - reader.offset,
+        ));
+    }
+    Ok(GridData::new(grid.clone(), nodes))
+}
+
+/// Returns the header of an updates stream for a grid of size `size`: the magic bytes `GHXS`, the format version and the grid size (as varints).
+///
+/// The header is followed by one record per [`GenerationUpdate`], see [`crate::generator::observer::StreamObserver`].
+pub(crate) fn stream_header(size: (u32, u32, u32)) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(STREAM_MAGIC.len() + 16);
+    bytes.extend_from_slice(STREAM_MAGIC);
+    for value in [FORMAT_VERSION, size.0, size.1, size.2] {
+        write_varint(&mut bytes, value as u64);
+    }
+    bytes
+}
+
+/// Appends the record of `update` to `bytes`: a tag byte, followed by the node index (as a varint) and for a generated node, the model index (as a varint) and the rotation index (on 1 byte), or by the seed (as a varint) for a reinitialization.
+pub(crate) fn write_stream_update(bytes: &mut Vec<u8>, update: &GenerationUpdate) {
+    match *update {
+        GenerationUpdate::Generated(node) => {
+            bytes.push(STREAM_TAG_GENERATED);
+            write_varint(bytes, node.node_index as u64);
+            write_varint(bytes, node.model_instance.model_index as u64);
+            bytes.push(node.model_instance.rotation.index());
+        }
+        GenerationUpdate::Reinitializing(seed) => {
+            bytes.push(STREAM_TAG_REINITIALIZING);
+            write_varint(bytes, seed);
+        }
+        GenerationUpdate::Failed(node_index) => {
+            bytes.push(STREAM_TAG_FAILED);
+            write_varint(bytes, node_index as u64);
+        }
+        GenerationUpdate::Reverted(node_index) => {
+            bytes.push(STREAM_TAG_REVERTED);
+            write_varint(bytes, node_index as u64);
+        }
+    }
+}
+
+/// Parses an updates stream written by a [`crate::generator::observer::StreamObserver`].
+///
+/// Returns the size of the grid and the updates, in order. A truncated last record (if the writing process stopped in the middle of it) is ignored, so that a stream can be read while it is still being written, or after a crash.
+pub fn read_stream(
+    bytes: &[u8],
+) -> Result<((u32, u32, u32), Vec<GenerationUpdate>), SerializationError> {
+    if !bytes.starts_with(STREAM_MAGIC) {
+        return Err(SerializationError::InvalidHeader);
+    }
+    let mut reader = BytesReader {
+        bytes,
+        offset: STREAM_MAGIC.len(),
+    };
+    reader.read_version()?;
+    let size = (reader.read_u32()?, reader.read_u32()?, reader.read_u32()?);
+
+    let mut updates = Vec::new();
+    while reader.offset < bytes.len() {
+        match reader.read_stream_update() {
+            Ok(update) => updates.push(update),
+            Err(SerializationError::UnexpectedEnd) => break,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok((size, updates))
+}
+
+/// Replays an updates stream (see [`read_stream`]) into a [`GridData`] using the given `grid`: the nodes generated since the last reinitialization, and not reverted, are `Some`.
+///
+/// Used to recover the progress of a generation interrupted by a crash, or to follow a generation from another process.
+pub fn replay_stream<C: CoordinateSystem>(
+    bytes: &[u8],
+    grid: &GridDefinition<C>,
+) -> Result<GridData<C, Option<ModelInstance>>, SerializationError> {
+    let (size, updates) = read_stream(bytes)?;
+    if size != grid.size() {
+        return Err(SerializationError::SizeMismatch(size, grid.size()));
+    }
+    let mut grid_data = GridData::new(grid.clone(), vec![None; grid.total_size()]);
+    for update in updates {
+        match update {
+            GenerationUpdate::Generated(node) => {
+                if node.node_index >= grid.total_size() {
+                    return Err(SerializationError::NodesCountMismatch(
+                        node.node_index + 1,
+                        grid.total_size(),
+                    ));
+                }
+                grid_data.set(node.node_index, Some(node.model_instance));
+            }
+            GenerationUpdate::Reinitializing(_) => grid_data.reset(None),
+            GenerationUpdate::Reverted(node_index) if node_index < grid.total_size() => {
+                grid_data.set(node_index, None)
+            }
+            GenerationUpdate::Reverted(_) | GenerationUpdate::Failed(_) => (),
+        }
+    }
+    Ok(grid_data)
+}
+
+/// A step of a generation timeline: the updates of a generation observed at the same time
+#[derive(Clone, Debug)]
+pub struct TimelineStep {
+    /// Time of the step, in milliseconds since the start of the timeline
+    pub time_ms: u64,
+    /// Updates of the step, in order
+    pub updates: Vec<GenerationUpdate>,
+}
+
+/// Serializes a generation timeline for a grid of size `size`: the steps of a generation with their timestamps, to play the generation back later at the recorded pace.
+///
+/// The format is: the magic bytes `GHXT`, the format version and the grid size (as varints), then one entry per step with its time in milliseconds and its number of updates (as varints), followed by the records of its updates (see [`crate::generator::observer::StreamObserver`] for the records format).
+pub fn timeline_to_bytes(size: (u32, u32, u32), steps: &[TimelineStep]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(TIMELINE_MAGIC.len() + 16);
+    bytes.extend_from_slice(TIMELINE_MAGIC);
+    for value in [FORMAT_VERSION, size.0, size.1, size.2] {
+        write_varint(&mut bytes, value as u64);
+    }
+    for step in steps.iter() {
+        write_varint(&mut bytes, step.time_ms);
+        write_varint(&mut bytes, step.updates.len() as u64);
+        for update in step.updates.iter() {
+            write_stream_update(&mut bytes, update);
+        }
+    }
+    bytes
+}
+
+/// Parses a generation timeline written by [`timeline_to_bytes`].
+///
+/// Returns the size of the grid and the steps, in order. Returns a [`SerializationError`] if the bytes are malformed or were written with an unsupported format version.
+pub fn timeline_from_bytes(
+    bytes: &[u8],
+) -> Result<((u32, u32, u32), Vec<TimelineStep>), SerializationError> {
+    if !bytes.starts_with(TIMELINE_MAGIC) {
+        return Err(SerializationError::InvalidHeader);
+    }
+    let mut reader = BytesReader {
+        bytes,
+        offset: TIMELINE_MAGIC.len(),
+    };
+    reader.read_version()?;
+    let size = (reader.read_u32()?, reader.read_u32()?, reader.read_u32()?);
+
+    let mut steps = Vec::new();
+    while reader.offset < bytes.len() {
+        let time_ms = reader.read_varint()?;
+        let updates_count = reader.read_usize()?;
+        // Not pre-allocated from the count read, which could be corrupted
+        let mut updates = Vec::new();
+        for _ in 0..updates_count {
+            updates.push(reader.read_stream_update()?);
+        }
+        steps.push(TimelineStep { time_ms, updates });
+    }
+    Ok((size, steps))
+}
+
+/// Writes `value` as a LEB128 varint: 7 bits per byte, the high bit is set on all the bytes but the last.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct BytesReader<'a> {
+    bytes: &'a [u8],
+    /// Offset of the next byte to read
+    offset: usize,
+}
+
+impl<'a> BytesReader<'a> {
+    fn read_byte(&mut self) -> Result<u8, SerializationError> {
+        let byte = *self
+            .bytes
+            .get(self.offset)
+            .ok_or(SerializationError::UnexpectedEnd)?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, SerializationError> {
+        let start = self.offset;
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_byte()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SerializationError::InvalidVarint(start))
+    }
+
+    /// Reads the format version, and checks that it is supported
+    fn read_version(&mut self) -> Result<u32, SerializationError> {
+        let version = self.read_u32()?;
+        if !(MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
+            return Err(SerializationError::UnsupportedVersion(
+                version,
+                FORMAT_VERSION,
+            ));
+        }
+        Ok(version)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, SerializationError> {
+        let start = self.offset;
+        u32::try_from(self.read_varint()?).map_err(|_| SerializationError::InvalidVarint(start))
+    }
+
+    fn read_usize(&mut self) -> Result<usize, SerializationError> {
+        let start = self.offset;
+        usize::try_from(self.read_varint()?).map_err(|_| SerializationError::InvalidVarint(start))
+    }
+
+    fn read_stream_update(&mut self) -> Result<GenerationUpdate, SerializationError> {
+        let start = self.offset;
+        match self.read_byte()? {
+            STREAM_TAG_GENERATED => {
+                let node_index = self.read_usize()?;
+                let model_index = self.read_usize()?;
+                let rotation_index = self.read_byte()?;
+                let rotation = ALL_MODEL_ROTATIONS.get(rotation_index as usize).ok_or(
+                    SerializationError::InvalidRotation(rotation_index, node_index),
+                )?;
+                Ok(GenerationUpdate::Generated(GeneratedNode {
+                    node_index,
+                    model_instance: ModelInstance {
+                        model_index,
+                        rotation: *rotation,
+                    },
+                }))
+            }
+            STREAM_TAG_REINITIALIZING => Ok(GenerationUpdate::Reinitializing(self.read_varint()?)),
+            STREAM_TAG_FAILED => Ok(GenerationUpdate::Failed(self.read_usize()?)),
+            STREAM_TAG_REVERTED => Ok(GenerationUpdate::Reverted(self.read_usize()?)),
+            tag => Err(SerializationError::InvalidRecordTag(tag, start)),
+        }
+    }
+}
+
+/// A generation output in a serde-friendly form, which can be saved in any serde format (RON, JSON, ...).
+///
+/// Like the binary format (see [`to_bytes`]), only the size of the grid is stored: the output is reloaded into a [`GridDefinition`] with [`SerializedGridData::into_grid_data`].
+///
+/// ### Example
+///
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use ghx_proc_gen::{serialization::SerializedGridData, generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection, RngMode}};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let ground = sockets.create();
+/// sockets.add_connection(ground, vec![ground]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(ground));
+/// models.create(SocketsCartesian2D::Mono(ground));
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+///
+/// let grid = GridDefinition::new_cartesian_2d(8, 8, false, false);
+/// let mut generator = GeneratorBuilder::new()
+///    .with_rules(rules)
+///    .with_grid(grid.clone())
+///    .with_rng(RngMode::Seeded(42))
+///    .build()
+///    .unwrap();
+/// let (_gen_info, output) = generator.generate_grid().unwrap();
+///
+/// let ron = ron::to_string(&SerializedGridData::from_grid_data(&output)).unwrap();
+/// let serialized: SerializedGridData = ron::from_str(&ron).unwrap();
+/// let reloaded = serialized.into_grid_data(&grid).unwrap();
+/// assert_eq!(reloaded.nodes(), output.nodes());
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializedGridData {
+    /// Version of the format, see [`FORMAT_VERSION`]
+    pub version: u32,
+    /// Size of the grid, as `(x, y, z)`
+    pub size: (u32, u32, u32),
+    /// Models of the nodes, in node index order
+    pub nodes: Vec<ModelInstance>,
+}
+
+#[cfg(feature = "serde")]
+impl SerializedGridData {
+    /// Creates a [`SerializedGridData`] from a generation output
+    pub fn from_grid_data<C: CoordinateSystem>(grid_data: &GridData<C, ModelInstance>) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            size: grid_data.grid().size(),
+            nodes: grid_data.nodes().to_vec(),
+        }
+    }
+
+    /// Converts back into a [`GridData`] using the given `grid`.
+    ///
+    /// Returns a [`SerializationError`] if the data was written with a format version which cannot be read (newer than [`FORMAT_VERSION`], or too old), or if its size does not match the size of `grid`.
+    pub fn into_grid_data<C: CoordinateSystem>(
+        self,
+        grid: &GridDefinition<C>,
+    ) -> Result<GridData<C, ModelInstance>, SerializationError> {
+        if !(MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&self.version) {
+            return Err(SerializationError::UnsupportedVersion(
+                self.version,
+                FORMAT_VERSION,
+            ));
+        }
+        if self.size != grid.size() {
+            return Err(SerializationError::SizeMismatch(self.size, grid.size()));
+        }
+        if self.nodes.len() != grid.total_size() {
+            return Err(SerializationError::NodesCountMismatch(
+                self.nodes.len(),
+                grid.total_size(),
+            ));
+        }
+        Ok(GridData::new(grid.clone(), self.nodes))
+    }
+}
@@ -0,0 +1,282 @@
+use ghx_grid::coordinate_system::{Cartesian2D, CoordinateSystem};
+
+use crate::generator::{
+    model::{ModelCollection, ModelIndex, ModelRotation},
+    rules::RulesBuilder,
+    socket::{SocketCollection, SocketsCartesian2D},
+};
+
+/// A ready-made set of models and sockets, returned by [`pipes`], [`platformer`] and [`dungeon`].
+///
+/// The models are named (with the `models-names` feature) and tagged, and their indexes are given by the constants of this module. Models and connections can still be added before building the rules.
+///
+/// ### Example
+/// ```
+/// use ghx_proc_gen::{generator::builder::GeneratorBuilder, templates};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let rules = templates::pipes().rules_builder().build().unwrap();
+/// let mut generator = GeneratorBuilder::new()
+///     .with_rules(rules)
+///     .with_grid(GridDefinition::new_cartesian_2d(12, 12, false, false))
+///     .build()
+///     .unwrap();
+/// let (_gen_info, _output) = generator.generate_grid().unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Template<C: CoordinateSystem> {
+    /// Models of the template
+    pub models: ModelCollection<C>,
+    /// Sockets used by the models, with their connections
+    pub sockets: SocketCollection,
+}
+
+impl Template<Cartesian2D> {
+    /// Returns a [`RulesBuilder`] for the models of this template
+    pub fn rules_builder(self) -> RulesBuilder<Cartesian2D> {
+        RulesBuilder::new_cartesian_2d(self.models, self.sockets)
+    }
+}
+
+/// Index of the empty model of [`pipes`]
+pub const PIPE_EMPTY: ModelIndex = 0;
+/// Index of the straight pipe model of [`pipes`], along the X axis before rotation
+pub const PIPE_STRAIGHT: ModelIndex = 1;
+/// Index of the corner pipe model of [`pipes`], connecting X+ and Y+ before rotation
+pub const PIPE_CORNER: ModelIndex = 2;
+/// Index of the T junction pipe model of [`pipes`], closed on Y- before rotation
+pub const PIPE_TEE: ModelIndex = 3;
+/// Index of the cross junction pipe model of [`pipes`]
+pub const PIPE_CROSS: ModelIndex = 4;
+/// Index of the pipe end model of [`pipes`], open on X+ before rotation
+pub const PIPE_END: ModelIndex = 5;
+
+/// 2d pipes network: straight pipes, corners, T and cross junctions and pipe ends on an empty background. All the pipes are connected, the network has no open end.
+///
+/// Models are tagged `"empty"` or `"pipe"`.
+pub fn pipes() -> Template<Cartesian2D> {
+    let mut sockets = SocketCollection::new();
+    let (empty, pipe) = (sockets.create(), sockets.create());
+    sockets.add_connections(vec![(empty, vec![empty]), (pipe, vec![pipe])]);
+
+    let mut models = ModelCollection::new();
+    models
+        .create(SocketsCartesian2D::Mono(empty))
+        .with_weight(4.)
+        .with_name("empty")
+        .with_tag("empty");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: pipe,
+            x_neg: pipe,
+            y_pos: empty,
+            y_neg: empty,
+        })
+        .with_rotations([ModelRotation::Rot0, ModelRotation::Rot90])
+        .with_name("pipe_straight")
+        .with_tag("pipe");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: pipe,
+            x_neg: empty,
+            y_pos: pipe,
+            y_neg: empty,
+        })
+        .with_all_rotations()
+        .with_name("pipe_corner")
+        .with_tag("pipe");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: pipe,
+            x_neg: pipe,
+            y_pos: pipe,
+            y_neg: empty,
+        })
+        .with_all_rotations()
+        .with_weight(0.5)
+        .with_name("pipe_tee")
+        .with_tag("pipe");
+    models
+        .create(SocketsCartesian2D::Mono(pipe))
+        .with_weight(0.25)
+        .with_name("pipe_cross")
+        .with_tag("pipe");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: pipe,
+            x_neg: empty,
+            y_pos: empty,
+            y_neg: empty,
+        })
+        .with_all_rotations()
+        .with_weight(0.25)
+        .with_name("pipe_end")
+        .with_tag("pipe");
+
+    Template { models, sockets }
+}
+
+/// Index of the air model of [`platformer`]
+pub const PLATFORMER_AIR: ModelIndex = 0;
+/// Index of the ground surface model of [`platformer`], with air above and dirt below
+pub const PLATFORMER_GROUND: ModelIndex = 1;
+/// Index of the dirt model of [`platformer`], under the ground surface
+pub const PLATFORMER_DIRT: ModelIndex = 2;
+/// Index of the middle floating platform model of [`platformer`]
+pub const PLATFORMER_PLATFORM: ModelIndex = 3;
+/// Index of the left end of a floating platform in [`platformer`]
+pub const PLATFORMER_PLATFORM_LEFT: ModelIndex = 4;
+/// Index of the right end of a floating platform in [`platformer`]
+pub const PLATFORMER_PLATFORM_RIGHT: ModelIndex = 5;
+
+/// 2d side-view platformer tiles, with Y+ as the up direction: a continuous ground surface with dirt below it, and floating platforms in the air above it. The models are not rotated.
+///
+/// Models are tagged `"air"`, `"ground"` or `"platform"`.
+pub fn platformer() -> Template<Cartesian2D> {
+    let mut sockets = SocketCollection::new();
+    let (air, solid) = (sockets.create(), sockets.create());
+    let (ground_side, platform_side) = (sockets.create(), sockets.create());
+    sockets.add_connections(vec![
+        (air, vec![air]),
+        (solid, vec![solid]),
+        (ground_side, vec![ground_side]),
+        (platform_side, vec![platform_side]),
+    ]);
+
+    let mut models = ModelCollection::new();
+    models
+        .create(SocketsCartesian2D::Mono(air))
+        .with_weight(8.)
+        .with_name("air")
+        .with_tag("air");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: ground_side,
+            x_neg: ground_side,
+            y_pos: air,
+            y_neg: solid,
+        })
+        .with_name("ground")
+        .with_tag("ground");
+    models
+        .create(SocketsCartesian2D::Mono(solid))
+        .with_name("dirt")
+        .with_tag("ground");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: platform_side,
+            x_neg: platform_side,
+            y_pos: air,
+            y_neg: air,
+        })
+        .with_weight(0.5)
+        .with_name("platform")
+        .with_tag("platform");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: platform_side,
+            x_neg: air,
+            y_pos: air,
+            y_neg: air,
+        })
+        .with_weight(0.25)
+        .with_name("platform_left")
+        .with_tag("platform");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: air,
+            x_neg: platform_side,
+            y_pos: air,
+            y_neg: air,
+        })
+        .with_weight(0.25)
+        .with_name("platform_right")
+        .with_tag("platform");
+
+    Template { models, sockets }
+}
+
+/// Index of the rock model of [`dungeon`], filling the space outside of the rooms and corridors
+pub const DUNGEON_ROCK: ModelIndex = 0;
+/// Index of the floor model of [`dungeon`]
+pub const DUNGEON_FLOOR: ModelIndex = 1;
+/// Index of the straight wall model of [`dungeon`], with the floor on X+ and the rock on X- before rotation
+pub const DUNGEON_WALL: ModelIndex = 2;
+/// Index of the outer corner wall model of [`dungeon`] (a convex corner of a room), with the walls on X+ and Y+ before rotation
+pub const DUNGEON_OUTER_CORNER: ModelIndex = 3;
+/// Index of the inner corner wall model of [`dungeon`] (a concave corner of a room), with the floor on X+ and Y+ before rotation
+pub const DUNGEON_INNER_CORNER: ModelIndex = 4;
+/// Index of the door model of [`dungeon`]: an opening in a straight wall, with the floor on both sides
+pub const DUNGEON_DOOR: ModelIndex = 5;
+
+/// 2d top-down dungeon tiles: floors enclosed by walls (straight walls, outer and inner corners and doors) in the rock.
+///
+/// Models are tagged `"rock"`, `"floor"`, `"wall"` or `"door"`.
+pub fn dungeon() -> Template<Cartesian2D> {
+    let mut sockets = SocketCollection::new();
+    let (rock, floor) = (sockets.create(), sockets.create());
+    // The two ends of a wall: a wall end only connects to the other end, so that consecutive walls keep the floor on the same side
+    let (wall_start, wall_end) = (sockets.create(), sockets.create());
+    sockets.add_connections(vec![
+        (rock, vec![rock]),
+        (floor, vec![floor]),
+        (wall_start, vec![wall_end]),
+    ]);
+
+    let mut models = ModelCollection::new();
+    models
+        .create(SocketsCartesian2D::Mono(rock))
+        .with_weight(4.)
+        .with_name("rock")
+        .with_tag("rock");
+    models
+        .create(SocketsCartesian2D::Mono(floor))
+        .with_weight(2.)
+        .with_name("floor")
+        .with_tag("floor");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: floor,
+            x_neg: rock,
+            y_pos: wall_start,
+            y_neg: wall_end,
+        })
+        .with_all_rotations()
+        .with_name("wall")
+        .with_tag("wall");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: wall_end,
+            x_neg: rock,
+            y_pos: wall_start,
+            y_neg: rock,
+        })
+        .with_all_rotations()
+        .with_weight(0.5)
+        .with_name("outer_corner")
+        .with_tag("wall");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: floor,
+            x_neg: wall_start,
+            y_pos: floor,
+            y_neg: wall_end,
+        })
+        .with_all_rotations()
+        .with_weight(0.5)
+        .with_name("inner_corner")
+        .with_tag("wall");
+    models
+        .create(SocketsCartesian2D::Simple {
+            x_pos: floor,
+            x_neg: floor,
+            y_pos: wall_start,
+            y_neg: wall_end,
+        })
+        .with_rotations([ModelRotation::Rot0, ModelRotation::Rot90])
+        .with_weight(0.1)
+        .with_name("door")
+        .with_tags(["wall", "door"]);
+
+    Template { models, sockets }
+}
@@ -0,0 +1,315 @@
+use std::{
+    borrow::Cow,
+    io::{self, stdout, Write},
+    time::Duration,
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute,
+    terminal::{self, Clear, ClearType},
+};
+use ghx_grid::{coordinate_system::Cartesian2D, grid::GridData};
+
+use crate::{
+    generator::{
+        model::{ModelIndex, ModelInstance},
+        observer::QueuedStatefulObserver,
+        GenerationStatus, Generator, RngMode,
+    },
+    GeneratorError,
+};
+
+/// Default glyph of the nodes not generated yet
+pub const DEFAULT_EMPTY_GLYPH: &str = "❓";
+/// Default delay between two steps of a [`ViewerMode::Auto`] generation
+pub const DEFAULT_STEP_DELAY: Duration = Duration::from_millis(100);
+
+/// How a [`TerminalViewer`] advances the generation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewerMode {
+    /// The generation only advances when requested with the step key
+    #[default]
+    Paused,
+    /// The generation advances by one step after each step delay
+    Auto,
+}
+
+/// State of the generation displayed by a [`TerminalViewer`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ViewerStatus {
+    Ongoing,
+    Done,
+    Failed,
+}
+
+/// Result of a key handled by [`TerminalViewer::handle_key`]
+pub(crate) enum KeyOutcome {
+    /// The key was ignored, or only changed the settings of the viewer
+    Handled,
+    /// The user asked to quit
+    Quit,
+    /// The generation was restarted with a new seed
+    Restarted,
+    /// The generation advanced by one step
+    Stepped(Result<GenerationStatus, GeneratorError>),
+}
+
+/// Displays a 2d generation in a terminal, one glyph per model, with keyboard controls to pause, step, run automatically or restart the generation.
+///
+/// The keys are listed under the grid:
+/// - `Space`: switches between [`ViewerMode::Paused`] and [`ViewerMode::Auto`]
+/// - `Right` or `n`: advances by one step
+/// - `+`/`-`: speeds up/slows down the [`ViewerMode::Auto`] mode
+/// - `r`: restarts the generation with a new seed
+/// - `q` or `Esc`: quits
+///
+/// ### Example
+///
+/// ```no_run
+/// use ghx_proc_gen::{terminal_viewer::{TerminalViewer, ViewerMode}, generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection}};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (white, black) = (sockets.create(), sockets.create());
+/// sockets.add_connection(white, vec![black]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(white));
+/// models.create(SocketsCartesian2D::Mono(black));
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+///
+/// let mut generator = GeneratorBuilder::new()
+///     .with_rules(rules)
+///     .with_grid(GridDefinition::new_cartesian_2d(8, 8, false, false))
+///     .build()
+///     .unwrap();
+/// let mut viewer = TerminalViewer::new(vec!["⬜", "⬛"]).with_mode(ViewerMode::Auto);
+/// viewer.run(&mut generator).unwrap();
+/// ```
+pub struct TerminalViewer {
+    /// Glyphs indexed by [`ModelIndex`]
+    glyphs: Vec<Cow<'static, str>>,
+    empty_glyph: Cow<'static, str>,
+    mode: ViewerMode,
+    step_delay: Duration,
+}
+
+impl TerminalViewer {
+    /// Creates a viewer displaying the model with index `i` with `glyphs[i]`. Models without a glyph are displayed with their model index.
+    pub fn new<G: Into<Cow<'static, str>>>(glyphs: Vec<G>) -> Self {
+        Self {
+            glyphs: glyphs.into_iter().map(Into::into).collect(),
+            empty_glyph: Cow::Borrowed(DEFAULT_EMPTY_GLYPH),
+            mode: ViewerMode::default(),
+            step_delay: DEFAULT_STEP_DELAY,
+        }
+    }
+
+    /// Sets the glyph of the nodes not generated yet. Defaults to [`DEFAULT_EMPTY_GLYPH`].
+    pub fn with_empty_glyph<G: Into<Cow<'static, str>>>(mut self, glyph: G) -> Self {
+        self.empty_glyph = glyph.into();
+        self
+    }
+
+    /// Sets the initial [`ViewerMode`]. Defaults to [`ViewerMode::Paused`].
+    pub fn with_mode(mut self, mode: ViewerMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the initial delay between two steps in [`ViewerMode::Auto`]. Defaults to [`DEFAULT_STEP_DELAY`].
+    pub fn with_step_delay(mut self, step_delay: Duration) -> Self {
+        self.step_delay = step_delay;
+        self
+    }
+
+    /// Returns the glyph of the nodes not generated yet
+    pub fn empty_glyph(&self) -> &str {
+        &self.empty_glyph
+    }
+
+    /// Returns the current [`ViewerMode`]
+    pub fn mode(&self) -> ViewerMode {
+        self.mode
+    }
+
+    /// Returns the current delay between two steps in [`ViewerMode::Auto`]
+    pub fn step_delay(&self) -> Duration {
+        self.step_delay
+    }
+
+    /// Returns the glyph of the model `model_index`
+    pub fn glyph(&self, model_index: ModelIndex) -> Cow<'_, str> {
+        match self.glyphs.get(model_index) {
+            Some(glyph) => Cow::Borrowed(glyph),
+            None => Cow::Owned(model_index.to_string()),
+        }
+    }
+
+    /// Renders `grid_data` as lines of glyphs, the highest y first. Does not need a terminal.
+    pub fn render(&self, grid_data: &GridData<Cartesian2D, Option<ModelInstance>>) -> Vec<String> {
+        let grid = grid_data.grid();
+        (0..grid.size_y())
+            .rev()
+            .map(|y| {
+                (0..grid.size_x())
+                    .map(|x| match grid_data.get_2d(x, y) {
+                        Some(model_instance) => self.glyph(model_instance.model_index),
+                        None => Cow::Borrowed(self.empty_glyph.as_ref()),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Runs the generation of `generator` in the terminal, until the user quits.
+    ///
+    /// The generator is reinitialized first. The terminal is switched to raw mode during the run, and restored when it returns.
+    pub fn run(&mut self, generator: &mut Generator<Cartesian2D>) -> io::Result<()> {
+        let mut observer = QueuedStatefulObserver::new(generator);
+        let _raw_mode = RawModeGuard::enable()?;
+        let mut stdout = stdout();
+
+        generator.reinitialize();
+        let mut step = 0;
+        let mut status = ViewerStatus::Ongoing;
+        loop {
+            observer.dequeue_all();
+            self.draw(
+                &mut stdout,
+                observer.grid_data(),
+                generator.seed(),
+                step,
+                status,
+            )?;
+
+            let ongoing = status == ViewerStatus::Ongoing;
+            let Some(key) = self.next_key(ongoing)? else {
+                continue;
+            };
+            match self.handle_key(key, ongoing, generator) {
+                KeyOutcome::Handled => (),
+                KeyOutcome::Quit => return Ok(()),
+                KeyOutcome::Restarted => {
+                    step = 0;
+                    status = ViewerStatus::Ongoing;
+                }
+                KeyOutcome::Stepped(result) => {
+                    status = match result {
+                        Ok(GenerationStatus::Ongoing) => ViewerStatus::Ongoing,
+                        Ok(GenerationStatus::Done) => ViewerStatus::Done,
+                        Err(_) => ViewerStatus::Failed,
+                    };
+                    step += 1;
+                }
+            }
+        }
+    }
+
+    /// Waits for the next pressed key. In [`ViewerMode::Auto`], while the generation is `ongoing`, returns the step key once the step delay elapsed without any key press.
+    pub(crate) fn next_key(&self, ongoing: bool) -> io::Result<Option<KeyCode>> {
+        match (self.mode, ongoing) {
+            (ViewerMode::Auto, true) => match event::poll(self.step_delay)? {
+                true => read_key(),
+                false => Ok(Some(KeyCode::Char('n'))),
+            },
+            _ => read_key(),
+        }
+    }
+
+    /// Handles the keys shared by the [`TerminalViewer`] and the `TuiDebugger`: quit, pause/auto, speed, restart and step (only while the generation is `ongoing`)
+    pub(crate) fn handle_key(
+        &mut self,
+        key: KeyCode,
+        ongoing: bool,
+        generator: &mut Generator<Cartesian2D>,
+    ) -> KeyOutcome {
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return KeyOutcome::Quit,
+            KeyCode::Char(' ') => {
+                self.mode = match self.mode {
+                    ViewerMode::Paused => ViewerMode::Auto,
+                    ViewerMode::Auto => ViewerMode::Paused,
+                }
+            }
+            KeyCode::Char('+') => {
+                self.step_delay = (self.step_delay / 2).max(Duration::from_millis(1))
+            }
+            KeyCode::Char('-') => self.step_delay *= 2,
+            KeyCode::Char('r') => {
+                generator.reinitialize_with_rng(RngMode::RandomSeed);
+                return KeyOutcome::Restarted;
+            }
+            KeyCode::Right | KeyCode::Char('n') if ongoing => {
+                return KeyOutcome::Stepped(generator.select_and_propagate());
+            }
+            _ => (),
+        }
+        KeyOutcome::Handled
+    }
+
+    fn draw<W: Write>(
+        &self,
+        out: &mut W,
+        grid_data: &GridData<Cartesian2D, Option<ModelInstance>>,
+        seed: u64,
+        step: u32,
+        status: ViewerStatus,
+    ) -> io::Result<()> {
+        execute!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+        // Raw mode does not return to the start of the line on a line feed
+        for line in self.render(grid_data) {
+            write!(out, "{}\r\n", line)?;
+        }
+        let status = match status {
+            ViewerStatus::Ongoing => "ongoing",
+            ViewerStatus::Done => "done",
+            ViewerStatus::Failed => "contradiction, press r to restart",
+        };
+        write!(
+            out,
+            "\r\nseed: {} | step: {} | {:?} ({} ms) | {}\r\n",
+            seed,
+            step,
+            self.mode,
+            self.step_delay.as_millis(),
+            status
+        )?;
+        write!(
+            out,
+            "[space] pause/auto  [n/→] step  [+/-] speed  [r] restart  [q] quit\r\n"
+        )?;
+        out.flush()
+    }
+}
+
+/// Returns the code of the next pressed key, or `None` for any other event
+fn read_key() -> io::Result<Option<KeyCode>> {
+    match event::read()? {
+        Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) => Ok(Some(code)),
+        _ => Ok(None),
+    }
+}
+
+/// Restores the terminal when dropped, even if the viewer returns early with an error
+struct RawModeGuard;
+
+impl RawModeGuard {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,416 @@
+use std::{fmt, sync::Arc};
+
+use ghx_grid::{
+    coordinate_system::{Cartesian2D, Cartesian3D, CoordinateSystem},
+    grid::{GridData, GridDefinition},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    generator::{
+        builder::GeneratorBuilder,
+        model::{ModelCollection, ModelInstance, ModelTemplate, ALL_MODEL_ROTATIONS},
+        rules::{Rules, RulesBuilder, Violation},
+        socket::{Socket, SocketCollection, SocketsCartesian2D, SocketsCartesian3D},
+        GenInfo, RngMode,
+    },
+    GeneratorBuilderError, InvariantError,
+};
+
+pub use crate::generator::rules::AdjacencyViolation;
+
+/// Options of the random rules created by [`random_rules_2d`] and [`random_rules_3d`]
+#[derive(Clone, Copy, Debug)]
+pub struct RandomRulesOptions {
+    /// Maximum number of sockets, at least 1 socket is created
+    pub max_sockets: u32,
+    /// Maximum number of models, at least 1 model is created
+    pub max_models: u32,
+    /// Maximum number of sockets on a side of a model, at least 1
+    pub max_sockets_per_side: u32,
+    /// Probability for two sockets to be connected
+    pub connection_chance: f32,
+    /// If `true`, models may have additional rotations
+    pub rotations: bool,
+}
+
+impl Default for RandomRulesOptions {
+    fn default() -> Self {
+        Self {
+            max_sockets: 6,
+            max_models: 8,
+            max_sockets_per_side: 2,
+            connection_chance: 0.3,
+            rotations: true,
+        }
+    }
+}
+
+/// Returns all the adjacencies of `grid_data` which are not allowed by `rules`. An output of a [`crate::generator::Generator`] using these rules never has any violation.
+///
+/// Same as the [`Violation::Adjacency`] of [`Rules::validate_output`]: nodes with a model instance that does not exist in the rules are skipped, and the adjacencies of a direction set (see [`RulesBuilder::with_direction_set`]) are not reported.
+///
+/// ### Example
+/// ```
+/// use ghx_proc_gen::{generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection}, testing::adjacency_violations};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (white, black) = (sockets.create(), sockets.create());
+/// sockets.add_connection(white, vec![black]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(white));
+/// models.create(SocketsCartesian2D::Mono(black));
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+///
+/// let mut generator = GeneratorBuilder::new()
+///     .with_rules(rules)
+///     .with_grid(GridDefinition::new_cartesian_2d(8, 8, false, false))
+///     .build()
+///     .unwrap();
+/// let (_gen_info, output) = generator.generate_grid().unwrap();
+/// assert!(adjacency_violations(generator.rules(), &output).is_empty());
+/// ```
+pub fn adjacency_violations<C: CoordinateSystem>(
+    rules: &Rules<C>,
+    grid_data: &GridData<C, ModelInstance>,
+) -> Vec<AdjacencyViolation> {
+    match rules.validate_output(grid_data) {
+        Ok(()) => Vec::new(),
+        Err(violations) => violations
+            .into_iter()
+            .filter_map(|violation| match violation {
+                Violation::Adjacency(adjacency_violation) => Some(adjacency_violation),
+                _ => None,
+            })
+            .collect(),
+    }
+}
+
+/// Creates random 2d [`Rules`] from `rng`. The rules always build, but may be impossible to satisfy on some grids.
+///
+/// Meant to be driven by a property-based testing framework: derive `rng` from a generated seed (`StdRng::seed_from_u64(seed)`) to get shrinkable and reproducible rules.
+pub fn random_rules_2d<R: Rng>(rng: &mut R, options: &RandomRulesOptions) -> Rules<Cartesian2D> {
+    let (sockets, collection) = random_sockets(rng, options);
+    let mut models = ModelCollection::<Cartesian2D>::new();
+    for _ in 0..rng.gen_range(1..=options.max_models.max(1)) {
+        let template: ModelTemplate<Cartesian2D> = SocketsCartesian2D::Multiple {
+            x_pos: random_side(rng, &sockets, options),
+            x_neg: random_side(rng, &sockets, options),
+            y_pos: random_side(rng, &sockets, options),
+            y_neg: random_side(rng, &sockets, options),
+        }
+        .into();
+        models.create(randomize_template(rng, template, options));
+    }
+    // Cannot fail: there is at least one socket and one model, all with sockets on every side and a valid weight
+    RulesBuilder::new_cartesian_2d(models, collection)
+        .build()
+        .unwrap()
+}
+
+/// Creates random 3d [`Rules`] from `rng`, rotating around the Y axis. See [`random_rules_2d`].
+pub fn random_rules_3d<R: Rng>(rng: &mut R, options: &RandomRulesOptions) -> Rules<Cartesian3D> {
+    let (sockets, collection) = random_sockets(rng, options);
+    let mut models = ModelCollection::<Cartesian3D>::new();
+    for _ in 0..rng.gen_range(1..=options.max_models.max(1)) {
+        let template: ModelTemplate<Cartesian3D> = SocketsCartesian3D::Multiple {
+            x_pos: random_side(rng, &sockets, options),
+            x_neg: random_side(rng, &sockets, options),
+            y_pos: random_side(rng, &sockets, options),
+            y_neg: random_side(rng, &sockets, options),
+            z_pos: random_side(rng, &sockets, options),
+            z_neg: random_side(rng, &sockets, options),
+        }
+        .into();
+        models.create(randomize_template(rng, template, options));
+    }
+    // Cannot fail, see `random_rules_2d`
+    RulesBuilder::new_cartesian_3d(models, collection)
+        .build()
+        .unwrap()
+}
+
+/// Creates a random 2d grid of at most `max_size` nodes on each axis (at least 1), looping or not on each axis.
+pub fn random_grid_2d<R: Rng>(rng: &mut R, max_size: u32) -> GridDefinition<Cartesian2D> {
+    GridDefinition::new_cartesian_2d(
+        rng.gen_range(1..=max_size.max(1)),
+        rng.gen_range(1..=max_size.max(1)),
+        rng.gen(),
+        rng.gen(),
+    )
+}
+
+/// Creates a random 3d grid of at most `max_size` nodes on each axis (at least 1), looping or not on each axis.
+pub fn random_grid_3d<R: Rng>(rng: &mut R, max_size: u32) -> GridDefinition<Cartesian3D> {
+    GridDefinition::new_cartesian_3d(
+        rng.gen_range(1..=max_size.max(1)),
+        rng.gen_range(1..=max_size.max(1)),
+        rng.gen_range(1..=max_size.max(1)),
+        rng.gen(),
+        rng.gen(),
+        rng.gen(),
+    )
+}
+
+/// Generates `grid` with `rules` and `seed` twice, and checks the invariants of the solver:
+/// - the output has no [`AdjacencyViolation`]
+/// - the generation did not take more than `max_retry_count + 1` tries
+/// - the same seed gives the same output
+///
+/// Returns the [`GenInfo`] of the generation, or `None` if the generation failed on all its tries (which is not a violation: random rules may be impossible to satisfy). A generator that cannot be built returns an [`InvariantError::Builder`].
+///
+/// ### Example
+/// ```
+/// use std::sync::Arc;
+/// use rand::{rngs::StdRng, SeedableRng};
+/// use ghx_proc_gen::testing::{check_solver_invariants, random_grid_2d, random_rules_2d, RandomRulesOptions};
+///
+/// // See `random_case_2d_strategy` and `RandomCase2D` to generate the cases with proptest or quickcheck
+/// for seed in 0..20 {
+///     let mut rng = StdRng::seed_from_u64(seed);
+///     let rules = Arc::new(random_rules_2d(&mut rng, &RandomRulesOptions::default()));
+///     let grid = random_grid_2d(&mut rng, 6);
+///     check_solver_invariants(rules, grid, seed, 10).unwrap();
+/// }
+/// ```
+pub fn check_solver_invariants<C: CoordinateSystem>(
+    rules: Arc<Rules<C>>,
+    grid: GridDefinition<C>,
+    seed: u64,
+    max_retry_count: u32,
+) -> Result<Option<GenInfo>, InvariantError> {
+    let generate = || -> Result<_, GeneratorBuilderError> {
+        let mut generator = GeneratorBuilder::new()
+            .with_shared_rules(Arc::clone(&rules))
+            .with_grid(grid.clone())
+            .with_max_retry_count(max_retry_count)
+            .with_rng(RngMode::Seeded(seed))
+            .build()?;
+        let configured_retry_count = generator.max_retry_count();
+        Ok((configured_retry_count, generator.generate_grid().ok()))
+    };
+    let (configured_retry_count, Some((gen_info, output))) = generate()? else {
+        return Ok(None);
+    };
+    if gen_info.try_count > configured_retry_count.saturating_add(1) {
+        return Err(InvariantError::TooManyTries(
+            gen_info.try_count,
+            configured_retry_count,
+        ));
+    }
+    let violations = adjacency_violations(&rules, &output);
+    if !violations.is_empty() {
+        return Err(InvariantError::AdjacencyViolations(seed, violations));
+    }
+    match generate()? {
+        (_, Some((_, other_output))) if other_output.nodes() == output.nodes() => {
+            Ok(Some(gen_info))
+        }
+        _ => Err(InvariantError::NonDeterministic(seed)),
+    }
+}
+
+/// A random case for [`check_solver_invariants`]: 2d rules and a grid, derived from a seed.
+///
+/// With the `proptest` feature, [`random_case_2d_strategy`] is a `proptest` strategy of random cases. With the `quickcheck` feature, [`RandomCase2D`] implements `quickcheck::Arbitrary`. In both cases, a failing case shrinks to smaller rules and grids.
+#[derive(Clone)]
+pub struct RandomCase2D {
+    /// Seed of the rules, of the grid and of the generation
+    pub seed: u64,
+    /// Options of the random rules
+    pub options: RandomRulesOptions,
+    /// Maximum size of the grid on each axis
+    pub max_grid_size: u32,
+    /// Random rules, see [`random_rules_2d`]
+    pub rules: Arc<Rules<Cartesian2D>>,
+    /// Random grid, see [`random_grid_2d`]
+    pub grid: GridDefinition<Cartesian2D>,
+}
+
+impl RandomCase2D {
+    /// Creates the random rules and grid of `seed`
+    pub fn new(seed: u64, options: RandomRulesOptions, max_grid_size: u32) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let rules = Arc::new(random_rules_2d(&mut rng, &options));
+        let grid = random_grid_2d(&mut rng, max_grid_size);
+        Self {
+            seed,
+            options,
+            max_grid_size,
+            rules,
+            grid,
+        }
+    }
+
+    /// Runs [`check_solver_invariants`] on this case
+    pub fn check(&self, max_retry_count: u32) -> Result<Option<GenInfo>, InvariantError> {
+        check_solver_invariants(
+            Arc::clone(&self.rules),
+            self.grid.clone(),
+            self.seed,
+            max_retry_count,
+        )
+    }
+
+    /// Returns the cases with the same seed but smaller rules or grid
+    #[cfg(feature = "quickcheck")]
+    fn shrunk(&self) -> Vec<RandomCase2D> {
+        let (seed, options, max_grid_size) = (self.seed, self.options, self.max_grid_size);
+        let mut cases = Vec::new();
+        if max_grid_size > 1 {
+            cases.push(Self::new(seed, options, max_grid_size / 2));
+        }
+        if options.max_models > 1 {
+            let options = RandomRulesOptions {
+                max_models: options.max_models / 2,
+                ..options
+            };
+            cases.push(Self::new(seed, options, max_grid_size));
+        }
+        if options.max_sockets > 1 {
+            let options = RandomRulesOptions {
+                max_sockets: options.max_sockets / 2,
+                ..options
+            };
+            cases.push(Self::new(seed, options, max_grid_size));
+        }
+        cases
+    }
+}
+
+impl fmt::Debug for RandomCase2D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RandomCase2D")
+            .field("seed", &self.seed)
+            .field("options", &self.options)
+            .field("max_grid_size", &self.max_grid_size)
+            .field("models_count", &self.rules.models_count())
+            .field("grid_size", &self.grid.size())
+            .finish()
+    }
+}
+
+/// Returns a `proptest` strategy of [`RandomCase2D`], with rules of at most `options.max_sockets` sockets and `options.max_models` models, on grids of at most `max_grid_size` nodes on each axis. Shrinking lowers these maximums.
+///
+/// ### Example
+/// ```
+/// use proptest::prelude::*;
+/// use ghx_proc_gen::testing::{random_case_2d_strategy, RandomRulesOptions};
+///
+/// proptest!(|(case in random_case_2d_strategy(RandomRulesOptions::default(), 6))| {
+///     case.check(10).unwrap();
+/// });
+/// ```
+#[cfg(feature = "proptest")]
+pub fn random_case_2d_strategy(
+    options: RandomRulesOptions,
+    max_grid_size: u32,
+) -> impl proptest::strategy::Strategy<Value = RandomCase2D> {
+    use proptest::prelude::*;
+
+    (
+        any::<u64>(),
+        1..=options.max_sockets.max(1),
+        1..=options.max_models.max(1),
+        1..=max_grid_size.max(1),
+    )
+        .prop_map(move |(seed, max_sockets, max_models, max_grid_size)| {
+            let options = RandomRulesOptions {
+                max_sockets,
+                max_models,
+                ..options
+            };
+            RandomCase2D::new(seed, options, max_grid_size)
+        })
+}
+
+/// Random cases with the default [`RandomRulesOptions`], on grids of at most `g.size()` (capped to 16) nodes on each axis. Shrinking halves the maximum size of the grid, and the maximum numbers of models and sockets.
+///
+/// ### Example
+/// ```
+/// use ghx_proc_gen::testing::RandomCase2D;
+///
+/// fn invariants_hold(case: RandomCase2D) -> bool {
+///     case.check(10).is_ok()
+/// }
+/// quickcheck::QuickCheck::new()
+///     .tests(20)
+///     .quickcheck(invariants_hold as fn(RandomCase2D) -> bool);
+/// ```
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for RandomCase2D {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let seed = <u64 as quickcheck::Arbitrary>::arbitrary(g);
+        let max_grid_size = (g.size() as u32).clamp(1, 16);
+        Self::new(seed, RandomRulesOptions::default(), max_grid_size)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.shrunk().into_iter())
+    }
+}
+
+/// Runs [`check_solver_invariants`] on `cases` random 2d rules and grids derived from `seed`. Returns the seed of the first failing case with its error.
+pub fn check_random_cases_2d(
+    seed: u64,
+    cases: u32,
+    options: &RandomRulesOptions,
+    max_grid_size: u32,
+    max_retry_count: u32,
+) -> Result<(), (u64, InvariantError)> {
+    let mut seeds_rng = StdRng::seed_from_u64(seed);
+    for _ in 0..cases {
+        let case = RandomCase2D::new(seeds_rng.gen::<u64>(), *options, max_grid_size);
+        case.check(max_retry_count)
+            .map_err(|err| (case.seed, err))?;
+    }
+    Ok(())
+}
+
+fn random_sockets<R: Rng>(
+    rng: &mut R,
+    options: &RandomRulesOptions,
+) -> (Vec<Socket>, SocketCollection) {
+    let mut collection = SocketCollection::new();
+    let sockets: Vec<Socket> = (0..rng.gen_range(1..=options.max_sockets.max(1)))
+        .map(|_| collection.create())
+        .collect();
+    for &from in sockets.iter() {
+        let to: Vec<Socket> = sockets
+            .iter()
+            .copied()
+            .filter(|_| rng.gen::<f32>() < options.connection_chance)
+            .collect();
+        if !to.is_empty() {
+            collection.add_connection(from, to);
+        }
+    }
+    (sockets, collection)
+}
+
+fn random_side<R: Rng>(
+    rng: &mut R,
+    sockets: &Vec<Socket>,
+    options: &RandomRulesOptions,
+) -> Vec<Socket> {
+    (0..rng.gen_range(1..=options.max_sockets_per_side.max(1)))
+        .map(|_| sockets[rng.gen_range(0..sockets.len())])
+        .collect()
+}
+
+fn randomize_template<R: Rng, C>(
+    rng: &mut R,
+    template: ModelTemplate<C>,
+    options: &RandomRulesOptions,
+) -> ModelTemplate<C> {
+    let mut template = template.with_weight(rng.gen_range(0.5..2.0f32));
+    if options.rotations {
+        for &rotation in ALL_MODEL_ROTATIONS.iter() {
+            if rng.gen::<bool>() {
+                template = template.with_additional_rotation(rotation);
+            }
+        }
+    }
+    template
+}
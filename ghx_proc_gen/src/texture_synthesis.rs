@@ -0,0 +1,249 @@
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+use ghx_grid::{
+    coordinate_system::Cartesian2D,
+    grid::{GridData, GridDefinition},
+};
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    generator::{
+        builder::GeneratorBuilder,
+        model::{ModelCollection, ModelInstance},
+        rules::{Rules, RulesBuilder},
+        socket::{Socket, SocketCollection, SocketsCartesian2D},
+        RngMode,
+    },
+    TextureSynthesisError,
+};
+
+/// Pixels of a pattern, row by row from the top of the image. Pixels are stored as `u32` to be cheaply ordered and compared.
+type Pattern = Vec<u32>;
+
+/// Options of an [`OverlappingModel`]
+#[derive(Clone, Copy, Debug)]
+pub struct OverlappingOptions {
+    /// Size in pixels of the square patterns extracted from the input. Bigger patterns reproduce bigger structures of the input, but need a bigger input to not simply copy it. Usually 2 or 3.
+    pub pattern_size: u32,
+    /// If `true`, patterns are also extracted across the borders of the input, as if it was tiling.
+    pub periodic_input: bool,
+    /// If `true`, the output is tiling.
+    pub periodic_output: bool,
+}
+
+impl Default for OverlappingOptions {
+    fn default() -> Self {
+        Self {
+            pattern_size: 3,
+            periodic_input: true,
+            periodic_output: false,
+        }
+    }
+}
+
+/// Overlapping WFC on the pixels of an image: every `pattern_size` square of pixels of the input becomes a model, weighted by its number of occurrences. Two patterns can be adjacent if they overlap on `pattern_size - 1` pixels.
+///
+/// The overlaps are expressed with sockets (one socket pair per distinct overlap), so the patterns are solved by a regular [`crate::generator::Generator`] on 2d [`Rules`].
+///
+/// ### Example
+///
+/// ```no_run
+/// use ghx_proc_gen::texture_synthesis::{OverlappingModel, OverlappingOptions};
+///
+/// let input = image::open("input.png").unwrap().to_rgba8();
+/// let model = OverlappingModel::new(&input, OverlappingOptions::default()).unwrap();
+/// let output = model.synthesize(64, 64, Some(42)).unwrap();
+/// output.save("output.png").unwrap();
+/// ```
+///
+/// With a small in-memory input, a 2x2 checkerboard only has 2 patterns of 2x2 pixels, which can only be synthesized as a bigger checkerboard:
+///
+/// ```
+/// use ghx_proc_gen::texture_synthesis::{OverlappingModel, OverlappingOptions};
+/// use image::{Rgba, RgbaImage};
+///
+/// let (black, white) = (Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255]));
+/// let input = RgbaImage::from_fn(2, 2, |x, y| if (x + y) % 2 == 0 { black } else { white });
+/// let options = OverlappingOptions {
+///     pattern_size: 2,
+///     ..Default::default()
+/// };
+/// let model = OverlappingModel::new(&input, options).unwrap();
+/// assert_eq!(model.patterns_count(), 2);
+///
+/// let output = model.synthesize(8, 8, Some(42)).unwrap();
+/// for y in 0..8 {
+///     for x in 0..7 {
+///         assert_ne!(output.get_pixel(x, y), output.get_pixel(x + 1, y));
+///     }
+/// }
+/// ```
+pub struct OverlappingModel {
+    options: OverlappingOptions,
+    patterns: Vec<Pattern>,
+    rules: Arc<Rules<Cartesian2D>>,
+}
+
+impl OverlappingModel {
+    /// Extracts the patterns of `input` and builds their [`Rules`].
+    ///
+    /// Returns a [`TextureSynthesisError`] if the pattern size is 0 or bigger than the input.
+    pub fn new(
+        input: &RgbaImage,
+        options: OverlappingOptions,
+    ) -> Result<OverlappingModel, TextureSynthesisError> {
+        let n = options.pattern_size;
+        if n == 0 || n > input.width() || n > input.height() {
+            return Err(TextureSynthesisError::InvalidPatternSize(
+                n,
+                input.width(),
+                input.height(),
+            ));
+        }
+
+        // Patterns ordered by first occurrence, with their number of occurrences
+        let mut patterns_indexes = BTreeMap::new();
+        let mut patterns = Vec::new();
+        let mut weights = Vec::new();
+        let (max_x, max_y) = match options.periodic_input {
+            true => (input.width(), input.height()),
+            false => (input.width() - n + 1, input.height() - n + 1),
+        };
+        for y in 0..max_y {
+            for x in 0..max_x {
+                let mut pattern = Vec::with_capacity((n * n) as usize);
+                for dy in 0..n {
+                    for dx in 0..n {
+                        let pixel =
+                            input.get_pixel((x + dx) % input.width(), (y + dy) % input.height());
+                        pattern.push(u32::from_le_bytes(pixel.0));
+                    }
+                }
+                let index = *patterns_indexes.entry(pattern.clone()).or_insert_with(|| {
+                    patterns.push(pattern);
+                    weights.push(0f32);
+                    patterns.len() - 1
+                });
+                weights[index] += 1.;
+            }
+        }
+
+        // One pair of sockets per distinct overlap on each axis: the `pos` socket of an overlap only connects to its `neg` socket.
+        let mut sockets = SocketCollection::new();
+        let mut x_overlaps: BTreeMap<Pattern, (Socket, Socket)> = BTreeMap::new();
+        let mut y_overlaps: BTreeMap<Pattern, (Socket, Socket)> = BTreeMap::new();
+        let mut overlap_sockets = |overlaps: &mut BTreeMap<Pattern, (Socket, Socket)>,
+                                   overlap: Pattern| {
+            *overlaps.entry(overlap).or_insert_with(|| {
+                let (pos, neg) = (sockets.create(), sockets.create());
+                (pos, neg)
+            })
+        };
+        let mut models = ModelCollection::<Cartesian2D>::new();
+        let mut models_sockets = Vec::with_capacity(patterns.len());
+        for pattern in patterns.iter() {
+            // The x+ neighbour overlaps on all the columns but the first one, the x- neighbour on all the columns but the last one.
+            let x_pos = overlap_sockets(&mut x_overlaps, sub_pattern(pattern, n, 1..n, 0..n)).0;
+            let x_neg = overlap_sockets(&mut x_overlaps, sub_pattern(pattern, n, 0..n - 1, 0..n)).1;
+            // The y+ neighbour (grid y is up) is above in the image: it overlaps on all the rows but the last one.
+            let y_pos = overlap_sockets(&mut y_overlaps, sub_pattern(pattern, n, 0..n, 0..n - 1)).0;
+            let y_neg = overlap_sockets(&mut y_overlaps, sub_pattern(pattern, n, 0..n, 1..n)).1;
+            models_sockets.push((x_pos, x_neg, y_pos, y_neg));
+        }
+        for &(pos, neg) in x_overlaps.values().chain(y_overlaps.values()) {
+            sockets.add_connection(pos, vec![neg]);
+        }
+        for ((x_pos, x_neg, y_pos, y_neg), weight) in models_sockets.into_iter().zip(weights) {
+            models
+                .create(SocketsCartesian2D::Simple {
+                    x_pos,
+                    x_neg,
+                    y_pos,
+                    y_neg,
+                })
+                .with_weight(weight);
+        }
+
+        let rules = RulesBuilder::new_cartesian_2d(models, sockets).build()?;
+        Ok(OverlappingModel {
+            options,
+            patterns,
+            rules: Arc::new(rules),
+        })
+    }
+
+    /// Returns the number of distinct patterns extracted from the input
+    pub fn patterns_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns the [`Rules`] of the patterns: each pattern is a model, indexed in order of first occurrence in the input.
+    pub fn rules(&self) -> &Rules<Cartesian2D> {
+        &self.rules
+    }
+
+    /// Generates an image of `width` x `height` pixels. Uses a random seed if `seed` is `None`.
+    pub fn synthesize(
+        &self,
+        width: u32,
+        height: u32,
+        seed: Option<u64>,
+    ) -> Result<RgbaImage, TextureSynthesisError> {
+        let grid = GridDefinition::new_cartesian_2d(
+            width,
+            height,
+            self.options.periodic_output,
+            self.options.periodic_output,
+        );
+        let mut generator = GeneratorBuilder::new()
+            .with_shared_rules(Arc::clone(&self.rules))
+            .with_grid(grid)
+            .with_rng(match seed {
+                Some(seed) => RngMode::Seeded(seed),
+                None => RngMode::RandomSeed,
+            })
+            .build()?;
+        let (_gen_info, grid_data) = generator.generate_grid()?;
+        Ok(self.to_image(&grid_data))
+    }
+
+    /// Converts a generated grid of patterns to an image: each pixel is the top-left pixel of the pattern of its node.
+    pub fn to_image(&self, grid_data: &GridData<Cartesian2D, ModelInstance>) -> RgbaImage {
+        let grid = grid_data.grid();
+        RgbaImage::from_fn(grid.size_x(), grid.size_y(), |x, y| {
+            // Y is up in the grid and down in the image
+            let node = grid_data.get_2d(x, grid.size_y() - 1 - y);
+            Rgba(self.patterns[node.model_index][0].to_le_bytes())
+        })
+    }
+}
+
+/// Reads the PNG at `input`, synthesizes a `width` x `height` image and writes it as a PNG to `output`.
+pub fn synthesize_png<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    width: u32,
+    height: u32,
+    options: OverlappingOptions,
+    seed: Option<u64>,
+) -> Result<(), TextureSynthesisError> {
+    let input = image::open(input)?.to_rgba8();
+    let model = OverlappingModel::new(&input, options)?;
+    model.synthesize(width, height, seed)?.save(output)?;
+    Ok(())
+}
+
+/// Returns the pixels of `pattern` (of size `n`) in the given columns and rows
+fn sub_pattern(
+    pattern: &Pattern,
+    n: u32,
+    columns: std::ops::Range<u32>,
+    rows: std::ops::Range<u32>,
+) -> Pattern {
+    rows.flat_map(|row| {
+        columns
+            .clone()
+            .map(move |column| pattern[(row * n + column) as usize])
+    })
+    .collect()
+}
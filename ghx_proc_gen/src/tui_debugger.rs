@@ -0,0 +1,302 @@
+use std::{
+    borrow::Cow,
+    io::{self, stdout, Stdout},
+};
+
+use crossterm::{
+    event::KeyCode,
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ghx_grid::{
+    coordinate_system::Cartesian2D,
+    grid::{GridData, GridPosition},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction as LayoutDirection, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+use crate::{
+    generator::{
+        model::ModelInstance, observer::QueuedStatefulObserver, GenerationStatus, Generator,
+    },
+    terminal_viewer::{KeyOutcome, TerminalViewer},
+};
+
+/// Keybindings displayed in the keys pane
+const KEYBINDINGS: &[(&str, &str)] = &[
+    ("←↑→↓ / hjkl", "move the node cursor"),
+    ("n", "advance by one step"),
+    ("space", "pause/auto"),
+    ("+ / -", "speed up/slow down"),
+    ("r", "restart with a new seed"),
+    ("q / esc", "quit"),
+];
+
+/// Interactive terminal debugger of 2d generations, built on [ratatui](https://ratatui.rs): a no-engine counterpart of the Bevy debug plugin, for server-side or CI debugging.
+///
+/// The screen is split in panes: the grid (with a node cursor), the candidates of the node under the cursor, the generation stats and the keybindings. The glyphs, initial mode and step delay are the ones of the given [`TerminalViewer`].
+///
+/// ### Example
+///
+/// ```no_run
+/// use ghx_proc_gen::{terminal_viewer::TerminalViewer, tui_debugger::TuiDebugger, generator::{builder::GeneratorBuilder, rules::RulesBuilder, socket::{SocketsCartesian2D, SocketCollection}, model::ModelCollection}};
+/// use ghx_grid::grid::GridDefinition;
+///
+/// let mut sockets = SocketCollection::new();
+/// let (white, black) = (sockets.create(), sockets.create());
+/// sockets.add_connection(white, vec![black]);
+/// let mut models = ModelCollection::new();
+/// models.create(SocketsCartesian2D::Mono(white));
+/// models.create(SocketsCartesian2D::Mono(black));
+/// let rules = RulesBuilder::new_cartesian_2d(models, sockets).build().unwrap();
+///
+/// let mut generator = GeneratorBuilder::new()
+///     .with_rules(rules)
+///     .with_grid(GridDefinition::new_cartesian_2d(8, 8, false, false))
+///     .build()
+///     .unwrap();
+/// let mut debugger = TuiDebugger::new(TerminalViewer::new(vec!["⬜", "⬛"]));
+/// debugger.run(&mut generator).unwrap();
+/// ```
+pub struct TuiDebugger {
+    viewer: TerminalViewer,
+    cursor: GridPosition,
+}
+
+/// State of a generation run by a [`TuiDebugger`]
+struct RunState {
+    step: u32,
+    status: Result<GenerationStatus, String>,
+}
+
+impl TuiDebugger {
+    /// Creates a debugger using the glyphs and settings of `viewer`
+    pub fn new(viewer: TerminalViewer) -> Self {
+        Self {
+            viewer,
+            cursor: GridPosition::new(0, 0, 0),
+        }
+    }
+
+    /// Runs the generation of `generator` in the debugger, until the user quits.
+    ///
+    /// The generator is reinitialized first. The terminal is switched to raw mode and to its alternate screen during the run, and restored when it returns.
+    pub fn run(&mut self, generator: &mut Generator<Cartesian2D>) -> io::Result<()> {
+        let mut observer = QueuedStatefulObserver::new(generator);
+        let _guard = TerminalGuard::enable()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+        generator.reinitialize();
+        let mut state = RunState {
+            step: 0,
+            status: Ok(GenerationStatus::Ongoing),
+        };
+        loop {
+            observer.dequeue_all();
+            self.draw(&mut terminal, generator, observer.grid_data(), &state)?;
+
+            let ongoing = matches!(state.status, Ok(GenerationStatus::Ongoing));
+            let Some(key) = self.viewer.next_key(ongoing)? else {
+                continue;
+            };
+            let (size_x, size_y, _) = generator.grid().size();
+            match key {
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.cursor.x = self.cursor.x.saturating_sub(1)
+                }
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.cursor.x = (self.cursor.x + 1).min(size_x - 1)
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.cursor.y = self.cursor.y.saturating_sub(1)
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.cursor.y = (self.cursor.y + 1).min(size_y - 1)
+                }
+                _ => match self.viewer.handle_key(key, ongoing, generator) {
+                    KeyOutcome::Handled => (),
+                    KeyOutcome::Quit => return Ok(()),
+                    KeyOutcome::Restarted => {
+                        state = RunState {
+                            step: 0,
+                            status: Ok(GenerationStatus::Ongoing),
+                        };
+                    }
+                    KeyOutcome::Stepped(result) => {
+                        state.status = result.map_err(|err| err.to_string());
+                        state.step += 1;
+                    }
+                },
+            }
+        }
+    }
+
+    fn draw(
+        &self,
+        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+        generator: &Generator<Cartesian2D>,
+        grid_data: &GridData<Cartesian2D, Option<ModelInstance>>,
+        state: &RunState,
+    ) -> io::Result<()> {
+        terminal.draw(|frame| {
+            let columns = Layout::default()
+                .direction(LayoutDirection::Horizontal)
+                .constraints([Constraint::Min(20), Constraint::Length(48)])
+                .split(frame.size());
+            let side = Layout::default()
+                .direction(LayoutDirection::Vertical)
+                .constraints([
+                    Constraint::Min(6),
+                    Constraint::Length(9),
+                    Constraint::Length(KEYBINDINGS.len() as u16 + 2),
+                ])
+                .split(columns[1]);
+            self.draw_grid(frame, columns[0], grid_data);
+            self.draw_candidates(frame, side[0], generator);
+            self.draw_stats(frame, side[1], generator, state);
+            draw_keybindings(frame, side[2]);
+        })?;
+        Ok(())
+    }
+
+    fn draw_grid(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        grid_data: &GridData<Cartesian2D, Option<ModelInstance>>,
+    ) {
+        let grid = grid_data.grid();
+        let lines: Vec<Line> = (0..grid.size_y())
+            .rev()
+            .map(|y| {
+                Line::from(
+                    (0..grid.size_x())
+                        .map(|x| {
+                            let glyph = match grid_data.get_2d(x, y) {
+                                Some(model_instance) => {
+                                    self.viewer.glyph(model_instance.model_index)
+                                }
+                                None => Cow::Borrowed(self.viewer.empty_glyph()),
+                            };
+                            match (x, y) == (self.cursor.x, self.cursor.y) {
+                                true => Span::styled(
+                                    glyph.into_owned(),
+                                    Style::default().add_modifier(Modifier::REVERSED),
+                                ),
+                                false => Span::raw(glyph.into_owned()),
+                            }
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Grid")),
+            area,
+        );
+    }
+
+    fn draw_candidates(&self, frame: &mut Frame, area: Rect, generator: &Generator<Cartesian2D>) {
+        let node_index = generator
+            .grid()
+            .index_from_coords(self.cursor.x, self.cursor.y, 0);
+        let (variations, models_count) = generator.get_models_variations_on(node_index);
+        let mut lines = vec![Line::from(format!(
+            "node {} at {{{}}}: {} possible models",
+            node_index, self.cursor, models_count
+        ))];
+        lines.extend(variations.iter().map(|variation| {
+            Line::from(format!(
+                "{} {}",
+                self.viewer.glyph(variation.index),
+                variation
+            ))
+        }));
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL).title("Candidates")),
+            area,
+        );
+    }
+
+    fn draw_stats(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        generator: &Generator<Cartesian2D>,
+        state: &RunState,
+    ) {
+        let status = match &state.status {
+            Ok(GenerationStatus::Ongoing) => "ongoing".to_string(),
+            Ok(GenerationStatus::Done) => "done".to_string(),
+            Err(err) => format!("failed: {}", err),
+        };
+        let total_nodes = generator.grid().total_size();
+        let lines = vec![
+            Line::from(format!("seed: {}", generator.seed())),
+            Line::from(format!("step: {}", state.step)),
+            Line::from(format!("status: {}", status)),
+            Line::from(format!(
+                "generated: {}/{}",
+                total_nodes - generator.nodes_left(),
+                total_nodes
+            )),
+            Line::from(format!("heuristic: {:?}", generator.node_heuristic())),
+            Line::from(format!(
+                "mode: {:?} ({} ms)",
+                self.viewer.mode(),
+                self.viewer.step_delay().as_millis()
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL).title("Generation")),
+            area,
+        );
+    }
+}
+
+fn draw_keybindings(frame: &mut Frame, area: Rect) {
+    let lines: Vec<Line> = KEYBINDINGS
+        .iter()
+        .map(|(keys, action)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>12} ", keys),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(*action),
+            ])
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Keys")),
+        area,
+    );
+}
+
+/// Restores the terminal when dropped, even if the debugger returns early with an error
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
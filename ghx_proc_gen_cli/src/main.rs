@@ -0,0 +1,145 @@
+//! Headless generation from a rules file and a grid configuration file, for batch content generation in build pipelines.
+//!
+//! ```text
+//! ghx_proc_gen_cli --rules assets/chessboard_rules.ron --grid assets/chessboard_grid.ron --seed 42 --output chessboard.png
+//! ```
+
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use ghx_proc_gen::{
+    generator::{builder::GeneratorBuilder, rules::Rules, RngMode},
+    ghx_grid::{coordinate_system::CoordinateSystem, grid::GridDefinition},
+    GeneratorBuilderError, GeneratorError, RulesBuilderError,
+};
+
+use crate::{
+    output::{write_output, OutputFormat},
+    rules_file::{CoordinateSystemFile, GridFile, RulesFile},
+};
+
+mod output;
+mod rules_file;
+
+#[derive(thiserror::Error, Debug)]
+pub enum CliError {
+    #[error("Io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid RON file: {0}")]
+    RonParsing(#[from] ron::error::SpannedError),
+    #[error("Failed to write RON: {0}")]
+    RonWriting(#[from] ron::Error),
+    #[error("Failed to write image: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("Unknown socket `{0}`")]
+    UnknownSocket(String),
+    #[error("Invalid rotation `{0}`, rotations are 0, 90, 180 or 270 degrees")]
+    InvalidRotation(u32),
+    #[error("Sockets of model `{0}` do not match the coordinate system of the rules")]
+    CoordinateSystemMismatch(String),
+    #[error("Invalid rules: {0}")]
+    Rules(#[from] RulesBuilderError),
+    #[error("Invalid generator configuration: {0}")]
+    GeneratorBuilder(#[from] GeneratorBuilderError),
+    #[error("Generation failed: {0}")]
+    Generation(#[from] GeneratorError),
+    #[error("Cannot guess the output format from the output path, use --format")]
+    UnknownOutputFormat,
+    #[error("Unsupported output: {0}")]
+    UnsupportedOutput(&'static str),
+}
+
+/// Generates a grid with ghx_proc_gen from a rules file and a grid configuration file
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// RON rules file: sockets, connections and models
+    #[arg(long)]
+    rules: PathBuf,
+    /// RON grid file: grid size, looping and generator configuration
+    #[arg(long)]
+    grid: PathBuf,
+    /// Seed of the generation, overrides the seed of the grid file
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Output file
+    #[arg(long, short)]
+    output: PathBuf,
+    /// Output format, guessed from the output file extension if not specified
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+}
+
+fn generate<C: CoordinateSystem>(
+    rules: Rules<C>,
+    grid: GridDefinition<C>,
+    grid_file: &GridFile,
+    rules_file: &RulesFile,
+    args: &Args,
+    format: OutputFormat,
+) -> Result<(), CliError> {
+    let rng_mode = match args.seed.or(grid_file.seed) {
+        Some(seed) => RngMode::Seeded(seed),
+        None => RngMode::RandomSeed,
+    };
+    let mut generator = GeneratorBuilder::new()
+        .with_rules(rules)
+        .with_grid(grid)
+        .with_max_retry_count(grid_file.max_retry_count)
+        .with_node_heuristic(grid_file.node_heuristic.into())
+        .with_rng(rng_mode)
+        .build()?;
+    let (gen_info, grid_data) = generator.generate_grid()?;
+    write_output(&grid_data, rules_file, format, &args.output)?;
+    println!(
+        "Generated {:?} in {} tries with seed {}, written to {}",
+        grid_data.grid().size(),
+        gen_info.try_count,
+        generator.seed(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+fn run(args: &Args) -> Result<(), CliError> {
+    let format = match args.format {
+        Some(format) => format,
+        None => OutputFormat::from_path(&args.output).ok_or(CliError::UnknownOutputFormat)?,
+    };
+    let rules_file: RulesFile = ron::from_str(&fs::read_to_string(&args.rules)?)?;
+    let grid_file: GridFile = ron::from_str(&fs::read_to_string(&args.grid)?)?;
+    let (size_x, size_y, size_z) = grid_file.size;
+    let (looping_x, looping_y, looping_z) = grid_file.looping;
+
+    match rules_file.coord_system {
+        CoordinateSystemFile::Cartesian2D => generate(
+            rules_file.build_rules_2d()?,
+            GridDefinition::new_cartesian_2d(size_x, size_y, looping_x, looping_y),
+            &grid_file,
+            &rules_file,
+            args,
+            format,
+        ),
+        CoordinateSystemFile::Cartesian3D => generate(
+            rules_file.build_rules_3d()?,
+            GridDefinition::new_cartesian_3d(
+                size_x, size_y, size_z, looping_x, looping_y, looping_z,
+            ),
+            &grid_file,
+            &rules_file,
+            args,
+            format,
+        ),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
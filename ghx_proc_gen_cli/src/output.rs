@@ -0,0 +1,182 @@
+use std::{fmt::Write, fs, path::Path};
+
+use ghx_proc_gen::{
+    generator::model::{ModelInstance, ModelRotation},
+    ghx_grid::{coordinate_system::CoordinateSystem, grid::GridData},
+    golden::to_golden_string,
+    serialization::{to_bytes, SerializedGridData},
+};
+use image::{Rgb, RgbImage};
+
+use crate::{rules_file::RulesFile, CliError};
+
+/// Size in pixels of the tiles referenced by the TMX output
+const TMX_TILE_SIZE: u32 = 16;
+
+// Tiled flags stored in the high bits of a tile gid
+const TMX_FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const TMX_FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const TMX_FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+
+/// Output format of the generated grid
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// RON serialization of the generated models, see `ghx_proc_gen::serialization::SerializedGridData`
+    Ron,
+    /// Compact binary serialization, see `ghx_proc_gen::serialization::to_bytes`
+    Bin,
+    /// Golden text format, see `ghx_proc_gen::golden`
+    Golden,
+    /// One pixel per node, using the models colors. 2d grids only.
+    Png,
+    /// Tiled map, with one layer per z level. The tileset has one tile per model, without images.
+    Tmx,
+}
+
+impl OutputFormat {
+    /// Guesses the format from the extension of `path`
+    pub fn from_path(path: &Path) -> Option<OutputFormat> {
+        match path.extension()?.to_str()? {
+            "ron" => Some(OutputFormat::Ron),
+            "bin" => Some(OutputFormat::Bin),
+            "txt" => Some(OutputFormat::Golden),
+            "png" => Some(OutputFormat::Png),
+            "tmx" => Some(OutputFormat::Tmx),
+            _ => None,
+        }
+    }
+}
+
+pub fn write_output<C: CoordinateSystem>(
+    grid_data: &GridData<C, ModelInstance>,
+    rules_file: &RulesFile,
+    format: OutputFormat,
+    path: &Path,
+) -> Result<(), CliError> {
+    match format {
+        OutputFormat::Ron => {
+            let ron = ron::ser::to_string_pretty(
+                &SerializedGridData::from_grid_data(grid_data),
+                ron::ser::PrettyConfig::default(),
+            )?;
+            fs::write(path, ron)?;
+        }
+        OutputFormat::Bin => fs::write(path, to_bytes(grid_data))?,
+        OutputFormat::Golden => fs::write(path, to_golden_string(grid_data))?,
+        OutputFormat::Png => to_png(grid_data, rules_file)?.save(path)?,
+        OutputFormat::Tmx => fs::write(path, to_tmx(grid_data, rules_file))?,
+    }
+    Ok(())
+}
+
+/// Color of a model: the color given in the rules file, or a color derived from its index.
+fn model_color(rules_file: &RulesFile, model_index: usize) -> Rgb<u8> {
+    if let Some(color) = rules_file
+        .models
+        .get(model_index)
+        .and_then(|model| model.color)
+    {
+        return Rgb(color);
+    }
+    // Spread the hues with the golden ratio
+    let hue = (model_index as f32 * 0.618_034).fract() * 6.;
+    let x = 1. - (hue % 2. - 1.).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1., x, 0.),
+        1 => (x, 1., 0.),
+        2 => (0., 1., x),
+        3 => (0., x, 1.),
+        4 => (x, 0., 1.),
+        _ => (1., 0., x),
+    };
+    Rgb([(r * 200.) as u8, (g * 200.) as u8, (b * 200.) as u8])
+}
+
+fn to_png<C: CoordinateSystem>(
+    grid_data: &GridData<C, ModelInstance>,
+    rules_file: &RulesFile,
+) -> Result<RgbImage, CliError> {
+    let grid = grid_data.grid();
+    if grid.size_z() != 1 {
+        return Err(CliError::UnsupportedOutput(
+            "the PNG output only supports 2d grids",
+        ));
+    }
+    // Y is up in the grid and down in the image
+    Ok(RgbImage::from_fn(grid.size_x(), grid.size_y(), |x, y| {
+        let node = grid_data.get_2d(x, grid.size_y() - 1 - y);
+        model_color(rules_file, node.model_index)
+    }))
+}
+
+/// Tiled flips equivalent to a counter-clockwise rotation (with the y axis up, as displayed by Tiled after flipping the rows)
+fn tmx_rotation_flags(rotation: ModelRotation) -> u32 {
+    match rotation {
+        ModelRotation::Rot0 => 0,
+        ModelRotation::Rot90 => TMX_FLIPPED_DIAGONALLY | TMX_FLIPPED_VERTICALLY,
+        ModelRotation::Rot180 => TMX_FLIPPED_HORIZONTALLY | TMX_FLIPPED_VERTICALLY,
+        ModelRotation::Rot270 => TMX_FLIPPED_DIAGONALLY | TMX_FLIPPED_HORIZONTALLY,
+    }
+}
+
+fn to_tmx<C: CoordinateSystem>(
+    grid_data: &GridData<C, ModelInstance>,
+    rules_file: &RulesFile,
+) -> String {
+    let grid = grid_data.grid();
+    let (size_x, size_y, size_z) = grid.size();
+    let mut tmx = String::new();
+    let _ = writeln!(tmx, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        tmx,
+        r#"<map version="1.10" orientation="orthogonal" renderorder="right-down" width="{size_x}" height="{size_y}" tilewidth="{TMX_TILE_SIZE}" tileheight="{TMX_TILE_SIZE}" infinite="0" nextlayerid="{}" nextobjectid="1">"#,
+        size_z + 1
+    );
+    let _ = writeln!(
+        tmx,
+        r#" <tileset firstgid="1" name="models" tilewidth="{TMX_TILE_SIZE}" tileheight="{TMX_TILE_SIZE}" tilecount="{}" columns="0">"#,
+        rules_file.models.len()
+    );
+    for (model_index, model) in rules_file.models.iter().enumerate() {
+        let _ = writeln!(
+            tmx,
+            r#"  <tile id="{model_index}" type="{}"/>"#,
+            escape_xml(&model.name)
+        );
+    }
+    let _ = writeln!(tmx, " </tileset>");
+    for z in 0..size_z {
+        let _ = writeln!(
+            tmx,
+            r#" <layer id="{}" name="z{z}" width="{size_x}" height="{size_y}">"#,
+            z + 1
+        );
+        let _ = writeln!(tmx, r#"  <data encoding="csv">"#);
+        // Tiled rows go down, the grid y axis goes up
+        let rows: Vec<String> = (0..size_y)
+            .rev()
+            .map(|y| {
+                (0..size_x)
+                    .map(|x| {
+                        let node = grid_data.get_3d(x, y, z);
+                        let gid = node.model_index as u32 + 1;
+                        (gid | tmx_rotation_flags(node.rotation)).to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect();
+        let _ = writeln!(tmx, "{}", rows.join(",\n"));
+        let _ = writeln!(tmx, "  </data>");
+        let _ = writeln!(tmx, " </layer>");
+    }
+    let _ = writeln!(tmx, "</map>");
+    tmx
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
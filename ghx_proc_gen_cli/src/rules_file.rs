@@ -0,0 +1,259 @@
+use std::collections::{BTreeSet, HashMap};
+
+use ghx_proc_gen::{
+    generator::{
+        model::{ModelCollection, ModelRotation, ModelTemplate, ALL_MODEL_ROTATIONS},
+        node_heuristic::NodeSelectionHeuristic,
+        rules::{Rules, RulesBuilder},
+        socket::{Socket, SocketCollection, SocketsCartesian2D, SocketsCartesian3D},
+    },
+    ghx_grid::coordinate_system::{Cartesian2D, Cartesian3D},
+};
+use serde::Deserialize;
+
+use crate::CliError;
+
+/// Coordinate system of a [`RulesFile`]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateSystemFile {
+    Cartesian2D,
+    Cartesian3D,
+}
+
+/// Sockets of a model, referenced by their name in [`RulesFile::sockets`]
+#[derive(Deserialize, Debug, Clone)]
+pub enum SocketsFile {
+    /// Same socket on every side
+    Mono(String),
+    Cartesian2D {
+        x_pos: Vec<String>,
+        x_neg: Vec<String>,
+        y_pos: Vec<String>,
+        y_neg: Vec<String>,
+    },
+    Cartesian3D {
+        x_pos: Vec<String>,
+        x_neg: Vec<String>,
+        y_pos: Vec<String>,
+        y_neg: Vec<String>,
+        z_pos: Vec<String>,
+        z_neg: Vec<String>,
+    },
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ModelFile {
+    /// Name of the model, written in the outputs which support it
+    pub name: String,
+    pub sockets: SocketsFile,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// Allowed rotations in degrees (counter-clockwise). Only `Rot0` if empty.
+    #[serde(default)]
+    pub rotations: Vec<u32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Color of the model in the PNG output. A color is derived from the model index if `None`.
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
+}
+
+/// Serialized description of some [`Rules`]: sockets are created and referenced by name.
+///
+/// ```ron
+/// (
+///     coord_system: Cartesian2D,
+///     sockets: ["white", "black"],
+///     connections: [("white", ["black"])],
+///     models: [
+///         (name: "white", sockets: Mono("white")),
+///         (name: "black", sockets: Mono("black"), weight: 2.0),
+///     ],
+/// )
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+pub struct RulesFile {
+    pub coord_system: CoordinateSystemFile,
+    pub sockets: Vec<String>,
+    /// `(from, to)` connections, see [`SocketCollection::add_connection`]
+    #[serde(default)]
+    pub connections: Vec<(String, Vec<String>)>,
+    /// `(from, to)` connections on the rotation axis, see [`SocketCollection::add_rotated_connection`]. Only for 3d rules.
+    #[serde(default)]
+    pub rotated_connections: Vec<(String, Vec<String>)>,
+    pub models: Vec<ModelFile>,
+}
+
+/// Node selection heuristic of a [`GridFile`]
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub enum NodeHeuristicFile {
+    #[default]
+    MinimumRemainingValue,
+    MinimumEntropy,
+    Random,
+}
+
+impl From<NodeHeuristicFile> for NodeSelectionHeuristic {
+    fn from(value: NodeHeuristicFile) -> Self {
+        match value {
+            NodeHeuristicFile::MinimumRemainingValue => {
+                NodeSelectionHeuristic::MinimumRemainingValue
+            }
+            NodeHeuristicFile::MinimumEntropy => NodeSelectionHeuristic::MinimumEntropy,
+            NodeHeuristicFile::Random => NodeSelectionHeuristic::Random,
+        }
+    }
+}
+
+fn default_max_retry_count() -> u32 {
+    50
+}
+
+/// Grid and generator configuration
+///
+/// ```ron
+/// (size: (32, 32, 1), looping: (false, false, false), seed: Some(42))
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+pub struct GridFile {
+    /// `(x, y, z)` size of the grid. `z` is ignored for 2d rules.
+    pub size: (u32, u32, u32),
+    #[serde(default)]
+    pub looping: (bool, bool, bool),
+    /// Random seed if `None`. Can be overridden from the command line.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default = "default_max_retry_count")]
+    pub max_retry_count: u32,
+    #[serde(default)]
+    pub node_heuristic: NodeHeuristicFile,
+}
+
+impl RulesFile {
+    /// Creates the sockets and their connections, and returns them with a map of the sockets by name.
+    fn sockets(&self) -> Result<(SocketCollection, HashMap<String, Socket>), CliError> {
+        let mut collection = SocketCollection::new();
+        let sockets: HashMap<String, Socket> = self
+            .sockets
+            .iter()
+            .map(|name| (name.clone(), collection.create()))
+            .collect();
+        for (from, to) in self.connections.iter() {
+            collection.add_connection(socket_ref(&sockets, from)?, socket_refs(&sockets, to)?);
+        }
+        for (from, to) in self.rotated_connections.iter() {
+            collection
+                .add_rotated_connection(socket_ref(&sockets, from)?, socket_refs(&sockets, to)?);
+        }
+        Ok((collection, sockets))
+    }
+
+    pub fn build_rules_2d(&self) -> Result<Rules<Cartesian2D>, CliError> {
+        let (collection, sockets) = self.sockets()?;
+        let mut models = ModelCollection::<Cartesian2D>::new();
+        for model in self.models.iter() {
+            let template = match &model.sockets {
+                SocketsFile::Mono(socket) => {
+                    SocketsCartesian2D::Mono(socket_ref(&sockets, socket)?).to_template()
+                }
+                SocketsFile::Cartesian2D {
+                    x_pos,
+                    x_neg,
+                    y_pos,
+                    y_neg,
+                } => SocketsCartesian2D::Multiple {
+                    x_pos: socket_refs(&sockets, x_pos)?,
+                    x_neg: socket_refs(&sockets, x_neg)?,
+                    y_pos: socket_refs(&sockets, y_pos)?,
+                    y_neg: socket_refs(&sockets, y_neg)?,
+                }
+                .to_template(),
+                SocketsFile::Cartesian3D { .. } => {
+                    return Err(CliError::CoordinateSystemMismatch(model.name.clone()))
+                }
+            };
+            models
+                .create(model.configure(template)?)
+                .with_name(model.name.clone());
+        }
+        Ok(RulesBuilder::new_cartesian_2d(models, collection).build()?)
+    }
+
+    pub fn build_rules_3d(&self) -> Result<Rules<Cartesian3D>, CliError> {
+        let (collection, sockets) = self.sockets()?;
+        let mut models = ModelCollection::<Cartesian3D>::new();
+        for model in self.models.iter() {
+            let template = match &model.sockets {
+                SocketsFile::Mono(socket) => {
+                    SocketsCartesian3D::Mono(socket_ref(&sockets, socket)?).to_template()
+                }
+                SocketsFile::Cartesian3D {
+                    x_pos,
+                    x_neg,
+                    y_pos,
+                    y_neg,
+                    z_pos,
+                    z_neg,
+                } => SocketsCartesian3D::Multiple {
+                    x_pos: socket_refs(&sockets, x_pos)?,
+                    x_neg: socket_refs(&sockets, x_neg)?,
+                    y_pos: socket_refs(&sockets, y_pos)?,
+                    y_neg: socket_refs(&sockets, y_neg)?,
+                    z_pos: socket_refs(&sockets, z_pos)?,
+                    z_neg: socket_refs(&sockets, z_neg)?,
+                }
+                .to_template(),
+                SocketsFile::Cartesian2D { .. } => {
+                    return Err(CliError::CoordinateSystemMismatch(model.name.clone()))
+                }
+            };
+            models
+                .create(model.configure(template)?)
+                .with_name(model.name.clone());
+        }
+        Ok(RulesBuilder::new_cartesian_3d(models, collection).build()?)
+    }
+}
+
+impl ModelFile {
+    fn configure<C>(&self, template: ModelTemplate<C>) -> Result<ModelTemplate<C>, CliError> {
+        let mut template = template
+            .with_weight(self.weight)
+            .with_tags(self.tags.clone());
+        if !self.rotations.is_empty() {
+            let rotations = self
+                .rotations
+                .iter()
+                .map(|&degrees| rotation_from_degrees(degrees))
+                .collect::<Result<BTreeSet<_>, _>>()?;
+            template = template.with_rotations(rotations);
+        }
+        Ok(template)
+    }
+}
+
+fn rotation_from_degrees(degrees: u32) -> Result<ModelRotation, CliError> {
+    ALL_MODEL_ROTATIONS
+        .iter()
+        .copied()
+        .find(|rotation| rotation.value() == degrees)
+        .ok_or(CliError::InvalidRotation(degrees))
+}
+
+fn socket_ref(sockets: &HashMap<String, Socket>, name: &String) -> Result<Socket, CliError> {
+    sockets
+        .get(name)
+        .copied()
+        .ok_or_else(|| CliError::UnknownSocket(name.clone()))
+}
+
+fn socket_refs(
+    sockets: &HashMap<String, Socket>,
+    names: &Vec<String>,
+) -> Result<Vec<Socket>, CliError> {
+    names.iter().map(|name| socket_ref(sockets, name)).collect()
+}